@@ -1,29 +1,31 @@
-use std::sync::{Arc, Mutex};
+use std::sync::Arc;
 
-use crate::{core::shell::Shell, kernel::{Kernel, Message}, process::Process};
+use crate::{core::shell::job::JobControl, kernel::Kernel, process::Process};
 
 pub struct DemoProcess {
     pid: usize,
     name: String,
     counter: u32,
     life: u32,
-    shell: Arc<Mutex<Shell>>,
+    /// Control of the shell job that started the demo: it does not tick
+    /// while the job is stopped.
+    job: Option<Arc<JobControl>>,
 }
 
 impl DemoProcess {
-    pub fn new(shell: &mut Shell) -> Self {
+    pub fn new(job: Option<Arc<JobControl>>) -> Self {
         Self {
             pid: 0,
             name: "demo_async_process".into(),
             counter: 0,
             life: 120,
-            shell: Arc::new(Mutex::new(shell.clone())),
+            job,
         }
     }
 }
 
 impl Process for DemoProcess {
-    
+
 
     fn pid(&self) -> usize {
         self.pid
@@ -34,19 +36,16 @@ impl Process for DemoProcess {
     }
 
     fn tick(&mut self, k: &mut Kernel) {
-        if self.life == 120 {
-            let shell_pid = self.shell.lock().unwrap().pid();
-            k.send(shell_pid, Message::SetWaitingForInput(false));
+        if self.job.as_ref().is_some_and(|job| job.is_stopped()) {
+            return;
         }
 
         if self.life == 0 {
-            let shell_pid = self.shell.lock().unwrap().pid();
-            k.send(shell_pid, Message::SetWaitingForInput(true));
             k.kill(self.pid);
             return;
         }
-        
-        if self.counter % 30 == 0 {
+
+        if self.counter.is_multiple_of(30) {
             k.print(&format!(
                 "\n[demo] tick {} ({} left)\n",
                 self.counter, self.life
@@ -59,10 +58,10 @@ impl Process for DemoProcess {
         self.counter += 1;
         self.life -= 1;
     }
-    
+
     fn name(&self) -> String {
         self.name.clone()
     }
-    
-    
+
+
 }
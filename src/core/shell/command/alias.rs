@@ -0,0 +1,99 @@
+use clap::{CommandFactory, Parser};
+use futures::{future::LocalBoxFuture, FutureExt};
+
+use crate::core::shell::{
+    command::{context::CommandContext, parse_args, Command},
+    printer::quote,
+    startup,
+};
+
+#[derive(Parser, Debug)]
+#[command(name = "alias", about = "rOS command to define or print aliases", version = "0.1.0")]
+pub struct AliasArgs {
+    /// `NAME=value` to define an alias, `NAME` to print it
+    aliases: Vec<String>,
+}
+
+pub struct AliasCommand;
+
+register!(AliasCommand);
+
+/// Whether `name` can be used as an alias: it must be a single word that
+/// the shell does not treat specially.
+pub fn is_valid_alias(name: &str) -> bool {
+    !name.is_empty()
+        && !name
+            .chars()
+            .any(|c| c.is_whitespace() || "/$`=\\'\"()<>;&|".contains(c))
+}
+
+impl Command for AliasCommand {
+    fn name(&self) -> &'static str {
+        "alias"
+    }
+
+    fn usage(&self) -> clap::Command {
+        AliasArgs::command()
+    }
+
+    fn run<'a>(&'a self, ctx: &'a mut CommandContext) -> LocalBoxFuture<'a, i32> {
+        async move {
+            let args = match parse_args::<AliasArgs>(ctx).await {
+                Ok(args) => args,
+                Err(status) => return status,
+            };
+
+            if args.aliases.is_empty() {
+                let listing: String = ctx.shell
+                    .aliases
+                    .lock()
+                    .await
+                    .iter()
+                    .map(|(name, value)| format!("alias {}={}\n", name, quote(value)))
+                    .collect();
+
+                ctx.print(&listing).await;
+                return 0;
+            }
+
+            let mut status = 0;
+            let mut changed = false;
+
+            for alias in &args.aliases {
+                match alias.split_once('=') {
+                    Some((name, value)) => {
+                        if !is_valid_alias(name) {
+                            ctx.eprint(&format!("alias: '{}': invalid alias name\n", name)).await;
+                            status = 1;
+                            continue;
+                        }
+
+                        ctx.shell.aliases.lock().await.insert(name.into(), value.into());
+                        changed = true;
+                    }
+                    None => {
+                        let value = ctx.shell.aliases.lock().await.get(alias).cloned();
+
+                        match value {
+                            Some(value) => ctx.print(&format!("alias {}={}\n", alias, quote(&value))).await,
+                            None => {
+                                ctx.eprint(&format!("alias: {}: not found\n", alias)).await;
+                                status = 1;
+                            }
+                        }
+                    }
+                }
+            }
+
+            if changed
+                && ctx.shell.at_prompt()
+                && let Err(err) = startup::save_aliases(ctx.shell).await
+            {
+                ctx.eprint(&format!("alias: cannot save aliases: {}\n", err)).await;
+            }
+
+            status
+        }
+        .boxed_local()
+    }
+}
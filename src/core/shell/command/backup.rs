@@ -0,0 +1,381 @@
+use std::collections::{HashMap, HashSet};
+
+use clap::{CommandFactory, Parser};
+use futures::{future::LocalBoxFuture, FutureExt};
+
+use crate::{
+    core::shell::command::{
+        context::CommandContext,
+        du::human_size,
+        find::contents_first,
+        parse_args,
+        tar::write_archive,
+        transfer::{pick_file, save},
+        Command,
+    },
+    vfs::{
+        entry::{FSEntry, FSEntryKind},
+        errors::SimpleFSError,
+        image::Image,
+        path,
+        storage::VOLUME,
+    },
+};
+
+#[derive(Parser, Debug)]
+#[command(
+    name = "backup",
+    about = "rOS command to save the whole volume in an image",
+    long_about = "Writes every entry of the volume, with its contents and metadata, to IMAGE, which restore \
+                  reads back, in this browser or another one. Without IMAGE the browser is handed the image \
+                  as a download.",
+    version = "0.1.0"
+)]
+pub struct BackupArgs {
+    /// File to write the image to, `-` for stdout
+    image: Option<String>,
+}
+
+#[derive(Parser, Debug)]
+#[command(
+    name = "restore",
+    about = "rOS command to restore the volume from an image",
+    long_about = "Puts back every entry saved in IMAGE by backup, in a single step: if anything fails the \
+                  volume is left as it was. Entries of the volume that differ from those of the image are \
+                  conflicts, which stop the restore unless -f is given. Entries missing from the image are \
+                  kept, unless --clean is given. With -n nothing is changed, what would be is reported. \
+                  Without IMAGE it is chosen on the host.",
+    version = "0.1.0"
+)]
+pub struct RestoreArgs {
+    /// Image to read, `-` for stdin
+    image: Option<String>,
+
+    /// Only report what would be done, conflicts included
+    #[arg(short = 'n', long)]
+    dry_run: bool,
+
+    /// Overwrite the entries in conflict with the image
+    #[arg(short, long)]
+    force: bool,
+
+    /// Remove the entries missing from the image
+    #[arg(long)]
+    clean: bool,
+}
+
+pub struct BackupCommand;
+pub struct RestoreCommand;
+
+register!(BackupCommand, RestoreCommand);
+
+/// What restoring an image does to the volume.
+#[derive(Debug, Default)]
+struct Plan {
+    added: usize,
+    unchanged: usize,
+    /// Entries of the volume replaced by different ones, with why they
+    /// differ.
+    conflicts: Vec<(String, String)>,
+    removed: Vec<String>,
+}
+
+fn kind_name(entry: &FSEntryKind) -> &'static str {
+    match entry {
+        FSEntryKind::File(_) => "file",
+        FSEntryKind::Folder(_) => "folder",
+        FSEntryKind::Link(_) => "link",
+    }
+}
+
+/// Why `current` cannot be replaced by `restored` without losing anything,
+/// if so. Metadata does not count, the image being taken as more recent.
+fn conflict(current: &FSEntryKind, restored: &FSEntryKind) -> Option<String> {
+    match (current, restored) {
+        (FSEntryKind::Folder(_), FSEntryKind::Folder(_)) => None,
+        (FSEntryKind::File(current), FSEntryKind::File(restored)) => {
+            (current.data != restored.data).then(|| "contents differ".to_string())
+        }
+        (FSEntryKind::Link(current), FSEntryKind::Link(restored)) => (current.target != restored.target)
+            .then(|| format!("links to {} in the image, to {} in the volume", restored.target, current.target)),
+        _ => Some(format!("{} in the image, {} in the volume", kind_name(restored), kind_name(current))),
+    }
+}
+
+/// Compares the image with the entries of the volume. Entries below a
+/// path the image holds something else than a folder at are removed, or
+/// they would be left without a parent. The contents of a folder are
+/// removed before it.
+fn plan(current: &[FSEntry], image: &Image, clean: bool) -> Plan {
+    let restored: HashMap<&str, &FSEntryKind> =
+        image.entries.iter().map(|entry| (entry.abs_path.as_str(), &entry.entry)).collect();
+    let existing: HashMap<&str, &FSEntryKind> =
+        current.iter().map(|entry| (entry.abs_path.as_str(), &entry.entry)).collect();
+    let mut plan = Plan::default();
+
+    for entry in &image.entries {
+        match existing.get(entry.abs_path.as_str()) {
+            None => plan.added += 1,
+            Some(kind) => match conflict(kind, &entry.entry) {
+                Some(reason) => plan.conflicts.push((entry.abs_path.clone(), reason)),
+                None => plan.unchanged += 1,
+            },
+        }
+    }
+
+    let shadowed: HashSet<&str> = restored
+        .iter()
+        .filter(|(_, kind)| !matches!(kind, FSEntryKind::Folder(_)))
+        .map(|(path, _)| *path)
+        .collect();
+
+    for entry in current {
+        let path = entry.abs_path.as_str();
+        if restored.contains_key(path) {
+            continue;
+        }
+
+        let mut parent = path::parent(path);
+        let mut below_other = false;
+        while parent != "/" && !below_other {
+            below_other = shadowed.contains(parent.as_str());
+            parent = path::parent(&parent);
+        }
+
+        if clean || below_other {
+            plan.removed.push(path.to_string());
+        }
+    }
+    plan.removed.sort_by(|a, b| contents_first(a, b));
+
+    plan
+}
+
+/// Every entry of the volume, with the contents of the files `load` is
+/// true for.
+async fn read_volume(
+    ctx: &CommandContext<'_>,
+    load: impl Fn(&FSEntry) -> bool,
+) -> Result<Vec<FSEntry>, SimpleFSError> {
+    let kernel = ctx.kernel().await;
+    let mut entries = kernel.fs.read_tree("/").await?;
+
+    for entry in entries.iter_mut().filter(|entry| load(entry)) {
+        kernel.fs.load_contents(entry).await?;
+    }
+
+    Ok(entries)
+}
+
+fn format_time(millis: i64) -> String {
+    chrono::DateTime::from_timestamp_millis(millis)
+        .map(|time| time.format("%Y-%m-%d %H:%M:%S").to_string())
+        .unwrap_or_default()
+}
+
+impl Command for BackupCommand {
+    fn name(&self) -> &'static str {
+        "backup"
+    }
+
+    fn usage(&self) -> clap::Command {
+        BackupArgs::command()
+    }
+
+    fn run<'a>(&'a self, ctx: &'a mut CommandContext) -> LocalBoxFuture<'a, i32> {
+        async move {
+            let args = match parse_args::<BackupArgs>(ctx).await {
+                Ok(args) => args,
+                Err(status) => return status,
+            };
+
+            let entries = match read_volume(ctx, |_| true).await {
+                Ok(entries) => entries,
+                Err(err) => {
+                    ctx.eprint(&format!("backup: {}: {}\n", VOLUME, err)).await;
+                    return 1;
+                }
+            };
+
+            let now = chrono::Utc::now();
+            let count = entries.len();
+            let data = Image::new(VOLUME, now.timestamp_millis(), entries).encode();
+
+            let (result, target) = match &args.image {
+                Some(file) => (write_archive(ctx, file, &data).await, file.clone()),
+                None => {
+                    let name = format!("{}-{}.img", crate::HOSTNAME, now.format("%Y%m%d-%H%M%S"));
+                    let result = save(&name, &data, "application/octet-stream").await;
+                    (result.map_err(|err| format!("{:?}", err)), name)
+                }
+            };
+
+            if let Err(err) = result {
+                ctx.eprint(&format!("backup: {}: {}\n", target, err)).await;
+                return 1;
+            }
+
+            if args.image.as_deref() != Some("-") {
+                let size = human_size(data.len() as u64);
+                ctx.print(&format!("{}: {} entries saved to {} ({})\n", VOLUME, count, target, size)).await;
+            }
+
+            0
+        }
+        .boxed_local()
+    }
+}
+
+impl Command for RestoreCommand {
+    fn name(&self) -> &'static str {
+        "restore"
+    }
+
+    fn usage(&self) -> clap::Command {
+        RestoreArgs::command()
+    }
+
+    fn run<'a>(&'a self, ctx: &'a mut CommandContext) -> LocalBoxFuture<'a, i32> {
+        async move {
+            let args = match parse_args::<RestoreArgs>(ctx).await {
+                Ok(args) => args,
+                Err(status) => return status,
+            };
+
+            let (name, data) = match &args.image {
+                Some(file) => (file.clone(), ctx.read_file(file).await),
+                None => {
+                    ctx.print("Choose an image to restore, or drop it on the console...\n").await;
+                    match pick_file(ctx).await {
+                        Ok(Some((name, data))) => (name, Ok(data)),
+                        Ok(None) => {
+                            ctx.eprint("restore: cancelled\n").await;
+                            return 1;
+                        }
+                        Err(err) => ("restore".to_string(), Err(format!("{:?}", err))),
+                    }
+                }
+            };
+
+            let image = match data.and_then(|data| Image::decode(&data).map_err(|e| e.to_string())) {
+                Ok(image) => image,
+                Err(err) => {
+                    ctx.eprint(&format!("restore: {}: {}\n", name, err)).await;
+                    return 1;
+                }
+            };
+
+            // contents only need comparing when the sizes are the same
+            let sizes: HashMap<&str, u64> = image
+                .entries
+                .iter()
+                .filter_map(|entry| match &entry.entry {
+                    FSEntryKind::File(file) => Some((entry.abs_path.as_str(), file.size)),
+                    _ => None,
+                })
+                .collect();
+            let same_size = |entry: &FSEntry| match &entry.entry {
+                FSEntryKind::File(file) => sizes.get(entry.abs_path.as_str()) == Some(&file.size),
+                _ => false,
+            };
+
+            let current = match read_volume(ctx, same_size).await {
+                Ok(entries) => entries,
+                Err(err) => {
+                    ctx.eprint(&format!("restore: {}: {}\n", VOLUME, err)).await;
+                    return 1;
+                }
+            };
+
+            let plan = plan(&current, &image, args.clean);
+            let conflicts: String = plan
+                .conflicts
+                .iter()
+                .map(|(path, reason)| format!("conflict: {}: {}\n", path, reason))
+                .collect();
+
+            if args.dry_run {
+                let mut report = format!(
+                    "{}: image of {} made {}, {} entries\n",
+                    name,
+                    image.volume,
+                    format_time(image.created_at),
+                    image.entries.len()
+                );
+                report.push_str(&conflicts);
+                report.extend(plan.removed.iter().map(|path| format!("remove: {}\n", path)));
+                report.push_str(&format!(
+                    "would add {}, overwrite {}, remove {} and keep {} entries\n",
+                    plan.added,
+                    plan.conflicts.len(),
+                    plan.removed.len(),
+                    plan.unchanged
+                ));
+                ctx.print(&report).await;
+
+                return if plan.conflicts.is_empty() || args.force { 0 } else { 1 };
+            }
+
+            if !plan.conflicts.is_empty() && !args.force {
+                ctx.eprint(&conflicts).await;
+                let count = plan.conflicts.len();
+                ctx.eprint(&format!("restore: {} conflicts, nothing restored, -f overwrites them\n", count)).await;
+                return 1;
+            }
+
+            if let Err(err) = ctx.kernel().await.fs.apply_entries(&plan.removed, &image.entries).await {
+                ctx.eprint(&format!("restore: {}: {}, nothing restored\n", VOLUME, err)).await;
+                return 1;
+            }
+
+            ctx.print(&format!(
+                "{}: {} entries restored from {}, {} removed\n",
+                VOLUME,
+                image.entries.len(),
+                name,
+                plan.removed.len()
+            ))
+            .await;
+
+            0
+        }
+        .boxed_local()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vfs::entry::{FSFile, FSFolder};
+
+    fn folder(path: &str) -> FSEntry {
+        FSEntry { abs_path: path.into(), entry: FSEntryKind::Folder(FSFolder::new(path::file_name(path), 0)) }
+    }
+
+    fn file(path: &str, data: &[u8]) -> FSEntry {
+        FSEntry { abs_path: path.into(), entry: FSEntryKind::File(FSFile::new(path::file_name(path), data.into(), 0)) }
+    }
+
+    #[test]
+    fn clean_removes_the_contents_of_folders_first() {
+        let current = [folder("/a"), folder("/a/b"), file("/a/b/c", b"c"), file("/a/d", b"d"), folder("/e")];
+        let image = Image { volume: VOLUME.into(), created_at: 0, entries: vec![folder("/e")] };
+
+        let plan = plan(&current, &image, true);
+
+        assert_eq!(plan.removed, ["/a/b/c", "/a/b", "/a/d", "/a"]);
+        assert_eq!((plan.added, plan.unchanged), (0, 1));
+    }
+
+    #[test]
+    fn entries_below_a_restored_file_are_removed() {
+        let current = [folder("/a"), file("/a/b", b"b"), file("/c", b"c")];
+        let image = Image { volume: VOLUME.into(), created_at: 0, entries: vec![file("/a", b"a")] };
+
+        let plan = plan(&current, &image, false);
+
+        assert_eq!(plan.removed, ["/a/b"]);
+        assert_eq!(plan.conflicts, [("/a".to_string(), "file in the image, folder in the volume".to_string())]);
+        assert_eq!(plan.added, 0);
+    }
+}
@@ -0,0 +1,97 @@
+use ::base64::{engine::general_purpose::STANDARD, Engine};
+use clap::{CommandFactory, Parser};
+use futures::{future::LocalBoxFuture, FutureExt};
+
+use crate::core::shell::command::{context::CommandContext, parse_args, Command};
+
+#[derive(Parser, Debug)]
+#[command(
+    name = "base64",
+    about = "rOS command to encode or decode base64 data",
+    long_about = "Encodes FILE, or stdin, to base64 on stdout, or decodes it with -d. Newlines are ignored \
+                  when decoding.",
+    version = "0.1.0"
+)]
+pub struct Base64Args {
+    /// File to read, `-` or nothing reads stdin
+    file: Option<String>,
+
+    /// Decode the data
+    #[arg(short, long)]
+    decode: bool,
+
+    /// Ignore characters outside of the alphabet when decoding
+    #[arg(short, long, requires = "decode")]
+    ignore_garbage: bool,
+
+    /// Wrap encoded lines after COLS characters, 0 to disable wrapping
+    #[arg(short, long, value_name = "COLS", default_value_t = 76)]
+    wrap: usize,
+}
+
+pub struct Base64Command;
+
+register!(Base64Command);
+
+fn is_alphabet(byte: u8) -> bool {
+    byte.is_ascii_alphanumeric() || matches!(byte, b'+' | b'/' | b'=')
+}
+
+impl Command for Base64Command {
+    fn name(&self) -> &'static str {
+        "base64"
+    }
+
+    fn usage(&self) -> clap::Command {
+        Base64Args::command()
+    }
+
+    fn run<'a>(&'a self, ctx: &'a mut CommandContext) -> LocalBoxFuture<'a, i32> {
+        async move {
+            let args = match parse_args::<Base64Args>(ctx).await {
+                Ok(args) => args,
+                Err(status) => return status,
+            };
+
+            let files: Vec<String> = args.file.into_iter().collect();
+            let (input, status) = ctx.read_input_bytes("base64", &files).await;
+            if status != 0 {
+                return status;
+            }
+
+            let output = if args.decode {
+                let encoded: Vec<u8> = input
+                    .into_iter()
+                    .filter(|byte| if args.ignore_garbage { is_alphabet(*byte) } else { !byte.is_ascii_whitespace() })
+                    .collect();
+
+                match STANDARD.decode(encoded) {
+                    Ok(decoded) => decoded,
+                    Err(_) => {
+                        ctx.eprint("base64: invalid input\n").await;
+                        return 1;
+                    }
+                }
+            } else {
+                let encoded = STANDARD.encode(input);
+                let mut lines = String::with_capacity(encoded.len() + encoded.len() / args.wrap.max(1) + 1);
+
+                // the encoding is ASCII, so that any byte is a character boundary
+                let width = if args.wrap == 0 { encoded.len().max(1) } else { args.wrap };
+                for line in encoded.as_bytes().chunks(width) {
+                    lines.push_str(&String::from_utf8_lossy(line));
+                    lines.push('\n');
+                }
+
+                lines.into_bytes()
+            };
+
+            if ctx.stdout().write(&output).await.is_err() {
+                return 1;
+            }
+
+            0
+        }
+        .boxed_local()
+    }
+}
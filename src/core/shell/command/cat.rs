@@ -0,0 +1,81 @@
+use clap::{CommandFactory, Parser};
+use futures::{future::LocalBoxFuture, FutureExt};
+
+use crate::{
+    core::shell::command::{context::CommandContext, parse_args, Command},
+    vfs::{chunk::CHUNK_SIZE, path},
+};
+
+#[derive(Parser, Debug)]
+#[command(name = "cat", about = "rOS command to concatenate files to stdout", version = "0.1.0")]
+pub struct CatArgs {
+    /// Files to print, `-` or nothing reads stdin
+    files: Vec<String>,
+}
+
+pub struct CatCommand;
+
+register!(CatCommand);
+
+impl Command for CatCommand {
+    fn name(&self) -> &'static str {
+        "cat"
+    }
+
+    fn usage(&self) -> clap::Command {
+        CatArgs::command()
+    }
+
+    fn run<'a>(&'a self, ctx: &'a mut CommandContext) -> LocalBoxFuture<'a, i32> {
+        async move {
+            let args = match parse_args::<CatArgs>(ctx).await {
+                Ok(args) => args,
+                Err(status) => return status,
+            };
+
+            let files = if args.files.is_empty() { vec!["-".to_string()] } else { args.files };
+            let mut status = 0;
+
+            for file in files {
+                if file == "-" {
+                    match ctx.stdin().read_to_end().await {
+                        Ok(data) => {
+                            if ctx.stdout().write(&data).await.is_err() {
+                                return 1;
+                            }
+                        }
+                        Err(err) => {
+                            ctx.eprint(&format!("cat: {}: {}\n", file, err)).await;
+                            status = 1;
+                        }
+                    }
+                    continue;
+                }
+
+                // files are read a chunk at a time, never held whole
+                let file_path = path::resolve(&ctx.cwd, &file);
+                let mut offset = 0;
+
+                loop {
+                    match ctx.kernel().await.fs.read_range(&file_path, offset, CHUNK_SIZE).await {
+                        Ok(data) if data.is_empty() => break,
+                        Ok(data) => {
+                            if ctx.stdout().write(&data).await.is_err() {
+                                return 1;
+                            }
+                            offset += data.len() as u64;
+                        }
+                        Err(err) => {
+                            ctx.eprint(&format!("cat: {}: {}\n", file, err)).await;
+                            status = 1;
+                            break;
+                        }
+                    }
+                }
+            }
+
+            status
+        }
+        .boxed_local()
+    }
+}
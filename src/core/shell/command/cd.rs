@@ -1,43 +1,80 @@
-use std::sync::Arc;
-
-use crate::{core::shell::command::ShellCommandWithData, kernel::Kernel};
-use async_std::sync::Mutex;
-use clap::Parser;
+use crate::{
+    core::shell::command::{context::CommandContext, parse_args, Command},
+    vfs::{entry::FSEntryKind, path},
+};
+use clap::{CommandFactory, Parser};
+use futures::{future::LocalBoxFuture, FutureExt};
 
 #[derive(Parser, Debug)]
-#[command(name = "cd", about = "rOS command to change directory", version = "0.1.1")]
-pub struct CdCommand {
-    #[arg(required = true)]
-    folder: String,
+#[command(name = "cd", about = "rOS command to change directory", version = "0.1.2")]
+pub struct CdArgs {
+    /// Folder to move to, `$HOME` when omitted
+    folder: Option<String>,
 }
 
-impl ShellCommandWithData<String> for CdCommand {
-    async fn execute_data(k: &mut Kernel, cmd: &str, data: Arc<Mutex<String>>) -> String
-    {
-        let args = CdCommand::try_parse_from(cmd.split_whitespace());
+pub struct CdCommand;
 
-        if let Err(e) = &args {
-            return format!("{}", e);
-        }
+register!(CdCommand);
 
-        let args = args.unwrap();
+impl Command for CdCommand {
+    fn name(&self) -> &'static str {
+        "cd"
+    }
 
-        let folder = args.folder;
+    fn usage(&self) -> clap::Command {
+        CdArgs::command()
+    }
 
-        if folder.is_empty() {
-            return "cd: missing operand".into();
-        }
+    fn run<'a>(&'a self, ctx: &'a mut CommandContext) -> LocalBoxFuture<'a, i32> {
+        async move {
+            let args = match parse_args::<CdArgs>(ctx).await {
+                Ok(args) => args,
+                Err(status) => return status,
+            };
 
-        let exists = k.fs.exists(&folder).await;
-        if let Err(err) = exists {
-            return format!("\nError checking existence of '{}': {:?}\n", folder, err);
-        } else if !exists.unwrap() {
-            return format!("\nError: folder '{}' does not exist.\n", folder);
-        }
+            let folder = match args.folder {
+                Some(folder) => folder,
+                None => match ctx.var("HOME").await {
+                    Some(home) => home,
+                    None => {
+                        ctx.eprint("cd: HOME not set\n").await;
+                        return 1;
+                    }
+                },
+            };
 
-        let mut folder_mut = data.lock().await;
-        *folder_mut = folder.to_string();
+            if folder.is_empty() {
+                ctx.eprint("cd: missing operand\n").await;
+                return 1;
+            }
 
-        format!("Changed directory to '{}'", folder)
+            let target = path::resolve(&ctx.cwd, &folder);
+
+            if target != "/" {
+                let entry = ctx.kernel().await.fs.get_entry(&target).await;
+
+                match entry {
+                    Err(err) => {
+                        ctx.eprint(&format!("cd: {}: {}\n", folder, err)).await;
+                        return 1;
+                    }
+                    Ok(None) => {
+                        ctx.eprint(&format!("cd: {}: No such file or directory\n", folder)).await;
+                        return 1;
+                    }
+                    Ok(Some(entry)) if !matches!(entry.entry, FSEntryKind::Folder(_)) => {
+                        ctx.eprint(&format!("cd: {}: Not a folder\n", folder)).await;
+                        return 1;
+                    }
+                    Ok(Some(_)) => {}
+                }
+            }
+
+            let mut folder_mut = ctx.shell.folder.lock().await;
+            *folder_mut = target;
+
+            0
+        }
+        .boxed_local()
     }
 }
@@ -0,0 +1,269 @@
+use clap::{Args, CommandFactory, Parser};
+use futures::{future::LocalBoxFuture, FutureExt};
+use md5::Md5;
+use sha1::Sha1;
+use sha2::{Digest, Sha256};
+
+use crate::core::shell::command::{context::CommandContext, parse_args, Command};
+
+const CHECK_HELP: &str = "Prints the checksum of each file, or checks the files listed with -c in lines of the \
+                          form CHECKSUM  FILE, as printed without it.";
+
+#[derive(Args, Debug)]
+pub struct SumOptions {
+    /// Files to read, `-` or nothing reads stdin
+    files: Vec<String>,
+
+    /// Read checksums from the files and check them
+    #[arg(short, long)]
+    check: bool,
+
+    /// Do not print OK for each file checked successfully
+    #[arg(long, requires = "check")]
+    quiet: bool,
+
+    /// Print nothing when checking, only the exit status tells the result
+    #[arg(long, requires = "check")]
+    status: bool,
+}
+
+#[derive(Parser, Debug)]
+#[command(
+    name = "sha256sum",
+    about = "rOS command to compute and check SHA-256 checksums",
+    long_about = CHECK_HELP,
+    version = "0.1.0"
+)]
+pub struct Sha256sumArgs {
+    #[command(flatten)]
+    options: SumOptions,
+}
+
+#[derive(Parser, Debug)]
+#[command(
+    name = "sha1sum",
+    about = "rOS command to compute and check SHA-1 checksums",
+    long_about = CHECK_HELP,
+    version = "0.1.0"
+)]
+pub struct Sha1sumArgs {
+    #[command(flatten)]
+    options: SumOptions,
+}
+
+#[derive(Parser, Debug)]
+#[command(
+    name = "md5sum",
+    about = "rOS command to compute and check MD5 checksums",
+    long_about = CHECK_HELP,
+    version = "0.1.0"
+)]
+pub struct Md5sumArgs {
+    #[command(flatten)]
+    options: SumOptions,
+}
+
+pub struct Sha256sumCommand;
+pub struct Sha1sumCommand;
+pub struct Md5sumCommand;
+
+register!(Sha256sumCommand, Sha1sumCommand, Md5sumCommand);
+
+#[derive(Debug, Clone, Copy)]
+enum Algorithm {
+    Sha256,
+    Sha1,
+    Md5,
+}
+
+impl Algorithm {
+    fn command(self) -> &'static str {
+        match self {
+            Algorithm::Sha256 => "sha256sum",
+            Algorithm::Sha1 => "sha1sum",
+            Algorithm::Md5 => "md5sum",
+        }
+    }
+
+    /// The checksum of `data` in lowercase hexadecimal.
+    fn hash(self, data: &[u8]) -> String {
+        let digest = match self {
+            Algorithm::Sha256 => Sha256::digest(data).to_vec(),
+            Algorithm::Sha1 => Sha1::digest(data).to_vec(),
+            Algorithm::Md5 => Md5::digest(data).to_vec(),
+        };
+
+        digest.iter().map(|byte| format!("{:02x}", byte)).collect()
+    }
+
+    fn hex_len(self) -> usize {
+        match self {
+            Algorithm::Sha256 => 64,
+            Algorithm::Sha1 => 40,
+            Algorithm::Md5 => 32,
+        }
+    }
+}
+
+/// `count` followed by `one` or `many`, e.g. `2 lines are`.
+fn counted(count: usize, one: &str, many: &str) -> String {
+    format!("{} {}", count, if count == 1 { one } else { many })
+}
+
+/// Splits a line of a checksum list into the checksum and the file name,
+/// a `*` before the name marking a file read in binary mode.
+fn parse_line(line: &str, algorithm: Algorithm) -> Option<(&str, &str)> {
+    let (checksum, name) = line.split_once(' ')?;
+    let name = name.strip_prefix(' ').or_else(|| name.strip_prefix('*'))?;
+
+    let valid = checksum.len() == algorithm.hex_len() && checksum.chars().all(|c| c.is_ascii_hexdigit());
+    (valid && !name.is_empty()).then_some((checksum, name))
+}
+
+async fn sum(ctx: &CommandContext<'_>, algorithm: Algorithm, options: SumOptions) -> i32 {
+    let command = algorithm.command();
+    let files = if options.files.is_empty() { vec!["-".to_string()] } else { options.files };
+
+    if !options.check {
+        let mut status = 0;
+
+        for file in &files {
+            match ctx.read_file(file).await {
+                Ok(data) => {
+                    if ctx.write(&format!("{}  {}\n", algorithm.hash(&data), file)).await.is_err() {
+                        return 1;
+                    }
+                }
+                Err(err) => {
+                    ctx.eprint(&format!("{}: {}: {}\n", command, file, err)).await;
+                    status = 1;
+                }
+            }
+        }
+
+        return status;
+    }
+
+    let (mut bad_lines, mut unreadable, mut mismatched) = (0, 0, 0);
+    let mut status = 0;
+
+    for list in &files {
+        let text = match ctx.read_file(list).await {
+            Ok(data) => String::from_utf8_lossy(&data).into_owned(),
+            Err(err) => {
+                ctx.eprint(&format!("{}: {}: {}\n", command, list, err)).await;
+                status = 1;
+                continue;
+            }
+        };
+
+        let mut checked = 0;
+
+        for line in text.lines().filter(|line| !line.trim().is_empty()) {
+            let Some((checksum, name)) = parse_line(line, algorithm) else {
+                bad_lines += 1;
+                continue;
+            };
+            checked += 1;
+
+            let result = match ctx.read_file(name).await {
+                Ok(data) if algorithm.hash(&data).eq_ignore_ascii_case(checksum) => "OK",
+                Ok(_) => {
+                    mismatched += 1;
+                    "FAILED"
+                }
+                Err(err) => {
+                    ctx.eprint(&format!("{}: {}: {}\n", command, name, err)).await;
+                    unreadable += 1;
+                    "FAILED open or read"
+                }
+            };
+
+            if !options.status && (result != "OK" || !options.quiet) {
+                ctx.print(&format!("{}: {}\n", name, result)).await;
+            }
+        }
+
+        if checked == 0 {
+            ctx.eprint(&format!("{}: {}: no properly formatted checksum lines found\n", command, list)).await;
+            status = 1;
+        }
+    }
+
+    if !options.status {
+        if bad_lines > 0 {
+            let lines = counted(bad_lines, "line is", "lines are");
+            ctx.eprint(&format!("{}: WARNING: {} improperly formatted\n", command, lines)).await;
+        }
+        if unreadable > 0 {
+            let files = counted(unreadable, "listed file", "listed files");
+            ctx.eprint(&format!("{}: WARNING: {} could not be read\n", command, files)).await;
+        }
+        if mismatched > 0 {
+            let checksums = counted(mismatched, "computed checksum", "computed checksums");
+            ctx.eprint(&format!("{}: WARNING: {} did NOT match\n", command, checksums)).await;
+        }
+    }
+
+    if unreadable > 0 || mismatched > 0 { 1 } else { status }
+}
+
+impl Command for Sha256sumCommand {
+    fn name(&self) -> &'static str {
+        "sha256sum"
+    }
+
+    fn usage(&self) -> clap::Command {
+        Sha256sumArgs::command()
+    }
+
+    fn run<'a>(&'a self, ctx: &'a mut CommandContext) -> LocalBoxFuture<'a, i32> {
+        async move {
+            match parse_args::<Sha256sumArgs>(ctx).await {
+                Ok(args) => sum(ctx, Algorithm::Sha256, args.options).await,
+                Err(status) => status,
+            }
+        }
+        .boxed_local()
+    }
+}
+
+impl Command for Sha1sumCommand {
+    fn name(&self) -> &'static str {
+        "sha1sum"
+    }
+
+    fn usage(&self) -> clap::Command {
+        Sha1sumArgs::command()
+    }
+
+    fn run<'a>(&'a self, ctx: &'a mut CommandContext) -> LocalBoxFuture<'a, i32> {
+        async move {
+            match parse_args::<Sha1sumArgs>(ctx).await {
+                Ok(args) => sum(ctx, Algorithm::Sha1, args.options).await,
+                Err(status) => status,
+            }
+        }
+        .boxed_local()
+    }
+}
+
+impl Command for Md5sumCommand {
+    fn name(&self) -> &'static str {
+        "md5sum"
+    }
+
+    fn usage(&self) -> clap::Command {
+        Md5sumArgs::command()
+    }
+
+    fn run<'a>(&'a self, ctx: &'a mut CommandContext) -> LocalBoxFuture<'a, i32> {
+        async move {
+            match parse_args::<Md5sumArgs>(ctx).await {
+                Ok(args) => sum(ctx, Algorithm::Md5, args.options).await,
+                Err(status) => status,
+            }
+        }
+        .boxed_local()
+    }
+}
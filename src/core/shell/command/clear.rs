@@ -0,0 +1,34 @@
+use clap::{CommandFactory, Parser};
+use futures::{future::LocalBoxFuture, FutureExt};
+
+use crate::core::shell::command::{context::CommandContext, parse_args, Command};
+
+#[derive(Parser, Debug)]
+#[command(name = "clear", about = "rOS command to clear the console", version = "0.1.0")]
+pub struct ClearArgs {}
+
+pub struct ClearCommand;
+
+register!(ClearCommand);
+
+impl Command for ClearCommand {
+    fn name(&self) -> &'static str {
+        "clear"
+    }
+
+    fn usage(&self) -> clap::Command {
+        ClearArgs::command()
+    }
+
+    fn run<'a>(&'a self, ctx: &'a mut CommandContext) -> LocalBoxFuture<'a, i32> {
+        async move {
+            if let Err(status) = parse_args::<ClearArgs>(ctx).await {
+                return status;
+            }
+
+            ctx.kernel().await.clear();
+            0
+        }
+        .boxed_local()
+    }
+}
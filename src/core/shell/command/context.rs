@@ -0,0 +1,129 @@
+use std::rc::Rc;
+
+use async_std::sync::{Mutex, MutexGuard};
+
+use crate::{
+    core::shell::Shell,
+    kernel::{
+        errors::IoError,
+        fd::{FdTable, FileDescriptor, STDERR, STDIN, STDOUT},
+        Kernel,
+    },
+    vfs::path,
+};
+
+/// Everything a running command has access to: its arguments, its standard
+/// streams, the shell that started it and the kernel.
+///
+/// The kernel is locked on demand rather than for the whole run, so that a
+/// command waiting on a full or empty pipe never blocks the other stages of
+/// its pipeline.
+pub struct CommandContext<'a> {
+    pub pid: usize,
+    pub args: Vec<String>,
+    /// Working directory the command was started from.
+    pub cwd: String,
+    pub fds: FdTable,
+    /// The shell running the command, for builtins changing its state.
+    pub shell: &'a mut Shell,
+    kernel: Rc<Mutex<Kernel>>,
+}
+
+impl<'a> CommandContext<'a> {
+    pub fn new(
+        pid: usize,
+        args: Vec<String>,
+        cwd: String,
+        fds: FdTable,
+        shell: &'a mut Shell,
+        kernel: Rc<Mutex<Kernel>>,
+    ) -> Self {
+        Self { pid, args, cwd, fds, shell, kernel }
+    }
+
+    pub async fn kernel(&self) -> MutexGuard<'_, Kernel> {
+        self.kernel.lock().await
+    }
+
+    fn fd(&self, fd: usize) -> &FileDescriptor {
+        self.fds.get(fd).unwrap_or(&FileDescriptor::Null)
+    }
+
+    pub fn stdin(&self) -> &FileDescriptor {
+        self.fd(STDIN)
+    }
+
+    pub fn stdout(&self) -> &FileDescriptor {
+        self.fd(STDOUT)
+    }
+
+    pub fn stderr(&self) -> &FileDescriptor {
+        self.fd(STDERR)
+    }
+
+    /// Writes to stdout, reporting a closed reader to the caller.
+    pub async fn write(&self, s: &str) -> Result<(), IoError> {
+        self.stdout().write(s.as_bytes()).await
+    }
+
+    /// Writes to stdout for commands that do not care whether anyone is
+    /// still reading.
+    pub async fn print(&self, s: &str) {
+        let _ = self.write(s).await;
+    }
+
+    pub async fn eprint(&self, s: &str) {
+        let _ = self.stderr().write(s.as_bytes()).await;
+    }
+
+    /// Returns the value of an environment variable of the command.
+    pub async fn var(&self, name: &str) -> Option<String> {
+        self.kernel().await.envs.get(&self.pid).and_then(|env| env.get(name)).map(str::to_string)
+    }
+
+    /// Reads stdin until end-of-file.
+    pub async fn read_to_string(&self) -> String {
+        let data = self.stdin().read_to_end().await.unwrap_or_default();
+        String::from_utf8_lossy(&data).into_owned()
+    }
+
+    /// Reads the input of a filter: `files` one after the other, `-` or no
+    /// file at all standing for stdin. Files that cannot be read are
+    /// reported as `command: file: error`, the exit status then being 1.
+    pub async fn read_inputs(&self, command: &str, files: &[String]) -> (String, i32) {
+        let (data, status) = self.read_input_bytes(command, files).await;
+        (String::from_utf8_lossy(&data).into_owned(), status)
+    }
+
+    /// Same as `read_inputs`, for filters of binary data.
+    pub async fn read_input_bytes(&self, command: &str, files: &[String]) -> (Vec<u8>, i32) {
+        if files.is_empty() {
+            return (self.stdin().read_to_end().await.unwrap_or_default(), 0);
+        }
+
+        let mut input = Vec::new();
+        let mut status = 0;
+
+        for file in files {
+            match self.read_file(file).await {
+                Ok(data) => input.extend(data),
+                Err(err) => {
+                    self.eprint(&format!("{}: {}: {}\n", command, file, err)).await;
+                    status = 1;
+                }
+            }
+        }
+
+        (input, status)
+    }
+
+    /// Reads `file`, relative to the working folder, `-` standing for stdin.
+    pub async fn read_file(&self, file: &str) -> Result<Vec<u8>, String> {
+        if file == "-" {
+            self.stdin().read_to_end().await.map_err(|e| e.to_string())
+        } else {
+            let file_path = path::resolve(&self.cwd, file);
+            self.kernel().await.fs.read_file(&file_path).await.map_err(|e| e.to_string())
+        }
+    }
+}
@@ -0,0 +1,146 @@
+use clap::{CommandFactory, Parser};
+use futures::{future::LocalBoxFuture, FutureExt};
+
+use crate::core::shell::command::{context::CommandContext, parse_args, Command};
+
+#[derive(Parser, Debug)]
+#[command(name = "cut", about = "rOS command to print selected parts of lines", version = "0.1.0")]
+pub struct CutArgs {
+    /// Files to read, `-` or nothing reads stdin
+    files: Vec<String>,
+
+    /// Select these characters, e.g. 1-3,5,7-
+    #[arg(short, long, value_name = "LIST", conflicts_with = "fields", required_unless_present = "fields")]
+    characters: Option<String>,
+
+    /// Select these fields, e.g. 1,3-
+    #[arg(short, long, value_name = "LIST")]
+    fields: Option<String>,
+
+    /// Separate fields with DELIM instead of a tab
+    #[arg(short, long, value_name = "DELIM", default_value_t = '\t')]
+    delimiter: char,
+
+    /// With -f, skip the lines holding no delimiter instead of printing them
+    #[arg(short = 's', long)]
+    only_delimited: bool,
+}
+
+pub struct CutCommand;
+
+register!(CutCommand);
+
+/// A list of 1-based ranges such as `1-3,5,7-`.
+struct Ranges(Vec<(usize, usize)>);
+
+impl Ranges {
+    fn parse(list: &str) -> Option<Self> {
+        let mut ranges = Vec::new();
+
+        for item in list.split(',') {
+            let (start, end) = match item.split_once('-') {
+                Some((start, end)) => (
+                    if start.is_empty() { 1 } else { start.parse().ok()? },
+                    if end.is_empty() { usize::MAX } else { end.parse().ok()? },
+                ),
+                None => {
+                    let n = item.parse().ok()?;
+                    (n, n)
+                }
+            };
+
+            if start == 0 || start > end {
+                return None;
+            }
+            ranges.push((start, end));
+        }
+
+        Some(Self(ranges))
+    }
+
+    fn contains(&self, n: usize) -> bool {
+        self.0.iter().any(|(start, end)| (*start..=*end).contains(&n))
+    }
+}
+
+impl Command for CutCommand {
+    fn name(&self) -> &'static str {
+        "cut"
+    }
+
+    fn usage(&self) -> clap::Command {
+        CutArgs::command()
+    }
+
+    fn run<'a>(&'a self, ctx: &'a mut CommandContext) -> LocalBoxFuture<'a, i32> {
+        async move {
+            let args = match parse_args::<CutArgs>(ctx).await {
+                Ok(args) => args,
+                Err(status) => return status,
+            };
+
+            let list = args.fields.as_deref().or(args.characters.as_deref()).unwrap_or_default();
+            let Some(ranges) = Ranges::parse(list) else {
+                ctx.eprint(&format!("cut: invalid list '{}'\n", list)).await;
+                return 1;
+            };
+
+            let (input, status) = ctx.read_inputs("cut", &args.files).await;
+            let mut output = String::new();
+
+            for line in input.lines() {
+                if args.fields.is_none() {
+                    output.extend(line.chars().enumerate().filter(|(i, _)| ranges.contains(i + 1)).map(|(_, c)| c));
+                } else if !line.contains(args.delimiter) {
+                    if args.only_delimited {
+                        continue;
+                    }
+                    output.push_str(line);
+                } else {
+                    let fields: Vec<&str> = line
+                        .split(args.delimiter)
+                        .enumerate()
+                        .filter(|(i, _)| ranges.contains(i + 1))
+                        .map(|(_, field)| field)
+                        .collect();
+                    output.push_str(&fields.join(&args.delimiter.to_string()));
+                }
+                output.push('\n');
+            }
+
+            if ctx.write(&output).await.is_err() {
+                return 1;
+            }
+
+            status
+        }
+        .boxed_local()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn selected(list: &str, upto: usize) -> Vec<usize> {
+        let ranges = Ranges::parse(list).unwrap();
+        (1..=upto).filter(|n| ranges.contains(*n)).collect()
+    }
+
+    #[test]
+    fn lists_are_made_of_numbers_and_ranges() {
+        assert_eq!(selected("1-3,5", 8), [1, 2, 3, 5]);
+        assert_eq!(selected("3-", 5), [3, 4, 5]);
+        assert_eq!(selected("-2", 5), [1, 2]);
+        assert_eq!(selected("4,2,2-3", 5), [2, 3, 4]);
+        assert_eq!(selected("2-2", 5), [2]);
+        assert!(Ranges::parse("3-").unwrap().contains(usize::MAX));
+    }
+
+    #[test]
+    fn malformed_lists_are_rejected() {
+        for list in ["", "0", "0-2", "3-1", "a", "1,", "1-2-3", "1;2"] {
+            assert!(Ranges::parse(list).is_none(), "{}", list);
+        }
+    }
+}
@@ -0,0 +1,42 @@
+use clap::{CommandFactory, Parser};
+use futures::{future::LocalBoxFuture, FutureExt};
+
+use crate::{
+    core::{
+        demo::DemoProcess,
+        shell::command::{context::CommandContext, parse_args, Command},
+    },
+    kernel::wait_for_exit,
+};
+
+#[derive(Parser, Debug)]
+#[command(name = "demo", about = "rOS command to run a demo process ticking for a while", version = "0.1.0")]
+pub struct DemoArgs {}
+
+pub struct DemoCommand;
+
+register!(DemoCommand);
+
+impl Command for DemoCommand {
+    fn name(&self) -> &'static str {
+        "demo"
+    }
+
+    fn usage(&self) -> clap::Command {
+        DemoArgs::command()
+    }
+
+    fn run<'a>(&'a self, ctx: &'a mut CommandContext) -> LocalBoxFuture<'a, i32> {
+        async move {
+            if let Err(status) = parse_args::<DemoArgs>(ctx).await {
+                return status;
+            }
+
+            let demo = ctx.kernel().await.spawn(Box::new(DemoProcess::new(ctx.shell.job.clone())));
+            ctx.print("Spawning demo process...\n").await;
+            wait_for_exit(demo).await;
+            0
+        }
+        .boxed_local()
+    }
+}
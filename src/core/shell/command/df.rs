@@ -0,0 +1,113 @@
+use std::collections::HashSet;
+
+use clap::{CommandFactory, Parser};
+use futures::{future::LocalBoxFuture, FutureExt};
+use wasm_bindgen::JsCast;
+use wasm_bindgen_futures::JsFuture;
+use web_sys::StorageEstimate;
+
+use crate::{
+    core::shell::command::{context::CommandContext, du::human_size, parse_args, Command},
+    vfs::{entry::FSEntryKind, storage::VOLUME},
+};
+
+#[derive(Parser, Debug)]
+#[command(
+    name = "df",
+    about = "rOS command to show the space used on the volume",
+    long_about = "Shows the bytes taken by the files of the volume, against the storage the browser \
+                  grants to the page. Available space is what is left of that quota once everything \
+                  the page stores, not only the volume, is counted.",
+    version = "0.1.0"
+)]
+pub struct DfArgs {
+    /// Show sizes in K, M and G
+    #[arg(short = 'h', long)]
+    human_readable: bool,
+}
+
+pub struct DfCommand;
+
+register!(DfCommand);
+
+/// The quota of the page and how much of it is used, as estimated by the
+/// browser.
+async fn estimate() -> Option<(u64, u64)> {
+    let promise = web_sys::window()?.navigator().storage().estimate().ok()?;
+    let estimate: StorageEstimate = JsFuture::from(promise).await.ok()?.unchecked_into();
+
+    Some((estimate.get_quota()? as u64, estimate.get_usage().unwrap_or(0.0) as u64))
+}
+
+impl Command for DfCommand {
+    fn name(&self) -> &'static str {
+        "df"
+    }
+
+    fn usage(&self) -> clap::Command {
+        DfArgs::command()
+    }
+
+    fn run<'a>(&'a self, ctx: &'a mut CommandContext) -> LocalBoxFuture<'a, i32> {
+        async move {
+            let args = match parse_args::<DfArgs>(ctx).await {
+                Ok(args) => args,
+                Err(status) => return status,
+            };
+
+            let used: u64 = match ctx.kernel().await.fs.read_folder("/").await {
+                Ok(entries) => {
+                    // a file with several names takes up space once
+                    let mut inodes = HashSet::new();
+                    entries
+                        .iter()
+                        .filter(|entry| match &entry.entry {
+                            FSEntryKind::File(file) => inodes.insert(file.inode),
+                            _ => true,
+                        })
+                        .map(|entry| entry.entry.size())
+                        .sum()
+                }
+                Err(err) => {
+                    ctx.eprint(&format!("df: {}: {}\n", VOLUME, err)).await;
+                    return 1;
+                }
+            };
+
+            let format = |bytes: u64| {
+                if args.human_readable { human_size(bytes) } else { bytes.div_ceil(1024).to_string() }
+            };
+
+            let (size, available, percent) = match estimate().await {
+                Some((quota, usage)) if quota > 0 => (
+                    format(quota),
+                    format(quota.saturating_sub(usage)),
+                    format!("{}%", (used * 100).div_ceil(quota)),
+                ),
+                _ => ("-".to_string(), "-".to_string(), "-".to_string()),
+            };
+
+            let header = if args.human_readable { "Size" } else { "1K-blocks" };
+            let output = format!(
+                "{:<10} {:>10} {:>10} {:>10} {:>4} Mounted on\n{:<10} {:>10} {:>10} {:>10} {:>4} /\n",
+                "Filesystem",
+                header,
+                "Used",
+                "Available",
+                "Use%",
+                VOLUME,
+                size,
+                format(used),
+                available,
+                percent,
+            );
+
+            if ctx.write(&output).await.is_err() {
+                return 1;
+            }
+
+            0
+        }
+        .boxed_local()
+    }
+}
@@ -0,0 +1,518 @@
+use std::cmp::Ordering;
+
+use clap::{CommandFactory, Parser};
+use futures::{future::LocalBoxFuture, FutureExt};
+
+use crate::{
+    core::shell::command::{context::CommandContext, parse_args, Command},
+    vfs::{
+        entry::{FSEntryKind, FSEntryTrait},
+        path,
+    },
+};
+
+#[derive(Parser, Debug)]
+#[command(
+    name = "diff",
+    about = "rOS command to compare files line by line",
+    long_about = "Shows the changes turning FILE1 into FILE2 as a unified diff, which `patch` can apply. \
+                  When both are folders, the files they have in common are compared, and with -r their \
+                  subfolders as well. A folder compared with a file stands for the file of the same name \
+                  in it.\n\n\
+                  The exit status is 0 when there are no differences, 1 when there are some and 2 on \
+                  trouble.",
+    version = "0.1.0"
+)]
+pub struct DiffArgs {
+    #[arg(value_name = "FILE1")]
+    old: String,
+
+    #[arg(value_name = "FILE2")]
+    new: String,
+
+    /// Output a unified diff, which is the only format there is
+    #[arg(short = 'u')]
+    unified_format: bool,
+
+    /// Show NUM lines of context around the changes
+    #[arg(short = 'U', long = "unified", value_name = "NUM", default_value_t = 3)]
+    context: usize,
+
+    /// Compare subfolders recursively
+    #[arg(short, long)]
+    recursive: bool,
+
+    /// Only tell whether files differ
+    #[arg(short = 'q', long)]
+    brief: bool,
+
+    /// Treat files missing on one side as empty
+    #[arg(short = 'N', long)]
+    new_file: bool,
+}
+
+pub struct DiffCommand;
+
+register!(DiffCommand);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Edit {
+    Keep,
+    Delete,
+    Insert,
+}
+
+/// The shortest edit script turning `a` into `b`, found with Myers'
+/// algorithm. Only the diagonals reached in each round are kept for the
+/// way back, so that memory grows with the square of the distance rather
+/// than with the size of the inputs.
+fn myers<T: PartialEq>(a: &[T], b: &[T]) -> Vec<Edit> {
+    let (n, m) = (a.len() as isize, b.len() as isize);
+    let max = n + m;
+    // furthest x on each diagonal k = x - y, indexed by k + max + 1
+    let mut v = vec![0isize; 2 * max as usize + 3];
+    let at = |k: isize| (k + max + 1) as usize;
+    let mut trace: Vec<Vec<isize>> = Vec::new();
+
+    'search: for d in 0..=max {
+        trace.push(v[at(-d)..=at(d)].to_vec());
+
+        for k in (-d..=d).step_by(2) {
+            // step down from diagonal k + 1 or right from diagonal k - 1,
+            // whichever got further
+            let down = k == -d || (k != d && v[at(k - 1)] < v[at(k + 1)]);
+            let mut x = if down { v[at(k + 1)] } else { v[at(k - 1)] + 1 };
+            let mut y = x - k;
+
+            while x < n && y < m && a[x as usize] == b[y as usize] {
+                x += 1;
+                y += 1;
+            }
+            v[at(k)] = x;
+
+            if x >= n && y >= m {
+                break 'search;
+            }
+        }
+    }
+
+    let mut edits = Vec::new();
+    let (mut x, mut y) = (n, m);
+
+    for (d, v) in trace.iter().enumerate().rev() {
+        let d = d as isize;
+        // the diagonals of round d - 1, saved as they were when round d began
+        let previous = |k: isize| v[(k + d) as usize];
+        let k = x - y;
+
+        let previous_k = if k == -d || (k != d && previous(k - 1) < previous(k + 1)) { k + 1 } else { k - 1 };
+        let previous_x = if d == 0 { 0 } else { previous(previous_k) };
+        let previous_y = if d == 0 { 0 } else { previous_x - previous_k };
+
+        while x > previous_x && y > previous_y {
+            edits.push(Edit::Keep);
+            x -= 1;
+            y -= 1;
+        }
+
+        if d > 0 {
+            edits.push(if x == previous_x { Edit::Insert } else { Edit::Delete });
+        }
+
+        x = previous_x;
+        y = previous_y;
+    }
+
+    edits.reverse();
+    edits
+}
+
+/// `start,count` of a hunk header, the count being left out when it is 1
+/// and the start being the line before when there are no lines.
+fn range(start: usize, count: usize) -> String {
+    match count {
+        0 => format!("{},0", start),
+        1 => format!("{}", start + 1),
+        count => format!("{},{}", start + 1, count),
+    }
+}
+
+fn push_line(output: &mut String, prefix: char, line: &str) {
+    output.push(prefix);
+    output.push_str(line);
+
+    if !line.ends_with('\n') {
+        output.push_str("\n\\ No newline at end of file\n");
+    }
+}
+
+/// The hunks of a unified diff between `old` and `new`, with `context`
+/// unchanged lines around each change.
+pub fn unified_hunks(old: &str, new: &str, context: usize) -> String {
+    let a: Vec<&str> = old.split_inclusive('\n').collect();
+    let b: Vec<&str> = new.split_inclusive('\n').collect();
+
+    // every edit with the lines it is at in both files
+    let mut ops = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    for edit in myers(&a, &b) {
+        ops.push((edit, i, j));
+        match edit {
+            Edit::Keep => (i, j) = (i + 1, j + 1),
+            Edit::Delete => i += 1,
+            Edit::Insert => j += 1,
+        }
+    }
+
+    let changes: Vec<usize> = (0..ops.len()).filter(|&op| ops[op].0 != Edit::Keep).collect();
+    let mut output = String::new();
+    let mut next = 0;
+
+    while next < changes.len() {
+        let start = changes[next].saturating_sub(context);
+        let mut last = changes[next];
+        next += 1;
+
+        // changes closer than twice the context share a hunk
+        while next < changes.len() && changes[next] - last - 1 <= 2 * context {
+            last = changes[next];
+            next += 1;
+        }
+
+        let end = (last + 1 + context).min(ops.len());
+        let hunk = &ops[start..end];
+
+        let old_count = hunk.iter().filter(|(edit, ..)| *edit != Edit::Insert).count();
+        let new_count = hunk.iter().filter(|(edit, ..)| *edit != Edit::Delete).count();
+        let (_, old_start, new_start) = hunk[0];
+
+        output.push_str(&format!("@@ -{} +{} @@\n", range(old_start, old_count), range(new_start, new_count)));
+
+        for (edit, i, j) in hunk {
+            match edit {
+                Edit::Keep => push_line(&mut output, ' ', a[*i]),
+                Edit::Delete => push_line(&mut output, '-', a[*i]),
+                Edit::Insert => push_line(&mut output, '+', b[*j]),
+            }
+        }
+    }
+
+    output
+}
+
+/// A file to compare: its contents and modification time, `None` for a
+/// missing file compared as empty with -N.
+struct Side {
+    shown: String,
+    data: Option<Vec<u8>>,
+    modified_at: i64,
+}
+
+fn label(side: &Side) -> String {
+    let time = chrono::DateTime::from_timestamp_millis(side.modified_at)
+        .map(|time| time.format("%Y-%m-%d %H:%M:%S%.9f %z").to_string())
+        .unwrap_or_default();
+
+    format!("{}\t{}", side.shown, time)
+}
+
+/// Compares two files, returning the output and whether they differ.
+fn compare(old: &Side, new: &Side, args: &DiffArgs) -> (String, bool) {
+    let (a, b) = (old.data.as_deref().unwrap_or_default(), new.data.as_deref().unwrap_or_default());
+
+    if a == b {
+        return (String::new(), false);
+    }
+
+    if args.brief {
+        return (format!("Files {} and {} differ\n", old.shown, new.shown), true);
+    }
+
+    if a.contains(&0) || b.contains(&0) {
+        return (format!("Binary files {} and {} differ\n", old.shown, new.shown), true);
+    }
+
+    let hunks = unified_hunks(&String::from_utf8_lossy(a), &String::from_utf8_lossy(b), args.context);
+    (format!("--- {}\n+++ {}\n{}", label(old), label(new), hunks), true)
+}
+
+fn components(path: &str) -> Vec<&str> {
+    path.split('/').collect()
+}
+
+/// The files and folders below `base`, relative to it, sorted folder by
+/// folder. Without `recursive` only the entries directly in it are listed.
+async fn list(ctx: &CommandContext<'_>, base: &str, recursive: bool) -> Vec<(String, bool)> {
+    let entries = ctx.kernel().await.fs.read_tree(base).await.unwrap_or_default();
+    let prefix = if base == "/" { "/".to_string() } else { format!("{}/", base) };
+
+    let mut found: Vec<(String, bool)> = entries
+        .into_iter()
+        .filter_map(|entry| {
+            let relative = entry.abs_path.strip_prefix(&prefix)?.to_string();
+            (recursive || !relative.contains('/')).then_some((relative, matches!(entry.entry, FSEntryKind::Folder(_))))
+        })
+        .collect();
+
+    found.sort_by(|(a, _), (b, _)| components(a).cmp(&components(b)));
+    found
+}
+
+/// Reads a side of the comparison, `-` being stdin.
+async fn read_side(ctx: &CommandContext<'_>, file: &str, shown: &str) -> Result<Side, String> {
+    if file == "-" {
+        let data = ctx.stdin().read_to_end().await.map_err(|e| e.to_string())?;
+        return Ok(Side { shown: shown.into(), data: Some(data), modified_at: chrono::Utc::now().timestamp_millis() });
+    }
+
+    let kernel = ctx.kernel().await;
+    match kernel.fs.get_entry(file).await {
+        Ok(Some(mut entry)) => {
+            let modified_at = entry.entry.modified_at();
+            kernel.fs.load_contents(&mut entry).await.map_err(|e| e.to_string())?;
+            match entry.entry {
+                FSEntryKind::File(file) => Ok(Side { shown: shown.into(), data: file.data, modified_at }),
+                _ => Err("Is a directory".into()),
+            }
+        }
+        Ok(None) => Err("No such file or directory".into()),
+        Err(err) => Err(err.to_string()),
+    }
+}
+
+fn missing(shown: &str) -> Side {
+    Side { shown: shown.into(), data: None, modified_at: 0 }
+}
+
+/// What `path` is: `Some(true)` for a folder, `Some(false)` for a file.
+async fn kind(ctx: &CommandContext<'_>, path: &str) -> Option<bool> {
+    if path == "/" {
+        return Some(true);
+    }
+
+    match ctx.kernel().await.fs.get_entry(path).await {
+        Ok(Some(entry)) => Some(matches!(entry.entry, FSEntryKind::Folder(_))),
+        _ => None,
+    }
+}
+
+impl Command for DiffCommand {
+    fn name(&self) -> &'static str {
+        "diff"
+    }
+
+    fn usage(&self) -> clap::Command {
+        DiffArgs::command()
+    }
+
+    fn run<'a>(&'a self, ctx: &'a mut CommandContext) -> LocalBoxFuture<'a, i32> {
+        async move {
+            let args = match parse_args::<DiffArgs>(ctx).await {
+                Ok(args) => args,
+                Err(status) => return status,
+            };
+
+            let (mut old_shown, mut new_shown) = (args.old.clone(), args.new.clone());
+            let mut old_path = path::resolve(&ctx.cwd, &old_shown);
+            let mut new_path = path::resolve(&ctx.cwd, &new_shown);
+
+            let old_kind = if old_shown == "-" { Some(false) } else { kind(ctx, &old_path).await };
+            let new_kind = if new_shown == "-" { Some(false) } else { kind(ctx, &new_path).await };
+
+            // a folder compared with a file stands for the file of the same name in it
+            match (old_kind, new_kind) {
+                (Some(true), Some(false)) if new_shown != "-" => {
+                    let name = path::file_name(&new_path).to_string();
+                    old_path = path::join(&old_path, &name);
+                    old_shown = format!("{}/{}", old_shown.trim_end_matches('/'), name);
+                }
+                (Some(false), Some(true)) if old_shown != "-" => {
+                    let name = path::file_name(&old_path).to_string();
+                    new_path = path::join(&new_path, &name);
+                    new_shown = format!("{}/{}", new_shown.trim_end_matches('/'), name);
+                }
+                (Some(true), Some(true)) => {
+                    return compare_folders(ctx, &args, (&old_path, &old_shown), (&new_path, &new_shown)).await;
+                }
+                _ => {}
+            }
+
+            let old_file = if old_shown == "-" { "-".to_string() } else { old_path };
+            let new_file = if new_shown == "-" { "-".to_string() } else { new_path };
+
+            let old = read_side(ctx, &old_file, &old_shown).await;
+            let new = read_side(ctx, &new_file, &new_shown).await;
+            let sides = match (old, new) {
+                (Ok(old), Ok(new)) => (old, new),
+                (Err(_), Ok(new)) if args.new_file && old_kind.is_none() => (missing(&old_shown), new),
+                (Ok(old), Err(_)) if args.new_file && new_kind.is_none() => (old, missing(&new_shown)),
+                (Err(err), _) => {
+                    ctx.eprint(&format!("diff: {}: {}\n", old_shown, err)).await;
+                    return 2;
+                }
+                (_, Err(err)) => {
+                    ctx.eprint(&format!("diff: {}: {}\n", new_shown, err)).await;
+                    return 2;
+                }
+            };
+
+            let (output, differ) = compare(&sides.0, &sides.1, &args);
+            if ctx.write(&output).await.is_err() {
+                return 2;
+            }
+
+            if differ { 1 } else { 0 }
+        }
+        .boxed_local()
+    }
+}
+
+async fn compare_folders(
+    ctx: &CommandContext<'_>,
+    args: &DiffArgs,
+    (old_base, old_shown): (&str, &str),
+    (new_base, new_shown): (&str, &str),
+) -> i32 {
+    let old_entries = list(ctx, old_base, args.recursive).await;
+    let new_entries = list(ctx, new_base, args.recursive).await;
+
+    // the names of both sides, merged in order, with what each side has
+    let mut names: Vec<(String, Option<bool>, Option<bool>)> = Vec::new();
+    let (mut old_iter, mut new_iter) = (old_entries.into_iter().peekable(), new_entries.into_iter().peekable());
+
+    loop {
+        let order = match (old_iter.peek(), new_iter.peek()) {
+            (Some((a, _)), Some((b, _))) => components(a).cmp(&components(b)),
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (None, None) => break,
+        };
+
+        match order {
+            Ordering::Less => {
+                let (name, is_folder) = old_iter.next().unwrap_or_default();
+                names.push((name, Some(is_folder), None));
+            }
+            Ordering::Greater => {
+                let (name, is_folder) = new_iter.next().unwrap_or_default();
+                names.push((name, None, Some(is_folder)));
+            }
+            Ordering::Equal => {
+                let (name, old_is_folder) = old_iter.next().unwrap_or_default();
+                let (_, new_is_folder) = new_iter.next().unwrap_or_default();
+                names.push((name, Some(old_is_folder), Some(new_is_folder)));
+            }
+        }
+    }
+
+    let shown = |base: &str, name: &str| format!("{}/{}", base.trim_end_matches('/'), name);
+    let command = if args.recursive { "diff -ru" } else { "diff -u" };
+
+    let mut status = 0;
+    // folders whose contents are not compared, being reported as a whole
+    let mut skipped: Vec<String> = Vec::new();
+
+    for (name, old_is_folder, new_is_folder) in names {
+        if skipped.iter().any(|folder| name.starts_with(&format!("{}/", folder))) {
+            continue;
+        }
+        ctx.shell.checkpoint().await;
+
+        let (old_name, new_name) = (shown(old_shown, &name), shown(new_shown, &name));
+        let (old_path, new_path) = (path::join(old_base, &name), path::join(new_base, &name));
+
+        let (old, new) = match (old_is_folder, new_is_folder) {
+            (Some(true), Some(true)) => {
+                if !args.recursive {
+                    ctx.print(&format!("Common subdirectories: {} and {}\n", old_name, new_name)).await;
+                }
+                continue;
+            }
+            (Some(old_is_folder), Some(new_is_folder)) if old_is_folder != new_is_folder => {
+                let kind = |is_folder| if is_folder { "directory" } else { "regular file" };
+                ctx.print(&format!(
+                    "File {} is a {} while file {} is a {}\n",
+                    old_name,
+                    kind(old_is_folder),
+                    new_name,
+                    kind(new_is_folder)
+                ))
+                .await;
+                skipped.push(name);
+                status = 1;
+                continue;
+            }
+            (Some(false), None) if args.new_file => {
+                (read_side(ctx, &old_path, &old_name).await, Ok(missing(&new_name)))
+            }
+            (None, Some(false)) if args.new_file => {
+                (Ok(missing(&old_name)), read_side(ctx, &new_path, &new_name).await)
+            }
+            (Some(true), None) | (None, Some(true)) if args.new_file && args.recursive => continue,
+            (Some(_), None) | (None, Some(_)) => {
+                let (base, _) = if old_is_folder.is_some() { (old_shown, &old_name) } else { (new_shown, &new_name) };
+                let parent = match name.rsplit_once('/') {
+                    Some((parent, _)) => shown(base, parent),
+                    None => base.to_string(),
+                };
+                ctx.print(&format!("Only in {}: {}\n", parent, path::file_name(&name))).await;
+                skipped.push(name);
+                status = 1;
+                continue;
+            }
+            _ => (read_side(ctx, &old_path, &old_name).await, read_side(ctx, &new_path, &new_name).await),
+        };
+
+        let (old, new) = match (old, new) {
+            (Ok(old), Ok(new)) => (old, new),
+            (Err(err), _) | (_, Err(err)) => {
+                ctx.eprint(&format!("diff: {}: {}\n", name, err)).await;
+                status = 2;
+                continue;
+            }
+        };
+
+        let (output, differ) = compare(&old, &new, args);
+        if differ {
+            let header = if args.brief { String::new() } else { format!("{} {} {}\n", command, old_name, new_name) };
+            if ctx.write(&format!("{}{}", header, output)).await.is_err() {
+                return 2;
+            }
+            status = status.max(1);
+        }
+    }
+
+    status
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn identical_texts_have_no_hunks() {
+        assert_eq!(unified_hunks("a\nb\n", "a\nb\n", 3), "");
+    }
+
+    #[test]
+    fn changes_come_with_their_context() {
+        assert_eq!(unified_hunks("a\nb\nc\nd\ne\n", "a\nb\nC\nd\ne\n", 1), "@@ -2,3 +2,3 @@\n b\n-c\n+C\n d\n");
+        assert_eq!(unified_hunks("", "a\n", 3), "@@ -0,0 +1 @@\n+a\n");
+        assert_eq!(unified_hunks("a\n", "", 3), "@@ -1 +0,0 @@\n-a\n");
+    }
+
+    #[test]
+    fn distant_changes_get_their_own_hunks() {
+        let old = "1\n2\n3\n4\n5\n6\n7\n8\n";
+        let new = "one\n2\n3\n4\n5\n6\n7\neight\n";
+
+        assert_eq!(unified_hunks(old, new, 1), "@@ -1,2 +1,2 @@\n-1\n+one\n 2\n@@ -7,2 +7,2 @@\n 7\n-8\n+eight\n");
+        assert_eq!(unified_hunks(old, new, 3).matches("@@ -").count(), 1);
+    }
+
+    #[test]
+    fn a_missing_newline_is_marked() {
+        assert_eq!(unified_hunks("a\n", "a", 0), "@@ -1 +1 @@\n-a\n+a\n\\ No newline at end of file\n");
+    }
+}
@@ -0,0 +1,166 @@
+use std::collections::HashMap;
+
+use clap::{CommandFactory, Parser};
+use futures::{future::LocalBoxFuture, FutureExt};
+
+use crate::{
+    core::shell::command::{context::CommandContext, find::contents_first, parse_args, Command},
+    vfs::{entry::FSEntryKind, path},
+};
+
+#[derive(Parser, Debug)]
+#[command(
+    name = "du",
+    about = "rOS command to show the space taken by files",
+    long_about = "Shows the size of the contents of each folder below the given paths, in 1024-byte \
+                  blocks rounded up unless -b or -h is given.",
+    version = "0.1.0"
+)]
+pub struct DuArgs {
+    /// Paths to measure, the current folder by default
+    paths: Vec<String>,
+
+    /// Show files as well as folders
+    #[arg(short, long)]
+    all: bool,
+
+    /// Only show a total for each path
+    #[arg(short, long, conflicts_with = "max_depth")]
+    summarize: bool,
+
+    /// Show sizes in K, M and G
+    #[arg(short = 'h', long)]
+    human_readable: bool,
+
+    /// Show sizes in bytes
+    #[arg(short, long, conflicts_with = "human_readable")]
+    bytes: bool,
+
+    /// Only show folders at most N levels below the paths
+    #[arg(short = 'd', long, value_name = "N")]
+    max_depth: Option<usize>,
+}
+
+pub struct DuCommand;
+
+register!(DuCommand);
+
+/// A size in powers of 1024 with a unit suffix, rounded up to one decimal
+/// below ten, e.g. `1.5K` or `12M`.
+pub fn human_size(bytes: u64) -> String {
+    if bytes < 1024 {
+        return bytes.to_string();
+    }
+
+    let mut size = bytes as f64;
+    for unit in ["K", "M", "G", "T"] {
+        size /= 1024.0;
+        let tenths = (size * 10.0).ceil() / 10.0;
+
+        if tenths < 10.0 {
+            return format!("{:.1}{}", tenths, unit);
+        }
+        if size.ceil() < 1024.0 || unit == "T" {
+            return format!("{}{}", size.ceil(), unit);
+        }
+    }
+
+    unreachable!()
+}
+
+/// `path`, which is below `base`, written from `shown`, the way `base` was
+/// given.
+fn shown_path(shown: &str, base: &str, path: &str) -> String {
+    let rest = path.strip_prefix(base).unwrap_or(path).trim_start_matches('/');
+
+    match (shown, rest) {
+        (shown, "") => shown.to_string(),
+        (shown, rest) if shown.ends_with('/') => format!("{}{}", shown, rest),
+        (shown, rest) => format!("{}/{}", shown, rest),
+    }
+}
+
+impl Command for DuCommand {
+    fn name(&self) -> &'static str {
+        "du"
+    }
+
+    fn usage(&self) -> clap::Command {
+        DuArgs::command()
+    }
+
+    fn run<'a>(&'a self, ctx: &'a mut CommandContext) -> LocalBoxFuture<'a, i32> {
+        async move {
+            let args = match parse_args::<DuArgs>(ctx).await {
+                Ok(args) => args,
+                Err(status) => return status,
+            };
+
+            let format = |bytes: u64| match (args.bytes, args.human_readable) {
+                (true, _) => bytes.to_string(),
+                (_, true) => human_size(bytes),
+                _ => bytes.div_ceil(1024).to_string(),
+            };
+            let max_depth = if args.summarize { Some(0) } else { args.max_depth };
+
+            let paths = if args.paths.is_empty() { vec![".".to_string()] } else { args.paths };
+            let mut status = 0;
+
+            for shown in paths {
+                let base = path::resolve(&ctx.cwd, &shown);
+                let mut entries = match ctx.kernel().await.fs.read_tree(&base).await {
+                    Ok(entries) if !entries.is_empty() || base == "/" => entries,
+                    Ok(_) => {
+                        ctx.eprint(&format!("du: cannot access '{}': No such file or directory\n", shown)).await;
+                        status = 1;
+                        continue;
+                    }
+                    Err(err) => {
+                        ctx.eprint(&format!("du: cannot access '{}': {}\n", shown, err)).await;
+                        status = 1;
+                        continue;
+                    }
+                };
+
+                // every folder comes after its contents, whose sizes are
+                // added up on the way
+                entries.sort_by(|a, b| contents_first(&a.abs_path, &b.abs_path));
+                let mut nodes: Vec<(String, bool, u64)> = entries
+                    .iter()
+                    .map(|entry| {
+                        let is_folder = matches!(entry.entry, FSEntryKind::Folder(_));
+                        (entry.abs_path.clone(), is_folder, entry.entry.size())
+                    })
+                    .collect();
+                if base == "/" {
+                    nodes.push(("/".to_string(), true, 0));
+                }
+
+                let mut totals: HashMap<String, u64> = HashMap::new();
+                let mut output = String::new();
+
+                for (node, is_folder, size) in nodes {
+                    let total = if is_folder { totals.remove(&node).unwrap_or(0) } else { size };
+
+                    if node != base {
+                        *totals.entry(path::parent(&node)).or_default() += total;
+                    }
+
+                    let depth = node.strip_prefix(&base).unwrap_or(&node).split('/').filter(|c| !c.is_empty()).count();
+                    let listed = is_folder || args.all || node == base;
+
+                    if listed && max_depth.is_none_or(|max_depth| depth <= max_depth) {
+                        output.push_str(&format!("{}\t{}\n", format(total), shown_path(&shown, &base, &node)));
+                    }
+                }
+
+                if ctx.write(&output).await.is_err() {
+                    return 1;
+                }
+            }
+
+            status
+        }
+        .boxed_local()
+    }
+}
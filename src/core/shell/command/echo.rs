@@ -0,0 +1,42 @@
+use clap::{CommandFactory, Parser};
+use futures::{future::LocalBoxFuture, FutureExt};
+
+use crate::core::shell::command::{context::CommandContext, Command};
+
+/// Only describes the usage of `echo`, which prints its arguments as they
+/// are, flags included.
+#[derive(Parser, Debug)]
+#[command(
+    name = "echo",
+    about = "rOS command to print its arguments",
+    version = "0.1.0",
+    disable_help_flag = true,
+    disable_version_flag = true
+)]
+pub struct EchoArgs {
+    /// Words to print, separated by spaces
+    #[arg(allow_hyphen_values = true)]
+    words: Vec<String>,
+}
+
+pub struct EchoCommand;
+
+register!(EchoCommand);
+
+impl Command for EchoCommand {
+    fn name(&self) -> &'static str {
+        "echo"
+    }
+
+    fn usage(&self) -> clap::Command {
+        EchoArgs::command()
+    }
+
+    fn run<'a>(&'a self, ctx: &'a mut CommandContext) -> LocalBoxFuture<'a, i32> {
+        async move {
+            ctx.print(&format!("{}\n", ctx.args[1..].join(" "))).await;
+            0
+        }
+        .boxed_local()
+    }
+}
@@ -0,0 +1,920 @@
+use clap::{CommandFactory, Parser};
+use futures::{future::LocalBoxFuture, FutureExt};
+use web_sys::HtmlTextAreaElement;
+
+use crate::{
+    core::shell::command::{context::CommandContext, more::LINE_HEIGHT_PX, parse_args, Command},
+    kernel::{fd::FileDescriptor, sleep},
+    pop_raw_key, push_key, set_raw_mode,
+    vfs::{errors::SimpleFSError, path},
+    KeyEvent,
+};
+
+/// Width in pixels of a console character, at the font size of the page.
+const CHAR_WIDTH_PX: f64 = 8.4;
+
+/// Padding in pixels around the text of the console.
+const PADDING_PX: i32 = 8;
+
+/// Smallest screen drawn, whatever the size of the console.
+const MIN_ROWS: usize = 6;
+const MIN_COLUMNS: usize = 20;
+
+/// Lines taken by the title bar, the status line and the shortcuts.
+const CHROME_ROWS: usize = 3;
+
+const TAB_WIDTH: usize = 8;
+
+/// Changes kept for undo.
+const UNDO_LIMIT: usize = 500;
+
+/// How often keys and the size of the console are checked.
+const POLL_MS: i32 = 20;
+
+/// How long the shell is given to stop the job after a Ctrl-Z.
+const SUSPEND_TIMEOUT_MS: i32 = 1000;
+
+const SHORTCUTS: &str = "^S Save  ^X Exit  ^F Search  M-U Undo  M-E Redo  ^Z Suspend";
+
+#[derive(Parser, Debug)]
+#[command(
+    name = "edit",
+    about = "rOS full-screen text editor",
+    long_about = "Edits FILE in the console, creating it on the first save when missing.\n\n\
+                  The arrows, Home, End, Page Up and Page Down move the cursor, Ctrl-Home and Ctrl-End \
+                  to the start and end of the file. ^S or ^O saves, ^X exits, asking first whether to \
+                  save unsaved changes. ^F searches, ignoring case, an empty search repeating the last \
+                  one. M-U (Alt-U) undoes the last change and M-E redoes it. ^Z suspends the editor \
+                  until `fg`.",
+    version = "0.1.0"
+)]
+pub struct EditArgs {
+    /// File to edit
+    file: String,
+}
+
+pub struct EditCommand;
+
+register!(EditCommand);
+
+/// The buffer and cursor as they were before a change.
+#[derive(Clone)]
+struct Snapshot {
+    lines: Vec<String>,
+    row: usize,
+    col: usize,
+    modified: bool,
+}
+
+/// Changes of the same kind in a row are undone together, e.g. a word
+/// being typed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum EditKind {
+    Insert,
+    Delete,
+    Other,
+}
+
+/// A question asked on the status line.
+enum Prompt {
+    Search(String),
+    SaveOnExit,
+}
+
+enum Action {
+    None,
+    Save,
+    SaveAndExit,
+    Exit,
+    Suspend,
+}
+
+struct Editor {
+    name: String,
+    lines: Vec<String>,
+    row: usize,
+    /// Position of the cursor in its line, in characters.
+    col: usize,
+    /// Screen column the cursor goes back to when moving up and down
+    /// through shorter lines.
+    goal: usize,
+    /// First line and screen column shown.
+    top: usize,
+    left: usize,
+    rows: usize,
+    columns: usize,
+    modified: bool,
+    undo: Vec<Snapshot>,
+    redo: Vec<Snapshot>,
+    last_edit: Option<EditKind>,
+    prompt: Option<Prompt>,
+    message: String,
+    last_search: String,
+}
+
+/// Byte offset of character `col` of `line`, the end for columns past it.
+fn byte_at(line: &str, col: usize) -> usize {
+    line.char_indices().nth(col).map_or(line.len(), |(i, _)| i)
+}
+
+/// Screen column of character `col` of `line`, tabs being expanded.
+fn visual_column(line: &str, col: usize) -> usize {
+    line.chars().take(col).fold(0, next_column)
+}
+
+fn next_column(column: usize, c: char) -> usize {
+    if c == '\t' { (column / TAB_WIDTH + 1) * TAB_WIDTH } else { column + 1 }
+}
+
+/// The character of `line` shown at screen column `goal`, or its end.
+fn column_at(line: &str, goal: usize) -> usize {
+    let mut column = 0;
+
+    for (i, c) in line.chars().enumerate() {
+        column = next_column(column, c);
+        if column > goal {
+            return i;
+        }
+    }
+
+    line.chars().count()
+}
+
+/// The part of `line` shown from screen column `left`, `width` columns wide.
+fn visible(line: &str, left: usize, width: usize) -> String {
+    let mut expanded = String::new();
+
+    for c in line.chars() {
+        if c == '\t' {
+            let column = expanded.chars().count();
+            expanded.push_str(&" ".repeat(next_column(column, c) - column));
+        } else {
+            expanded.push(c);
+        }
+    }
+
+    expanded.chars().skip(left).take(width).collect()
+}
+
+fn title_bar(name: &str, modified: bool, width: usize) -> String {
+    let title = "  edit 0.1.0";
+    let file = format!("File: {}", name);
+    let state = if modified { "Modified  " } else { "" };
+
+    let start = (width.saturating_sub(file.chars().count()) / 2).max(title.len() + 2);
+    let bar = format!("{:<start$}{}", title, file);
+    let end = width.saturating_sub(state.len()).max(bar.chars().count() + 2);
+
+    format!("{:<end$}{}", bar, state).chars().take(width).collect()
+}
+
+impl Editor {
+    fn new(name: String, text: &str) -> Self {
+        let mut lines: Vec<String> = text.split('\n').map(String::from).collect();
+        if lines.len() > 1 && lines.last().is_some_and(String::is_empty) {
+            lines.pop();
+        }
+
+        Self {
+            name,
+            lines,
+            row: 0,
+            col: 0,
+            goal: 0,
+            top: 0,
+            left: 0,
+            rows: MIN_ROWS,
+            columns: MIN_COLUMNS,
+            modified: false,
+            undo: Vec::new(),
+            redo: Vec::new(),
+            last_edit: None,
+            prompt: None,
+            message: String::new(),
+            last_search: String::new(),
+        }
+    }
+
+    /// The text to save, every line ending with a newline.
+    fn contents(&self) -> String {
+        if self.lines.len() == 1 && self.lines[0].is_empty() {
+            return String::new();
+        }
+
+        self.lines.iter().map(|line| format!("{}\n", line)).collect()
+    }
+
+    fn text_rows(&self) -> usize {
+        self.rows - CHROME_ROWS
+    }
+
+    fn line_len(&self) -> usize {
+        self.lines[self.row].chars().count()
+    }
+
+    /// Width of the line numbers, with the space after them.
+    fn gutter(&self) -> usize {
+        self.lines.len().to_string().len().max(3) + 1
+    }
+
+    /// Fits the screen to the console, returning whether its size changed.
+    fn resize(&mut self, console: &HtmlTextAreaElement) -> bool {
+        let rows = ((console.client_height() - 2 * PADDING_PX) / LINE_HEIGHT_PX).max(MIN_ROWS as i32) as usize;
+        let width = f64::from(console.client_width() - 2 * PADDING_PX) / CHAR_WIDTH_PX;
+        let columns = (width as usize).max(MIN_COLUMNS);
+
+        let changed = (rows, columns) != (self.rows, self.columns);
+        self.rows = rows;
+        self.columns = columns;
+        changed
+    }
+
+    fn snapshot(&self) -> Snapshot {
+        Snapshot { lines: self.lines.clone(), row: self.row, col: self.col, modified: self.modified }
+    }
+
+    fn restore(&mut self, snapshot: Snapshot) {
+        self.lines = snapshot.lines;
+        self.row = snapshot.row;
+        self.col = snapshot.col;
+        self.modified = snapshot.modified;
+        self.last_edit = None;
+    }
+
+    /// Saves the buffer for undo before a change, unless the change goes on
+    /// the previous one.
+    fn record(&mut self, kind: EditKind) {
+        if kind == EditKind::Other || self.last_edit != Some(kind) {
+            self.undo.push(self.snapshot());
+            if self.undo.len() > UNDO_LIMIT {
+                self.undo.remove(0);
+            }
+            self.redo.clear();
+        }
+
+        self.last_edit = Some(kind);
+        self.modified = true;
+    }
+
+    fn undo(&mut self) {
+        match self.undo.pop() {
+            Some(snapshot) => {
+                self.redo.push(self.snapshot());
+                self.restore(snapshot);
+                self.message = "[ Undid the last change ]".into();
+            }
+            None => self.message = "[ Nothing to undo ]".into(),
+        }
+    }
+
+    fn redo(&mut self) {
+        match self.redo.pop() {
+            Some(snapshot) => {
+                self.undo.push(self.snapshot());
+                self.restore(snapshot);
+                self.message = "[ Redid the last change ]".into();
+            }
+            None => self.message = "[ Nothing to redo ]".into(),
+        }
+    }
+
+    fn insert(&mut self, c: char) {
+        self.record(EditKind::Insert);
+        let at = byte_at(&self.lines[self.row], self.col);
+        self.lines[self.row].insert(at, c);
+        self.col += 1;
+    }
+
+    fn newline(&mut self) {
+        self.record(EditKind::Other);
+        let at = byte_at(&self.lines[self.row], self.col);
+        let rest = self.lines[self.row].split_off(at);
+        self.lines.insert(self.row + 1, rest);
+        self.row += 1;
+        self.col = 0;
+    }
+
+    fn backspace(&mut self) {
+        if self.col > 0 {
+            self.record(EditKind::Delete);
+            self.col -= 1;
+            let at = byte_at(&self.lines[self.row], self.col);
+            self.lines[self.row].remove(at);
+        } else if self.row > 0 {
+            self.record(EditKind::Other);
+            let line = self.lines.remove(self.row);
+            self.row -= 1;
+            self.col = self.line_len();
+            self.lines[self.row].push_str(&line);
+        }
+    }
+
+    fn delete(&mut self) {
+        if self.col < self.line_len() {
+            self.record(EditKind::Delete);
+            let at = byte_at(&self.lines[self.row], self.col);
+            self.lines[self.row].remove(at);
+        } else if self.row + 1 < self.lines.len() {
+            self.record(EditKind::Other);
+            let next = self.lines.remove(self.row + 1);
+            self.lines[self.row].push_str(&next);
+        }
+    }
+
+    fn move_vertically(&mut self, row: usize) {
+        self.row = row.min(self.lines.len() - 1);
+        self.col = column_at(&self.lines[self.row], self.goal);
+    }
+
+    /// Moves to the next match of `query` after the cursor, going on from
+    /// the start of the buffer when there is none below.
+    fn find(&mut self, query: &str) {
+        let needle = query.to_ascii_lowercase();
+        let count = self.lines.len();
+
+        for step in 0..=count {
+            let row = (self.row + step) % count;
+            // lowering ASCII letters only keeps the byte offsets
+            let line = self.lines[row].to_ascii_lowercase();
+            let from = if step == 0 { byte_at(&line, self.col + 1) } else { 0 };
+
+            let Some(found) = line[from..].find(&needle).map(|i| i + from) else {
+                continue;
+            };
+
+            let col = line[..found].chars().count();
+            if step == count && col == self.col {
+                self.message = "[ This is the only occurrence ]".into();
+            } else if self.row + step >= count {
+                self.message = "[ Search Wrapped ]".into();
+            }
+
+            self.row = row;
+            self.col = col;
+            return;
+        }
+
+        self.message = format!("[ \"{}\" not found ]", query);
+    }
+
+    /// Handles a key press, returning what the caller has to do next.
+    fn handle(&mut self, key: KeyEvent) -> Action {
+        // Ctrl and Alt shortcuts are the same with Shift held
+        let name = if (key.ctrl || key.alt) && key.key.chars().count() == 1 {
+            key.key.to_ascii_lowercase()
+        } else {
+            key.key
+        };
+
+        if let Some(prompt) = self.prompt.take() {
+            return self.answer(prompt, &name, key.ctrl, key.alt);
+        }
+
+        self.message.clear();
+        let mut vertical = false;
+
+        match (key.ctrl, key.alt, name.as_str()) {
+            (true, false, "s" | "o") => return Action::Save,
+            (true, false, "x") if self.modified => self.prompt = Some(Prompt::SaveOnExit),
+            (true, false, "x") => return Action::Exit,
+            (true, false, "f" | "w") => self.prompt = Some(Prompt::Search(String::new())),
+            (true, false, "z") => return Action::Suspend,
+            (false, true, "u") => self.undo(),
+            (false, true, "e") => self.redo(),
+            (_, false, "ArrowLeft") => {
+                if self.col > 0 {
+                    self.col -= 1;
+                } else if self.row > 0 {
+                    self.row -= 1;
+                    self.col = self.line_len();
+                }
+                self.last_edit = None;
+            }
+            (_, false, "ArrowRight") => {
+                if self.col < self.line_len() {
+                    self.col += 1;
+                } else if self.row + 1 < self.lines.len() {
+                    self.row += 1;
+                    self.col = 0;
+                }
+                self.last_edit = None;
+            }
+            (_, false, "ArrowUp" | "ArrowDown" | "PageUp" | "PageDown") => {
+                let row = match name.as_str() {
+                    "ArrowUp" => self.row.saturating_sub(1),
+                    "ArrowDown" => self.row + 1,
+                    "PageUp" => self.row.saturating_sub(self.text_rows()),
+                    _ => self.row + self.text_rows(),
+                };
+                self.move_vertically(row);
+                self.last_edit = None;
+                vertical = true;
+            }
+            (ctrl, false, "Home") => {
+                if ctrl {
+                    self.row = 0;
+                }
+                self.col = 0;
+                self.last_edit = None;
+            }
+            (ctrl, false, "End") => {
+                if ctrl {
+                    self.row = self.lines.len() - 1;
+                }
+                self.col = self.line_len();
+                self.last_edit = None;
+            }
+            (false, false, "Backspace") => self.backspace(),
+            (false, false, "Delete") => self.delete(),
+            (false, false, "Enter") => self.newline(),
+            (false, false, "Tab") => self.insert('\t'),
+            (false, false, text) if text.chars().count() == 1 => {
+                if let Some(c) = text.chars().next() {
+                    self.insert(c);
+                }
+            }
+            _ => return Action::None,
+        }
+
+        if !vertical {
+            self.goal = visual_column(&self.lines[self.row], self.col);
+        }
+        Action::None
+    }
+
+    /// Handles a key press while `prompt` is asked.
+    fn answer(&mut self, prompt: Prompt, name: &str, ctrl: bool, alt: bool) -> Action {
+        let cancelled = name == "Escape" || (ctrl && name == "c");
+
+        match prompt {
+            _ if cancelled => self.message = "[ Cancelled ]".into(),
+            Prompt::Search(typed) if name == "Enter" => {
+                let query = if typed.is_empty() { self.last_search.clone() } else { typed };
+
+                if query.is_empty() {
+                    self.message = "[ Cancelled ]".into();
+                } else {
+                    self.find(&query);
+                    self.goal = visual_column(&self.lines[self.row], self.col);
+                    self.last_edit = None;
+                    self.last_search = query;
+                }
+            }
+            Prompt::Search(mut typed) => {
+                if name == "Backspace" {
+                    typed.pop();
+                } else if !ctrl && !alt && name.chars().count() == 1 {
+                    typed.push_str(name);
+                }
+                self.prompt = Some(Prompt::Search(typed));
+            }
+            Prompt::SaveOnExit => match name {
+                "y" | "Y" => return Action::SaveAndExit,
+                "n" | "N" => return Action::Exit,
+                _ => self.prompt = Some(Prompt::SaveOnExit),
+            },
+        }
+
+        Action::None
+    }
+
+    /// Records that `contents` has been written.
+    fn saved(&mut self, contents: &str) {
+        let count = contents.lines().count();
+        self.message = format!("[ Wrote {} line{} ]", count, if count == 1 { "" } else { "s" });
+        self.modified = false;
+
+        // undoing or redoing now moves away from what was saved
+        for snapshot in self.undo.iter_mut().chain(self.redo.iter_mut()) {
+            snapshot.modified = true;
+        }
+    }
+
+    /// Writes the buffer to `file_path`, reporting the outcome on the
+    /// status line.
+    async fn save(&mut self, ctx: &CommandContext<'_>, file_path: &str) -> bool {
+        let contents = self.contents();
+
+        match ctx.kernel().await.fs.write_file(file_path, contents.as_bytes(), false).await {
+            Ok(_) => {
+                self.saved(&contents);
+                true
+            }
+            Err(err) => {
+                self.message = format!("[ Error writing {}: {} ]", self.name, err);
+                false
+            }
+        }
+    }
+
+    /// Keeps the cursor on screen.
+    fn scroll(&mut self) {
+        let rows = self.text_rows();
+        if self.row < self.top {
+            self.top = self.row;
+        } else if self.row >= self.top + rows {
+            self.top = self.row + 1 - rows;
+        }
+
+        let width = self.columns.saturating_sub(self.gutter()).max(1);
+        let column = visual_column(&self.lines[self.row], self.col);
+        if column < self.left {
+            self.left = column;
+        } else if column >= self.left + width {
+            self.left = column + 1 - width;
+        }
+    }
+
+    /// Draws the screen, returning it with the offset of the caret in
+    /// UTF-16 code units, as the console counts them.
+    fn render(&mut self) -> (String, u32) {
+        self.scroll();
+
+        let gutter = self.gutter();
+        let width = self.columns.saturating_sub(gutter);
+        let mut screen = vec![title_bar(&self.name, self.modified, self.columns)];
+
+        for number in self.top..self.top + self.text_rows() {
+            match self.lines.get(number) {
+                Some(line) => screen.push(format!(
+                    "{:>digits$} {}",
+                    number + 1,
+                    visible(line, self.left, width),
+                    digits = gutter - 1
+                )),
+                None => screen.push(String::new()),
+            }
+        }
+
+        let status = match &self.prompt {
+            Some(Prompt::Search(typed)) if self.last_search.is_empty() => format!("Search: {}", typed),
+            Some(Prompt::Search(typed)) => format!("Search [{}]: {}", self.last_search, typed),
+            Some(Prompt::SaveOnExit) => "Save modified buffer? (y)es, (n)o, ^C cancel ".into(),
+            None => self.message.clone(),
+        };
+        screen.push(status.chars().take(self.columns).collect());
+        screen.push(SHORTCUTS.chars().take(self.columns).collect());
+
+        let (caret_row, caret_col) = match self.prompt {
+            Some(_) => (screen.len() - 2, self.columns),
+            None => {
+                let column = visual_column(&self.lines[self.row], self.col);
+                (1 + self.row - self.top, gutter + column - self.left)
+            }
+        };
+
+        let before: usize = screen[..caret_row].iter().map(|line| line.encode_utf16().count() + 1).sum();
+        let within: usize = screen[caret_row].chars().take(caret_col).map(char::len_utf16).sum();
+
+        (screen.join("\n"), (before + within) as u32)
+    }
+}
+
+/// The console as the shell left it, put back when the editor is done
+/// with it.
+struct SavedConsole {
+    text: String,
+    wrap: String,
+}
+
+fn take_over(console: &HtmlTextAreaElement) -> SavedConsole {
+    let saved = SavedConsole { text: console.value(), wrap: console.wrap() };
+
+    console.set_wrap("off");
+    set_raw_mode(true);
+    saved
+}
+
+fn give_back(console: &HtmlTextAreaElement, saved: &SavedConsole) {
+    set_raw_mode(false);
+    console.set_wrap(&saved.wrap);
+    console.set_value(&saved.text);
+    console.set_scroll_top(console.scroll_height());
+}
+
+fn draw(console: &HtmlTextAreaElement, editor: &mut Editor) {
+    let (screen, caret) = editor.render();
+
+    console.set_value(&screen);
+    console.set_scroll_top(0);
+    let _ = console.set_selection_range(caret, caret);
+}
+
+/// Gives the console back to the shell while the job is stopped. With
+/// `forward_key` the Ctrl-Z that asked for it is passed on to the shell,
+/// which only sees keys outside of raw mode.
+async fn suspend(
+    ctx: &CommandContext<'_>,
+    console: &HtmlTextAreaElement,
+    saved: SavedConsole,
+    forward_key: bool,
+) -> SavedConsole {
+    give_back(console, &saved);
+
+    if forward_key {
+        push_key(KeyEvent { key: "z".into(), ctrl: true, alt: false });
+
+        let mut waited = 0;
+        while !ctx.shell.job.as_ref().is_some_and(|job| job.is_stopped()) && waited < SUSPEND_TIMEOUT_MS {
+            sleep(POLL_MS).await;
+            waited += POLL_MS;
+        }
+    }
+
+    ctx.shell.checkpoint().await;
+    take_over(console)
+}
+
+impl Command for EditCommand {
+    fn name(&self) -> &'static str {
+        "edit"
+    }
+
+    fn usage(&self) -> clap::Command {
+        EditArgs::command()
+    }
+
+    fn run<'a>(&'a self, ctx: &'a mut CommandContext) -> LocalBoxFuture<'a, i32> {
+        async move {
+            let args = match parse_args::<EditArgs>(ctx).await {
+                Ok(args) => args,
+                Err(status) => return status,
+            };
+
+            let console = match (ctx.stdin(), ctx.stdout()) {
+                (FileDescriptor::Console(console), FileDescriptor::Console(_)) => console.clone(),
+                _ => {
+                    ctx.eprint("edit: standard input and output must be the console\n").await;
+                    return 1;
+                }
+            };
+
+            let file_path = path::resolve(&ctx.cwd, &args.file);
+            let mut editor = match ctx.kernel().await.fs.read_file(&file_path).await {
+                Ok(data) => Editor::new(args.file.clone(), &String::from_utf8_lossy(&data)),
+                Err(SimpleFSError::NotFound) => {
+                    let mut editor = Editor::new(args.file.clone(), "");
+                    editor.message = "[ New File ]".into();
+                    editor
+                }
+                Err(SimpleFSError::NotAFile) => {
+                    ctx.eprint(&format!("edit: {}: Is a directory\n", args.file)).await;
+                    return 1;
+                }
+                Err(err) => {
+                    ctx.eprint(&format!("edit: {}: {}\n", args.file, err)).await;
+                    return 1;
+                }
+            };
+
+            let mut saved = take_over(&console);
+            editor.resize(&console);
+            draw(&console, &mut editor);
+
+            loop {
+                let Some(key) = pop_raw_key() else {
+                    sleep(POLL_MS).await;
+
+                    // stopped some other way than with Ctrl-Z
+                    let stopped = ctx.shell.job.as_ref().is_some_and(|job| job.is_stopped());
+                    if stopped {
+                        saved = suspend(ctx, &console, saved, false).await;
+                    }
+
+                    if editor.resize(&console) || stopped {
+                        draw(&console, &mut editor);
+                    }
+                    continue;
+                };
+
+                match editor.handle(key) {
+                    Action::None => {}
+                    Action::Save => {
+                        editor.save(ctx, &file_path).await;
+                    }
+                    Action::SaveAndExit => {
+                        if editor.save(ctx, &file_path).await {
+                            break;
+                        }
+                    }
+                    Action::Exit => break,
+                    Action::Suspend => {
+                        saved = suspend(ctx, &console, saved, true).await;
+                        editor.resize(&console);
+                    }
+                }
+
+                draw(&console, &mut editor);
+            }
+
+            give_back(&console, &saved);
+            0
+        }
+        .boxed_local()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn press(editor: &mut Editor, key: &str) -> Action {
+        editor.handle(KeyEvent { key: key.into(), ctrl: false, alt: false })
+    }
+
+    fn ctrl(editor: &mut Editor, key: &str) -> Action {
+        editor.handle(KeyEvent { key: key.into(), ctrl: true, alt: false })
+    }
+
+    fn alt(editor: &mut Editor, key: &str) -> Action {
+        editor.handle(KeyEvent { key: key.into(), ctrl: false, alt: true })
+    }
+
+    fn type_text(editor: &mut Editor, text: &str) {
+        for c in text.chars() {
+            press(editor, &c.to_string());
+        }
+    }
+
+    fn cursor(editor: &Editor) -> (usize, usize) {
+        (editor.row, editor.col)
+    }
+
+    #[test]
+    fn arrows_go_on_to_the_next_and_previous_lines() {
+        let mut editor = Editor::new("f".into(), "ab\ncd\n");
+
+        press(&mut editor, "ArrowLeft");
+        assert_eq!(cursor(&editor), (0, 0));
+        press(&mut editor, "End");
+        press(&mut editor, "ArrowRight");
+        assert_eq!(cursor(&editor), (1, 0));
+        press(&mut editor, "ArrowLeft");
+        assert_eq!(cursor(&editor), (0, 2));
+
+        ctrl(&mut editor, "End");
+        assert_eq!(cursor(&editor), (1, 2));
+        press(&mut editor, "ArrowRight");
+        assert_eq!(cursor(&editor), (1, 2));
+        press(&mut editor, "Home");
+        assert_eq!(cursor(&editor), (1, 0));
+        ctrl(&mut editor, "Home");
+        assert_eq!(cursor(&editor), (0, 0));
+    }
+
+    #[test]
+    fn moving_up_and_down_keeps_the_column_through_shorter_lines() {
+        let mut editor = Editor::new("f".into(), "abcdef\nab\n\tx\nabcdefghij\n");
+
+        press(&mut editor, "End");
+        press(&mut editor, "ArrowDown");
+        assert_eq!(cursor(&editor), (1, 2));
+        // the tab takes the screen columns up to 8
+        press(&mut editor, "ArrowDown");
+        assert_eq!(cursor(&editor), (2, 0));
+        press(&mut editor, "ArrowDown");
+        assert_eq!(cursor(&editor), (3, 6));
+        press(&mut editor, "ArrowDown");
+        assert_eq!(cursor(&editor), (3, 6));
+
+        press(&mut editor, "PageUp");
+        assert_eq!(cursor(&editor), (0, 6));
+        press(&mut editor, "PageDown");
+        assert_eq!(cursor(&editor), (3, 6));
+    }
+
+    #[test]
+    fn enter_splits_lines_and_deleting_at_their_ends_joins_them() {
+        let mut editor = Editor::new("f".into(), "héllo\n");
+
+        press(&mut editor, "ArrowRight");
+        press(&mut editor, "ArrowRight");
+        press(&mut editor, "Enter");
+        assert_eq!(editor.lines, ["hé", "llo"]);
+        assert_eq!(cursor(&editor), (1, 0));
+
+        press(&mut editor, "Backspace");
+        assert_eq!(editor.lines, ["héllo"]);
+        assert_eq!(cursor(&editor), (0, 2));
+
+        press(&mut editor, "Enter");
+        press(&mut editor, "ArrowLeft");
+        press(&mut editor, "Delete");
+        assert_eq!(editor.lines, ["héllo"]);
+        assert_eq!(cursor(&editor), (0, 2));
+
+        press(&mut editor, "Backspace");
+        press(&mut editor, "Delete");
+        assert_eq!(editor.lines, ["hlo"]);
+        assert_eq!(cursor(&editor), (0, 1));
+    }
+
+    #[test]
+    fn deleting_past_the_ends_of_the_buffer_does_nothing() {
+        let mut editor = Editor::new("f".into(), "a\n");
+
+        press(&mut editor, "Backspace");
+        press(&mut editor, "End");
+        press(&mut editor, "Delete");
+
+        assert_eq!(editor.lines, ["a"]);
+        assert!(!editor.modified);
+    }
+
+    #[test]
+    fn a_word_typed_is_undone_at_once() {
+        let mut editor = Editor::new("f".into(), "");
+
+        type_text(&mut editor, "ab");
+        press(&mut editor, "Enter");
+        type_text(&mut editor, "cd");
+        assert_eq!(editor.lines, ["ab", "cd"]);
+
+        alt(&mut editor, "u");
+        assert_eq!(editor.lines, ["ab", ""]);
+        alt(&mut editor, "U");
+        assert_eq!(editor.lines, ["ab"]);
+        alt(&mut editor, "u");
+        assert_eq!(editor.lines, [""]);
+        assert!(!editor.modified);
+
+        alt(&mut editor, "u");
+        assert_eq!(editor.message, "[ Nothing to undo ]");
+        alt(&mut editor, "e");
+        alt(&mut editor, "e");
+        assert_eq!(editor.lines, ["ab", ""]);
+        assert_eq!(cursor(&editor), (1, 0));
+    }
+
+    #[test]
+    fn contents_end_every_line_with_a_newline() {
+        assert_eq!(Editor::new("f".into(), "a\nb\n").contents(), "a\nb\n");
+        assert_eq!(Editor::new("f".into(), "a\nb").contents(), "a\nb\n");
+        assert_eq!(Editor::new("f".into(), "a\n\n").contents(), "a\n\n");
+        // a lone empty line is an empty file
+        assert_eq!(Editor::new("f".into(), "").contents(), "");
+        assert_eq!(Editor::new("f".into(), "\n").contents(), "");
+    }
+
+    #[test]
+    fn saving_clears_the_modified_flag_until_the_next_change() {
+        let mut editor = Editor::new("f".into(), "a\n");
+
+        type_text(&mut editor, "b");
+        assert!(matches!(ctrl(&mut editor, "s"), Action::Save));
+
+        let contents = editor.contents();
+        editor.saved(&contents);
+        assert_eq!(contents, "ba\n");
+        assert_eq!(editor.message, "[ Wrote 1 line ]");
+        assert!(!editor.modified);
+        assert!(matches!(ctrl(&mut editor, "x"), Action::Exit));
+
+        alt(&mut editor, "u");
+        assert!(editor.modified);
+        // back to what was saved
+        alt(&mut editor, "e");
+        assert!(!editor.modified);
+    }
+
+    #[test]
+    fn exiting_with_changes_asks_whether_to_save() {
+        let mut editor = Editor::new("f".into(), "");
+        type_text(&mut editor, "a");
+
+        assert!(matches!(ctrl(&mut editor, "X"), Action::None));
+        assert!(matches!(press(&mut editor, "q"), Action::None));
+        assert!(matches!(press(&mut editor, "y"), Action::SaveAndExit));
+
+        ctrl(&mut editor, "x");
+        assert!(matches!(press(&mut editor, "Escape"), Action::None));
+        assert_eq!(editor.message, "[ Cancelled ]");
+        ctrl(&mut editor, "x");
+        assert!(matches!(press(&mut editor, "n"), Action::Exit));
+    }
+
+    #[test]
+    fn searches_ignore_case_and_wrap_around() {
+        let mut editor = Editor::new("f".into(), "one\nTwo two\nthree\n");
+
+        ctrl(&mut editor, "f");
+        type_text(&mut editor, "tw");
+        press(&mut editor, "Enter");
+        assert_eq!(cursor(&editor), (1, 0));
+
+        ctrl(&mut editor, "f");
+        press(&mut editor, "Enter");
+        assert_eq!(cursor(&editor), (1, 4));
+
+        ctrl(&mut editor, "f");
+        press(&mut editor, "Enter");
+        assert_eq!(cursor(&editor), (1, 0));
+        assert_eq!(editor.message, "[ Search Wrapped ]");
+
+        ctrl(&mut editor, "f");
+        type_text(&mut editor, "four");
+        press(&mut editor, "Enter");
+        assert_eq!(cursor(&editor), (1, 0));
+        assert_eq!(editor.message, "[ \"four\" not found ]");
+    }
+}
@@ -0,0 +1,43 @@
+use clap::{CommandFactory, Parser};
+use futures::{future::LocalBoxFuture, FutureExt};
+
+use crate::core::shell::command::{context::CommandContext, parse_args, Command};
+
+#[derive(Parser, Debug)]
+#[command(name = "env", about = "rOS command to print the environment", version = "0.1.0")]
+pub struct EnvArgs {}
+
+pub struct EnvCommand;
+
+register!(EnvCommand);
+
+impl Command for EnvCommand {
+    fn name(&self) -> &'static str {
+        "env"
+    }
+
+    fn usage(&self) -> clap::Command {
+        EnvArgs::command()
+    }
+
+    fn run<'a>(&'a self, ctx: &'a mut CommandContext) -> LocalBoxFuture<'a, i32> {
+        async move {
+            if let Err(status) = parse_args::<EnvArgs>(ctx).await {
+                return status;
+            }
+
+            let listing: String = {
+                let kernel = ctx.kernel().await;
+                kernel
+                    .envs
+                    .get(&ctx.pid)
+                    .map(|env| env.iter().map(|(name, value, _)| format!("{}={}\n", name, value)).collect())
+                    .unwrap_or_default()
+            };
+
+            ctx.print(&listing).await;
+            0
+        }
+        .boxed_local()
+    }
+}
@@ -1,27 +1,52 @@
-use crate::{core::shell::command::ShellCommand, kernel::Kernel};
+use clap::{CommandFactory, Parser};
+use futures::{future::LocalBoxFuture, FutureExt};
+
+use crate::core::shell::command::{context::CommandContext, parse_args, Command};
+
+#[derive(Parser, Debug)]
+#[command(name = "exists", about = "rOS command to tell whether a file or folder exists", version = "0.1.0")]
+pub struct ExistsArgs {
+    /// Path to look up
+    entry: String,
+}
 
 pub struct ExistsCommand;
 
-impl ShellCommand for ExistsCommand {
-    async fn execute(k: &mut Kernel, cmd: &str) -> String {
-        let args: Vec<&str> = cmd.split_whitespace().skip(1).collect();
-        
-        if args.is_empty() {
-            return "exists: missing operand".into();
-        }
-        let dir_name = args[0];
-
-        let exists = k.fs.exists(dir_name).await;
-
-        if let Err(err) = exists {
-            return format!("exists: error checking existence of '{}': {:?}", dir_name, err);
-        } else {
-            let exists = exists.unwrap();
-            if exists {
-                return format!("Entry '{}' exists.", dir_name)
-            } else {
-                return format!("Entry '{}' does not exist.", dir_name);
+register!(ExistsCommand);
+
+impl Command for ExistsCommand {
+    fn name(&self) -> &'static str {
+        "exists"
+    }
+
+    fn usage(&self) -> clap::Command {
+        ExistsArgs::command()
+    }
+
+    fn run<'a>(&'a self, ctx: &'a mut CommandContext) -> LocalBoxFuture<'a, i32> {
+        async move {
+            let dir_name = match parse_args::<ExistsArgs>(ctx).await {
+                Ok(args) => args.entry,
+                Err(status) => return status,
+            };
+
+            let exists = ctx.kernel().await.fs.exists(&dir_name).await;
+
+            match exists {
+                Err(err) => {
+                    ctx.eprint(&format!("exists: error checking existence of '{}': {:?}\n", dir_name, err)).await;
+                    1
+                }
+                Ok(true) => {
+                    ctx.print(&format!("Entry '{}' exists.\n", dir_name)).await;
+                    0
+                }
+                Ok(false) => {
+                    ctx.print(&format!("Entry '{}' does not exist.\n", dir_name)).await;
+                    1
+                }
             }
         }
+        .boxed_local()
     }
 }
@@ -0,0 +1,101 @@
+use clap::{CommandFactory, Parser};
+use futures::{future::LocalBoxFuture, FutureExt};
+
+use crate::{
+    core::shell::{
+        command::{context::CommandContext, parse_args, Command},
+    },
+    kernel::env::is_valid_name,
+};
+
+#[derive(Parser, Debug)]
+#[command(name = "export", about = "rOS command to export shell variables to child processes", version = "0.1.0")]
+pub struct ExportArgs {
+    /// `NAME=value` to set and export a variable, `NAME` to export an existing one
+    variables: Vec<String>,
+}
+
+pub struct ExportCommand;
+
+register!(ExportCommand);
+
+/// Quotes `value` so that it can be read back by the shell.
+pub fn quote_value(value: &str) -> String {
+    let mut quoted = String::from("\"");
+
+    for c in value.chars() {
+        if matches!(c, '"' | '\\' | '$') {
+            quoted.push('\\');
+        }
+        quoted.push(c);
+    }
+
+    quoted.push('"');
+    quoted
+}
+
+impl Command for ExportCommand {
+    fn name(&self) -> &'static str {
+        "export"
+    }
+
+    fn usage(&self) -> clap::Command {
+        ExportArgs::command()
+    }
+
+    fn run<'a>(&'a self, ctx: &'a mut CommandContext) -> LocalBoxFuture<'a, i32> {
+        async move {
+            let args = match parse_args::<ExportArgs>(ctx).await {
+                Ok(args) => args,
+                Err(status) => return status,
+            };
+
+            if args.variables.is_empty() {
+                let listing: String = {
+                    let kernel = ctx.kernel().await;
+                    kernel
+                        .envs
+                        .get(&ctx.shell.pid)
+                        .map(|env| {
+                            env.iter()
+                                .filter(|(_, _, exported)| *exported)
+                                .map(|(name, value, _)| format!("export {}={}\n", name, quote_value(value)))
+                                .collect()
+                        })
+                        .unwrap_or_default()
+                };
+
+                ctx.print(&listing).await;
+                return 0;
+            }
+
+            let mut status = 0;
+
+            for variable in &args.variables {
+                let (name, value) = match variable.split_once('=') {
+                    Some((name, value)) => (name, Some(value)),
+                    None => (variable.as_str(), None),
+                };
+
+                if !is_valid_name(name) {
+                    ctx.eprint(&format!("export: '{}': not a valid identifier\n", variable)).await;
+                    status = 1;
+                    continue;
+                }
+
+                let mut kernel = ctx.kernel().await;
+                let env = kernel.envs.entry(ctx.shell.pid).or_default();
+
+                match value {
+                    Some(value) => env.set_exported(name, value),
+                    // exporting a variable that is not set yet exports it empty
+                    None if !env.export(name) => env.set_exported(name, ""),
+                    None => {}
+                }
+            }
+
+            status
+        }
+        .boxed_local()
+    }
+}
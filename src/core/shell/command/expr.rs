@@ -0,0 +1,106 @@
+//! Expressions spelled out as command arguments, as `test` and `find` take
+//! them: terms joined by `!`, `-a` and `-o`, from the tightest to the
+//! loosest, and grouped with `(` and `)`.
+//!
+//! The operators and their precedence are shared, what a term is and how
+//! it is read is up to each command through `Grammar`.
+
+/// A parsed expression over the terms `T` of a command.
+#[derive(Debug)]
+pub enum Expr<T> {
+    Not(Box<Expr<T>>),
+    And(Box<Expr<T>>, Box<Expr<T>>),
+    Or(Box<Expr<T>>, Box<Expr<T>>),
+    Term(T),
+}
+
+impl<T> Expr<T> {
+    /// Whether `f` holds for any term of the expression.
+    pub fn any(&self, f: &impl Fn(&T) -> bool) -> bool {
+        match self {
+            Expr::Not(expr) => expr.any(f),
+            Expr::And(left, right) | Expr::Or(left, right) => left.any(f) || right.any(f),
+            Expr::Term(term) => f(term),
+        }
+    }
+}
+
+/// The spelling of the operators of a command and how it reads its terms.
+pub trait Grammar: Sized {
+    type Term;
+
+    const NOT: &'static [&'static str];
+    const AND: &'static [&'static str];
+    const OR: &'static [&'static str];
+    /// Whether two terms in a row are joined by an implied `-a`.
+    const IMPLIED_AND: bool;
+
+    /// Reads a term, or a group between parentheses by going back to
+    /// `ExprParser::or`.
+    fn primary(parser: &mut ExprParser<'_, Self>) -> Result<Expr<Self::Term>, String>;
+}
+
+pub struct ExprParser<'a, G> {
+    pub args: &'a [String],
+    pub pos: usize,
+    /// State of the command kept while parsing, e.g. the options of `find`.
+    pub grammar: G,
+}
+
+impl<'a, G: Grammar> ExprParser<'a, G> {
+    pub fn new(args: &'a [String], grammar: G) -> Self {
+        Self { args, pos: 0, grammar }
+    }
+
+    pub fn peek(&self) -> Option<&'a str> {
+        self.args.get(self.pos).map(String::as_str)
+    }
+
+    /// Number of arguments not read yet.
+    pub fn remaining(&self) -> usize {
+        self.args.len() - self.pos
+    }
+
+    pub fn next(&mut self) -> Option<String> {
+        let arg = self.args.get(self.pos).cloned()?;
+        self.pos += 1;
+        Some(arg)
+    }
+
+    /// Parses an expression, stopping at the first argument that cannot
+    /// continue it: callers check that `pos` reached the end.
+    pub fn or(&mut self) -> Result<Expr<G::Term>, String> {
+        let mut expr = self.and()?;
+
+        while self.peek().is_some_and(|arg| G::OR.contains(&arg)) {
+            self.pos += 1;
+            expr = Expr::Or(Box::new(expr), Box::new(self.and()?));
+        }
+
+        Ok(expr)
+    }
+
+    fn and(&mut self) -> Result<Expr<G::Term>, String> {
+        let mut expr = self.not()?;
+
+        loop {
+            match self.peek() {
+                Some(arg) if G::AND.contains(&arg) => self.pos += 1,
+                Some(arg) if G::IMPLIED_AND && arg != ")" && !G::OR.contains(&arg) => {}
+                _ => return Ok(expr),
+            }
+            expr = Expr::And(Box::new(expr), Box::new(self.not()?));
+        }
+    }
+
+    fn not(&mut self) -> Result<Expr<G::Term>, String> {
+        // a `!` ending the arguments is left to `primary`, `test` takes it
+        // as a string
+        if self.peek().is_some_and(|arg| G::NOT.contains(&arg)) && self.remaining() > 1 {
+            self.pos += 1;
+            return Ok(Expr::Not(Box::new(self.not()?)));
+        }
+
+        G::primary(self)
+    }
+}
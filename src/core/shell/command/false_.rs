@@ -0,0 +1,37 @@
+use clap::{CommandFactory, Parser};
+use futures::{future::LocalBoxFuture, FutureExt};
+
+use crate::core::shell::command::{context::CommandContext, Command};
+
+/// Only describes the usage of `false`, which ignores its arguments.
+#[derive(Parser, Debug)]
+#[command(
+    name = "false",
+    about = "rOS command to do nothing, unsuccessfully",
+    version = "0.1.0",
+    disable_help_flag = true,
+    disable_version_flag = true
+)]
+pub struct FalseArgs {
+    /// Ignored
+    #[arg(allow_hyphen_values = true)]
+    args: Vec<String>,
+}
+
+pub struct FalseCommand;
+
+register!(FalseCommand);
+
+impl Command for FalseCommand {
+    fn name(&self) -> &'static str {
+        "false"
+    }
+
+    fn usage(&self) -> clap::Command {
+        FalseArgs::command()
+    }
+
+    fn run<'a>(&'a self, _ctx: &'a mut CommandContext) -> LocalBoxFuture<'a, i32> {
+        async { 1 }.boxed_local()
+    }
+}
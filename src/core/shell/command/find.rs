@@ -0,0 +1,554 @@
+use std::{cmp::Ordering, collections::HashMap};
+
+use clap::{CommandFactory, Parser};
+use futures::{future::LocalBoxFuture, FutureExt};
+
+use crate::{
+    core::shell::{
+        command::{
+            context::CommandContext,
+            expr::{Expr, ExprParser, Grammar},
+            Command,
+        },
+        exec, pattern,
+    },
+    vfs::{
+        entry::{FSEntryKind, FSEntryTrait},
+        path,
+    },
+};
+
+const MS_PER_DAY: i64 = 24 * 60 * 60 * 1000;
+
+/// Usage shown by `--help` and `man find`. The expression is read by
+/// `FindGrammar` instead, clap would take its primaries for options.
+#[derive(Parser, Debug)]
+#[command(
+    name = "find",
+    about = "rOS command to search for files in a folder hierarchy",
+    long_about = "Walks the folders given, the working folder by default, and evaluates the expression \
+                  for every entry below them. Entries are read with one scan of the key range of each \
+                  folder rather than one lookup per entry.\n\n\
+                  Tests: -name GLOB, -type f|d|l, -mtime [+-]DAYS, -size [+-]N[cwbkMG], -newer FILE. \
+                  Actions: -print, -exec COMMAND {} ; and -delete, which removes the entries it is \
+                  given after their contents. Without an action, matching entries are printed.\n\n\
+                  Tests and actions are joined with ! (or -not), -a (or -and, implied between two \
+                  terms), -o (or -or) and grouped with ( ). -maxdepth N limits how deep the walk goes.",
+    version = "0.1.0",
+    override_usage = "find [PATH]... [EXPRESSION]"
+)]
+pub struct FindArgs {
+    /// Folders to search, followed by the expression
+    #[arg(allow_hyphen_values = true)]
+    args: Vec<String>,
+}
+
+pub struct FindCommand;
+
+register!(FindCommand);
+
+/// `+n`, `-n` or `n`: more than, less than or exactly `n`.
+#[derive(Debug, Clone, Copy)]
+struct Bound {
+    ordering: Ordering,
+    value: i64,
+}
+
+impl Bound {
+    fn parse(arg: &str) -> Option<Self> {
+        let (ordering, number) = match arg.as_bytes().first() {
+            Some(b'+') => (Ordering::Greater, &arg[1..]),
+            Some(b'-') => (Ordering::Less, &arg[1..]),
+            _ => (Ordering::Equal, arg),
+        };
+
+        number.parse().ok().map(|value| Self { ordering, value })
+    }
+
+    fn matches(&self, value: i64) -> bool {
+        value.cmp(&self.value) == self.ordering
+    }
+}
+
+/// A test or an action of `find`.
+#[derive(Debug)]
+enum Predicate {
+    /// Options such as `-maxdepth`, which hold wherever they are.
+    True,
+    Name(String),
+    Type(char),
+    /// Age in whole days.
+    Mtime(Bound),
+    /// Size in units of the given number of bytes, rounded up.
+    Size(Bound, i64),
+    /// Modified after the given time.
+    Newer(i64),
+    Print,
+    /// Command words, `{}` standing for the path of the entry.
+    Exec(Vec<String>),
+    Delete,
+}
+
+impl Predicate {
+    fn is_action(&self) -> bool {
+        matches!(self, Predicate::Print | Predicate::Exec(_) | Predicate::Delete)
+    }
+}
+
+struct FindGrammar<'a> {
+    /// Modification times of the files named by `-newer`, looked up ahead.
+    newer: &'a HashMap<String, i64>,
+    max_depth: Option<usize>,
+}
+
+/// The argument of `primary`.
+fn value(parser: &mut ExprParser<'_, FindGrammar>, primary: &str) -> Result<String, String> {
+    parser.next().ok_or_else(|| format!("missing argument to `{}'", primary))
+}
+
+impl Grammar for FindGrammar<'_> {
+    type Term = Predicate;
+
+    const NOT: &'static [&'static str] = &["!", "-not"];
+    const AND: &'static [&'static str] = &["-a", "-and"];
+    const OR: &'static [&'static str] = &["-o", "-or"];
+    const IMPLIED_AND: bool = true;
+
+    fn primary(parser: &mut ExprParser<'_, Self>) -> Result<Expr<Predicate>, String> {
+        let primary = value(parser, "expression")?;
+
+        let predicate = match primary.as_str() {
+            "(" => {
+                let expr = parser.or()?;
+                return match value(parser, "(")?.as_str() {
+                    ")" => Ok(expr),
+                    other => Err(format!("expected `)', found `{}'", other)),
+                };
+            }
+            "-name" => Predicate::Name(value(parser, &primary)?),
+            "-type" => match value(parser, &primary)?.as_str() {
+                kind @ ("f" | "d" | "l") => Predicate::Type(kind.chars().next().unwrap_or('f')),
+                kind => return Err(format!("Unknown argument to -type: {}", kind)),
+            },
+            "-mtime" => {
+                let arg = value(parser, &primary)?;
+                Bound::parse(&arg)
+                    .map(Predicate::Mtime)
+                    .ok_or_else(|| format!("invalid argument `{}' to `-mtime'", arg))?
+            }
+            "-size" => {
+                let arg = value(parser, &primary)?;
+                let (number, unit) = match arg.char_indices().last() {
+                    Some((i, c)) if c.is_ascii_alphabetic() => (&arg[..i], c),
+                    _ => (arg.as_str(), 'b'),
+                };
+
+                let unit = match unit {
+                    'c' => 1,
+                    'w' => 2,
+                    'b' => 512,
+                    'k' => 1024,
+                    'M' => 1024 * 1024,
+                    'G' => 1024 * 1024 * 1024,
+                    _ => return Err(format!("invalid -size type `{}'", unit)),
+                };
+
+                Bound::parse(number)
+                    .map(|bound| Predicate::Size(bound, unit))
+                    .ok_or_else(|| format!("invalid argument `{}' to `-size'", arg))?
+            }
+            "-newer" => {
+                let file = value(parser, &primary)?;
+                parser
+                    .grammar
+                    .newer
+                    .get(&file)
+                    .map(|time| Predicate::Newer(*time))
+                    .ok_or_else(|| format!("'{}': No such file or directory", file))?
+            }
+            "-maxdepth" => {
+                let arg = value(parser, &primary)?;
+                let max_depth = arg.parse().map_err(|_| format!("invalid argument `{}' to `-maxdepth'", arg))?;
+                parser.grammar.max_depth = Some(max_depth);
+                Predicate::True
+            }
+            "-print" => Predicate::Print,
+            "-delete" => Predicate::Delete,
+            "-exec" => {
+                let mut words = Vec::new();
+
+                loop {
+                    match value(parser, &primary)? {
+                        word if word == ";" => break,
+                        word => words.push(word),
+                    }
+                }
+
+                if words.is_empty() {
+                    return Err(format!("missing argument to `{}'", primary));
+                }
+                Predicate::Exec(words)
+            }
+            other => return Err(format!("unknown predicate `{}'", other)),
+        };
+
+        Ok(Expr::Term(predicate))
+    }
+}
+
+/// Whole days from `time` to `now`, both in milliseconds.
+fn age_in_days(now: i64, time: i64) -> i64 {
+    (now - time).div_euclid(MS_PER_DAY)
+}
+
+/// `size` in units of `unit` bytes, the last one counted even when partly
+/// used.
+fn size_in_units(size: usize, unit: i64) -> i64 {
+    (size as i64 + unit - 1) / unit
+}
+
+/// An entry met during the walk.
+struct Node {
+    path: String,
+    /// The path as printed: below the folder the walk started from, as it
+    /// was typed.
+    display: String,
+    kind: char,
+    size: usize,
+    modified_at: i64,
+}
+
+impl Node {
+    fn name(&self) -> &str {
+        match path::file_name(&self.display) {
+            "" => &self.display,
+            name => name,
+        }
+    }
+}
+
+struct Walk {
+    now: i64,
+    failed: bool,
+    /// Set once stdout has no reader anymore.
+    closed: bool,
+}
+
+/// Evaluates `expr` for `node`, running its actions.
+fn eval<'a, 'c>(
+    expr: &'a Expr<Predicate>,
+    node: &'a Node,
+    ctx: &'a mut CommandContext<'c>,
+    walk: &'a mut Walk,
+) -> LocalBoxFuture<'a, bool> {
+    async move {
+        match expr {
+            Expr::Not(expr) => !eval(expr, node, ctx, walk).await,
+            Expr::And(left, right) => eval(left, node, ctx, walk).await && eval(right, node, ctx, walk).await,
+            Expr::Or(left, right) => eval(left, node, ctx, walk).await || eval(right, node, ctx, walk).await,
+            Expr::Term(Predicate::True) => true,
+            Expr::Term(Predicate::Name(glob)) => pattern::matches(glob, node.name()),
+            Expr::Term(Predicate::Type(kind)) => node.kind == *kind,
+            Expr::Term(Predicate::Mtime(bound)) => bound.matches(age_in_days(walk.now, node.modified_at)),
+            Expr::Term(Predicate::Size(bound, unit)) => bound.matches(size_in_units(node.size, *unit)),
+            Expr::Term(Predicate::Newer(time)) => node.modified_at > *time,
+            Expr::Term(Predicate::Print) => {
+                if ctx.write(&format!("{}\n", node.display)).await.is_err() {
+                    walk.closed = true;
+                }
+                true
+            }
+            Expr::Term(Predicate::Exec(words)) => {
+                let args = words.iter().map(|word| word.replace("{}", &node.display)).collect();
+                let (cwd, fds) = (ctx.cwd.clone(), ctx.fds.clone());
+
+                exec::run_args(ctx.shell, &[], args, cwd, fds).await == 0
+            }
+            Expr::Term(Predicate::Delete) => {
+                let removed = ctx.kernel().await.fs.remove_entry(&node.path).await;
+
+                match removed {
+                    Ok(()) => true,
+                    Err(err) => {
+                        ctx.eprint(&format!("find: cannot delete '{}': {}\n", node.display, err)).await;
+                        walk.failed = true;
+                        false
+                    }
+                }
+            }
+        }
+    }
+    .boxed_local()
+}
+
+/// The components of an absolute path, only one (empty) for the root.
+fn components(path: &str) -> impl Iterator<Item = &str> {
+    path.trim_end_matches('/').split('/')
+}
+
+/// Orders paths so that every folder comes after its contents.
+pub fn contents_first(a: &str, b: &str) -> Ordering {
+    let (a, b): (Vec<&str>, Vec<&str>) = (components(a).collect(), components(b).collect());
+
+    match a.iter().zip(&b).map(|(a, b)| a.cmp(b)).find(|ordering| ordering.is_ne()) {
+        Some(ordering) => ordering,
+        None => b.len().cmp(&a.len()),
+    }
+}
+
+impl Command for FindCommand {
+    fn name(&self) -> &'static str {
+        "find"
+    }
+
+    fn usage(&self) -> clap::Command {
+        FindArgs::command()
+    }
+
+    fn run<'a>(&'a self, ctx: &'a mut CommandContext) -> LocalBoxFuture<'a, i32> {
+        async move {
+            let args = ctx.args[1..].to_vec();
+
+            if args.first().is_some_and(|arg| arg == "--help") {
+                ctx.print(&FindArgs::command().render_long_help().to_string()).await;
+                return 0;
+            }
+
+            let split = args
+                .iter()
+                .position(|arg| (arg.starts_with('-') && arg.len() > 1) || arg == "!" || arg == "(")
+                .unwrap_or(args.len());
+            let (paths, expression) = args.split_at(split);
+
+            // reference times are looked up ahead so that parsing stays synchronous
+            let mut newer = HashMap::new();
+            for pair in expression.windows(2).filter(|pair| pair[0] == "-newer") {
+                let file_path = path::resolve(&ctx.cwd, &pair[1]);
+
+                if let Ok(Some(entry)) = ctx.kernel().await.fs.get_entry(&file_path).await {
+                    newer.insert(pair[1].clone(), entry.entry.modified_at());
+                }
+            }
+
+            let grammar = FindGrammar { newer: &newer, max_depth: None };
+            let mut parser = ExprParser::new(expression, grammar);
+            let expr = if expression.is_empty() { Ok(Expr::Term(Predicate::Print)) } else { parser.or() };
+
+            let expr = match expr {
+                Ok(_) if parser.pos < expression.len() => Err(format!("unexpected `{}'", expression[parser.pos])),
+                Ok(expr) if !expr.any(&Predicate::is_action) => {
+                    Ok(Expr::And(Box::new(expr), Box::new(Expr::Term(Predicate::Print))))
+                }
+                result => result,
+            };
+
+            let expr = match expr {
+                Ok(expr) => expr,
+                Err(err) => {
+                    ctx.eprint(&format!("find: {}\n", err)).await;
+                    return 1;
+                }
+            };
+
+            let paths = if paths.is_empty() { vec![".".to_string()] } else { paths.to_vec() };
+            let max_depth = parser.grammar.max_depth;
+            let mut walk = Walk { now: chrono::Utc::now().timestamp_millis(), failed: false, closed: false };
+
+            for start in paths {
+                let start_path = path::resolve(&ctx.cwd, &start);
+                let entries = ctx.kernel().await.fs.read_tree(&start_path).await;
+
+                let entries = match entries {
+                    Ok(entries) if !entries.is_empty() || start_path == "/" => entries,
+                    Ok(_) => {
+                        ctx.eprint(&format!("find: '{}': No such file or directory\n", start)).await;
+                        walk.failed = true;
+                        continue;
+                    }
+                    Err(err) => {
+                        ctx.eprint(&format!("find: '{}': {}\n", start, err)).await;
+                        walk.failed = true;
+                        continue;
+                    }
+                };
+
+                let mut nodes: Vec<(usize, Node)> = entries
+                    .into_iter()
+                    .filter_map(|entry| {
+                        let relative = entry.abs_path.strip_prefix(&start_path)?.trim_start_matches('/').to_string();
+                        let depth = if relative.is_empty() { 0 } else { relative.split('/').count() };
+
+                        let (kind, size) = match &entry.entry {
+                            FSEntryKind::File(file) => ('f', file.size as usize),
+                            FSEntryKind::Folder(_) => ('d', 0),
+                            FSEntryKind::Link(_) => ('l', 0),
+                        };
+                        let display = if relative.is_empty() { start.clone() } else { path::join(&start, &relative) };
+
+                        let node =
+                            Node { display, kind, size, modified_at: entry.entry.modified_at(), path: entry.abs_path };
+                        Some((depth, node))
+                    })
+                    .filter(|(depth, _)| max_depth.is_none_or(|max| *depth <= max))
+                    .collect();
+
+                // the root folder has no entry of its own
+                if start_path == "/" {
+                    let root = Node { path: "/".into(), display: start.clone(), kind: 'd', size: 0, modified_at: 0 };
+                    nodes.push((0, root));
+                }
+
+                if expr.any(&|predicate| matches!(predicate, Predicate::Delete)) {
+                    nodes.sort_by(|(_, a), (_, b)| contents_first(&a.path, &b.path));
+                } else {
+                    nodes.sort_by(|(_, a), (_, b)| components(&a.path).cmp(components(&b.path)));
+                }
+
+                for (_, node) in &nodes {
+                    ctx.shell.checkpoint().await;
+
+                    eval(&expr, node, ctx, &mut walk).await;
+                    if walk.closed {
+                        return 1;
+                    }
+                }
+            }
+
+            if walk.failed { 1 } else { 0 }
+        }
+        .boxed_local()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `expression` parsed, written with prefix operators, e.g.
+    /// `(or name:a (and type:f print))`, with the `-maxdepth` found.
+    fn parse(expression: &str) -> Result<(String, Option<usize>), String> {
+        fn show(expr: &Expr<Predicate>) -> String {
+            match expr {
+                Expr::Not(expr) => format!("(not {})", show(expr)),
+                Expr::And(left, right) => format!("(and {} {})", show(left), show(right)),
+                Expr::Or(left, right) => format!("(or {} {})", show(left), show(right)),
+                Expr::Term(Predicate::Name(glob)) => format!("name:{}", glob),
+                Expr::Term(Predicate::Type(kind)) => format!("type:{}", kind),
+                Expr::Term(Predicate::Exec(words)) => format!("exec:{}", words.join(" ")),
+                Expr::Term(predicate) => format!("{:?}", predicate).to_lowercase(),
+            }
+        }
+
+        let args: Vec<String> = expression.split_whitespace().map(String::from).collect();
+        let newer = HashMap::from([("ref".to_string(), 5)]);
+        let mut parser = ExprParser::new(&args, FindGrammar { newer: &newer, max_depth: None });
+
+        let expr = parser.or()?;
+        if parser.pos < args.len() {
+            return Err(format!("unexpected `{}'", args[parser.pos]));
+        }
+        Ok((show(&expr), parser.grammar.max_depth))
+    }
+
+    fn shown(expression: &str) -> String {
+        parse(expression).unwrap().0
+    }
+
+    #[test]
+    fn not_binds_tighter_than_and_which_binds_tighter_than_or() {
+        assert_eq!(shown("-name a -o -type f -print"), "(or name:a (and type:f print))");
+        assert_eq!(shown("! -name a -type d"), "(and (not name:a) type:d)");
+        assert_eq!(shown("-not -not -name a"), "(not (not name:a))");
+        assert_eq!(shown("-name a -and -type f -or -name b"), "(or (and name:a type:f) name:b)");
+        assert_eq!(shown("-name a -o -name b -o -name c"), "(or (or name:a name:b) name:c)");
+    }
+
+    #[test]
+    fn parentheses_group_terms() {
+        assert_eq!(shown("( -name a -o -name b ) -delete"), "(and (or name:a name:b) delete)");
+        assert_eq!(shown("! ( -type f )"), "(not type:f)");
+        assert_eq!(parse("( -name a").unwrap_err(), "missing argument to `('");
+        assert_eq!(parse("( -name a -print").unwrap_err(), "missing argument to `('");
+        assert_eq!(parse("-name a )").unwrap_err(), "unexpected `)'");
+    }
+
+    #[test]
+    fn primaries_take_their_arguments() {
+        assert_eq!(shown("-exec rm {} ; -print"), "(and exec:rm {} print)");
+        assert_eq!(parse("-maxdepth 2 -name a"), Ok(("(and true name:a)".to_string(), Some(2))));
+        assert_eq!(shown("-newer ref"), "newer(5)");
+
+        assert_eq!(parse("-exec rm {}").unwrap_err(), "missing argument to `-exec'");
+        assert_eq!(parse("-exec ;").unwrap_err(), "missing argument to `-exec'");
+        assert_eq!(parse("-name").unwrap_err(), "missing argument to `-name'");
+        assert_eq!(parse("-type x").unwrap_err(), "Unknown argument to -type: x");
+        assert_eq!(parse("-newer nowhere").unwrap_err(), "'nowhere': No such file or directory");
+        assert_eq!(parse("-size 3q").unwrap_err(), "invalid -size type `q'");
+        assert_eq!(parse("-mtime x").unwrap_err(), "invalid argument `x' to `-mtime'");
+        assert_eq!(parse("-depth").unwrap_err(), "unknown predicate `-depth'");
+    }
+
+    #[test]
+    fn bounds_compare_more_less_or_exactly() {
+        let more = Bound::parse("+3").unwrap();
+        let less = Bound::parse("-3").unwrap();
+        let exactly = Bound::parse("3").unwrap();
+
+        assert_eq!([2, 3, 4].map(|value| more.matches(value)), [false, false, true]);
+        assert_eq!([2, 3, 4].map(|value| less.matches(value)), [true, false, false]);
+        assert_eq!([2, 3, 4].map(|value| exactly.matches(value)), [false, true, false]);
+        assert!(Bound::parse("+").is_none());
+        assert!(Bound::parse("3k").is_none());
+    }
+
+    /// Whether `-size ARG` or `-mtime ARG`, `primary`, holds for `value`:
+    /// a size in bytes or a modification time a number of hours ago.
+    fn holds(primary: &str, arg: &str, value: i64) -> bool {
+        let args = [primary.to_string(), arg.to_string()];
+        let newer = HashMap::new();
+        let expr = ExprParser::new(&args, FindGrammar { newer: &newer, max_depth: None }).or();
+
+        match expr {
+            Ok(Expr::Term(Predicate::Size(bound, unit))) => bound.matches(size_in_units(value as usize, unit)),
+            Ok(Expr::Term(Predicate::Mtime(bound))) => {
+                let now = 100 * MS_PER_DAY;
+                bound.matches(age_in_days(now, now - value * MS_PER_DAY / 24))
+            }
+            _ => panic!("not a bound: {} {}", primary, arg),
+        }
+    }
+
+    #[test]
+    fn sizes_are_rounded_up_to_their_unit() {
+        assert!(holds("-size", "+1k", 1025));
+        assert!(!holds("-size", "+1k", 1024));
+        assert!(holds("-size", "-2k", 1024));
+        assert!(!holds("-size", "-2k", 1025));
+        assert!(holds("-size", "1", 1));
+        assert!(holds("-size", "1", 512));
+        assert!(!holds("-size", "1", 513));
+        assert!(holds("-size", "10c", 10));
+        assert!(holds("-size", "+0", 1));
+        assert!(!holds("-size", "+0", 0));
+    }
+
+    #[test]
+    fn ages_are_counted_in_whole_days() {
+        assert!(holds("-mtime", "0", 23));
+        assert!(holds("-mtime", "-1", 23));
+        assert!(!holds("-mtime", "-1", 24));
+        assert!(holds("-mtime", "1", 47));
+        assert!(holds("-mtime", "+1", 48));
+        assert!(!holds("-mtime", "+1", 47));
+        // modified in the future, e.g. with a clock set back
+        assert!(holds("-mtime", "-0", -1));
+    }
+
+    #[test]
+    fn contents_come_before_their_folder() {
+        let mut paths = vec!["/a", "/a/b", "/a/b/c", "/a-z", "/a/d", "/"];
+        paths.sort_by(|a, b| contents_first(a, b));
+
+        assert_eq!(paths, ["/a/b/c", "/a/b", "/a/d", "/a", "/a-z", "/"]);
+    }
+}
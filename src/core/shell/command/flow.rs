@@ -0,0 +1,161 @@
+use std::sync::atomic::Ordering;
+
+use clap::{CommandFactory, Parser};
+use futures::{future::LocalBoxFuture, FutureExt};
+
+use crate::core::shell::{
+    command::{context::CommandContext, parse_args, Command},
+    exec::Flow,
+};
+
+#[derive(Parser, Debug)]
+#[command(name = "break", about = "rOS command to leave the enclosing loops", version = "0.1.0")]
+pub struct BreakArgs {
+    /// Number of loops to leave
+    #[arg(default_value_t = 1)]
+    n: usize,
+}
+
+pub struct BreakCommand;
+
+#[derive(Parser, Debug)]
+#[command(name = "continue", about = "rOS command to start the next iteration of a loop", version = "0.1.0")]
+pub struct ContinueArgs {
+    /// Resume the n-th enclosing loop
+    #[arg(default_value_t = 1)]
+    n: usize,
+}
+
+pub struct ContinueCommand;
+
+#[derive(Parser, Debug)]
+#[command(name = "return", about = "rOS command to return from a function or sourced script", version = "0.1.0")]
+pub struct ReturnArgs {
+    /// Exit status, the one of the last command by default
+    #[arg(allow_negative_numbers = true)]
+    status: Option<i32>,
+}
+
+pub struct ReturnCommand;
+
+#[derive(Parser, Debug)]
+#[command(name = "exit", about = "rOS command to leave a script", version = "0.1.0")]
+pub struct ExitArgs {
+    /// Exit status, the one of the last command by default
+    #[arg(allow_negative_numbers = true)]
+    status: Option<i32>,
+}
+
+pub struct ExitCommand;
+
+register!(BreakCommand, ContinueCommand, ReturnCommand, ExitCommand);
+
+/// Requests `flow` for `break` and `continue`, which leave at most as many
+/// loops as are running.
+async fn loop_flow(ctx: &mut CommandContext<'_>, n: usize, flow: fn(usize) -> Flow) -> i32 {
+    if n == 0 {
+        ctx.eprint(&format!("{}: {}: loop count out of range\n", ctx.args[0], n)).await;
+        return 1;
+    }
+
+    if ctx.shell.loop_depth == 0 {
+        ctx.eprint(&format!("{}: only meaningful in a `for', `while', or `until' loop\n", ctx.args[0])).await;
+        return 0;
+    }
+
+    ctx.shell.flow = Some(flow(n.min(ctx.shell.loop_depth)));
+    0
+}
+
+impl Command for BreakCommand {
+    fn name(&self) -> &'static str {
+        "break"
+    }
+
+    fn usage(&self) -> clap::Command {
+        BreakArgs::command()
+    }
+
+    fn run<'a>(&'a self, ctx: &'a mut CommandContext) -> LocalBoxFuture<'a, i32> {
+        async move {
+            match parse_args::<BreakArgs>(ctx).await {
+                Ok(args) => loop_flow(ctx, args.n, Flow::Break).await,
+                Err(status) => status,
+            }
+        }
+        .boxed_local()
+    }
+}
+
+impl Command for ContinueCommand {
+    fn name(&self) -> &'static str {
+        "continue"
+    }
+
+    fn usage(&self) -> clap::Command {
+        ContinueArgs::command()
+    }
+
+    fn run<'a>(&'a self, ctx: &'a mut CommandContext) -> LocalBoxFuture<'a, i32> {
+        async move {
+            match parse_args::<ContinueArgs>(ctx).await {
+                Ok(args) => loop_flow(ctx, args.n, Flow::Continue).await,
+                Err(status) => status,
+            }
+        }
+        .boxed_local()
+    }
+}
+
+impl Command for ReturnCommand {
+    fn name(&self) -> &'static str {
+        "return"
+    }
+
+    fn usage(&self) -> clap::Command {
+        ReturnArgs::command()
+    }
+
+    fn run<'a>(&'a self, ctx: &'a mut CommandContext) -> LocalBoxFuture<'a, i32> {
+        async move {
+            let args = match parse_args::<ReturnArgs>(ctx).await {
+                Ok(args) => args,
+                Err(status) => return status,
+            };
+
+            if ctx.shell.call_depth == 0 {
+                ctx.eprint("return: can only `return' from a function or sourced script\n").await;
+                return 1;
+            }
+
+            let status = args.status.unwrap_or_else(|| ctx.shell.last_status.load(Ordering::Relaxed));
+            ctx.shell.flow = Some(Flow::Return(status));
+            status
+        }
+        .boxed_local()
+    }
+}
+
+impl Command for ExitCommand {
+    fn name(&self) -> &'static str {
+        "exit"
+    }
+
+    fn usage(&self) -> clap::Command {
+        ExitArgs::command()
+    }
+
+    fn run<'a>(&'a self, ctx: &'a mut CommandContext) -> LocalBoxFuture<'a, i32> {
+        async move {
+            let args = match parse_args::<ExitArgs>(ctx).await {
+                Ok(args) => args,
+                Err(status) => return status,
+            };
+
+            let status = args.status.unwrap_or_else(|| ctx.shell.last_status.load(Ordering::Relaxed));
+            ctx.shell.flow = Some(Flow::Exit(status));
+            status
+        }
+        .boxed_local()
+    }
+}
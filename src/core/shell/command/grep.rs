@@ -0,0 +1,337 @@
+use clap::{CommandFactory, Parser, ValueEnum};
+use futures::{future::LocalBoxFuture, FutureExt};
+use regex::Regex;
+
+use crate::{
+    core::shell::{
+        command::{context::CommandContext, parse_args, Command},
+        regexp,
+    },
+    vfs::{
+        entry::{FSEntry, FSEntryKind},
+        path,
+    },
+};
+
+/// Name shown for lines read from stdin.
+const STDIN_NAME: &str = "(standard input)";
+
+/// Escapes around a highlighted match: bold red, as GNU grep.
+const MATCH_COLOR: &str = "\x1b[01;31m";
+const RESET_COLOR: &str = "\x1b[m";
+
+/// Exit status when a file could not be read or the pattern is invalid.
+const STATUS_ERROR: i32 = 2;
+
+/// When to highlight matches. There is no `auto`: the console is a plain
+/// textarea that cannot render ANSI escapes, so nothing grep writes to
+/// would ever ask for them.
+#[derive(ValueEnum, Clone, Copy, Debug)]
+pub enum ColorWhen {
+    /// Wrap matches in ANSI escapes, e.g. for a file read outside R-OS
+    Always,
+    Never,
+}
+
+#[derive(Parser, Debug)]
+#[command(name = "grep", about = "rOS command to print the lines matching a pattern", version = "0.1.0")]
+pub struct GrepArgs {
+    /// Regular expression, basic syntax unless -E is given
+    pattern: String,
+
+    /// Files to search, `-` or nothing reads stdin (the working folder with -r)
+    files: Vec<String>,
+
+    /// Use extended regular expressions
+    #[arg(short = 'E', long)]
+    extended_regexp: bool,
+
+    /// Ignore case distinctions
+    #[arg(short, long)]
+    ignore_case: bool,
+
+    /// Select the lines that do not match
+    #[arg(short = 'v', long)]
+    invert_match: bool,
+
+    /// Prefix each line with its line number
+    #[arg(short = 'n', long)]
+    line_number: bool,
+
+    /// Only print the number of selected lines of each file
+    #[arg(short, long)]
+    count: bool,
+
+    /// Only print the names of the files with selected lines
+    #[arg(short = 'l', long)]
+    files_with_matches: bool,
+
+    /// Search folders and everything below them
+    #[arg(short, long)]
+    recursive: bool,
+
+    /// Print NUM lines of context after each selected line
+    #[arg(short = 'A', long, value_name = "NUM")]
+    after_context: Option<usize>,
+
+    /// Print NUM lines of context before each selected line
+    #[arg(short = 'B', long, value_name = "NUM")]
+    before_context: Option<usize>,
+
+    /// Print NUM lines of context around each selected line
+    #[arg(short = 'C', long, value_name = "NUM")]
+    context: Option<usize>,
+
+    /// Highlight the matches with ANSI escapes, which the console shows as is
+    #[arg(long, value_name = "WHEN", default_value = "never")]
+    color: ColorWhen,
+}
+
+pub struct GrepCommand;
+
+register!(GrepCommand);
+
+/// A file to search, with the name its lines are reported under.
+struct Source {
+    name: String,
+    /// `None` for stdin.
+    path: Option<String>,
+}
+
+/// How lines are selected and printed, shared by all files.
+struct Printer<'r> {
+    regex: &'r Regex,
+    invert: bool,
+    line_number: bool,
+    show_names: bool,
+    before: usize,
+    after: usize,
+    color: bool,
+    /// Whether a group of lines has been printed yet, to separate the next
+    /// one with `--` when printing context.
+    printed_group: bool,
+}
+
+impl Printer<'_> {
+    fn is_selected(&self, line: &str) -> bool {
+        self.regex.is_match(line) != self.invert
+    }
+
+    /// Formats the selected lines of `text` with their context.
+    fn format(&mut self, name: &str, text: &str) -> String {
+        let lines: Vec<&str> = text.lines().collect();
+        let mut output = String::new();
+        let mut last_printed: Option<usize> = None;
+        let mut after_left = 0;
+
+        for (i, line) in lines.iter().enumerate() {
+            if self.is_selected(line) {
+                let first = i.saturating_sub(self.before).max(last_printed.map_or(0, |last| last + 1));
+
+                if last_printed.is_none_or(|last| first > last + 1) {
+                    if self.printed_group && (self.before > 0 || self.after > 0) {
+                        output.push_str("--\n");
+                    }
+                    self.printed_group = true;
+                }
+
+                for (j, context) in lines.iter().enumerate().take(i).skip(first) {
+                    output.push_str(&self.line(name, j, context, '-'));
+                }
+                output.push_str(&self.line(name, i, line, ':'));
+
+                last_printed = Some(i);
+                after_left = self.after;
+            } else if after_left > 0 {
+                output.push_str(&self.line(name, i, line, '-'));
+                last_printed = Some(i);
+                after_left -= 1;
+            }
+        }
+
+        output
+    }
+
+    /// One output line: `name:12:text` for a selected line, `name-12-text`
+    /// for context.
+    fn line(&self, name: &str, index: usize, line: &str, separator: char) -> String {
+        let mut output = String::new();
+
+        if self.show_names {
+            output.push_str(&format!("{}{}", name, separator));
+        }
+        if self.line_number {
+            output.push_str(&format!("{}{}", index + 1, separator));
+        }
+
+        if self.color && separator == ':' && !self.invert {
+            output.push_str(&self.highlight(line));
+        } else {
+            output.push_str(line);
+        }
+
+        output.push('\n');
+        output
+    }
+
+    fn highlight(&self, line: &str) -> String {
+        let mut output = String::new();
+        let mut end = 0;
+
+        for found in self.regex.find_iter(line).filter(|found| !found.is_empty()) {
+            output.push_str(&line[end..found.start()]);
+            output.push_str(&format!("{}{}{}", MATCH_COLOR, found.as_str(), RESET_COLOR));
+            end = found.end();
+        }
+
+        output.push_str(&line[end..]);
+        output
+    }
+}
+
+impl Command for GrepCommand {
+    fn name(&self) -> &'static str {
+        "grep"
+    }
+
+    fn usage(&self) -> clap::Command {
+        GrepArgs::command()
+    }
+
+    fn run<'a>(&'a self, ctx: &'a mut CommandContext) -> LocalBoxFuture<'a, i32> {
+        async move {
+            let args = match parse_args::<GrepArgs>(ctx).await {
+                Ok(args) => args,
+                Err(status) => return status,
+            };
+
+            let regex = match regexp::compile(&args.pattern, args.extended_regexp, args.ignore_case) {
+                Ok(regex) => regex,
+                Err(_) => {
+                    ctx.eprint(&format!("grep: invalid regular expression '{}'\n", args.pattern)).await;
+                    return STATUS_ERROR;
+                }
+            };
+
+            let mut status = 1;
+            let mut failed = false;
+
+            let files = match (args.files.is_empty(), args.recursive) {
+                (false, _) => args.files.clone(),
+                (true, true) => vec![".".to_string()],
+                (true, false) => vec!["-".to_string()],
+            };
+
+            let mut sources = Vec::new();
+            let mut searched_folder = false;
+
+            for file in &files {
+                if file == "-" {
+                    sources.push(Source { name: STDIN_NAME.to_string(), path: None });
+                    continue;
+                }
+
+                let file_path = path::resolve(&ctx.cwd, file);
+                let entry = ctx.kernel().await.fs.get_entry(&file_path).await;
+
+                match entry {
+                    Ok(Some(FSEntry { entry: FSEntryKind::Folder(_), .. })) if args.recursive => {
+                        searched_folder = true;
+                        // files found below the working folder are named relative to it
+                        let prefix = if args.files.is_empty() { None } else { Some(file.as_str()) };
+                        sources.extend(files_below(ctx, &file_path, prefix).await);
+                    }
+                    Ok(Some(FSEntry { entry: FSEntryKind::Folder(_), .. })) => {
+                        ctx.eprint(&format!("grep: {}: Is a directory\n", file)).await;
+                        failed = true;
+                    }
+                    Ok(Some(_)) => sources.push(Source { name: file.clone(), path: Some(file_path) }),
+                    Ok(None) => {
+                        ctx.eprint(&format!("grep: {}: No such file or directory\n", file)).await;
+                        failed = true;
+                    }
+                    Err(err) => {
+                        ctx.eprint(&format!("grep: {}: {}\n", file, err)).await;
+                        failed = true;
+                    }
+                }
+            }
+
+            let context = args.context.unwrap_or(0);
+            let mut printer = Printer {
+                regex: &regex,
+                invert: args.invert_match,
+                line_number: args.line_number,
+                show_names: files.len() > 1 || searched_folder,
+                before: args.before_context.unwrap_or(context),
+                after: args.after_context.unwrap_or(context),
+                color: matches!(args.color, ColorWhen::Always),
+                printed_group: false,
+            };
+
+            for source in sources {
+                ctx.shell.checkpoint().await;
+
+                let data = match &source.path {
+                    None => ctx.stdin().read_to_end().await.map_err(|e| e.to_string()),
+                    Some(file_path) => ctx.kernel().await.fs.read_file(file_path).await.map_err(|e| e.to_string()),
+                };
+
+                let text = match data {
+                    Ok(data) => String::from_utf8_lossy(&data).into_owned(),
+                    Err(err) => {
+                        ctx.eprint(&format!("grep: {}: {}\n", source.name, err)).await;
+                        failed = true;
+                        continue;
+                    }
+                };
+
+                let selected = text.lines().filter(|line| printer.is_selected(line)).count();
+                if selected > 0 {
+                    status = 0;
+                }
+
+                let output = if args.files_with_matches {
+                    if selected > 0 { format!("{}\n", source.name) } else { String::new() }
+                } else if args.count {
+                    if printer.show_names {
+                        format!("{}:{}\n", source.name, selected)
+                    } else {
+                        format!("{}\n", selected)
+                    }
+                } else {
+                    printer.format(&source.name, &text)
+                };
+
+                if ctx.write(&output).await.is_err() {
+                    break;
+                }
+            }
+
+            if failed { STATUS_ERROR } else { status }
+        }
+        .boxed_local()
+    }
+}
+
+/// The files below `folder`, sorted by path. They are named by their path
+/// relative to `folder`, after `prefix` when there is one.
+async fn files_below(ctx: &CommandContext<'_>, folder: &str, prefix: Option<&str>) -> Vec<Source> {
+    let folder_prefix = format!("{}/", folder.trim_end_matches('/'));
+    let mut entries = ctx.kernel().await.fs.read_folder(&folder_prefix).await.unwrap_or_default();
+    entries.sort_by(|a, b| a.abs_path.cmp(&b.abs_path));
+
+    entries
+        .into_iter()
+        .filter(|entry| matches!(entry.entry, FSEntryKind::File(_)))
+        .filter_map(|entry| {
+            let relative = entry.abs_path.strip_prefix(&folder_prefix)?.to_string();
+            let name = match prefix {
+                Some(prefix) => path::join(prefix, &relative),
+                None => relative,
+            };
+
+            Some(Source { name, path: Some(entry.abs_path) })
+        })
+        .collect()
+}
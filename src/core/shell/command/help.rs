@@ -0,0 +1,58 @@
+use clap::{CommandFactory, Parser};
+use futures::{future::LocalBoxFuture, FutureExt};
+
+use crate::core::shell::command::{self, context::CommandContext, parse_args, Command};
+
+#[derive(Parser, Debug)]
+#[command(name = "help", about = "rOS command to list the builtins or describe one of them", version = "0.1.0")]
+pub struct HelpArgs {
+    /// Builtin to describe
+    name: Option<String>,
+}
+
+pub struct HelpCommand;
+
+register!(HelpCommand);
+
+impl Command for HelpCommand {
+    fn name(&self) -> &'static str {
+        "help"
+    }
+
+    fn usage(&self) -> clap::Command {
+        HelpArgs::command()
+    }
+
+    fn run<'a>(&'a self, ctx: &'a mut CommandContext) -> LocalBoxFuture<'a, i32> {
+        async move {
+            let args = match parse_args::<HelpArgs>(ctx).await {
+                Ok(args) => args,
+                Err(status) => return status,
+            };
+
+            if let Some(name) = args.name {
+                return match command::find(&name) {
+                    Some(builtin) => {
+                        ctx.print(&format!("{}\n", builtin.usage().render_long_help())).await;
+                        0
+                    }
+                    None => {
+                        ctx.eprint(&format!("help: no help topics match '{}'\n", name)).await;
+                        1
+                    }
+                };
+            }
+
+            let mut output = String::from("Shell builtins, 'help NAME' describes one of them:\n\n");
+
+            for builtin in command::commands() {
+                let names = std::iter::once(builtin.name()).chain(builtin.aliases().iter().copied());
+                output.push_str(&format!("  {:<12} {}\n", names.collect::<Vec<_>>().join(", "), builtin.help()));
+            }
+
+            ctx.print(&output).await;
+            0
+        }
+        .boxed_local()
+    }
+}
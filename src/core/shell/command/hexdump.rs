@@ -0,0 +1,356 @@
+use clap::{CommandFactory, Parser};
+use futures::{future::LocalBoxFuture, FutureExt};
+
+use crate::{
+    core::shell::command::{context::CommandContext, parse_args, Command},
+    vfs::path,
+};
+
+/// Most zero bytes `xxd -r` fills in between the data and the offset of a
+/// line, a larger gap being taken for a corrupt dump.
+const MAX_REVERT_GAP: usize = 1024 * 1024;
+
+#[derive(Parser, Debug)]
+#[command(
+    name = "xxd",
+    about = "rOS command to make a hex dump or turn one back into bytes",
+    long_about = "Dumps INFILE, or stdin, as offsets, groups of hex bytes and the matching text. With -r a \
+                  dump, plain with -p, is turned back into the bytes it shows. The result is written to \
+                  OUTFILE when given, stdout otherwise.",
+    version = "0.1.0"
+)]
+pub struct XxdArgs {
+    /// File to dump, `-` or nothing reads stdin
+    infile: Option<String>,
+
+    /// File to write to instead of stdout
+    outfile: Option<String>,
+
+    /// Turn a dump back into bytes
+    #[arg(short, long)]
+    revert: bool,
+
+    /// Plain dump: only hex digits, without offsets or text
+    #[arg(short, long)]
+    plain: bool,
+
+    /// Bytes per line, 16 by default and 30 for plain dumps
+    #[arg(short, long, value_name = "COLS", value_parser = parse_number)]
+    cols: Option<usize>,
+
+    /// Bytes per group of hex digits
+    #[arg(short, long, value_name = "BYTES", default_value_t = 2)]
+    groupsize: usize,
+
+    /// Stop after LEN bytes
+    #[arg(short, long, value_name = "LEN", value_parser = parse_number)]
+    len: Option<usize>,
+
+    /// Start at byte OFFSET
+    #[arg(short, long, value_name = "OFFSET", value_parser = parse_number)]
+    seek: Option<usize>,
+
+    /// Use uppercase hex digits
+    #[arg(short, long)]
+    uppercase: bool,
+}
+
+#[derive(Parser, Debug)]
+#[command(
+    name = "hexdump",
+    about = "rOS command to show files in hexadecimal",
+    long_about = "Dumps the files, or stdin, as 16-bit words, or with -C as hex bytes next to the matching \
+                  text. Lines repeating the one before are shown as a single *, unless -v is given.",
+    version = "0.1.0"
+)]
+pub struct HexdumpArgs {
+    /// Files to dump one after the other, `-` or nothing reads stdin
+    files: Vec<String>,
+
+    /// Canonical dump: hex bytes and text
+    #[arg(short = 'C', long)]
+    canonical: bool,
+
+    /// Stop after LEN bytes
+    #[arg(short = 'n', long, value_name = "LEN", value_parser = parse_number)]
+    length: Option<usize>,
+
+    /// Start at byte OFFSET
+    #[arg(short, long, value_name = "OFFSET", value_parser = parse_number)]
+    skip: Option<usize>,
+
+    /// Show every line, even when it repeats the one before
+    #[arg(short = 'v', long)]
+    no_squeezing: bool,
+}
+
+pub struct XxdCommand;
+pub struct HexdumpCommand;
+
+register!(XxdCommand, HexdumpCommand);
+
+/// A decimal number, or a hexadecimal one after `0x`.
+fn parse_number(text: &str) -> Result<usize, String> {
+    match text.strip_prefix("0x").or_else(|| text.strip_prefix("0X")) {
+        Some(hex) => usize::from_str_radix(hex, 16),
+        None => text.parse(),
+    }
+    .map_err(|_| format!("invalid number '{}'", text))
+}
+
+/// The bytes of `data` from `start`, at most `len` of them.
+fn window(data: &[u8], start: Option<usize>, len: Option<usize>) -> &[u8] {
+    let data = data.get(start.unwrap_or(0)..).unwrap_or_default();
+    &data[..len.unwrap_or(data.len()).min(data.len())]
+}
+
+/// The text column of a dump, `.` standing for anything not printable.
+fn printable(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| if byte.is_ascii_graphic() || *byte == b' ' { *byte as char } else { '.' }).collect()
+}
+
+fn hex_digits(text: &str) -> Vec<u8> {
+    let digits: Vec<u8> = text.chars().filter_map(|c| c.to_digit(16)).map(|digit| digit as u8).collect();
+    digits.chunks_exact(2).map(|pair| pair[0] << 4 | pair[1]).collect()
+}
+
+fn xxd_dump(data: &[u8], args: &XxdArgs) -> String {
+    let hex = |byte: &u8| if args.uppercase { format!("{:02X}", byte) } else { format!("{:02x}", byte) };
+    let start = args.seek.unwrap_or(0);
+    let data = window(data, args.seek, args.len);
+    let mut output = String::new();
+
+    if args.plain {
+        for line in data.chunks(args.cols.unwrap_or(30).max(1)) {
+            output.push_str(&line.iter().map(hex).collect::<String>());
+            output.push('\n');
+        }
+        return output;
+    }
+
+    let cols = args.cols.unwrap_or(16).max(1);
+    let group = if args.groupsize == 0 { cols } else { args.groupsize };
+
+    for (i, line) in data.chunks(cols).enumerate() {
+        output.push_str(&format!("{:08x}: ", start + i * cols));
+
+        for col in 0..cols {
+            output.push_str(&line.get(col).map_or_else(|| "  ".to_string(), hex));
+            if (col + 1).is_multiple_of(group) {
+                output.push(' ');
+            }
+        }
+        if !cols.is_multiple_of(group) {
+            output.push(' ');
+        }
+
+        output.push(' ');
+        output.push_str(&printable(line));
+        output.push('\n');
+    }
+
+    output
+}
+
+/// Turns a dump back into bytes, each line of a normal dump being written
+/// at its offset.
+fn xxd_revert(dump: &str, plain: bool) -> Result<Vec<u8>, String> {
+    if plain {
+        return Ok(hex_digits(dump));
+    }
+
+    let mut data = Vec::new();
+
+    for (number, line) in dump.lines().enumerate() {
+        let Some((offset, rest)) = line.split_once(':') else {
+            continue;
+        };
+        let Ok(offset) = usize::from_str_radix(offset.trim(), 16) else {
+            continue;
+        };
+
+        // the text column starts after two spaces
+        let rest = rest.strip_prefix(' ').unwrap_or(rest);
+        let hex = rest.split("  ").next().unwrap_or_default();
+        let bytes = hex_digits(hex);
+
+        let end = offset
+            .checked_add(bytes.len())
+            .filter(|_| offset.saturating_sub(data.len()) <= MAX_REVERT_GAP)
+            .ok_or_else(|| format!("line {}: offset {:x} is too far past the data", number + 1, offset))?;
+
+        if data.len() < end {
+            data.resize(end, 0);
+        }
+        data[offset..end].copy_from_slice(&bytes);
+    }
+
+    Ok(data)
+}
+
+fn hexdump(data: &[u8], args: &HexdumpArgs) -> String {
+    let start = args.skip.unwrap_or(0);
+    let data = window(data, args.skip, args.length);
+    let mut output = String::new();
+    let mut previous: Option<&[u8]> = None;
+    let mut squeezed = false;
+
+    for (i, line) in data.chunks(16).enumerate() {
+        if !args.no_squeezing && previous == Some(line) {
+            if !squeezed {
+                output.push_str("*\n");
+                squeezed = true;
+            }
+            continue;
+        }
+        previous = Some(line);
+        squeezed = false;
+
+        let offset = start + i * 16;
+
+        if args.canonical {
+            output.push_str(&format!("{:08x}  ", offset));
+            for col in 0..16 {
+                output.push_str(&line.get(col).map_or_else(|| "   ".to_string(), |byte| format!("{:02x} ", byte)));
+                if col == 7 {
+                    output.push(' ');
+                }
+            }
+            output.push_str(&format!(" |{}|\n", printable(line)));
+        } else {
+            output.push_str(&format!("{:07x}", offset));
+            for word in line.chunks(2) {
+                let word = u16::from(word[0]) | u16::from(word.get(1).copied().unwrap_or(0)) << 8;
+                output.push_str(&format!(" {:04x}", word));
+            }
+            output.push('\n');
+        }
+    }
+
+    if !data.is_empty() {
+        let end = start + data.len();
+        output.push_str(&if args.canonical { format!("{:08x}\n", end) } else { format!("{:07x}\n", end) });
+    }
+
+    output
+}
+
+impl Command for XxdCommand {
+    fn name(&self) -> &'static str {
+        "xxd"
+    }
+
+    fn usage(&self) -> clap::Command {
+        XxdArgs::command()
+    }
+
+    fn run<'a>(&'a self, ctx: &'a mut CommandContext) -> LocalBoxFuture<'a, i32> {
+        async move {
+            let args = match parse_args::<XxdArgs>(ctx).await {
+                Ok(args) => args,
+                Err(status) => return status,
+            };
+
+            let files: Vec<String> = args.infile.iter().cloned().collect();
+            let (input, status) = ctx.read_input_bytes("xxd", &files).await;
+            if status != 0 {
+                return status;
+            }
+
+            let output = if args.revert {
+                match xxd_revert(&String::from_utf8_lossy(&input), args.plain) {
+                    Ok(output) => output,
+                    Err(err) => {
+                        ctx.eprint(&format!("xxd: {}\n", err)).await;
+                        return 1;
+                    }
+                }
+            } else {
+                xxd_dump(&input, &args).into_bytes()
+            };
+
+            match &args.outfile {
+                Some(outfile) if outfile != "-" => {
+                    let file_path = path::resolve(&ctx.cwd, outfile);
+                    if let Err(err) = ctx.kernel().await.fs.write_file(&file_path, &output, false).await {
+                        ctx.eprint(&format!("xxd: {}: {}\n", outfile, err)).await;
+                        return 1;
+                    }
+                }
+                _ => {
+                    if ctx.stdout().write(&output).await.is_err() {
+                        return 1;
+                    }
+                }
+            }
+
+            0
+        }
+        .boxed_local()
+    }
+}
+
+impl Command for HexdumpCommand {
+    fn name(&self) -> &'static str {
+        "hexdump"
+    }
+
+    fn usage(&self) -> clap::Command {
+        HexdumpArgs::command()
+    }
+
+    fn run<'a>(&'a self, ctx: &'a mut CommandContext) -> LocalBoxFuture<'a, i32> {
+        async move {
+            let args = match parse_args::<HexdumpArgs>(ctx).await {
+                Ok(args) => args,
+                Err(status) => return status,
+            };
+
+            let (input, status) = ctx.read_input_bytes("hexdump", &args.files).await;
+
+            if ctx.write(&hexdump(&input, &args)).await.is_err() {
+                return 1;
+            }
+
+            status
+        }
+        .boxed_local()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dumps_turn_back_into_their_bytes() {
+        let data: Vec<u8> = (0..=255).chain(b"tail".iter().copied()).collect();
+        let args = XxdArgs::try_parse_from(["xxd"]).unwrap();
+        let plain = XxdArgs::try_parse_from(["xxd", "-p"]).unwrap();
+
+        assert_eq!(xxd_revert(&xxd_dump(&data, &args), false), Ok(data.clone()));
+        assert_eq!(xxd_revert(&xxd_dump(&data, &plain), true), Ok(data));
+    }
+
+    #[test]
+    fn lines_are_written_at_their_offsets() {
+        let dump = "00000004: 4142  AB\nnot a dump line\nzz: 43\n00000000: 6162  ab\n";
+
+        assert_eq!(xxd_revert(dump, false), Ok(b"ab\0\0AB".to_vec()));
+    }
+
+    #[test]
+    fn offsets_far_past_the_data_are_rejected() {
+        assert_eq!(
+            xxd_revert("00000000: 41\nffffffff: 41\n", false),
+            Err("line 2: offset ffffffff is too far past the data".to_string())
+        );
+        assert_eq!(
+            xxd_revert("ffffffffffffffff: 4142\n", false),
+            Err("line 1: offset ffffffffffffffff is too far past the data".to_string())
+        );
+
+        let gap = format!("{:08x}: 41\n", MAX_REVERT_GAP);
+        assert_eq!(xxd_revert(&gap, false).map(|data| data.len()), Ok(MAX_REVERT_GAP + 1));
+    }
+}
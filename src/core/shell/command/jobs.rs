@@ -0,0 +1,192 @@
+use clap::{CommandFactory, Parser};
+use futures::{future::LocalBoxFuture, FutureExt};
+
+use crate::core::shell::{
+    command::{context::CommandContext, parse_args, Command},
+    job::JobState,
+};
+
+#[derive(Parser, Debug)]
+#[command(name = "jobs", about = "rOS command to list the jobs of the shell", version = "0.1.0")]
+pub struct JobsArgs {
+    /// Jobs to list (%n, %+, %- or %prefix), all of them by default
+    jobs: Vec<String>,
+
+    /// Show the pid of each job
+    #[arg(short = 'l')]
+    long: bool,
+}
+
+pub struct JobsCommand;
+
+#[derive(Parser, Debug)]
+#[command(name = "fg", about = "rOS command to resume a job in the foreground", version = "0.1.0")]
+pub struct FgArgs {
+    /// Job to resume (%n, %+, %- or %prefix), the current one by default
+    job: Option<String>,
+}
+
+pub struct FgCommand;
+
+#[derive(Parser, Debug)]
+#[command(name = "bg", about = "rOS command to resume a stopped job in the background", version = "0.1.0")]
+pub struct BgArgs {
+    /// Job to resume (%n, %+, %- or %prefix), the current one by default
+    job: Option<String>,
+}
+
+pub struct BgCommand;
+
+register!(JobsCommand, FgCommand, BgCommand);
+
+impl Command for JobsCommand {
+    fn name(&self) -> &'static str {
+        "jobs"
+    }
+
+    fn usage(&self) -> clap::Command {
+        JobsArgs::command()
+    }
+
+    fn run<'a>(&'a self, ctx: &'a mut CommandContext) -> LocalBoxFuture<'a, i32> {
+        async move {
+            let args = match parse_args::<JobsArgs>(ctx).await {
+                Ok(args) => args,
+                Err(status) => return status,
+            };
+
+            let mut status = 0;
+            let mut output = String::new();
+            let mut errors = String::new();
+
+            {
+                let mut jobs = ctx.shell.jobs.lock().await;
+
+                let ids: Vec<usize> = if args.jobs.is_empty() {
+                    jobs.iter().map(|(id, _)| id).collect()
+                } else {
+                    args.jobs
+                        .iter()
+                        .filter_map(|spec| match jobs.resolve(Some(spec)) {
+                            Ok(id) => Some(id),
+                            Err(err) => {
+                                errors.push_str(&format!("jobs: {}\n", err));
+                                status = 1;
+                                None
+                            }
+                        })
+                        .collect()
+                };
+
+                for id in ids {
+                    output.push_str(&format!("{}\n", jobs.describe(id, args.long)));
+                }
+
+                // exited jobs are reported once, here or before the next prompt
+                if args.jobs.is_empty() {
+                    jobs.take_notices();
+                }
+            }
+
+            ctx.print(&output).await;
+            ctx.eprint(&errors).await;
+            status
+        }
+        .boxed_local()
+    }
+}
+
+impl Command for FgCommand {
+    fn name(&self) -> &'static str {
+        "fg"
+    }
+
+    fn usage(&self) -> clap::Command {
+        FgArgs::command()
+    }
+
+    fn run<'a>(&'a self, ctx: &'a mut CommandContext) -> LocalBoxFuture<'a, i32> {
+        async move {
+            let args = match parse_args::<FgArgs>(ctx).await {
+                Ok(args) => args,
+                Err(status) => return status,
+            };
+
+            if !ctx.shell.interactive {
+                ctx.eprint("fg: no job control\n").await;
+                return 1;
+            }
+
+            let resumed = {
+                let mut jobs = ctx.shell.jobs.lock().await;
+
+                jobs.resolve(args.job.as_deref()).map(|id| match jobs.get(id).map(|job| job.state) {
+                    // exited before it could be brought back, nothing to wait for
+                    Some(JobState::Done(status)) => (jobs.remove(id).map(|job| job.command), Some(status)),
+                    _ => (jobs.bring_to_foreground(id).map(|job| job.command.clone()), None),
+                })
+            };
+
+            match resumed {
+                Ok((command, status)) => {
+                    ctx.print(&format!("{}\n", command.unwrap_or_default())).await;
+                    // otherwise the prompt comes back when the resumed job exits
+                    status.unwrap_or(0)
+                }
+                Err(err) => {
+                    ctx.eprint(&format!("fg: {}\n", err)).await;
+                    1
+                }
+            }
+        }
+        .boxed_local()
+    }
+}
+
+impl Command for BgCommand {
+    fn name(&self) -> &'static str {
+        "bg"
+    }
+
+    fn usage(&self) -> clap::Command {
+        BgArgs::command()
+    }
+
+    fn run<'a>(&'a self, ctx: &'a mut CommandContext) -> LocalBoxFuture<'a, i32> {
+        async move {
+            let args = match parse_args::<BgArgs>(ctx).await {
+                Ok(args) => args,
+                Err(status) => return status,
+            };
+
+            if !ctx.shell.interactive {
+                ctx.eprint("bg: no job control\n").await;
+                return 1;
+            }
+
+            let resumed = {
+                let mut jobs = ctx.shell.jobs.lock().await;
+
+                jobs.resolve(args.job.as_deref()).and_then(|id| match jobs.get(id).map(|job| job.state) {
+                    Some(JobState::Stopped) => {
+                        jobs.resume(id);
+                        Ok(jobs.describe(id, false))
+                    }
+                    _ => Err(format!("job {} already in background", id)),
+                })
+            };
+
+            match resumed {
+                Ok(line) => {
+                    ctx.print(&format!("{}\n", line)).await;
+                    0
+                }
+                Err(err) => {
+                    ctx.eprint(&format!("bg: {}\n", err)).await;
+                    1
+                }
+            }
+        }
+        .boxed_local()
+    }
+}
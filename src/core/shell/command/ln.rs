@@ -0,0 +1,227 @@
+use clap::{CommandFactory, Parser};
+use futures::{future::LocalBoxFuture, FutureExt};
+
+use crate::{
+    core::shell::command::{context::CommandContext, parse_args, Command},
+    vfs::{
+        entry::{FSEntry, FSEntryKind, FSLink},
+        errors::SimpleFSError,
+        path,
+    },
+};
+
+#[derive(Parser, Debug)]
+#[command(
+    name = "ln",
+    about = "rOS command to make links between files",
+    long_about = "Gives TARGET another name, LINK_NAME: both are then the same file, which stays until its \
+                  last name is removed. Given a folder last, or several targets, each target is linked in \
+                  it under its own name, and given a single target, in the current folder. With -s \
+                  symbolic links are made instead, which point to the path TARGET, whatever is there.",
+    version = "0.1.0"
+)]
+pub struct LnArgs {
+    /// TARGET, then LINK_NAME or the folder to make the links in
+    #[arg(required = true, value_name = "PATH")]
+    paths: Vec<String>,
+
+    /// Make symbolic links instead of hard links
+    #[arg(short, long)]
+    symbolic: bool,
+
+    /// Remove the files or links at the names given first
+    #[arg(short, long)]
+    force: bool,
+
+    /// Print the name of each link made
+    #[arg(short, long)]
+    verbose: bool,
+}
+
+pub struct LnCommand;
+
+register!(LnCommand);
+
+/// Checks that `link_path` can become a link to `target`, found at
+/// `target_path`, before anything is changed: the entries there are
+/// `linked` and `existing`. A hard link needs a file or link to name, and
+/// only -f lets one replace what is at `link_path`, neither a folder nor
+/// the target itself.
+fn check_link(
+    target: &str,
+    target_path: &str,
+    linked: Option<&FSEntry>,
+    link_path: &str,
+    existing: Option<&FSEntry>,
+    args: &LnArgs,
+) -> Result<(), String> {
+    if !args.symbolic {
+        match linked {
+            Some(FSEntry { entry: FSEntryKind::Folder(_), .. }) => {
+                return Err(format!("{}: {}", target, SimpleFSError::NotAFile));
+            }
+            Some(_) => {}
+            None => return Err(format!("{}: {}", target, SimpleFSError::NotFound)),
+        }
+    }
+
+    let same_inode = match (linked, existing) {
+        (Some(FSEntry { entry: FSEntryKind::File(a), .. }), Some(FSEntry { entry: FSEntryKind::File(b), .. })) => {
+            a.inode != 0 && a.inode == b.inode
+        }
+        _ => false,
+    };
+
+    match existing {
+        None => Ok(()),
+        Some(FSEntry { entry: FSEntryKind::Folder(_), .. }) => {
+            Err(format!("{}: {}", link_path, SimpleFSError::AlreadyExists))
+        }
+        Some(_) if !args.force => Err(format!("{}: {}", link_path, SimpleFSError::AlreadyExists)),
+        Some(_) if same_inode || path::normalize(target_path) == path::normalize(link_path) => {
+            Err(format!("'{}' and '{}' are the same file", target, link_path))
+        }
+        Some(_) => Ok(()),
+    }
+}
+
+/// Makes `link_path` a link to `target`, which a symbolic link points to
+/// as given, relative or not. What -f replaces goes in the same change as
+/// the link is made in.
+async fn make_link(ctx: &CommandContext<'_>, target: &str, link_path: &str, args: &LnArgs) -> Result<(), String> {
+    // a symbolic link points to its target from the folder it is in
+    let target_path = if args.symbolic {
+        path::resolve(&path::parent(link_path), target)
+    } else {
+        path::resolve(&ctx.cwd, target)
+    };
+    let mut kernel = ctx.kernel().await;
+
+    let failed = |err: SimpleFSError| format!("{}: {}", link_path, err);
+    let existing = kernel.fs.get_entry(link_path).await.map_err(failed)?;
+    let linked = kernel.fs.get_entry(&target_path).await.map_err(failed)?;
+    check_link(target, &target_path, linked.as_ref(), link_path, existing.as_ref(), args)?;
+
+    if args.symbolic {
+        let now = chrono::Utc::now().timestamp_millis();
+        let entry = FSEntry {
+            abs_path: link_path.into(),
+            entry: FSEntryKind::Link(FSLink::new(path::file_name(link_path), target, now)),
+        };
+        // a file or link there is replaced
+        kernel.fs.restore_entry(&entry).await.map_err(failed)
+    } else {
+        kernel.fs.link(&target_path, link_path, args.force).await.map(|_| ()).map_err(failed)
+    }
+}
+
+impl Command for LnCommand {
+    fn name(&self) -> &'static str {
+        "ln"
+    }
+
+    fn usage(&self) -> clap::Command {
+        LnArgs::command()
+    }
+
+    fn run<'a>(&'a self, ctx: &'a mut CommandContext) -> LocalBoxFuture<'a, i32> {
+        async move {
+            let args = match parse_args::<LnArgs>(ctx).await {
+                Ok(args) => args,
+                Err(status) => return status,
+            };
+
+            let (targets, destination) = match args.paths.as_slice() {
+                [target] => (std::slice::from_ref(target), ctx.cwd.clone()),
+                [targets @ .., destination] => (targets, path::resolve(&ctx.cwd, destination)),
+                [] => return 1,
+            };
+
+            let in_folder = destination == "/"
+                || matches!(
+                    ctx.kernel().await.fs.get_entry(&destination).await,
+                    Ok(Some(FSEntry { entry: FSEntryKind::Folder(_), .. }))
+                );
+            if targets.len() > 1 && !in_folder {
+                ctx.eprint(&format!("ln: {}: Not a folder\n", destination)).await;
+                return 1;
+            }
+
+            let mut status = 0;
+
+            for target in targets {
+                let link_path = if in_folder {
+                    path::join(&destination, path::file_name(target))
+                } else {
+                    destination.clone()
+                };
+
+                if let Err(err) = make_link(ctx, target, &link_path, &args).await {
+                    ctx.eprint(&format!("ln: {}\n", err)).await;
+                    status = 1;
+                    continue;
+                }
+
+                if args.verbose {
+                    let arrow = if args.symbolic { "->" } else { "=>" };
+                    ctx.print(&format!("'{}' {} '{}'\n", link_path, arrow, target)).await;
+                }
+            }
+
+            status
+        }
+        .boxed_local()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vfs::entry::{FSFile, FSFolder};
+
+    fn args(line: &[&str]) -> LnArgs {
+        LnArgs::try_parse_from(line).unwrap()
+    }
+
+    fn file(path: &str, inode: u64) -> FSEntry {
+        let mut file = FSFile::new(path::file_name(path), Vec::new(), 0);
+        file.inode = inode;
+        FSEntry { abs_path: path.into(), entry: FSEntryKind::File(file) }
+    }
+
+    #[test]
+    fn a_file_is_not_linked_over_itself() {
+        let a = file("/a", 1);
+        let check = |line, existing: &FSEntry| {
+            check_link("a", "/a", Some(&a), &existing.abs_path, Some(existing), &args(line))
+        };
+
+        assert_eq!(check(&["ln", "-f", "a", "a"], &a), Err("'a' and '/a' are the same file".to_string()));
+        // another name of the same file
+        assert_eq!(check(&["ln", "-f", "a", "b"], &file("/b", 1)), Err("'a' and '/b' are the same file".to_string()));
+        assert_eq!(check(&["ln", "-sf", "a", "a"], &a), Err("'a' and '/a' are the same file".to_string()));
+        assert_eq!(check(&["ln", "-f", "a", "b"], &file("/b", 2)), Ok(()));
+    }
+
+    #[test]
+    fn nothing_is_replaced_for_a_missing_target() {
+        let b = file("/b", 2);
+        let check = |line| check_link("missing", "/missing", None, "/b", Some(&b), &args(line));
+
+        assert_eq!(check(&["ln", "-f", "missing", "b"]), Err("missing: Not found".to_string()));
+        // symbolic links may point to nothing
+        assert_eq!(check(&["ln", "-sf", "missing", "b"]), Ok(()));
+    }
+
+    #[test]
+    fn only_force_replaces_and_never_a_folder() {
+        let (a, b) = (file("/a", 1), file("/b", 2));
+        let folder = FSEntry { abs_path: "/d".into(), entry: FSEntryKind::Folder(FSFolder::new("d", 0)) };
+        let check = |line, linked, existing| check_link("a", "/a", linked, "/b", existing, &args(line));
+
+        assert_eq!(check(&["ln", "a", "b"], Some(&a), None), Ok(()));
+        assert_eq!(check(&["ln", "a", "b"], Some(&a), Some(&b)), Err("/b: Already exists".to_string()));
+        assert_eq!(check(&["ln", "-f", "a", "b"], Some(&a), Some(&folder)), Err("/b: Already exists".to_string()));
+        assert_eq!(check(&["ln", "-f", "a", "b"], Some(&folder), None), Err("a: Is a folder".to_string()));
+    }
+}
@@ -1,56 +1,70 @@
-use crate::{core::shell::command::ShellCommand, kernel::Kernel, vfs::entry::FSEntryTrait};
+use crate::{core::shell::command::{context::CommandContext, parse_args, Command}, vfs::entry::FSEntryTrait};
 
-use clap::Parser;
+use clap::{CommandFactory, Parser};
+use futures::{future::LocalBoxFuture, FutureExt};
 
 #[derive(Parser, Debug)]
 #[command(name = "ls", about = "rOS command ", version = "0.1.1")]
-pub struct LsCommand {
+pub struct LsArgs {
     #[arg(short, long, default_value_t = false)]
     all: bool,
 }
 
-impl ShellCommand for LsCommand {
-    async fn execute(k: &mut Kernel, cmd: &str) -> String {
-        let args = LsCommand::try_parse_from(cmd.trim().split_whitespace());
+pub struct LsCommand;
 
-        if let Err(e) = &args {
-            return format!("{}", e);
-        }
+register!(LsCommand);
+
+impl Command for LsCommand {
+    fn name(&self) -> &'static str {
+        "ls"
+    }
 
-        let args = args.unwrap();
+    fn usage(&self) -> clap::Command {
+        LsArgs::command()
+    }
+
+    fn run<'a>(&'a self, ctx: &'a mut CommandContext) -> LocalBoxFuture<'a, i32> {
+        async move {
+            let args = match parse_args::<LsArgs>(ctx).await {
+                Ok(args) => args,
+                Err(status) => return status,
+            };
 
-        let mut entries = k.fs.read_folder("/").await.unwrap_or_else(|_| vec![]);
+            let mut entries = ctx.kernel().await.fs.read_folder("/").await.unwrap_or_else(|_| vec![]);
 
-        entries.sort_by(|a, b| {
-            return a.abs_path.cmp(&b.abs_path);
-        });
+            entries.sort_by(|a, b| a.abs_path.cmp(&b.abs_path));
 
-        k.print(format!("\n{:<30} {:<20} {:<20}", "Name", "Created At", "Modified At").as_str());
+            let header = format!("{:<30} {:<20} {:<20}", "Name", "Created At", "Modified At");
 
-        let entries = entries
-            .iter()
-            .map(|entry| {
-                let abs_path = entry.abs_path.clone();
-                if args.all {
-                    format!("{}", abs_path)
-                } else {
-                    let entry = entry.entry.clone();
-                    let created_at = chrono::DateTime::from_timestamp_millis(entry.created_at())
-                        .map(|dt| dt.format("%Y-%m-%d %H:%M").to_string())
-                        .unwrap_or_else(|| "Unknown".to_string());
+            let entries = entries
+                .iter()
+                .map(|entry| {
+                    let abs_path = entry.abs_path.clone();
+                    if args.all {
+                        abs_path
+                    } else {
+                        let entry = entry.entry.clone();
+                        let created_at = chrono::DateTime::from_timestamp_millis(entry.created_at())
+                            .map(|dt| dt.format("%Y-%m-%d %H:%M").to_string())
+                            .unwrap_or_else(|| "Unknown".to_string());
 
-                    let modified_at = chrono::DateTime::from_timestamp_millis(entry.modified_at())
-                        .map(|dt| dt.format("%Y-%m-%d %H:%M").to_string())
-                        .unwrap_or_else(|| "Unknown".to_string());
+                        let modified_at = chrono::DateTime::from_timestamp_millis(entry.modified_at())
+                            .map(|dt| dt.format("%Y-%m-%d %H:%M").to_string())
+                            .unwrap_or_else(|| "Unknown".to_string());
 
-                    let output = "".to_string();
+                        format!("{:<30} {:<20} {:<20}", abs_path, created_at, modified_at)
+                    }
+                })
+                .collect::<Vec<String>>();
 
-                    format!("{:<30} {:<20} {:<20}", abs_path, created_at, modified_at)
+            for line in std::iter::once(header).chain(entries) {
+                if ctx.write(&format!("{}\n", line)).await.is_err() {
+                    return 1;
                 }
-            })
-            .collect::<Vec<String>>()
-            .join("\n");
+            }
 
-        entries
+            0
+        }
+        .boxed_local()
     }
 }
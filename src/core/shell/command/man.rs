@@ -0,0 +1,153 @@
+use clap::{CommandFactory, Parser};
+use futures::{future::LocalBoxFuture, FutureExt};
+
+use crate::core::shell::{
+    command::{self, context::CommandContext, more, parse_args, Command},
+    man::{self, PAGE_WIDTH},
+};
+
+#[derive(Parser, Debug)]
+#[command(
+    name = "man",
+    about = "rOS command to show the manual page of a command",
+    long_about = "Shows a page of the manual in /usr/share/man, one screen at a time. Pages of the \
+                  builtins are generated at startup; pages can also be written by hand, see rshell(1).",
+    version = "0.1.0",
+    override_usage = "man [SECTION] NAME\n       man -k KEYWORD"
+)]
+pub struct ManArgs {
+    /// Section to look in (1-9) when followed by NAME, else the page to show
+    #[arg(value_name = "SECTION")]
+    first: String,
+
+    /// Page to show
+    name: Option<String>,
+
+    /// Search the summaries of the pages for the keyword, like apropos
+    #[arg(short = 'k', default_value_t = false)]
+    apropos: bool,
+}
+
+pub struct ManCommand;
+
+impl Command for ManCommand {
+    fn name(&self) -> &'static str {
+        "man"
+    }
+
+    fn usage(&self) -> clap::Command {
+        ManArgs::command()
+    }
+
+    fn run<'a>(&'a self, ctx: &'a mut CommandContext) -> LocalBoxFuture<'a, i32> {
+        async move {
+            let args = match parse_args::<ManArgs>(ctx).await {
+                Ok(args) => args,
+                Err(status) => return status,
+            };
+
+            let (section, name) = match args.name {
+                Some(name) => (Some(args.first), name),
+                None => (None, args.first),
+            };
+
+            if args.apropos {
+                return apropos(ctx, &name).await;
+            }
+
+            if let Some(section) = &section
+                && !matches!(section.parse::<u8>(), Ok(1..=9))
+            {
+                ctx.eprint(&format!("man: invalid section '{}'\n", section)).await;
+                return 2;
+            }
+
+            // `man [` shows the page of `test`
+            let name = command::find(&name).map_or(name.as_str(), |builtin| builtin.name());
+
+            let page = {
+                let kernel = ctx.kernel().await;
+                let found = man::pages(&kernel.fs)
+                    .await
+                    .into_iter()
+                    .find(|page| page.name == name && section.as_ref().is_none_or(|s| *s == page.section));
+
+                match found {
+                    Some(page) => kernel.fs.read_file(&page.path).await.ok(),
+                    None => None,
+                }
+            };
+
+            let Some(page) = page else {
+                match section {
+                    Some(section) => ctx.eprint(&format!("No entry for {} in section {} of the manual\n", name, section)).await,
+                    None => ctx.eprint(&format!("No manual entry for {}\n", name)).await,
+                }
+                return 16;
+            };
+
+            more::page(ctx, &man::render(&String::from_utf8_lossy(&page), PAGE_WIDTH)).await
+        }
+        .boxed_local()
+    }
+}
+
+#[derive(Parser, Debug)]
+#[command(name = "apropos", about = "rOS command to search the manual page names and summaries", version = "0.1.0")]
+pub struct AproposArgs {
+    /// Word to look for, case does not matter
+    keyword: String,
+}
+
+pub struct AproposCommand;
+
+register!(ManCommand, AproposCommand);
+
+impl Command for AproposCommand {
+    fn name(&self) -> &'static str {
+        "apropos"
+    }
+
+    fn usage(&self) -> clap::Command {
+        AproposArgs::command()
+    }
+
+    fn run<'a>(&'a self, ctx: &'a mut CommandContext) -> LocalBoxFuture<'a, i32> {
+        async move {
+            match parse_args::<AproposArgs>(ctx).await {
+                Ok(args) => apropos(ctx, &args.keyword).await,
+                Err(status) => status,
+            }
+        }
+        .boxed_local()
+    }
+}
+
+/// Prints the pages whose name or summary contains `keyword`.
+async fn apropos(ctx: &CommandContext<'_>, keyword: &str) -> i32 {
+    let keyword = keyword.to_lowercase();
+    let mut matches = Vec::new();
+
+    {
+        let kernel = ctx.kernel().await;
+
+        for page in man::pages(&kernel.fs).await {
+            let Ok(data) = kernel.fs.read_file(&page.path).await else {
+                continue;
+            };
+            let (names, summary) = man::summary(&String::from_utf8_lossy(&data)).unwrap_or((page.name.clone(), String::new()));
+
+            if names.to_lowercase().contains(&keyword) || summary.to_lowercase().contains(&keyword) {
+                matches.push(format!("{} ({}) - {}\n", names, page.section, summary));
+            }
+        }
+    }
+
+    if matches.is_empty() {
+        ctx.eprint(&format!("{}: nothing appropriate.\n", keyword)).await;
+        return 1;
+    }
+
+    ctx.print(&matches.concat()).await;
+    0
+}
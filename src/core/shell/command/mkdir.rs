@@ -1,10 +1,11 @@
-use crate::{console_log, core::shell::{command::ShellCommandWithShell, Shell}, kernel::Kernel, vfs::fs::SimpleFS};
+use crate::{console_log, core::shell::{command::{context::CommandContext, parse_args, Command}}, vfs::fs::SimpleFS};
 
-use clap::Parser;
+use clap::{CommandFactory, Parser};
+use futures::{future::LocalBoxFuture, FutureExt};
 
 #[derive(Parser, Debug)]
 #[command(name = "mkdir", about = "rOS command to create a new directory", version = "0.1.1")]
-pub struct MkDirCommand {
+pub struct MkDirArgs {
     #[arg(required = true)]
     folders: Vec<String>,
 
@@ -12,62 +13,73 @@ pub struct MkDirCommand {
     verbose: bool,
 }
 
-impl ShellCommandWithShell for MkDirCommand {
-    async fn execute(k: &mut Kernel, shell: &mut Shell, cmd: &str) -> String {
-        let args = MkDirCommand::try_parse_from(cmd.trim().split_whitespace());
+pub struct MkDirCommand;
 
-        if let Err(e) = &args {
-            return format!("{}", e);
-        }
+register!(MkDirCommand);
 
-        let args = args.unwrap();
-        let folders = args.folders;
+impl Command for MkDirCommand {
+    fn name(&self) -> &'static str {
+        "mkdir"
+    }
 
-        let mut created = Vec::new();
+    fn usage(&self) -> clap::Command {
+        MkDirArgs::command()
+    }
 
-        for folder in folders {
-            console_log(&format!("mkdir: creating directory '{}'", folder));
+    fn run<'a>(&'a self, ctx: &'a mut CommandContext) -> LocalBoxFuture<'a, i32> {
+        async move {
+            let args = match parse_args::<MkDirArgs>(ctx).await {
+                Ok(args) => args,
+                Err(status) => return status,
+            };
+            let folders = args.folders;
 
-            let current_folder = shell.folder.lock().await.clone();
+            let mut created = Vec::new();
+            let mut failed = false;
 
-            if !SimpleFS::is_folder_path(&folder) {
-                k.print(format!("mkdir: cannot create directory '{}': Not valid folder path", folder).as_str());
-                continue;
-            } else if k.fs.exists(&folder).await.unwrap_or(false) {
-                k.print(format!("mkdir: cannot create directory '{}': File exists", folder).as_str());
-                continue;
-            }
+            for folder in folders {
+                console_log(&format!("mkdir: creating directory '{}'", folder));
 
-            if !SimpleFS::is_absolute_path(&folder) {
-                let result = k.fs.create_folder_relative(&current_folder, &folder).await;
+                let current_folder = ctx.shell.folder.lock().await.clone();
 
-                if let Ok(folder) = result {
-                    k.print(format!("mkdir: created directory '{}'", folder.path()).as_str());
-                    created.push(folder);
-                } else {
-                    k.print(format!("mkdir: cannot create directory '{}': {}", folder, result.unwrap_err()).as_str());
-                    continue;
+                let result = {
+                    let mut k = ctx.kernel().await;
+
+                    if !SimpleFS::is_folder_path(&folder) {
+                        Err("Not valid folder path".to_string())
+                    } else if k.fs.exists(&folder).await.unwrap_or(false) {
+                        Err("File exists".to_string())
+                    } else if !SimpleFS::is_absolute_path(&folder) {
+                        k.fs.create_folder_relative(&current_folder, &folder).await.map_err(|e| e.to_string())
+                    } else {
+                        k.fs.create_folder(&folder).await.map_err(|e| e.to_string())
+                    }
+                };
+
+                match result {
+                    Ok(folder) => {
+                        ctx.print(&format!("mkdir: created directory '{}'\n", folder.path())).await;
+                        created.push(folder);
+                    }
+                    Err(err) => {
+                        ctx.eprint(&format!("mkdir: cannot create directory '{}': {}\n", folder, err)).await;
+                        failed = true;
+                    }
                 }
-            } else {
-                let result = k.fs.create_folder(&folder).await;
+            }
 
-                if let Ok(folder) = result {
-                    k.print(format!("mkdir: created directory '{}'", folder.path()).as_str());
-                    created.push(folder);
+            if !created.is_empty() {
+                if args.verbose {
+                    ctx.print(&format!("Created {} directories\n", created.len())).await;
                 } else {
-                    k.print(format!("mkdir: cannot create directory '{}': {}", folder, result.unwrap_err()).as_str());
-                    continue;
+                    for folder in &created {
+                        ctx.print(&format!("{}\n", folder.path())).await;
+                    }
                 }
             }
 
+            if failed { 1 } else { 0 }
         }
-
-        return if created.is_empty() {
-            "".into()
-        } else if args.verbose {
-            format!("Created {} directories", created.len())
-        } else {
-            created.iter().map(|f| f.path()).collect::<Vec<String>>().join("\n")
-        };
+        .boxed_local()
     }
-}
\ No newline at end of file
+}
@@ -1,34 +1,163 @@
-use std::{any::Any, sync::Arc};
+use clap::{error::ErrorKind, Parser};
+use futures::future::LocalBoxFuture;
 
-use async_std::sync::Mutex;
+use crate::core::shell::command::context::CommandContext;
 
-use crate::{core::shell::Shell, kernel::Kernel};
-
-pub mod cd;
-pub mod ls;
-pub mod mkdir;
+pub mod context;
+pub mod expr;
 pub mod touch;
-pub mod exists;
-pub mod time;
-pub mod top;
 
-pub trait ShellCommand {
-    async fn execute(k: &mut Kernel, cmd: &str) -> String {
-        panic!("This command must be implemented in the specific command module");
+/// Registers the commands defined in a module, e.g. `register!(CatCommand);`.
+/// Every module listed in `modules!` has one.
+macro_rules! register {
+    ($($command:expr),+ $(,)?) => {
+        pub(super) static REGISTERED: &[&dyn $crate::core::shell::command::Command] = &[$(&$command),+];
+    };
+}
+
+/// Declares the modules of the builtins and gathers what they register.
+macro_rules! modules {
+    ($($module:ident),+ $(,)?) => {
+        $(pub mod $module;)+
+
+        static REGISTERED: &[&[&dyn Command]] = &[$($module::REGISTERED),+];
+    };
+}
+
+// adding a command only takes its module here and a `register!` in it
+modules! {
+    alias,
+    backup,
+    base64,
+    cat,
+    cd,
+    checksum,
+    clear,
+    cut,
+    demo,
+    df,
+    diff,
+    du,
+    echo,
+    edit,
+    env,
+    exists,
+    export,
+    false_,
+    find,
+    flow,
+    grep,
+    help,
+    hexdump,
+    jobs,
+    ln,
+    ls,
+    man,
+    mkdir,
+    more,
+    patch,
+    sed,
+    set,
+    shopt,
+    sleep,
+    sort,
+    source,
+    stat,
+    tar,
+    test,
+    time,
+    top,
+    tr,
+    transfer,
+    tree,
+    true_,
+    type_,
+    unalias,
+    uniq,
+    unset,
+    wc,
+    zip,
+}
+
+/// A builtin command of the shell.
+pub trait Command: Sync {
+    /// Name the command is run by.
+    fn name(&self) -> &'static str;
+
+    /// Other names the command is run by, e.g. `[` for `test`.
+    fn aliases(&self) -> &'static [&'static str] {
+        &[]
+    }
+
+    /// The clap definition of the arguments, giving the usage shown by
+    /// `--help` and `help NAME` and the options offered by completion.
+    fn usage(&self) -> clap::Command;
+
+    /// One line description, as listed by `help`.
+    fn help(&self) -> String {
+        self.usage().get_about().map(|about| about.to_string()).unwrap_or_default()
     }
+
+    /// Runs the command, returning its exit status.
+    fn run<'a>(&'a self, ctx: &'a mut CommandContext) -> LocalBoxFuture<'a, i32>;
 }
 
-pub trait ShellCommandWithShell {
-    async fn execute(k: &mut Kernel, shell: &mut Shell, cmd: &str) -> String {
-        panic!("This command must be implemented in the specific command module");
+/// Commands of every module, in the order the modules are declared.
+fn registered() -> impl Iterator<Item = &'static dyn Command> {
+    REGISTERED.iter().flat_map(|commands| commands.iter().copied())
+}
+
+/// Every builtin of the shell, sorted by name as `help` lists them.
+pub fn commands() -> Vec<&'static dyn Command> {
+    let mut commands: Vec<_> = registered().collect();
+    commands.sort_by_key(|command| command.name());
+    commands
+}
+
+/// Looks a builtin up by name or alias.
+pub fn find(name: &str) -> Option<&'static dyn Command> {
+    registered().find(|command| command.name() == name || command.aliases().contains(&name))
+}
+
+/// Names and aliases of every builtin.
+pub fn names() -> impl Iterator<Item = &'static str> {
+    registered().flat_map(|command| std::iter::once(command.name()).chain(command.aliases().iter().copied()))
+}
+
+/// Parses the arguments of `ctx` with clap. `--help`, `--version` and usage
+/// errors are printed here, the caller only has to return the exit status.
+pub async fn parse_args<T: Parser>(ctx: &CommandContext<'_>) -> Result<T, i32> {
+    match T::try_parse_from(&ctx.args) {
+        Ok(args) => Ok(args),
+        Err(e) => {
+            match e.kind() {
+                ErrorKind::DisplayHelp | ErrorKind::DisplayVersion => ctx.print(&e.to_string()).await,
+                _ => ctx.eprint(&e.to_string()).await,
+            }
+            Err(e.exit_code())
+        }
     }
 }
 
-pub trait ShellCommandWithData<T>
-where
-    T: AsRef<str> + Send + Sync + Any + 'static,
-{
-    async fn execute_data(k: &mut Kernel, cmd: &str, data: Arc<Mutex<T>>) -> String {
-        panic!("This command must be implemented in the specific command module");
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn commands_are_found_by_their_exact_name() {
+        assert_eq!(find("ls").map(|command| command.name()), Some("ls"));
+        assert!(find("lsfoo").is_none());
+        assert!(find("l").is_none());
+        assert!(find("LS").is_none());
+    }
+
+    #[test]
+    fn names_are_unique() {
+        let mut names: Vec<&str> = names().collect();
+        let count = names.len();
+        names.sort_unstable();
+        names.dedup();
+
+        assert_eq!(names.len(), count);
     }
-}
\ No newline at end of file
+}
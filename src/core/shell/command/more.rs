@@ -0,0 +1,140 @@
+use clap::{CommandFactory, Parser};
+use futures::{future::LocalBoxFuture, FutureExt};
+use web_sys::HtmlTextAreaElement;
+
+use crate::{
+    core::shell::command::{context::CommandContext, parse_args, Command},
+    kernel::{fd::FileDescriptor, sleep},
+    vfs::path,
+};
+
+/// Height in pixels of a console line, at the font size of the page.
+pub const LINE_HEIGHT_PX: i32 = 17;
+
+/// Fewest lines shown at once, whatever the size of the console.
+const MIN_PAGE_LINES: usize = 5;
+
+/// How often the console is checked for an answer to `--More--`.
+const POLL_MS: i32 = 50;
+
+#[derive(Parser, Debug)]
+#[command(
+    name = "more",
+    about = "rOS command to show text one screen at a time",
+    long_about = "Shows the files, or stdin, one screen at a time when printing to the console. \
+                  Press Enter at the --More-- prompt for the next screen, or type q to quit.",
+    version = "0.1.0"
+)]
+pub struct MoreArgs {
+    /// Files to show, `-` or nothing reads stdin
+    files: Vec<String>,
+}
+
+pub struct MoreCommand;
+
+register!(MoreCommand);
+
+impl Command for MoreCommand {
+    fn name(&self) -> &'static str {
+        "more"
+    }
+
+    fn usage(&self) -> clap::Command {
+        MoreArgs::command()
+    }
+
+    fn run<'a>(&'a self, ctx: &'a mut CommandContext) -> LocalBoxFuture<'a, i32> {
+        async move {
+            let args = match parse_args::<MoreArgs>(ctx).await {
+                Ok(args) => args,
+                Err(status) => return status,
+            };
+
+            let files = if args.files.is_empty() { vec!["-".to_string()] } else { args.files };
+            let mut text = String::new();
+            let mut status = 0;
+
+            for file in files {
+                let data = if file == "-" {
+                    ctx.stdin().read_to_end().await.map_err(|e| e.to_string())
+                } else {
+                    let file_path = path::resolve(&ctx.cwd, &file);
+                    ctx.kernel().await.fs.read_file(&file_path).await.map_err(|e| e.to_string())
+                };
+
+                match data {
+                    Ok(data) => text.push_str(&String::from_utf8_lossy(&data)),
+                    Err(err) => {
+                        ctx.eprint(&format!("more: {}: {}\n", file, err)).await;
+                        status = 1;
+                    }
+                }
+            }
+
+            match page(ctx, &text).await {
+                0 => status,
+                failed => failed,
+            }
+        }
+        .boxed_local()
+    }
+}
+
+/// Writes `text` to stdout, pausing after each screen when the command
+/// reads from and writes to the console. Anywhere else, e.g. in a pipeline
+/// or a background job, the text is written at once.
+pub async fn page(ctx: &CommandContext<'_>, text: &str) -> i32 {
+    let console = match (ctx.stdin(), ctx.stdout()) {
+        (FileDescriptor::Console(console), FileDescriptor::Console(_)) => console.clone(),
+        _ => return if ctx.write(text).await.is_ok() { 0 } else { 1 },
+    };
+
+    let height = (console.client_height() / LINE_HEIGHT_PX).saturating_sub(2).max(MIN_PAGE_LINES as i32) as usize;
+    let lines: Vec<&str> = text.split_inclusive('\n').collect();
+    let mut shown = 0;
+
+    while shown < lines.len() {
+        let end = (shown + height).min(lines.len());
+        ctx.print(&lines[shown..end].concat()).await;
+        shown = end;
+
+        if shown == lines.len() {
+            break;
+        }
+
+        let prompt = format!("--More--({}%) ", shown * 100 / lines.len());
+        if read_answer(ctx, &console, &prompt).await.trim().eq_ignore_ascii_case("q") {
+            break;
+        }
+    }
+
+    0
+}
+
+/// Prints `prompt` and waits for the user to type a line in the console.
+/// While the job is stopped, what is typed belongs to the shell, so the
+/// prompt is printed again once the job resumes.
+async fn read_answer(ctx: &CommandContext<'_>, console: &HtmlTextAreaElement, prompt: &str) -> String {
+    ctx.print(prompt).await;
+    let mut start = console.value().len();
+
+    loop {
+        sleep(POLL_MS).await;
+
+        if ctx.shell.job.as_ref().is_some_and(|job| job.is_stopped()) {
+            ctx.shell.checkpoint().await;
+
+            ctx.print(prompt).await;
+            start = console.value().len();
+            continue;
+        }
+
+        let text = console.value();
+        match text.get(start..) {
+            Some(typed) if typed.ends_with('\n') => return typed.to_string(),
+            Some(_) => {}
+            // the console was cleared
+            None => start = text.len(),
+        }
+    }
+}
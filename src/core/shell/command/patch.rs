@@ -0,0 +1,562 @@
+use clap::{CommandFactory, Parser};
+use futures::{future::LocalBoxFuture, FutureExt};
+
+use crate::{
+    core::shell::command::{context::CommandContext, parse_args, Command},
+    vfs::{
+        entry::{FSEntry, FSEntryKind},
+        path,
+    },
+};
+
+const NO_FILE: &str = "/dev/null";
+
+#[derive(Parser, Debug)]
+#[command(
+    name = "patch",
+    about = "rOS command to apply a diff to files",
+    long_about = "Applies a unified diff, as made by `diff`, read from PATCHFILE, -i or stdin. The file \
+                  patched is named by the diff, or ORIGFILE when given. A hunk whose lines have moved is \
+                  looked for around its place, and with fuzz some of its context may be ignored. Hunks that \
+                  cannot be applied are saved to the file with a .rej suffix.\n\n\
+                  The exit status is 0 when every hunk applied, 1 when some failed and 2 on trouble.",
+    version = "0.1.0"
+)]
+pub struct PatchArgs {
+    /// File to patch instead of the one named by the diff
+    #[arg(value_name = "ORIGFILE")]
+    file: Option<String>,
+
+    /// File to read the diff from
+    #[arg(value_name = "PATCHFILE", conflicts_with = "input")]
+    patch_file: Option<String>,
+
+    /// File to read the diff from, stdin by default
+    #[arg(short, long, value_name = "PATCHFILE")]
+    input: Option<String>,
+
+    /// Strip NUM leading components from the file names, all but the last by default
+    #[arg(short = 'p', long = "strip", value_name = "NUM")]
+    strip: Option<usize>,
+
+    /// Ignore up to NUM lines of context at either end of a hunk that does not match
+    #[arg(short = 'F', long, value_name = "NUM", default_value_t = 2)]
+    fuzz: usize,
+
+    /// Undo the diff, turning the new files back into the old ones
+    #[arg(short = 'R', long)]
+    reverse: bool,
+
+    /// Only report what would happen, without changing any file
+    #[arg(long)]
+    dry_run: bool,
+}
+
+pub struct PatchCommand;
+
+register!(PatchCommand);
+
+/// A hunk of a unified diff: the lines it changes, each with its ` `, `-`
+/// or `+` marker, and its text as it was read, saved when it is rejected.
+struct Hunk {
+    old_start: usize,
+    new_start: usize,
+    lines: Vec<(char, String)>,
+    text: String,
+}
+
+impl Hunk {
+    /// The lines of the hunk on one side, `-` for the old one and `+` for
+    /// the new one, with `top` and `bottom` lines of context left out.
+    fn side(&self, marker: char, top: usize, bottom: usize) -> Vec<&str> {
+        let lines = &self.lines[top..self.lines.len() - bottom];
+        lines.iter().filter(|(m, _)| *m == ' ' || *m == marker).map(|(_, line)| line.as_str()).collect()
+    }
+
+    /// How many context lines the hunk starts and ends with.
+    fn context(&self) -> (usize, usize) {
+        let top = self.lines.iter().take_while(|(marker, _)| *marker == ' ').count();
+        let bottom = self.lines.iter().rev().take_while(|(marker, _)| *marker == ' ').count();
+        (top, bottom)
+    }
+
+    fn reverse(&mut self) {
+        std::mem::swap(&mut self.old_start, &mut self.new_start);
+        for (marker, _) in &mut self.lines {
+            *marker = match *marker {
+                '-' => '+',
+                '+' => '-',
+                other => other,
+            };
+        }
+    }
+}
+
+/// The changes a diff makes to one file.
+struct FilePatch {
+    old: String,
+    new: String,
+    hunks: Vec<Hunk>,
+}
+
+/// `start,count` of a hunk header, the count being 1 when left out.
+fn parse_range(range: &str) -> Option<(usize, usize)> {
+    match range.split_once(',') {
+        Some((start, count)) => Some((start.parse().ok()?, count.parse().ok()?)),
+        None => Some((range.parse().ok()?, 1)),
+    }
+}
+
+/// The name in a `---` or `+++` line, without the time after it.
+fn header_name(line: &str, prefix: &str) -> String {
+    let name = line.strip_prefix(prefix).unwrap_or(line).trim_end_matches(['\n', '\r']);
+    name.split('\t').next().unwrap_or(name).trim_end().to_string()
+}
+
+/// Reads the file patches of a unified diff, anything between them being
+/// skipped as comments.
+fn parse_diff(text: &str) -> Result<Vec<FilePatch>, String> {
+    let lines: Vec<&str> = text.split_inclusive('\n').collect();
+    let mut patches = Vec::new();
+    let mut i = 0;
+
+    while i < lines.len() {
+        if !(lines[i].starts_with("--- ") && lines.get(i + 1).is_some_and(|line| line.starts_with("+++ "))) {
+            i += 1;
+            continue;
+        }
+
+        let (old, new) = (header_name(lines[i], "--- "), header_name(lines[i + 1], "+++ "));
+        let mut patch = FilePatch { old, new, hunks: Vec::new() };
+        i += 2;
+
+        while i < lines.len() && lines[i].starts_with("@@ -") {
+            let header = lines[i].trim_end();
+            let ranges = header.strip_prefix("@@ -").and_then(|rest| rest.split_once(" @@")).map(|(ranges, _)| ranges);
+            let Some(((old_start, mut old_left), (new_start, mut new_left))) = ranges
+                .and_then(|ranges| ranges.split_once(" +"))
+                .and_then(|(old, new)| Some((parse_range(old)?, parse_range(new)?)))
+            else {
+                return Err(format!("malformed hunk header at line {}: {}", i + 1, header));
+            };
+
+            let mut hunk = Hunk { old_start, new_start, lines: Vec::new(), text: lines[i].to_string() };
+            i += 1;
+
+            while i < lines.len() && (old_left > 0 || new_left > 0 || lines[i].starts_with('\\')) {
+                let line = lines[i];
+                let (marker, content) = match line.chars().next() {
+                    Some(marker @ (' ' | '-' | '+')) => (marker, &line[1..]),
+                    // a missing newline at the end of the file, for the line before
+                    Some('\\') => {
+                        if let Some((_, last)) = hunk.lines.last_mut()
+                            && last.ends_with('\n')
+                        {
+                            last.pop();
+                        }
+                        hunk.text.push_str(line);
+                        i += 1;
+                        continue;
+                    }
+                    // context lines whose blank was lost on the way
+                    _ if line.trim_end_matches(['\n', '\r']).is_empty() => (' ', line),
+                    _ => return Err(format!("malformed patch at line {}: {}", i + 1, line.trim_end())),
+                };
+
+                match marker {
+                    ' ' if old_left > 0 && new_left > 0 => (old_left, new_left) = (old_left - 1, new_left - 1),
+                    '-' if old_left > 0 => old_left -= 1,
+                    '+' if new_left > 0 => new_left -= 1,
+                    _ => return Err(format!("malformed patch at line {}: {}", i + 1, line.trim_end())),
+                }
+
+                hunk.lines.push((marker, content.to_string()));
+                hunk.text.push_str(line);
+                if !line.ends_with('\n') {
+                    hunk.text.push('\n');
+                }
+                i += 1;
+            }
+
+            if old_left > 0 || new_left > 0 {
+                return Err("unexpected end of patch".into());
+            }
+            patch.hunks.push(hunk);
+        }
+
+        patches.push(patch);
+    }
+
+    Ok(patches)
+}
+
+/// `name` without its first `strip` components, or all but the last one
+/// when `strip` is not given. `None` when it has too few.
+fn strip_name(name: &str, strip: Option<usize>) -> Option<String> {
+    match strip {
+        None => Some(path::file_name(name).to_string()),
+        Some(0) => Some(name.to_string()),
+        Some(strip) => {
+            let components: Vec<&str> = name.split('/').collect();
+            (components.len() > strip).then(|| components[strip..].join("/"))
+        }
+    }
+}
+
+/// Where `old` starts in `lines`, looked for from `guess` outwards and no
+/// earlier than `first`.
+fn locate(lines: &[String], old: &[&str], guess: isize, first: usize) -> Option<usize> {
+    let last = lines.len().checked_sub(old.len())?;
+    if first > last {
+        return None;
+    }
+
+    let guess = guess.clamp(first as isize, last as isize) as usize;
+    let matches = |at: usize| lines[at..at + old.len()].iter().zip(old).all(|(line, old)| line == old);
+
+    (0..=last - first).find_map(|distance| {
+        let after = Some(guess + distance).filter(|&at| at <= last);
+        let before = guess.checked_sub(distance).filter(|&at| at >= first && distance > 0);
+        [after, before].into_iter().flatten().find(|&at| matches(at))
+    })
+}
+
+fn lines_word(count: isize) -> &'static str {
+    if count.abs() == 1 { "line" } else { "lines" }
+}
+
+/// Applies `hunks` to `lines` in order, each where it is found nearest to
+/// its place with the least fuzz. Returns the report of the hunks that did
+/// not apply exactly where expected and the text of those rejected.
+fn apply_hunks<'a>(lines: &mut Vec<String>, hunks: &'a [Hunk], max_fuzz: usize) -> (String, Vec<&'a String>) {
+    // lines added minus lines removed by the hunks applied so far, and how
+    // far from its place the last one was found
+    let (mut delta, mut last_offset) = (0isize, 0isize);
+    let mut first = 0;
+    let mut report = String::new();
+    let mut rejects = Vec::new();
+
+    for (number, hunk) in hunks.iter().enumerate() {
+        let number = number + 1;
+        let expected = hunk.old_start.saturating_sub(1) as isize + delta;
+        let (top_context, bottom_context) = hunk.context();
+
+        // a hunk with less context on one side than the other is at the
+        // start or end of the file, and only applies there until fuzz
+        // makes up for the difference
+        let context = top_context.max(bottom_context);
+        let context_lines = hunk.lines.iter().filter(|(marker, _)| *marker == ' ').count();
+
+        let found = (0..=max_fuzz)
+            .map_while(|fuzz| {
+                let top = (fuzz + top_context).saturating_sub(context);
+                let bottom = (fuzz + bottom_context).saturating_sub(context);
+
+                // more fuzz would leave out changed lines, or all the context
+                // telling where the hunk goes
+                let too_much = top > top_context || bottom > bottom_context;
+                let no_context = fuzz > 0 && top + bottom >= context_lines;
+                (!too_much && !no_context).then_some((fuzz, top, bottom))
+            })
+            .find_map(|(fuzz, top, bottom)| {
+                let old = hunk.side('-', top, bottom);
+                let at_start = fuzz + top_context < context;
+                let at_end = fuzz + bottom_context < context;
+
+                let at = match lines.len().checked_sub(old.len()) {
+                    _ if !at_start && !at_end => locate(lines, &old, expected + top as isize + last_offset, first),
+                    Some(last) => {
+                        let at = if at_start { 0 } else { last };
+                        (at >= first && (!at_end || at == last) && lines[at..at + old.len()] == old[..]).then_some(at)
+                    }
+                    None => None,
+                };
+                at.map(|at| (at, fuzz, top, bottom))
+            });
+
+        let Some((at, fuzz, top, bottom)) = found else {
+            report.push_str(&format!("Hunk #{} FAILED at {}.\n", number, hunk.old_start));
+            rejects.push(&hunk.text);
+            continue;
+        };
+
+        let (old, new) = (hunk.side('-', top, bottom), hunk.side('+', top, bottom));
+        let offset = at as isize - top as isize - expected;
+
+        if offset != 0 || fuzz > 0 {
+            report.push_str(&format!("Hunk #{} succeeded at {}", number, at - top + 1));
+            if fuzz > 0 {
+                report.push_str(&format!(" with fuzz {}", fuzz));
+            }
+            if offset != 0 {
+                report.push_str(&format!(" (offset {} {})", offset, lines_word(offset)));
+            }
+            report.push_str(".\n");
+        }
+
+        lines.splice(at..at + old.len(), new.iter().map(|line| line.to_string()));
+        delta += new.len() as isize - old.len() as isize;
+        last_offset = offset;
+        first = at + new.len();
+    }
+
+    (report, rejects)
+}
+
+/// The file a patch applies to, among the existing ones it names: the one
+/// with the fewest components, then the shortest base name, then the
+/// shortest name. `None` when none of them exists.
+async fn pick_target(ctx: &CommandContext<'_>, names: &[String]) -> Option<String> {
+    let mut existing = Vec::new();
+
+    for name in names {
+        let file_path = path::resolve(&ctx.cwd, name);
+        let entry = ctx.kernel().await.fs.get_entry(&file_path).await;
+        if let Ok(Some(FSEntry { entry: FSEntryKind::File(_), .. })) = entry {
+            existing.push(name.clone());
+        }
+    }
+
+    existing.into_iter().min_by_key(|name| (name.split('/').count(), path::file_name(name).len(), name.len()))
+}
+
+/// Applies `patch`, returning the exit status.
+async fn apply(ctx: &CommandContext<'_>, args: &PatchArgs, mut patch: FilePatch) -> i32 {
+    if args.reverse {
+        std::mem::swap(&mut patch.old, &mut patch.new);
+        patch.hunks.iter_mut().for_each(Hunk::reverse);
+    }
+
+    let creating = patch.old == NO_FILE
+        || patch.hunks.iter().all(|hunk| hunk.old_start == 0 && hunk.side('-', 0, 0).is_empty());
+    let deleting = patch.new == NO_FILE;
+
+    let names: Vec<String> = [&patch.old, &patch.new]
+        .into_iter()
+        .filter(|name| *name != NO_FILE)
+        .filter_map(|name| strip_name(name, args.strip))
+        .collect();
+
+    let target = match (&args.file, pick_target(ctx, &names).await) {
+        (Some(file), _) => file.clone(),
+        (None, Some(existing)) => existing,
+        (None, None) if creating => match names.last() {
+            Some(name) => name.clone(),
+            None => {
+                ctx.eprint("patch: no file name in patch\n").await;
+                return 2;
+            }
+        },
+        (None, None) => {
+            let shown = names.first().cloned().unwrap_or_default();
+            ctx.eprint(&format!("patch: can't find file to patch: {}\n", shown)).await;
+            ctx.print(&format!("{} out of {} hunks ignored\n", patch.hunks.len(), patch.hunks.len())).await;
+            return 1;
+        }
+    };
+
+    let file_path = path::resolve(&ctx.cwd, &target);
+    let original = match ctx.kernel().await.fs.read_file(&file_path).await {
+        Ok(data) => String::from_utf8_lossy(&data).into_owned(),
+        Err(_) if creating => String::new(),
+        Err(err) => {
+            ctx.eprint(&format!("patch: {}: {}\n", target, err)).await;
+            return 2;
+        }
+    };
+
+    ctx.print(&format!("{} file {}\n", if args.dry_run { "checking" } else { "patching" }, target)).await;
+
+    let mut lines: Vec<String> = original.split_inclusive('\n').map(str::to_string).collect();
+    let (report, rejects) = apply_hunks(&mut lines, &patch.hunks, args.fuzz);
+    ctx.print(&report).await;
+
+    let mut status = 0;
+
+    if !rejects.is_empty() {
+        let reject_file = format!("{}.rej", target);
+        ctx.print(&format!(
+            "{} out of {} hunk{} FAILED -- saving rejects to file {}\n",
+            rejects.len(),
+            patch.hunks.len(),
+            if patch.hunks.len() == 1 { "" } else { "s" },
+            reject_file
+        ))
+        .await;
+
+        if !args.dry_run {
+            let hunks: String = rejects.into_iter().cloned().collect();
+            let text = format!("--- {}\n+++ {}\n{}", patch.old, patch.new, hunks);
+            let reject_path = path::resolve(&ctx.cwd, &reject_file);
+            if let Err(err) = ctx.kernel().await.fs.write_file(&reject_path, text.as_bytes(), false).await {
+                ctx.eprint(&format!("patch: {}: {}\n", reject_file, err)).await;
+                return 2;
+            }
+        }
+        status = 1;
+    }
+
+    if args.dry_run {
+        return status;
+    }
+
+    let result = if deleting && lines.is_empty() {
+        ctx.kernel().await.fs.remove_entry(&file_path).await
+    } else {
+        ctx.kernel().await.fs.write_file(&file_path, lines.concat().as_bytes(), false).await.map(|_| ())
+    };
+
+    if let Err(err) = result {
+        ctx.eprint(&format!("patch: {}: {}\n", target, err)).await;
+        return 2;
+    }
+
+    status
+}
+
+impl Command for PatchCommand {
+    fn name(&self) -> &'static str {
+        "patch"
+    }
+
+    fn usage(&self) -> clap::Command {
+        PatchArgs::command()
+    }
+
+    fn run<'a>(&'a self, ctx: &'a mut CommandContext) -> LocalBoxFuture<'a, i32> {
+        async move {
+            let args = match parse_args::<PatchArgs>(ctx).await {
+                Ok(args) => args,
+                Err(status) => return status,
+            };
+
+            let input = args.input.as_ref().or(args.patch_file.as_ref()).map_or("-", String::as_str);
+            let text = match ctx.read_file(input).await {
+                Ok(data) => String::from_utf8_lossy(&data).into_owned(),
+                Err(err) => {
+                    ctx.eprint(&format!("patch: {}: {}\n", input, err)).await;
+                    return 2;
+                }
+            };
+
+            let patches = match parse_diff(&text) {
+                Ok(patches) if patches.is_empty() => {
+                    ctx.eprint("patch: Only garbage was found in the patch input.\n").await;
+                    return 2;
+                }
+                Ok(patches) => patches,
+                Err(err) => {
+                    ctx.eprint(&format!("patch: {}\n", err)).await;
+                    return 2;
+                }
+            };
+
+            let mut status = 0;
+            for patch in patches {
+                ctx.shell.checkpoint().await;
+                status = status.max(apply(ctx, &args, patch).await);
+            }
+
+            status
+        }
+        .boxed_local()
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::shell::command::diff::unified_hunks;
+
+    /// Applies the hunks of the first patch of `diff` to `original`,
+    /// returning the patched text, the report and how many hunks failed.
+    fn apply_diff(diff: &str, original: &str, fuzz: usize) -> (String, String, usize) {
+        let patches = parse_diff(diff).unwrap();
+        let mut lines: Vec<String> = original.split_inclusive('\n').map(str::to_string).collect();
+        let (report, rejects) = apply_hunks(&mut lines, &patches[0].hunks, fuzz);
+
+        (lines.concat(), report, rejects.len())
+    }
+
+    const DIFF: &str = "comment\n--- a/f.txt\t2024-01-01\n+++ b/f.txt\n@@ -2,3 +2,3 @@\n b\n-c\n+C\n d\n";
+
+    #[test]
+    fn diffs_are_parsed_into_file_patches() {
+        let patches = parse_diff(DIFF).unwrap();
+
+        assert_eq!(patches.len(), 1);
+        assert_eq!((patches[0].old.as_str(), patches[0].new.as_str()), ("a/f.txt", "b/f.txt"));
+
+        let hunk = &patches[0].hunks[0];
+        assert_eq!((hunk.old_start, hunk.new_start), (2, 2));
+        assert_eq!(hunk.side('-', 0, 0), ["b\n", "c\n", "d\n"]);
+        assert_eq!(hunk.side('+', 0, 0), ["b\n", "C\n", "d\n"]);
+        assert_eq!(hunk.context(), (1, 1));
+    }
+
+    #[test]
+    fn missing_newlines_and_malformed_hunks() {
+        let patches = parse_diff("--- a\n+++ b\n@@ -1 +1 @@\n-x\n\\ No newline at end of file\n+y\n").unwrap();
+        assert_eq!(patches[0].hunks[0].side('-', 0, 0), ["x"]);
+        assert_eq!(patches[0].hunks[0].side('+', 0, 0), ["y\n"]);
+
+        assert!(parse_diff("--- a\n+++ b\n@@ -1,2 +1,2 @@\n a\n").is_err());
+        assert!(parse_diff("--- a\n+++ b\n@@ -x +1 @@\n").is_err());
+        assert!(parse_diff("--- a\n+++ b\n@@ -1 +1 @@\n*a\n").is_err());
+    }
+
+    #[test]
+    fn hunks_apply_in_place_or_at_an_offset() {
+        assert_eq!(apply_diff(DIFF, "a\nb\nc\nd\ne\n", 0), ("a\nb\nC\nd\ne\n".into(), String::new(), 0));
+
+        let (text, report, failed) = apply_diff(DIFF, "0\n1\na\nb\nc\nd\n", 0);
+        assert_eq!((text.as_str(), failed), ("0\n1\na\nb\nC\nd\n", 0));
+        assert_eq!(report, "Hunk #1 succeeded at 4 (offset 2 lines).\n");
+    }
+
+    #[test]
+    fn fuzz_ignores_context_that_changed() {
+        let diff = "--- a\n+++ b\n@@ -1,5 +1,5 @@\n a\n b\n-c\n+C\n d\n e\n";
+
+        assert_eq!(apply_diff(diff, "A\nb\nc\nd\ne\n", 0).2, 1);
+        assert_eq!(apply_diff(diff, "a\nB\nc\nd\ne\n", 1).2, 1);
+
+        let (text, report, failed) = apply_diff(diff, "A\nb\nc\nd\ne\n", 1);
+        assert_eq!((text.as_str(), failed), ("A\nb\nC\nd\ne\n", 0));
+        assert_eq!(report, "Hunk #1 succeeded at 1 with fuzz 1.\n");
+    }
+
+    #[test]
+    fn fuzz_never_leaves_out_changes_or_all_the_context() {
+        // short hunks used to panic or apply with no line checked
+        for diff in [
+            "--- a\n+++ b\n@@ -1,2 +1,3 @@\n a\n+b\n c\n",
+            "--- a\n+++ b\n@@ -1,3 +1,3 @@\n a\n-b\n+B\n c\n",
+            "--- a\n+++ b\n@@ -1,1 +1,2 @@\n a\n+b\n",
+            "--- a\n+++ b\n@@ -1,3 +1,3 @@\n a\n b\n c\n",
+        ] {
+            assert_eq!(apply_diff(diff, "x\ny\n", 3), ("x\ny\n".into(), "Hunk #1 FAILED at 1.\n".into(), 1));
+        }
+
+        // context left between the changes is enough
+        let diff = "--- a\n+++ b\n@@ -1,5 +1,3 @@\n a\n-b\n c\n-d\n e\n";
+        assert_eq!(apply_diff(diff, "x\nb\nc\nd\ny\n", 1).0, "x\nc\ny\n");
+    }
+
+    #[test]
+    fn unified_hunks_apply_back() {
+        let old = "1\n2\n3\n4\n5\n6\n7\n8\n9\n10\n11\n12\n";
+        let new = "1\ntwo\n3\n4\n5\n6\n7\n8\n9\n10\neleven\n12\nthirteen";
+        let diff = format!("--- a\n+++ b\n{}", unified_hunks(old, new, 3));
+
+        let (text, report, failed) = apply_diff(&diff, old, 0);
+        assert_eq!((text.as_str(), report.as_str(), failed), (new, "", 0));
+
+        let mut patches = parse_diff(&diff).unwrap();
+        patches[0].hunks.iter_mut().for_each(Hunk::reverse);
+        let mut lines: Vec<String> = new.split_inclusive('\n').map(str::to_string).collect();
+        apply_hunks(&mut lines, &patches[0].hunks, 0);
+        assert_eq!(lines.concat(), old);
+    }
+}
@@ -0,0 +1,507 @@
+use std::{iter::Peekable, str::Chars};
+
+use clap::{CommandFactory, Parser};
+use futures::{future::LocalBoxFuture, FutureExt};
+use regex::Regex;
+
+use crate::core::shell::{
+    command::{context::CommandContext, parse_args, Command},
+    regexp,
+};
+
+#[derive(Parser, Debug)]
+#[command(
+    name = "sed",
+    about = "rOS command to edit a stream of text",
+    long_about = "Runs a script on every line of the input, then prints the line unless -n is given.\n\n\
+                  A script is made of commands separated by ; or newlines, each one optionally preceded \
+                  by an address: a line number, $ for the last line or /REGEX/, or a range ADDR1,ADDR2. \
+                  A ! after the address runs the command on the other lines.\n\n\
+                  Commands: s/REGEX/REPLACEMENT/FLAGS replaces the first match (every match with the g \
+                  flag, the Nth with a number, printing the line with p, ignoring case with i); & and \
+                  \\1 to \\9 in REPLACEMENT stand for the match and its groups. d deletes the line and \
+                  starts the next one, p prints it.",
+    version = "0.1.0",
+    override_usage = "sed [OPTIONS] SCRIPT [FILE]...\n       sed [OPTIONS] -e SCRIPT... [FILE]..."
+)]
+pub struct SedArgs {
+    /// The script, unless given with -e, followed by the files to edit (stdin by default)
+    #[arg(value_name = "ARGS")]
+    args: Vec<String>,
+
+    /// Add SCRIPT to the commands to run
+    #[arg(short, long = "expression", value_name = "SCRIPT")]
+    expressions: Vec<String>,
+
+    /// Only print the lines asked for with p
+    #[arg(short = 'n', long)]
+    quiet: bool,
+
+    /// Use extended regular expressions
+    #[arg(short = 'E', long)]
+    regexp_extended: bool,
+}
+
+pub struct SedCommand;
+
+register!(SedCommand);
+
+enum Address {
+    Line(usize),
+    Last,
+    Match(Regex),
+}
+
+impl Address {
+    fn matches(&self, number: usize, line: &str, last: bool) -> bool {
+        match self {
+            Address::Line(n) => *n == number,
+            Address::Last => last,
+            Address::Match(regex) => regex.is_match(line),
+        }
+    }
+}
+
+/// A piece of the replacement of `s`.
+enum Piece {
+    Text(String),
+    /// The match itself for 0, a group otherwise.
+    Group(usize),
+}
+
+enum Op {
+    Substitute {
+        regex: Regex,
+        replacement: Vec<Piece>,
+        /// Replace every match from the `nth` one on, rather than only it.
+        global: bool,
+        nth: usize,
+        print: bool,
+    },
+    Delete,
+    Print,
+}
+
+struct Instruction {
+    start: Option<Address>,
+    end: Option<Address>,
+    negated: bool,
+    op: Op,
+    /// Whether a range address has started and not ended yet.
+    in_range: bool,
+}
+
+impl Instruction {
+    fn selects(&mut self, number: usize, line: &str, last: bool) -> bool {
+        let selected = match (&self.start, &self.end) {
+            (None, _) => true,
+            (Some(start), None) => start.matches(number, line, last),
+            (Some(start), Some(end)) => {
+                if self.in_range {
+                    self.in_range = !end.matches(number, line, last);
+                    true
+                } else if start.matches(number, line, last) {
+                    // a line number already passed ends the range straight away
+                    self.in_range = !matches!(end, Address::Line(n) if *n <= number) && !last;
+                    true
+                } else {
+                    false
+                }
+            }
+        };
+
+        selected != self.negated
+    }
+}
+
+struct ScriptParser<'a> {
+    chars: Peekable<Chars<'a>>,
+    extended: bool,
+}
+
+impl ScriptParser<'_> {
+    fn skip_blanks(&mut self) {
+        while self.chars.next_if(|c| *c == ' ' || *c == '\t').is_some() {}
+    }
+
+    fn parse(&mut self) -> Result<Vec<Instruction>, String> {
+        let mut instructions = Vec::new();
+
+        loop {
+            while self.chars.next_if(|c| c.is_whitespace() || *c == ';').is_some() {}
+            if self.chars.peek().is_none() {
+                return Ok(instructions);
+            }
+
+            let start = self.address()?;
+            let end = match (&start, self.chars.next_if_eq(&',')) {
+                (Some(_), Some(_)) => Some(self.address()?.ok_or("unexpected `,'")?),
+                (None, Some(_)) => return Err("unexpected `,'".into()),
+                _ => None,
+            };
+
+            self.skip_blanks();
+            let negated = self.chars.next_if_eq(&'!').is_some();
+            self.skip_blanks();
+
+            let op = match self.chars.next() {
+                Some('d') => Op::Delete,
+                Some('p') => Op::Print,
+                Some('s') => self.substitute()?,
+                Some(c) => return Err(format!("unknown command: `{}'", c)),
+                None => return Err("missing command".into()),
+            };
+
+            self.skip_blanks();
+            match self.chars.peek() {
+                None | Some(';' | '\n') => {}
+                Some(c) => return Err(format!("extra characters after command: `{}'", c)),
+            }
+
+            instructions.push(Instruction { start, end, negated, op, in_range: false });
+        }
+    }
+
+    fn address(&mut self) -> Result<Option<Address>, String> {
+        match self.chars.peek() {
+            Some('$') => {
+                self.chars.next();
+                Ok(Some(Address::Last))
+            }
+            Some('/') => {
+                self.chars.next();
+                let pattern = self.delimited('/')?;
+                Ok(Some(Address::Match(self.compile(&pattern, false)?)))
+            }
+            Some(c) if c.is_ascii_digit() => {
+                let mut number = String::new();
+                while let Some(digit) = self.chars.next_if(char::is_ascii_digit) {
+                    number.push(digit);
+                }
+
+                match number.parse() {
+                    Ok(0) | Err(_) => Err("invalid usage of line address 0".into()),
+                    Ok(n) => Ok(Some(Address::Line(n))),
+                }
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// `s/REGEX/REPLACEMENT/FLAGS`, after the `s`.
+    fn substitute(&mut self) -> Result<Op, String> {
+        let delimiter = match self.chars.next() {
+            Some(c) if c != '\n' && c != '\\' => c,
+            _ => return Err("unterminated `s' command".into()),
+        };
+
+        let pattern = self.delimited(delimiter)?;
+        let replacement = replacement(&self.delimited(delimiter)?);
+
+        let (mut global, mut print, mut ignore_case) = (false, false, false);
+        let mut nth = String::new();
+
+        while let Some(flag) = self.chars.next_if(|c| !matches!(c, ';' | '\n' | ' ' | '\t')) {
+            match flag {
+                'g' => global = true,
+                'p' => print = true,
+                'i' | 'I' => ignore_case = true,
+                digit if digit.is_ascii_digit() => nth.push(digit),
+                other => return Err(format!("unknown option to `s': `{}'", other)),
+            }
+        }
+
+        let nth = match nth.parse::<usize>() {
+            Ok(0) => return Err("number option to `s' command may not be zero".into()),
+            Ok(n) => n,
+            Err(_) => 1,
+        };
+
+        Ok(Op::Substitute { regex: self.compile(&pattern, ignore_case)?, replacement, global, nth, print })
+    }
+
+    /// Text up to the next unescaped `delimiter`, which is consumed. An
+    /// escaped delimiter stands for itself, other escapes are kept.
+    fn delimited(&mut self, delimiter: char) -> Result<String, String> {
+        let mut text = String::new();
+
+        loop {
+            match self.chars.next() {
+                Some(c) if c == delimiter => return Ok(text),
+                Some('\\') => match self.chars.next() {
+                    Some(c) if c == delimiter => text.push(c),
+                    Some(c) => {
+                        text.push('\\');
+                        text.push(c);
+                    }
+                    None => return Err(format!("unterminated address regex or `s' command at `{}'", text)),
+                },
+                Some(c) => text.push(c),
+                None => return Err(format!("unterminated address regex or `s' command at `{}'", text)),
+            }
+        }
+    }
+
+    fn compile(&self, pattern: &str, ignore_case: bool) -> Result<Regex, String> {
+        regexp::compile(pattern, self.extended, ignore_case)
+            .map_err(|_| format!("invalid regular expression `{}'", pattern))
+    }
+}
+
+/// Splits the replacement of `s` into text and references to the match.
+fn replacement(text: &str) -> Vec<Piece> {
+    let mut pieces = Vec::new();
+    let mut current = String::new();
+    let mut chars = text.chars();
+
+    while let Some(c) = chars.next() {
+        let group = match c {
+            '&' => Some(0),
+            '\\' => match chars.next() {
+                Some(digit @ '1'..='9') => digit.to_digit(10).map(|n| n as usize),
+                Some('n') => {
+                    current.push('\n');
+                    None
+                }
+                Some('t') => {
+                    current.push('\t');
+                    None
+                }
+                Some(other) => {
+                    current.push(other);
+                    None
+                }
+                None => {
+                    current.push('\\');
+                    None
+                }
+            },
+            c => {
+                current.push(c);
+                None
+            }
+        };
+
+        if let Some(group) = group {
+            pieces.push(Piece::Text(std::mem::take(&mut current)));
+            pieces.push(Piece::Group(group));
+        }
+    }
+
+    pieces.push(Piece::Text(current));
+    pieces
+}
+
+/// Runs `s` on `line`, returning whether anything was replaced.
+fn substitute(line: &mut String, regex: &Regex, replacement: &[Piece], global: bool, nth: usize) -> bool {
+    let mut output = String::new();
+    let mut end = 0;
+    let mut replaced = false;
+
+    for (i, captures) in regex.captures_iter(line).enumerate() {
+        if i + 1 < nth {
+            continue;
+        }
+        if replaced && !global {
+            break;
+        }
+
+        let found = captures.get(0).map_or(0..0, |found| found.range());
+        output.push_str(&line[end..found.start]);
+
+        for piece in replacement {
+            match piece {
+                Piece::Text(text) => output.push_str(text),
+                Piece::Group(group) => output.push_str(captures.get(*group).map_or("", |group| group.as_str())),
+            }
+        }
+
+        end = found.end;
+        replaced = true;
+    }
+
+    if replaced {
+        output.push_str(&line[end..]);
+        *line = output;
+    }
+    replaced
+}
+
+/// Runs the script on every line of `input`, returning what is printed.
+fn edit(instructions: &mut [Instruction], input: &str, quiet: bool) -> String {
+    let lines: Vec<&str> = input.lines().collect();
+    let mut output = String::new();
+
+    for (i, line) in lines.iter().enumerate() {
+        let (number, last) = (i + 1, i + 1 == lines.len());
+        let mut line = line.to_string();
+        let mut deleted = false;
+
+        for instruction in instructions.iter_mut() {
+            if !instruction.selects(number, &line, last) {
+                continue;
+            }
+
+            match &instruction.op {
+                Op::Delete => {
+                    deleted = true;
+                    break;
+                }
+                Op::Print => output.push_str(&format!("{}\n", line)),
+                Op::Substitute { regex, replacement, global, nth, print } => {
+                    if substitute(&mut line, regex, replacement, *global, *nth) && *print {
+                        output.push_str(&format!("{}\n", line));
+                    }
+                }
+            }
+        }
+
+        if !deleted && !quiet {
+            output.push_str(&format!("{}\n", line));
+        }
+    }
+
+    output
+}
+
+impl Command for SedCommand {
+    fn name(&self) -> &'static str {
+        "sed"
+    }
+
+    fn usage(&self) -> clap::Command {
+        SedArgs::command()
+    }
+
+    fn run<'a>(&'a self, ctx: &'a mut CommandContext) -> LocalBoxFuture<'a, i32> {
+        async move {
+            let args = match parse_args::<SedArgs>(ctx).await {
+                Ok(args) => args,
+                Err(status) => return status,
+            };
+
+            let (script, files) = if args.expressions.is_empty() {
+                match args.args.split_first() {
+                    Some((script, files)) => (script.clone(), files),
+                    None => {
+                        ctx.eprint("sed: no script specified\n").await;
+                        return 1;
+                    }
+                }
+            } else {
+                (args.expressions.join("\n"), args.args.as_slice())
+            };
+
+            let mut parser = ScriptParser { chars: script.chars().peekable(), extended: args.regexp_extended };
+            let mut instructions = match parser.parse() {
+                Ok(instructions) => instructions,
+                Err(err) => {
+                    ctx.eprint(&format!("sed: -e expression: {}\n", err)).await;
+                    return 1;
+                }
+            };
+
+            let (input, status) = ctx.read_inputs("sed", files).await;
+            let output = edit(&mut instructions, &input, args.quiet);
+
+            if ctx.write(&output).await.is_err() {
+                return 1;
+            }
+
+            status
+        }
+        .boxed_local()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(script: &str, extended: bool) -> Result<Vec<Instruction>, String> {
+        ScriptParser { chars: script.chars().peekable(), extended }.parse()
+    }
+
+    /// Output of `sed script` on `input`, or the error of the script.
+    fn sed(script: &str, input: &str) -> Result<String, String> {
+        parse(script, false).map(|mut instructions| edit(&mut instructions, input, false))
+    }
+
+    fn sed_quiet(script: &str, input: &str) -> String {
+        edit(&mut parse(script, false).unwrap(), input, true)
+    }
+
+    #[test]
+    fn substitutions_replace_the_first_nth_or_every_match() {
+        assert_eq!(sed("s/a/b/", "aaa\n").unwrap(), "baa\n");
+        assert_eq!(sed("s/a/b/g", "aaa\n").unwrap(), "bbb\n");
+        assert_eq!(sed("s/a/b/2", "aaa\n").unwrap(), "aba\n");
+        assert_eq!(sed("s/a/b/2g", "aaaa\n").unwrap(), "abbb\n");
+        assert_eq!(sed("s/A/b/gi", "aAa\n").unwrap(), "bbb\n");
+        assert_eq!(sed("s/x/y/", "aaa\n").unwrap(), "aaa\n");
+        assert_eq!(sed("s/a*/-/g", "baac\n").unwrap(), "-b-c-\n");
+    }
+
+    #[test]
+    fn replacements_refer_to_the_match_and_its_groups() {
+        assert_eq!(sed("s/[0-9][0-9]*/<&>/g", "a1b22\n").unwrap(), "a<1>b<22>\n");
+        assert_eq!(sed(r"s/\(a*\)\(b*\)/\2\1/", "aabbb\n").unwrap(), "bbbaa\n");
+        assert_eq!(sed(r"s/a/\&\n\//", "a\n").unwrap(), "&\n/\n");
+        assert_eq!(sed("s|/|:|g", "/a/b\n").unwrap(), ":a:b\n");
+        assert_eq!(sed(r"s/b/[\3]/", "abc\n").unwrap(), "a[]c\n");
+
+        let mut extended = parse("s/(a+)(b)/\\2\\1/", true).unwrap();
+        assert_eq!(edit(&mut extended, "aab\n", false), "baa\n");
+    }
+
+    #[test]
+    fn addresses_select_lines() {
+        let input = "one\ntwo\nthree\nfour\n";
+
+        assert_eq!(sed("2d", input).unwrap(), "one\nthree\nfour\n");
+        assert_eq!(sed("$d", input).unwrap(), "one\ntwo\nthree\n");
+        assert_eq!(sed("/o/d", input).unwrap(), "three\n");
+        assert_eq!(sed("/o/!d", input).unwrap(), "one\ntwo\nfour\n");
+        assert_eq!(sed_quiet("2p", input), "two\n");
+        assert_eq!(sed_quiet("s/o/0/p", input), "0ne\ntw0\nf0ur\n");
+    }
+
+    #[test]
+    fn ranges_go_from_the_first_address_to_the_second() {
+        let input = "a\nb\nc\nd\ne\n";
+
+        assert_eq!(sed_quiet("2,4p", input), "b\nc\nd\n");
+        assert_eq!(sed_quiet("/b/,/d/p", input), "b\nc\nd\n");
+        assert_eq!(sed_quiet("3,$p", input), "c\nd\ne\n");
+        assert_eq!(sed_quiet("2,4!p", input), "a\ne\n");
+        // an end already passed only selects the start
+        assert_eq!(sed_quiet("3,1p", input), "c\n");
+        // a range whose end never matches goes on to the last line
+        assert_eq!(sed_quiet("/d/,/x/p", input), "d\ne\n");
+        // the end is only looked for after the start
+        assert_eq!(sed_quiet("/a/,/[ab]/p", input), "a\nb\n");
+    }
+
+    #[test]
+    fn scripts_run_every_command_in_order() {
+        assert_eq!(sed("s/a/b/; s/b/c/", "a\n").unwrap(), "c\n");
+        assert_eq!(sed("1d\n s/b/x/", "a\nb\n").unwrap(), "x\n");
+        assert_eq!(sed("p;p", "a\n").unwrap(), "a\na\na\n");
+    }
+
+    #[test]
+    fn malformed_scripts_are_rejected() {
+        let error = |script: &str| sed(script, "").err().unwrap();
+
+        assert_eq!(error("x"), "unknown command: `x'");
+        assert_eq!(error("2"), "missing command");
+        assert_eq!(error("0p"), "invalid usage of line address 0");
+        assert_eq!(error(",2p"), "unexpected `,'");
+        assert_eq!(error("1,p"), "unexpected `,'");
+        assert_eq!(error("s/a/b"), "unterminated address regex or `s' command at `b'");
+        assert_eq!(error("s/a/b/q"), "unknown option to `s': `q'");
+        assert_eq!(error("s/a/b/0"), "number option to `s' command may not be zero");
+        assert_eq!(error("p x"), "extra characters after command: `x'");
+        assert_eq!(error(r"s/\(/x/"), "invalid regular expression `\\('");
+    }
+}
@@ -0,0 +1,45 @@
+use clap::{CommandFactory, Parser};
+use futures::{future::LocalBoxFuture, FutureExt};
+
+use crate::core::shell::{
+    command::{context::CommandContext, export::quote_value, parse_args, Command},
+};
+
+#[derive(Parser, Debug)]
+#[command(name = "set", about = "rOS command to list the shell variables", version = "0.1.0")]
+pub struct SetArgs {}
+
+pub struct SetCommand;
+
+register!(SetCommand);
+
+impl Command for SetCommand {
+    fn name(&self) -> &'static str {
+        "set"
+    }
+
+    fn usage(&self) -> clap::Command {
+        SetArgs::command()
+    }
+
+    fn run<'a>(&'a self, ctx: &'a mut CommandContext) -> LocalBoxFuture<'a, i32> {
+        async move {
+            if let Err(status) = parse_args::<SetArgs>(ctx).await {
+                return status;
+            }
+
+            let listing: String = {
+                let kernel = ctx.kernel().await;
+                kernel
+                    .envs
+                    .get(&ctx.shell.pid)
+                    .map(|env| env.iter().map(|(name, value, _)| format!("{}={}\n", name, quote_value(value))).collect())
+                    .unwrap_or_default()
+            };
+
+            ctx.print(&listing).await;
+            0
+        }
+        .boxed_local()
+    }
+}
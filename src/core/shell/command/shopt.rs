@@ -0,0 +1,85 @@
+use clap::{CommandFactory, Parser};
+use futures::{future::LocalBoxFuture, FutureExt};
+
+use crate::core::shell::{
+    command::{context::CommandContext, parse_args, Command},
+    OPTIONS,
+};
+
+#[derive(Parser, Debug)]
+#[command(name = "shopt", about = "rOS command to set and unset shell options", version = "0.1.0")]
+pub struct ShoptArgs {
+    /// Options to set, unset or show (dotglob, nullglob), all of them by default
+    options: Vec<String>,
+
+    /// Turn the options on
+    #[arg(short = 's', conflicts_with = "unset")]
+    set: bool,
+
+    /// Turn the options off
+    #[arg(short = 'u')]
+    unset: bool,
+}
+
+pub struct ShoptCommand;
+
+register!(ShoptCommand);
+
+impl Command for ShoptCommand {
+    fn name(&self) -> &'static str {
+        "shopt"
+    }
+
+    fn usage(&self) -> clap::Command {
+        ShoptArgs::command()
+    }
+
+    fn run<'a>(&'a self, ctx: &'a mut CommandContext) -> LocalBoxFuture<'a, i32> {
+        async move {
+            let args = match parse_args::<ShoptArgs>(ctx).await {
+                Ok(args) => args,
+                Err(status) => return status,
+            };
+
+            let mut names = Vec::new();
+            for name in &args.options {
+                match OPTIONS.iter().find(|option| *option == name) {
+                    Some(option) => names.push(*option),
+                    None => {
+                        ctx.eprint(&format!("shopt: {}: invalid shell option name\n", name)).await;
+                        return 1;
+                    }
+                }
+            }
+
+            let mut options = ctx.shell.options.lock().await;
+
+            if !names.is_empty() && (args.set || args.unset) {
+                for name in names {
+                    if args.set {
+                        options.insert(name);
+                    } else {
+                        options.remove(name);
+                    }
+                }
+                return 0;
+            }
+
+            // `shopt -s` and `shopt -u` alone list the options on or off
+            let listed: Vec<&str> = if names.is_empty() { OPTIONS.to_vec() } else { names.clone() };
+            let listed = listed.into_iter().filter(|name| {
+                let on = options.contains(name);
+                (!args.set || on) && (!args.unset || !on)
+            });
+
+            let output: String =
+                listed.map(|name| format!("{:<15}\t{}\n", name, if options.contains(name) { "on" } else { "off" })).collect();
+            let all_on = names.iter().all(|name| options.contains(name));
+            drop(options);
+
+            ctx.print(&output).await;
+            if all_on { 0 } else { 1 }
+        }
+        .boxed_local()
+    }
+}
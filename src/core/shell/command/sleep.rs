@@ -0,0 +1,60 @@
+use clap::{CommandFactory, Parser};
+use futures::{future::LocalBoxFuture, FutureExt};
+
+use crate::{
+    core::shell::{
+        command::{context::CommandContext, parse_args, Command},
+    },
+    kernel::sleep,
+};
+
+/// Longest wait between two checks of whether the job has been stopped.
+const STEP_MS: f64 = 100.0;
+
+#[derive(Parser, Debug)]
+#[command(name = "sleep", about = "rOS command to wait for a number of seconds", version = "0.1.0")]
+pub struct SleepArgs {
+    /// Seconds to wait, fractions allowed
+    seconds: f64,
+}
+
+pub struct SleepCommand;
+
+register!(SleepCommand);
+
+impl Command for SleepCommand {
+    fn name(&self) -> &'static str {
+        "sleep"
+    }
+
+    fn usage(&self) -> clap::Command {
+        SleepArgs::command()
+    }
+
+    fn run<'a>(&'a self, ctx: &'a mut CommandContext) -> LocalBoxFuture<'a, i32> {
+        async move {
+            let args = match parse_args::<SleepArgs>(ctx).await {
+                Ok(args) => args,
+                Err(status) => return status,
+            };
+
+            if !args.seconds.is_finite() || args.seconds < 0.0 {
+                ctx.eprint(&format!("sleep: invalid time interval '{}'\n", args.seconds)).await;
+                return 1;
+            }
+
+            // time spent stopped does not count
+            let mut remaining = args.seconds * 1000.0;
+            while remaining > 0.0 {
+                ctx.shell.checkpoint().await;
+
+                let step = remaining.min(STEP_MS);
+                sleep(step.ceil() as i32).await;
+                remaining -= step;
+            }
+
+            0
+        }
+        .boxed_local()
+    }
+}
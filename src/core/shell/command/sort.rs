@@ -0,0 +1,213 @@
+use std::cmp::Ordering;
+
+use clap::{CommandFactory, Parser};
+use futures::{future::LocalBoxFuture, FutureExt};
+
+use crate::core::shell::command::{context::CommandContext, parse_args, Command};
+
+#[derive(Parser, Debug)]
+#[command(name = "sort", about = "rOS command to sort lines of text", version = "0.1.0")]
+pub struct SortArgs {
+    /// Files to sort together, `-` or nothing reads stdin
+    files: Vec<String>,
+
+    /// Compare by numeric value
+    #[arg(short, long)]
+    numeric_sort: bool,
+
+    /// Reverse the result of comparisons
+    #[arg(short, long)]
+    reverse: bool,
+
+    /// Only keep the first of lines comparing equal
+    #[arg(short, long)]
+    unique: bool,
+
+    /// Sort by fields START to END (1-based, END defaulting to the end of the
+    /// line), optionally followed by n or r for this key only, e.g. 2,2n
+    #[arg(short, long = "key", value_name = "START[,END]")]
+    keys: Vec<String>,
+
+    /// Separate fields with SEP instead of runs of blanks
+    #[arg(short = 't', long, value_name = "SEP")]
+    field_separator: Option<char>,
+}
+
+pub struct SortCommand;
+
+register!(SortCommand);
+
+/// A `-k` sort key.
+#[derive(Debug)]
+struct Key {
+    start: usize,
+    end: Option<usize>,
+    numeric: bool,
+    reverse: bool,
+}
+
+impl Key {
+    fn parse(spec: &str) -> Option<Self> {
+        let (fields, flags) = spec.split_at(spec.find(|c: char| c.is_ascii_alphabetic()).unwrap_or(spec.len()));
+
+        if !flags.chars().all(|flag| matches!(flag, 'n' | 'r')) {
+            return None;
+        }
+
+        let (start, end) = match fields.split_once(',') {
+            Some((start, end)) => (start.parse().ok()?, Some(end.parse().ok()?)),
+            None => (fields.parse().ok()?, None),
+        };
+
+        (start > 0).then_some(Self { start, end, numeric: flags.contains('n'), reverse: flags.contains('r') })
+    }
+
+    /// The text of the key in `line`.
+    fn extract(&self, line: &str, separator: Option<char>) -> String {
+        let fields: Vec<&str> = match separator {
+            Some(separator) => line.split(separator).collect(),
+            None => line.split_whitespace().collect(),
+        };
+
+        let end = self.end.unwrap_or(fields.len()).min(fields.len());
+        let joined = fields.get(self.start - 1..end).unwrap_or_default();
+
+        joined.join(&separator.map(String::from).unwrap_or_else(|| " ".into()))
+    }
+}
+
+/// The number a line starts with, as `sort -n` sees it: lines not starting
+/// with a number count as zero.
+fn numeric_value(text: &str) -> f64 {
+    let text = text.trim_start();
+    let end = text
+        .char_indices()
+        .take_while(|(i, c)| c.is_ascii_digit() || *c == '.' || (*i == 0 && *c == '-'))
+        .map(|(i, c)| i + c.len_utf8())
+        .last()
+        .unwrap_or(0);
+
+    text[..end].parse().unwrap_or(0.0)
+}
+
+fn compare(a: &str, b: &str, numeric: bool) -> Ordering {
+    if numeric {
+        numeric_value(a).total_cmp(&numeric_value(b))
+    } else {
+        a.cmp(b)
+    }
+}
+
+impl Command for SortCommand {
+    fn name(&self) -> &'static str {
+        "sort"
+    }
+
+    fn usage(&self) -> clap::Command {
+        SortArgs::command()
+    }
+
+    fn run<'a>(&'a self, ctx: &'a mut CommandContext) -> LocalBoxFuture<'a, i32> {
+        async move {
+            let args = match parse_args::<SortArgs>(ctx).await {
+                Ok(args) => args,
+                Err(status) => return status,
+            };
+
+            let mut keys = Vec::new();
+            for spec in &args.keys {
+                match Key::parse(spec) {
+                    Some(key) => keys.push(key),
+                    None => {
+                        ctx.eprint(&format!("sort: invalid key '{}'\n", spec)).await;
+                        return 2;
+                    }
+                }
+            }
+
+            let (input, status) = ctx.read_inputs("sort", &args.files).await;
+            let mut lines: Vec<&str> = input.lines().collect();
+
+            let primary = |a: &&str, b: &&str| {
+                if keys.is_empty() {
+                    return compare(a, b, args.numeric_sort);
+                }
+
+                keys.iter()
+                    .map(|key| {
+                        let ordering = compare(
+                            &key.extract(a, args.field_separator),
+                            &key.extract(b, args.field_separator),
+                            key.numeric || args.numeric_sort,
+                        );
+                        if key.reverse { ordering.reverse() } else { ordering }
+                    })
+                    .find(|ordering| ordering.is_ne())
+                    .unwrap_or(Ordering::Equal)
+            };
+
+            // lines with equal keys are ordered byte by byte, unless only one
+            // of them is kept
+            lines.sort_by(|a, b| {
+                let ordering = primary(a, b).then_with(|| if args.unique { Ordering::Equal } else { a.cmp(b) });
+                if args.reverse { ordering.reverse() } else { ordering }
+            });
+
+            if args.unique {
+                lines.dedup_by(|a, b| primary(a, b).is_eq());
+            }
+
+            let output: String = lines.iter().map(|line| format!("{}\n", line)).collect();
+            if ctx.write(&output).await.is_err() {
+                return 1;
+            }
+
+            if status != 0 { 2 } else { 0 }
+        }
+        .boxed_local()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(spec: &str) -> (usize, Option<usize>, bool, bool) {
+        let key = Key::parse(spec).unwrap();
+        (key.start, key.end, key.numeric, key.reverse)
+    }
+
+    #[test]
+    fn keys_take_a_field_range_and_flags() {
+        assert_eq!(key("2"), (2, None, false, false));
+        assert_eq!(key("2,2n"), (2, Some(2), true, false));
+        assert_eq!(key("1,3nr"), (1, Some(3), true, true));
+        assert_eq!(key("3r"), (3, None, false, true));
+
+        for spec in ["0", "", "a", "2,", ",2", "2,2x", "2n,3", "-1"] {
+            assert!(Key::parse(spec).is_none(), "{}", spec);
+        }
+    }
+
+    #[test]
+    fn keys_extract_their_fields() {
+        assert_eq!(Key::parse("2").unwrap().extract("a  b c", None), "b c");
+        assert_eq!(Key::parse("2,2").unwrap().extract("a  b c", None), "b");
+        assert_eq!(Key::parse("2,3").unwrap().extract("a:b::c", Some(':')), "b:");
+        assert_eq!(Key::parse("4").unwrap().extract("a b", None), "");
+        assert_eq!(Key::parse("2,9").unwrap().extract("a b", None), "b");
+    }
+
+    #[test]
+    fn numbers_are_read_from_the_start_of_the_text() {
+        assert_eq!(numeric_value("  42abc"), 42.0);
+        assert_eq!(numeric_value("-3.5"), -3.5);
+        assert_eq!(numeric_value("1-2"), 1.0);
+        assert_eq!(numeric_value("abc"), 0.0);
+        assert_eq!(numeric_value(""), 0.0);
+
+        assert_eq!(compare("10", "9", true), Ordering::Greater);
+        assert_eq!(compare("10", "9", false), Ordering::Less);
+        assert_eq!(compare("x", "0", true), Ordering::Equal);
+    }
+}
@@ -0,0 +1,69 @@
+use clap::{CommandFactory, Parser};
+use futures::{future::LocalBoxFuture, FutureExt};
+
+use crate::{
+    core::shell::{
+        command::{context::CommandContext, parse_args, Command},
+        exec,
+    },
+    vfs::path,
+};
+
+#[derive(Parser, Debug)]
+#[command(name = "source", about = "rOS command to run a script in the current shell", version = "0.1.0")]
+pub struct SourceArgs {
+    file: String,
+
+    /// Positional parameters for the script, the current ones by default
+    #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
+    args: Vec<String>,
+}
+
+pub struct SourceCommand;
+
+register!(SourceCommand);
+
+impl Command for SourceCommand {
+    fn name(&self) -> &'static str {
+        "source"
+    }
+
+    fn aliases(&self) -> &'static [&'static str] {
+        &["."]
+    }
+
+    fn usage(&self) -> clap::Command {
+        SourceArgs::command()
+    }
+
+    fn run<'a>(&'a self, ctx: &'a mut CommandContext) -> LocalBoxFuture<'a, i32> {
+        async move {
+            let args = match parse_args::<SourceArgs>(ctx).await {
+                Ok(args) => args,
+                Err(status) => return status,
+            };
+
+            let file_path = path::resolve(&ctx.cwd, &args.file);
+
+            let saved = (!args.args.is_empty()).then(|| {
+                let params = std::iter::once(ctx.shell.params[0].clone()).chain(args.args).collect();
+                std::mem::replace(&mut ctx.shell.params, params)
+            });
+
+            let result = exec::source_file(ctx.shell, &file_path, &ctx.fds).await;
+
+            if let Some(params) = saved {
+                ctx.shell.params = params;
+            }
+
+            match result {
+                Ok(status) => status,
+                Err(err) => {
+                    ctx.eprint(&format!("{}: {}\n", ctx.args[0], err)).await;
+                    1
+                }
+            }
+        }
+        .boxed_local()
+    }
+}
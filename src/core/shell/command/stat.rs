@@ -0,0 +1,124 @@
+use clap::{CommandFactory, Parser};
+use futures::{future::LocalBoxFuture, FutureExt};
+
+use crate::{
+    core::shell::command::{context::CommandContext, parse_args, Command},
+    vfs::{
+        entry::{FSEntryKind, FSEntryTrait, FSFolder},
+        path,
+    },
+};
+
+#[derive(Parser, Debug)]
+#[command(name = "stat", about = "rOS command to show the details of files", version = "0.1.0")]
+pub struct StatArgs {
+    /// Files to show
+    #[arg(required = true)]
+    files: Vec<String>,
+}
+
+pub struct StatCommand;
+
+register!(StatCommand);
+
+/// The permissions of an entry as `ls -l` shows them, e.g. `drwxr-xr-x`.
+pub fn mode_string(entry: &FSEntryKind) -> String {
+    let kind = match entry {
+        FSEntryKind::File(_) => '-',
+        FSEntryKind::Folder(_) => 'd',
+        FSEntryKind::Link(_) => 'l',
+    };
+
+    let mode = entry.mode();
+    let bits = (0..9).rev().map(|bit| match ((mode >> bit) & 1, bit % 3) {
+        (0, _) => '-',
+        (_, 2) => 'r',
+        (_, 1) => 'w',
+        _ => 'x',
+    });
+
+    std::iter::once(kind).chain(bits).collect()
+}
+
+fn format_time(millis: Option<i64>) -> String {
+    millis
+        .and_then(chrono::DateTime::from_timestamp_millis)
+        .map(|time| time.format("%Y-%m-%d %H:%M:%S%.3f %z").to_string())
+        .unwrap_or_else(|| "-".to_string())
+}
+
+impl Command for StatCommand {
+    fn name(&self) -> &'static str {
+        "stat"
+    }
+
+    fn usage(&self) -> clap::Command {
+        StatArgs::command()
+    }
+
+    fn run<'a>(&'a self, ctx: &'a mut CommandContext) -> LocalBoxFuture<'a, i32> {
+        async move {
+            let args = match parse_args::<StatArgs>(ctx).await {
+                Ok(args) => args,
+                Err(status) => return status,
+            };
+
+            let mut status = 0;
+
+            for file in &args.files {
+                let file_path = path::resolve(&ctx.cwd, file);
+
+                // the root folder has no entry of its own, nor times
+                let (entry, times) = if file_path == "/" {
+                    (FSEntryKind::Folder(FSFolder::new("/", 0)), None)
+                } else {
+                    match ctx.kernel().await.fs.get_entry(&file_path).await {
+                        Ok(Some(entry)) => {
+                            let times = Some((entry.entry.modified_at(), entry.entry.created_at()));
+                            (entry.entry, times)
+                        }
+                        Ok(None) => {
+                            ctx.eprint(&format!("stat: cannot stat '{}': No such file or directory\n", file)).await;
+                            status = 1;
+                            continue;
+                        }
+                        Err(err) => {
+                            ctx.eprint(&format!("stat: cannot stat '{}': {}\n", file, err)).await;
+                            status = 1;
+                            continue;
+                        }
+                    }
+                };
+
+                let (kind, name) = match &entry {
+                    FSEntryKind::File(_) if entry.size() == 0 => ("regular empty file", file.clone()),
+                    FSEntryKind::File(_) => ("regular file", file.clone()),
+                    FSEntryKind::Folder(_) => ("directory", file.clone()),
+                    FSEntryKind::Link(link) => ("symbolic link", format!("'{}' -> '{}'", file, link.target)),
+                };
+
+                let output = format!(
+                    "  File: {}\n  Size: {:<15} Links: {:<6} Type: {}\n\
+                     Access: ({:04o}/{})  Owner: {}\nModify: {}\n Birth: {}\n",
+                    name,
+                    entry.size(),
+                    entry.links(),
+                    kind,
+                    entry.mode(),
+                    mode_string(&entry),
+                    entry.owner(),
+                    format_time(times.map(|(modified, _)| modified)),
+                    format_time(times.map(|(_, created)| created)),
+                );
+
+                if ctx.write(&output).await.is_err() {
+                    return 1;
+                }
+            }
+
+            status
+        }
+        .boxed_local()
+    }
+}
+
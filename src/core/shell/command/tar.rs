@@ -0,0 +1,535 @@
+use std::io::{Read, Write};
+
+use clap::{ArgGroup, CommandFactory, Parser};
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+use futures::{future::LocalBoxFuture, FutureExt};
+
+use crate::{
+    core::shell::command::{context::CommandContext, parse_args, stat::mode_string, Command},
+    vfs::{
+        entry::{FSEntry, FSEntryKind, FSEntryTrait, FSFile, FSFolder, FSLink},
+        path,
+    },
+};
+
+#[derive(Parser, Debug)]
+#[command(
+    name = "tar",
+    about = "rOS command to create, extract or list tar archives",
+    long_about = "Creates an archive of the files and folders given with -c, extracts it with -x or lists it \
+                  with -t, the members named after the operands restricting the last two. Modes and \
+                  modification times are kept. The archive is read from or written to -f, stdin or stdout \
+                  by default, and compressed with gzip when -z is given, which is detected by itself when \
+                  reading.",
+    version = "0.1.0",
+    group(ArgGroup::new("operation").required(true).args(["create", "extract", "list"]))
+)]
+pub struct TarArgs {
+    /// Files and folders to archive, or members to extract or list
+    files: Vec<String>,
+
+    /// Create an archive
+    #[arg(short, long)]
+    create: bool,
+
+    /// Extract members from an archive
+    #[arg(short = 'x', long)]
+    extract: bool,
+
+    /// List the members of an archive
+    #[arg(short = 't', long)]
+    list: bool,
+
+    /// Archive to use, `-` or nothing for stdin or stdout
+    #[arg(short, long, value_name = "ARCHIVE")]
+    file: Option<String>,
+
+    /// Compress the archive with gzip
+    #[arg(short = 'z', long)]
+    gzip: bool,
+
+    /// Archive from or extract to DIR instead of the working folder
+    #[arg(short = 'C', long, value_name = "DIR")]
+    directory: Option<String>,
+
+    /// Show the members processed, in long form when listing
+    #[arg(short, long)]
+    verbose: bool,
+}
+
+pub struct TarCommand;
+
+register!(TarCommand);
+
+/// An entry of an archive, named relative to where it was taken from,
+/// with a trailing `/` for folders.
+pub struct Member {
+    pub name: String,
+    pub entry: FSEntryKind,
+}
+
+impl Member {
+    /// A member read from an archive, with the mode and modification time
+    /// it was stored with. Entries without a mode get the default of their
+    /// kind.
+    pub fn new(name: String, mut entry: FSEntryKind, mode: Option<u32>, modified_at: i64) -> Self {
+        let metadata = entry.metadata_mut();
+        if let Some(mode) = mode {
+            metadata.mode = Some(mode & 0o7777);
+        }
+        metadata.created_at = modified_at;
+        metadata.modified_at = modified_at;
+
+        Self { name, entry }
+    }
+
+    /// A member archiving `entry`, folders being named with a trailing `/`.
+    fn archived(mut name: String, entry: FSEntryKind) -> Self {
+        if matches!(entry, FSEntryKind::Folder(_)) {
+            name.push('/');
+        }
+
+        Self { name, entry }
+    }
+
+    /// Whether the member is one of `names` or below one of them, every
+    /// member matching when there are none.
+    pub fn is_selected(&self, names: &[String]) -> bool {
+        is_selected(&self.name, names)
+    }
+
+    /// Where the member goes when extracted below the folder `dest`: an
+    /// absolute name is taken relative to it as well, and no name may climb
+    /// out of it with `..`.
+    pub fn target(&self, dest: &str) -> Result<String, String> {
+        let name = self.name.trim_start_matches('/');
+        if name.split('/').any(|component| component == "..") {
+            return Err("Member name contains '..'".into());
+        }
+
+        Ok(path::resolve(dest, name))
+    }
+
+    /// The member as `tar -tv` lists it.
+    pub fn long_listing(&self) -> String {
+        let owner = self.entry.owner();
+        let time = chrono::DateTime::from_timestamp_millis(self.entry.modified_at())
+            .map(|time| time.format("%Y-%m-%d %H:%M").to_string())
+            .unwrap_or_default();
+        let target = match &self.entry {
+            FSEntryKind::Link(link) => format!(" -> {}", link.target),
+            _ => String::new(),
+        };
+
+        format!(
+            "{} {}/{} {:>8} {} {}{}",
+            mode_string(&self.entry),
+            owner,
+            owner,
+            self.entry.size(),
+            time,
+            self.name,
+            target
+        )
+    }
+}
+
+fn is_selected(name: &str, names: &[String]) -> bool {
+    let name = name.trim_end_matches('/');
+    names.is_empty()
+        || names.iter().map(|selected| selected.trim_end_matches('/')).any(|selected| {
+            name == selected || name.strip_prefix(selected).is_some_and(|rest| rest.starts_with('/'))
+        })
+}
+
+fn components(path: &str) -> Vec<&str> {
+    path.split('/').collect()
+}
+
+/// `entry` as a member named `name`, with the contents of a file.
+async fn archived(ctx: &CommandContext<'_>, name: String, mut entry: FSEntry) -> Member {
+    let _ = ctx.kernel().await.fs.load_contents(&mut entry).await;
+    Member::archived(name, entry.entry)
+}
+
+/// The members archiving `operands`, found from `dir`: each one, with
+/// everything below it when `recursive`, parents before their contents.
+/// Missing operands are reported, false being returned with the members.
+pub async fn collect_members(
+    ctx: &CommandContext<'_>,
+    command: &str,
+    dir: &str,
+    operands: &[String],
+    recursive: bool,
+) -> (Vec<Member>, bool) {
+    let mut members = Vec::new();
+    let mut found = true;
+    let mut warned = false;
+
+    for operand in operands {
+        let base = path::resolve(dir, operand);
+        let mut name = operand.trim_end_matches('/').to_string();
+
+        if name.starts_with('/') {
+            if !warned {
+                ctx.eprint(&format!("{}: Removing leading `/' from member names\n", command)).await;
+                warned = true;
+            }
+            name = name.trim_start_matches('/').to_string();
+        }
+
+        if base != "/" {
+            match ctx.kernel().await.fs.get_entry(&base).await {
+                Ok(Some(entry)) => members.push(archived(ctx, name.clone(), entry).await),
+                _ => {
+                    ctx.eprint(&format!("{}: {}: No such file or directory\n", command, operand)).await;
+                    found = false;
+                    continue;
+                }
+            }
+        }
+
+        if !recursive {
+            continue;
+        }
+
+        let prefix = if base == "/" { "/".to_string() } else { format!("{}/", base) };
+        let mut below: Vec<FSEntry> = ctx.kernel().await.fs.read_tree(&base).await.unwrap_or_default();
+        below.retain(|entry| entry.abs_path.starts_with(&prefix));
+        below.sort_by(|a, b| components(&a.abs_path).cmp(&components(&b.abs_path)));
+
+        for entry in below {
+            let rest = &entry.abs_path[prefix.len()..];
+            let name = if name.is_empty() { rest.to_string() } else { format!("{}/{}", name, rest) };
+            members.push(archived(ctx, name, entry).await);
+        }
+    }
+
+    (members, found)
+}
+
+/// Extracts `member` below the folder `dest`, creating the folders above
+/// it that are missing. Returns the path it was extracted to.
+pub async fn extract_member(ctx: &CommandContext<'_>, dest: &str, member: Member) -> Result<String, String> {
+    let target = member.target(dest)?;
+    if target == path::normalize(dest) {
+        return Ok(target);
+    }
+
+    let mut kernel = ctx.kernel().await;
+    let mut folder = "/".to_string();
+
+    for component in path::parent(&target).split('/').filter(|component| !component.is_empty()) {
+        folder = path::join(&folder, component);
+        if !kernel.fs.exists(&folder).await.unwrap_or(false) {
+            kernel.fs.create_folder_absolute(&folder).await.map_err(|e| e.to_string())?;
+        }
+    }
+
+    let mut entry = member.entry;
+    let metadata = entry.metadata_mut();
+    metadata.name = path::file_name(&target).to_string();
+    metadata.is_hidden = metadata.name.starts_with('.');
+
+    let entry = FSEntry { abs_path: target.clone(), entry };
+    kernel.fs.restore_entry(&entry).await.map_err(|e| e.to_string())?;
+
+    Ok(target)
+}
+
+/// Writes an archive to `file`, relative to the working folder, stdout
+/// when it is `-`.
+pub async fn write_archive(ctx: &CommandContext<'_>, file: &str, data: &[u8]) -> Result<(), String> {
+    if file == "-" {
+        return ctx.stdout().write(data).await.map(|_| ()).map_err(|e| e.to_string());
+    }
+
+    let file_path = path::resolve(&ctx.cwd, file);
+    ctx.kernel().await.fs.write_file(&file_path, data, false).await.map(|_| ()).map_err(|e| e.to_string())
+}
+
+fn create(members: &[Member], gzip: bool) -> std::io::Result<Vec<u8>> {
+    let mut builder = tar::Builder::new(Vec::new());
+
+    for member in members {
+        let mut header = tar::Header::new_gnu();
+        header.set_mode(member.entry.mode());
+        header.set_mtime((member.entry.modified_at() / 1000).max(0) as u64);
+        header.set_username(&member.entry.owner())?;
+        header.set_groupname(&member.entry.owner())?;
+
+        match &member.entry {
+            FSEntryKind::File(file) => {
+                let data = file.data.as_deref().unwrap_or_default();
+                header.set_entry_type(tar::EntryType::Regular);
+                header.set_size(data.len() as u64);
+                builder.append_data(&mut header, &member.name, data)?;
+            }
+            FSEntryKind::Folder(_) => {
+                header.set_entry_type(tar::EntryType::Directory);
+                header.set_size(0);
+                builder.append_data(&mut header, &member.name, std::io::empty())?;
+            }
+            FSEntryKind::Link(link) => {
+                header.set_entry_type(tar::EntryType::Symlink);
+                header.set_size(0);
+                builder.append_link(&mut header, &member.name, &link.target)?;
+            }
+        }
+    }
+
+    let archive = builder.into_inner()?;
+    if !gzip {
+        return Ok(archive);
+    }
+
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(&archive)?;
+    encoder.finish()
+}
+
+/// The name of a member left out when reading an archive, with the reason.
+type Skipped = (String, String);
+
+/// The members of an archive, gunzipped first when it starts with the
+/// gzip magic number, and those left out.
+fn read(data: &[u8]) -> std::io::Result<(Vec<Member>, Vec<Skipped>)> {
+    let mut unzipped = Vec::new();
+    let data = if data.starts_with(&[0x1f, 0x8b]) {
+        GzDecoder::new(data).read_to_end(&mut unzipped)?;
+        &unzipped[..]
+    } else {
+        data
+    };
+
+    let mut archive = tar::Archive::new(data);
+    let mut members = Vec::new();
+    let mut skipped = Vec::new();
+
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        let name = entry.path()?.to_string_lossy().into_owned();
+        let base_name = path::file_name(&name).to_string();
+        let mode = entry.header().mode().ok();
+        let modified_at = entry.header().mtime().unwrap_or(0) as i64 * 1000;
+
+        let kind = match entry.header().entry_type() {
+            tar::EntryType::Directory => FSEntryKind::Folder(FSFolder::new(&base_name, modified_at)),
+            tar::EntryType::Symlink => {
+                let target = entry.link_name()?.map(|target| target.to_string_lossy().into_owned()).unwrap_or_default();
+                FSEntryKind::Link(FSLink::new(&base_name, &target, modified_at))
+            }
+            tar::EntryType::Regular | tar::EntryType::Continuous => {
+                let mut data = Vec::new();
+                entry.read_to_end(&mut data)?;
+                FSEntryKind::File(FSFile::new(&base_name, data, modified_at))
+            }
+            tar::EntryType::Link => {
+                let target = entry.link_name()?.map(|target| target.to_string_lossy().into_owned()).unwrap_or_default();
+                skipped.push((name, format!("Skipping hard link to {}", target)));
+                continue;
+            }
+            // devices and the like have nothing to stand for them
+            kind => {
+                skipped.push((name, format!("Skipping member of unsupported type {:?}", kind)));
+                continue;
+            }
+        };
+
+        members.push(Member::new(name, kind, mode, modified_at));
+    }
+
+    Ok((members, skipped))
+}
+
+impl Command for TarCommand {
+    fn name(&self) -> &'static str {
+        "tar"
+    }
+
+    fn usage(&self) -> clap::Command {
+        TarArgs::command()
+    }
+
+    fn run<'a>(&'a self, ctx: &'a mut CommandContext) -> LocalBoxFuture<'a, i32> {
+        async move {
+            let args = match parse_args::<TarArgs>(ctx).await {
+                Ok(args) => args,
+                Err(status) => return status,
+            };
+
+            let archive = args.file.clone().unwrap_or_else(|| "-".to_string());
+            let dir = path::resolve(&ctx.cwd, args.directory.as_deref().unwrap_or("."));
+
+            if args.create {
+                if args.files.is_empty() {
+                    ctx.eprint("tar: Cowardly refusing to create an empty archive\n").await;
+                    return 2;
+                }
+
+                let (members, found) = collect_members(ctx, "tar", &dir, &args.files, true).await;
+
+                if args.verbose {
+                    let names: String = members.iter().map(|member| format!("{}\n", member.name)).collect();
+                    // the names would end up in the archive on stdout
+                    if archive == "-" { ctx.eprint(&names).await } else { ctx.print(&names).await }
+                }
+
+                let data = match create(&members, args.gzip) {
+                    Ok(data) => data,
+                    Err(err) => {
+                        ctx.eprint(&format!("tar: {}\n", err)).await;
+                        return 2;
+                    }
+                };
+
+                if let Err(err) = write_archive(ctx, &archive, &data).await {
+                    ctx.eprint(&format!("tar: {}: {}\n", archive, err)).await;
+                    return 2;
+                }
+
+                return if found { 0 } else { 2 };
+            }
+
+            let contents = ctx.read_file(&archive).await.map(|data| read(&data).map_err(|e| e.to_string()));
+            let (members, skipped) = match contents {
+                Ok(Ok(contents)) => contents,
+                Ok(Err(err)) | Err(err) => {
+                    ctx.eprint(&format!("tar: {}: {}\n", archive, err)).await;
+                    return 2;
+                }
+            };
+
+            let mut status = 0;
+
+            for (name, warning) in skipped.iter().filter(|(name, _)| is_selected(name, &args.files)) {
+                ctx.eprint(&format!("tar: {}: {}\n", name, warning)).await;
+                if !args.list {
+                    status = 2;
+                }
+            }
+            let mut matched = vec![false; args.files.len()];
+
+            for member in members {
+                if !member.is_selected(&args.files) {
+                    continue;
+                }
+                for (i, name) in args.files.iter().enumerate() {
+                    matched[i] |= member.is_selected(std::slice::from_ref(name));
+                }
+
+                if args.list {
+                    let line = if args.verbose { member.long_listing() } else { member.name.clone() };
+                    ctx.print(&format!("{}\n", line)).await;
+                    continue;
+                }
+
+                ctx.shell.checkpoint().await;
+                if args.verbose {
+                    ctx.print(&format!("{}\n", member.name)).await;
+                }
+
+                let name = member.name.clone();
+                if let Err(err) = extract_member(ctx, &dir, member).await {
+                    ctx.eprint(&format!("tar: {}: Cannot extract: {}\n", name, err)).await;
+                    status = 2;
+                }
+            }
+
+            for (name, _) in args.files.iter().zip(matched).filter(|(_, matched)| !matched) {
+                ctx.eprint(&format!("tar: {}: Not found in archive\n", name)).await;
+                status = 2;
+            }
+
+            status
+        }
+        .boxed_local()
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A file, a folder and a link with modes other than the default and a
+    /// modification time that is not a whole second.
+    fn members() -> Vec<Member> {
+        let now = 1_700_000_001_500;
+        let mut file = FSEntryKind::File(FSFile::new("a.txt", b"hello".to_vec(), now));
+        file.metadata_mut().mode = Some(0o640);
+        let mut folder = FSEntryKind::Folder(FSFolder::new("docs", now));
+        folder.metadata_mut().mode = Some(0o700);
+        let link = FSEntryKind::Link(FSLink::new("latest", "docs/a.txt", now));
+
+        vec![
+            Member::archived("docs".into(), folder),
+            Member::archived("docs/a.txt".into(), file),
+            Member::archived("latest".into(), link),
+        ]
+    }
+
+    fn summary(members: &[Member]) -> Vec<(String, u32, i64, String)> {
+        members
+            .iter()
+            .map(|member| {
+                let contents = match &member.entry {
+                    FSEntryKind::File(file) => String::from_utf8_lossy(file.data.as_deref().unwrap_or_default()).into(),
+                    FSEntryKind::Folder(_) => "folder".into(),
+                    FSEntryKind::Link(link) => format!("-> {}", link.target),
+                };
+                (member.name.clone(), member.entry.mode(), member.entry.modified_at(), contents)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn members_survive_an_archive_to_the_second() {
+        for gzip in [false, true] {
+            let (read, skipped) = read(&create(&members(), gzip).unwrap()).unwrap();
+            assert!(skipped.is_empty());
+            assert_eq!(
+                summary(&read),
+                [
+                    ("docs/".into(), 0o700, 1_700_000_001_000, "folder".into()),
+                    ("docs/a.txt".into(), 0o640, 1_700_000_001_000, "hello".into()),
+                    ("latest".into(), 0o777, 1_700_000_001_000, "-> docs/a.txt".into()),
+                ]
+            );
+        }
+    }
+
+    #[test]
+    fn hard_links_are_reported_rather_than_dropped_silently() {
+        let mut builder = tar::Builder::new(Vec::new());
+        let mut header = tar::Header::new_gnu();
+        header.set_entry_type(tar::EntryType::Link);
+        header.set_size(0);
+        builder.append_link(&mut header, "b.txt", "a.txt").unwrap();
+
+        let (read, skipped) = read(&builder.into_inner().unwrap()).unwrap();
+        assert!(read.is_empty());
+        assert_eq!(skipped, [("b.txt".to_string(), "Skipping hard link to a.txt".to_string())]);
+    }
+
+    #[test]
+    fn members_are_extracted_below_the_destination() {
+        let member = |name: &str| Member::archived(name.into(), FSEntryKind::File(FSFile::new("x", Vec::new(), 0)));
+
+        assert_eq!(member("a/b").target("/tmp"), Ok("/tmp/a/b".to_string()));
+        assert_eq!(member("/etc/passwd").target("/tmp"), Ok("/tmp/etc/passwd".to_string()));
+        assert_eq!(member("../x").target("/tmp"), Err("Member name contains '..'".to_string()));
+        assert_eq!(member("a/../../x").target("/tmp"), Err("Member name contains '..'".to_string()));
+        assert_eq!(member("/../x").target("/tmp"), Err("Member name contains '..'".to_string()));
+        assert_eq!(member("a..b").target("/tmp"), Ok("/tmp/a..b".to_string()));
+    }
+
+    #[test]
+    fn selection_covers_what_is_below_a_name() {
+        let names = ["docs/".to_string()];
+        assert!(is_selected("docs/", &names));
+        assert!(is_selected("docs/a.txt", &names));
+        assert!(!is_selected("docsx", &names));
+        assert!(is_selected("anything", &[]));
+    }
+}
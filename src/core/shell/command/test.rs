@@ -0,0 +1,238 @@
+use clap::{CommandFactory, Parser};
+use futures::{future::LocalBoxFuture, FutureExt};
+
+use crate::{
+    core::shell::command::{
+        context::CommandContext,
+        expr::{Expr, ExprParser, Grammar},
+        Command,
+    },
+    vfs::{entry::FSEntryKind, path},
+};
+
+/// Exit status of `test` when the expression is malformed.
+const STATUS_ERROR: i32 = 2;
+
+const UNARY_OPS: &[&str] = &["-n", "-z", "-e", "-f", "-d", "-s", "-L", "-h", "-r", "-w", "-x"];
+
+const BINARY_OPS: &[&str] = &["=", "==", "!=", "<", ">", "-eq", "-ne", "-lt", "-le", "-gt", "-ge"];
+
+/// Only describes the usage of `test`: its operands look like flags, so
+/// they are not parsed by clap.
+#[derive(Parser, Debug)]
+#[command(
+    name = "test",
+    about = "rOS command to evaluate a conditional expression",
+    version = "0.1.0",
+    disable_help_flag = true,
+    disable_version_flag = true
+)]
+pub struct TestArgs {
+    /// Strings, files and integers to compare, e.g. `-f FILE` or `A -lt B`
+    #[arg(allow_hyphen_values = true)]
+    expression: Vec<String>,
+}
+
+/// `test expression` and `[ expression ]`.
+pub struct TestCommand;
+
+register!(TestCommand);
+
+/// A term of `test`: an operator with its operands, or a lone string.
+enum Primary {
+    /// True when it is not empty.
+    Str(String),
+    Unary(String, String),
+    Binary(String, String, String),
+}
+
+struct TestGrammar;
+
+/// The next argument, which the expression cannot end before.
+fn argument(parser: &mut ExprParser<'_, TestGrammar>) -> Result<String, String> {
+    parser.next().ok_or_else(|| "argument expected".into())
+}
+
+impl Grammar for TestGrammar {
+    type Term = Primary;
+
+    const NOT: &'static [&'static str] = &["!"];
+    const AND: &'static [&'static str] = &["-a"];
+    const OR: &'static [&'static str] = &["-o"];
+    const IMPLIED_AND: bool = false;
+
+    fn primary(parser: &mut ExprParser<'_, Self>) -> Result<Expr<Primary>, String> {
+        let remaining = parser.remaining();
+
+        if remaining >= 3 && BINARY_OPS.contains(&parser.args[parser.pos + 1].as_str()) {
+            let left = argument(parser)?;
+            let op = argument(parser)?;
+            let right = argument(parser)?;
+            return Ok(Expr::Term(Primary::Binary(op, left, right)));
+        }
+
+        let arg = argument(parser)?;
+
+        if arg == "(" && remaining >= 2 {
+            let expr = parser.or()?;
+            if parser.next().as_deref() != Some(")") {
+                return Err("`)' expected".into());
+            }
+            return Ok(expr);
+        }
+
+        if remaining >= 2 && UNARY_OPS.contains(&arg.as_str()) {
+            return Ok(Expr::Term(Primary::Unary(arg, argument(parser)?)));
+        }
+
+        Ok(Expr::Term(Primary::Str(arg)))
+    }
+}
+
+fn integer(s: &str) -> Result<i64, String> {
+    s.trim().parse().map_err(|_| format!("{}: integer expression expected", s))
+}
+
+fn eval<'a>(ctx: &'a CommandContext, expr: &'a Expr<Primary>) -> LocalBoxFuture<'a, Result<bool, String>> {
+    async move {
+        match expr {
+            Expr::Not(expr) => Ok(!eval(ctx, expr).await?),
+            Expr::And(left, right) => Ok(eval(ctx, left).await? && eval(ctx, right).await?),
+            Expr::Or(left, right) => Ok(eval(ctx, left).await? || eval(ctx, right).await?),
+            Expr::Term(Primary::Str(s)) => Ok(!s.is_empty()),
+            Expr::Term(Primary::Unary(op, arg)) => match op.as_str() {
+                "-n" => Ok(!arg.is_empty()),
+                "-z" => Ok(arg.is_empty()),
+                _ => {
+                    let file_path = path::resolve(&ctx.cwd, arg);
+                    let entry = ctx.kernel().await.fs.get_entry(&file_path).await.ok().flatten();
+
+                    Ok(match (op.as_str(), entry.map(|e| e.entry)) {
+                        (_, None) => file_path == "/" && matches!(op.as_str(), "-e" | "-d" | "-s" | "-r" | "-w" | "-x"),
+                        ("-f", Some(kind)) => matches!(kind, FSEntryKind::File(_)),
+                        ("-d", Some(kind)) => matches!(kind, FSEntryKind::Folder(_)),
+                        ("-L" | "-h", Some(kind)) => matches!(kind, FSEntryKind::Link(_)),
+                        ("-s", Some(FSEntryKind::File(file))) => file.size > 0,
+                        // permissions are not enforced, any existing entry is readable and writable
+                        (_, Some(_)) => true,
+                    })
+                }
+            },
+            Expr::Term(Primary::Binary(op, left, right)) => match op.as_str() {
+                "=" | "==" => Ok(left == right),
+                "!=" => Ok(left != right),
+                "<" => Ok(left < right),
+                ">" => Ok(left > right),
+                _ => {
+                    let (left, right) = (integer(left)?, integer(right)?);
+
+                    Ok(match op.as_str() {
+                        "-eq" => left == right,
+                        "-ne" => left != right,
+                        "-lt" => left < right,
+                        "-le" => left <= right,
+                        "-gt" => left > right,
+                        _ => left >= right,
+                    })
+                }
+            },
+        }
+    }
+    .boxed_local()
+}
+
+impl Command for TestCommand {
+    fn name(&self) -> &'static str {
+        "test"
+    }
+
+    fn aliases(&self) -> &'static [&'static str] {
+        &["["]
+    }
+
+    fn usage(&self) -> clap::Command {
+        TestArgs::command()
+    }
+
+    fn run<'a>(&'a self, ctx: &'a mut CommandContext) -> LocalBoxFuture<'a, i32> {
+        async move {
+            let name = ctx.args[0].clone();
+            let mut args = &ctx.args[1..];
+
+            if name == "[" {
+                match args.split_last() {
+                    Some((last, rest)) if last == "]" => args = rest,
+                    _ => {
+                        ctx.eprint("[: missing `]'\n").await;
+                        return STATUS_ERROR;
+                    }
+                }
+            }
+
+            if args.is_empty() {
+                return 1;
+            }
+
+            let mut parser = ExprParser::new(args, TestGrammar);
+            let expr = match parser.or() {
+                Ok(_) if parser.pos < args.len() => Err("too many arguments".to_string()),
+                result => result,
+            };
+
+            let result = match expr {
+                Ok(expr) => eval(ctx, &expr).await,
+                Err(err) => Err(err),
+            };
+
+            match result {
+                Ok(true) => 0,
+                Ok(false) => 1,
+                Err(err) => {
+                    ctx.eprint(&format!("{}: {}\n", name, err)).await;
+                    STATUS_ERROR
+                }
+            }
+        }
+        .boxed_local()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `expression` parsed, written with prefix operators.
+    fn parse(expression: &str) -> Result<String, String> {
+        fn show(expr: &Expr<Primary>) -> String {
+            match expr {
+                Expr::Not(expr) => format!("(not {})", show(expr)),
+                Expr::And(left, right) => format!("(and {} {})", show(left), show(right)),
+                Expr::Or(left, right) => format!("(or {} {})", show(left), show(right)),
+                Expr::Term(Primary::Str(s)) => format!("{:?}", s),
+                Expr::Term(Primary::Unary(op, arg)) => format!("({} {})", op, arg),
+                Expr::Term(Primary::Binary(op, left, right)) => format!("({} {} {})", op, left, right),
+            }
+        }
+
+        let args: Vec<String> = expression.split_whitespace().map(String::from).collect();
+        let mut parser = ExprParser::new(&args, TestGrammar);
+
+        parser.or().map(|expr| show(&expr))
+    }
+
+    #[test]
+    fn operators_bind_as_in_posix_test() {
+        assert_eq!(parse("-n a -o ! -f b -a c = d"), Ok("(or (-n a) (and (not (-f b)) (= c d)))".into()));
+        assert_eq!(parse("( a -o b ) -a c"), Ok(r#"(and (or "a" "b") "c")"#.into()));
+    }
+
+    #[test]
+    fn operators_without_operands_are_strings() {
+        assert_eq!(parse("!"), Ok(r#""!""#.into()));
+        assert_eq!(parse("-f"), Ok(r#""-f""#.into()));
+        assert_eq!(parse("! -n"), Ok(r#"(not "-n")"#.into()));
+        assert_eq!(parse("-n = -n"), Ok("(= -n -n)".into()));
+        assert_eq!(parse("( a"), Err("`)' expected".into()));
+        assert_eq!(parse("a -a"), Err("argument expected".into()));
+    }
+}
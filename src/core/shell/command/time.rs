@@ -1,26 +1,40 @@
+use clap::{CommandFactory, Parser};
+use futures::{future::LocalBoxFuture, FutureExt};
 
-use clap::Parser;
-
-use crate::core::shell::command::ShellCommand;
+use crate::core::shell::command::{context::CommandContext, parse_args, Command};
 
 #[derive(Parser, Debug)]
 #[command(name = "time", about = "rOS time command", version = "0.1.0")]
-pub struct TimeCommand {
+pub struct TimeArgs {
 }
 
-impl ShellCommand for TimeCommand {
-    async fn execute(k: &mut crate::kernel::Kernel, cmd: &str) -> String {
-        let args = TimeCommand::try_parse_from(cmd.trim().split_whitespace());
+impl Command for TimeCommand {
+    fn name(&self) -> &'static str {
+        "time"
+    }
 
-        if let Err(e) = &args {
-            return format!("{}", e);
-        }
+    fn usage(&self) -> clap::Command {
+        TimeArgs::command()
+    }
+
+    fn run<'a>(&'a self, ctx: &'a mut CommandContext) -> LocalBoxFuture<'a, i32> {
+        async move {
+            if let Err(status) = parse_args::<TimeArgs>(ctx).await {
+                return status;
+            }
 
-        let time = k.get_time();
-        let timestamp = k.get_timestamp();
-        
-        k.print(&format!("\nSystem clock:\nTimestamp: {}\nUNIX Epoch (mills): {}\n", timestamp, time));
+            let (time, timestamp) = {
+                let k = ctx.kernel().await;
+                (k.get_time(), k.get_timestamp())
+            };
 
-        "".into()
+            ctx.print(&format!("System clock:\nTimestamp: {}\nUNIX Epoch (mills): {}\n", timestamp, time)).await;
+            0
+        }
+        .boxed_local()
     }
-}
\ No newline at end of file
+}
+
+pub struct TimeCommand;
+
+register!(TimeCommand);
@@ -1,32 +1,44 @@
+use clap::{CommandFactory, Parser};
+use futures::{future::LocalBoxFuture, FutureExt};
 
-use clap::Parser;
-
-use crate::{core::{shell::command::ShellCommand}};
+use crate::core::shell::command::{context::CommandContext, parse_args, Command};
 
 #[derive(Parser, Debug)]
 #[command(name = "top", about = "rOS process manager command", version = "0.1.0")]
-pub struct TopCommand {
+pub struct TopArgs {
 }
 
-impl ShellCommand for TopCommand {
-    async fn execute(k: &mut crate::kernel::Kernel, cmd: &str) -> String {
-        let args = TopCommand::try_parse_from(cmd.trim().split_whitespace());
+impl Command for TopCommand {
+    fn name(&self) -> &'static str {
+        "top"
+    }
 
-        if let Err(e) = &args {
-            return format!("{}", e);
-        }
-        
-        let mut output = format!("\n{:<30} {:<30}\n", "Process Name", "PID");
+    fn usage(&self) -> clap::Command {
+        TopArgs::command()
+    }
 
-        for p in k.processes.values() {
-            let pid = p.pid();
-            let pname = p.name();
+    fn run<'a>(&'a self, ctx: &'a mut CommandContext) -> LocalBoxFuture<'a, i32> {
+        async move {
+            if let Err(status) = parse_args::<TopArgs>(ctx).await {
+                return status;
+            }
 
-            output.push_str(&format!("{:<30} {:<30}\n", pname, pid));
-        }
-        
-        k.print(&output);
+            let mut output = format!("{:<30} {:<30}\n", "Process Name", "PID");
+
+            for p in ctx.kernel().await.processes.values() {
+                let pid = p.pid();
+                let pname = p.name();
 
-        "".into()
+                output.push_str(&format!("{:<30} {:<30}\n", pname, pid));
+            }
+
+            ctx.print(&output).await;
+            0
+        }
+        .boxed_local()
     }
-}
\ No newline at end of file
+}
+
+pub struct TopCommand;
+
+register!(TopCommand);
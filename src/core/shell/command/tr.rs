@@ -0,0 +1,212 @@
+use clap::{CommandFactory, Parser};
+use futures::{future::LocalBoxFuture, FutureExt};
+
+use crate::core::shell::command::{context::CommandContext, parse_args, Command};
+
+#[derive(Parser, Debug)]
+#[command(
+    name = "tr",
+    about = "rOS command to translate, squeeze or delete characters of stdin",
+    long_about = "Copies stdin to stdout, replacing the characters of SET1 by the ones at the same \
+                  position in SET2, the last one of SET2 being repeated as needed.\n\n\
+                  Sets are made of characters, ranges such as a-z, the escapes \\n, \\t, \\r and \\\\, \
+                  and classes: [:alpha:], [:digit:], [:alnum:], [:upper:], [:lower:], [:space:], \
+                  [:blank:], [:punct:] and [:xdigit:].",
+    version = "0.1.0"
+)]
+pub struct TrArgs {
+    #[arg(value_name = "SET1")]
+    set1: String,
+
+    #[arg(value_name = "SET2")]
+    set2: Option<String>,
+
+    /// Delete the characters of SET1 instead of translating them
+    #[arg(short, long)]
+    delete: bool,
+
+    /// Replace runs of a character of the last set given by a single one
+    #[arg(short, long)]
+    squeeze_repeats: bool,
+}
+
+pub struct TrCommand;
+
+register!(TrCommand);
+
+/// The characters of a class, e.g. `alpha` for `[:alpha:]`.
+fn class(name: &str) -> Option<Vec<char>> {
+    let test: fn(&char) -> bool = match name {
+        "alpha" => char::is_ascii_alphabetic,
+        "digit" => char::is_ascii_digit,
+        "alnum" => char::is_ascii_alphanumeric,
+        "upper" => char::is_ascii_uppercase,
+        "lower" => char::is_ascii_lowercase,
+        "space" => char::is_ascii_whitespace,
+        "blank" => |c| *c == ' ' || *c == '\t',
+        "punct" => char::is_ascii_punctuation,
+        "xdigit" => char::is_ascii_hexdigit,
+        _ => return None,
+    };
+
+    Some((0..=127u8).map(char::from).filter(test).collect())
+}
+
+/// Expands a set to its characters, in order.
+fn parse_set(spec: &str) -> Result<Vec<char>, String> {
+    let mut chars = Vec::new();
+    let mut rest = spec;
+
+    while let Some(c) = rest.chars().next() {
+        if let Some(after) = rest.strip_prefix("[:")
+            && let Some((name, after)) = after.split_once(":]")
+        {
+            chars.extend(class(name).ok_or_else(|| format!("invalid character class '{}'", name))?);
+            rest = after;
+            continue;
+        }
+
+        let (c, len) = match (c, rest[c.len_utf8()..].chars().next()) {
+            ('\\', Some('n')) => ('\n', 2),
+            ('\\', Some('t')) => ('\t', 2),
+            ('\\', Some('r')) => ('\r', 2),
+            ('\\', Some(escaped)) => (escaped, 1 + escaped.len_utf8()),
+            (c, _) => (c, c.len_utf8()),
+        };
+        rest = &rest[len..];
+
+        // `a-z`, a `-` at either end of the set standing for itself
+        if let Some(after) = rest.strip_prefix('-')
+            && let Some(end) = after.chars().next()
+        {
+            if end < c {
+                return Err(format!("range-endpoints of '{}-{}' are in reverse collating sequence order", c, end));
+            }
+            chars.extend(c..=end);
+            rest = &after[end.len_utf8()..];
+            continue;
+        }
+
+        chars.push(c);
+    }
+
+    Ok(chars)
+}
+
+impl Command for TrCommand {
+    fn name(&self) -> &'static str {
+        "tr"
+    }
+
+    fn usage(&self) -> clap::Command {
+        TrArgs::command()
+    }
+
+    fn run<'a>(&'a self, ctx: &'a mut CommandContext) -> LocalBoxFuture<'a, i32> {
+        async move {
+            let args = match parse_args::<TrArgs>(ctx).await {
+                Ok(args) => args,
+                Err(status) => return status,
+            };
+
+            let sets = parse_set(&args.set1).and_then(|set1| {
+                let set2 = args.set2.as_deref().map(parse_set).transpose()?;
+                Ok((set1, set2))
+            });
+
+            let (set1, set2) = match sets {
+                Ok(sets) => sets,
+                Err(err) => {
+                    ctx.eprint(&format!("tr: {}\n", err)).await;
+                    return 1;
+                }
+            };
+
+            let translating = !args.delete;
+            if translating && set2.as_ref().is_none_or(Vec::is_empty) && !args.squeeze_repeats {
+                ctx.eprint("tr: missing operand after SET1\n").await;
+                return 1;
+            }
+
+            // squeezing applies to the last set given
+            let squeeze: &[char] = match (&set2, args.squeeze_repeats) {
+                (_, false) => &[],
+                (Some(set2), true) => set2,
+                (None, true) => &set1,
+            };
+
+            let input = ctx.read_to_string().await;
+            let mut output = String::with_capacity(input.len());
+            let mut last = None;
+
+            for c in input.chars() {
+                let c = if args.delete {
+                    if set1.contains(&c) {
+                        continue;
+                    }
+                    c
+                } else {
+                    match (set1.iter().rposition(|from| *from == c), &set2) {
+                        (Some(i), Some(set2)) if !set2.is_empty() => set2[i.min(set2.len() - 1)],
+                        _ => c,
+                    }
+                };
+
+                if last == Some(c) && squeeze.contains(&c) {
+                    continue;
+                }
+
+                output.push(c);
+                last = Some(c);
+            }
+
+            if ctx.write(&output).await.is_err() {
+                return 1;
+            }
+
+            0
+        }
+        .boxed_local()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn set(spec: &str) -> String {
+        parse_set(spec).unwrap().into_iter().collect()
+    }
+
+    #[test]
+    fn sets_expand_ranges_and_escapes() {
+        assert_eq!(set("abc"), "abc");
+        assert_eq!(set("a-e"), "abcde");
+        assert_eq!(set("a-cx-z0"), "abcxyz0");
+        assert_eq!(set("-a-"), "-a-");
+        assert_eq!(set(r"\n\t\r\\"), "\n\t\r\\");
+        assert_eq!(set(r"\-"), "-");
+        assert_eq!(set("é-ë"), "éêë");
+        assert_eq!(set("a-a"), "a");
+    }
+
+    #[test]
+    fn sets_expand_classes() {
+        assert_eq!(set("[:digit:]"), "0123456789");
+        assert_eq!(set("[:upper:]").len(), 26);
+        assert_eq!(set("[:xdigit:]"), "0123456789ABCDEFabcdef");
+        assert_eq!(set("[:blank:]"), "\t ");
+        assert_eq!(set("x[:digit:]y"), "x0123456789y");
+        // not a class without its closing `:]`
+        assert_eq!(set("[:digit"), "[:digit");
+    }
+
+    #[test]
+    fn malformed_sets_are_rejected() {
+        assert_eq!(parse_set("[:foo:]"), Err("invalid character class 'foo'".to_string()));
+        assert_eq!(
+            parse_set("z-a"),
+            Err("range-endpoints of 'z-a' are in reverse collating sequence order".to_string())
+        );
+    }
+}
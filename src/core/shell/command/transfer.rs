@@ -0,0 +1,531 @@
+use std::{cell::RefCell, rc::Rc};
+
+use clap::{CommandFactory, Parser};
+use futures::{future::LocalBoxFuture, FutureExt};
+use js_sys::{Array, Promise, Uint8Array};
+use wasm_bindgen::{closure::Closure, JsCast, JsValue};
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{
+    Blob, BlobPropertyBag, DragEvent, Event, File, FileSystemDirectoryEntry, FileSystemEntry, FileSystemFileEntry,
+    HtmlAnchorElement, HtmlInputElement, HtmlTextAreaElement, Url,
+};
+
+use crate::{
+    core::shell::command::{context::CommandContext, du::human_size, parse_args, tar::collect_members, zip, Command},
+    kernel::{fd::FileDescriptor, sleep},
+    vfs::{
+        entry::{FSEntry, FSEntryKind, FSFile},
+        path,
+    },
+};
+
+/// Bytes read from a host file at a time.
+const CHUNK_SIZE: f64 = 1024.0 * 1024.0;
+
+/// How often the choice of files is checked for while waiting for it.
+const POLL_MS: i32 = 50;
+
+#[derive(Parser, Debug)]
+#[command(
+    name = "upload",
+    about = "rOS command to copy files from the host into the volume",
+    long_about = "Opens the file picker of the browser and copies the files chosen into DEST, the working \
+                  folder by default. Files and folders can also be dropped on the console instead, folders \
+                  being copied with everything in them. Modification times are kept. Escape or Ctrl-C \
+                  cancels while waiting for the files.",
+    version = "0.1.0"
+)]
+pub struct UploadArgs {
+    /// Folder to copy the files into
+    dest: Option<String>,
+}
+
+#[derive(Parser, Debug)]
+#[command(
+    name = "download",
+    about = "rOS command to save a file of the volume on the host",
+    long_about = "Hands PATH to the browser as a download. A folder is saved as a zip archive of everything \
+                  in it.",
+    version = "0.1.0"
+)]
+pub struct DownloadArgs {
+    /// File or folder to save
+    path: String,
+}
+
+pub struct UploadCommand;
+pub struct DownloadCommand;
+
+register!(UploadCommand, DownloadCommand);
+
+/// What was chosen on the host: files from the picker, or entries dropped
+/// on the console, which may be folders.
+enum Picked {
+    File(File),
+    Entry(FileSystemEntry),
+}
+
+/// A line of the console rewritten as a transfer goes on. It is only shown
+/// when stdout is the console, the final report being printed anyway.
+struct Progress {
+    console: Option<HtmlTextAreaElement>,
+    start: usize,
+    shown: String,
+}
+
+impl Progress {
+    fn new(ctx: &CommandContext<'_>) -> Self {
+        let console = match ctx.stdout() {
+            FileDescriptor::Console(console) => Some(console.clone()),
+            _ => None,
+        };
+        let start = console.as_ref().map_or(0, |console| console.value().len());
+
+        Self { console, start, shown: String::new() }
+    }
+
+    fn update(&mut self, text: &str) {
+        let Some(console) = &self.console else {
+            return;
+        };
+
+        let mut value = console.value();
+        // something else was printed meanwhile, or the console was cleared
+        if value.len() != self.start + self.shown.len() || !value.ends_with(&self.shown) {
+            self.start = value.len();
+        }
+
+        value.truncate(self.start);
+        value.push_str(text);
+        console.set_value(&value);
+        console.set_scroll_top(console.scroll_height());
+
+        self.shown = text.to_string();
+    }
+
+    async fn finish(&mut self, ctx: &CommandContext<'_>, report: &str) {
+        self.update("");
+        ctx.print(&format!("{}\n", report)).await;
+
+        if let Some(console) = &self.console {
+            self.start = console.value().len();
+        }
+    }
+}
+
+/// The file a file entry stands for.
+async fn entry_file(entry: &FileSystemFileEntry) -> Result<File, JsValue> {
+    let promise = Promise::new(&mut |resolve, reject| entry.file_with_callback_and_callback(&resolve, &reject));
+    Ok(JsFuture::from(promise).await?.unchecked_into())
+}
+
+/// Every entry of a dropped folder, which are handed out a batch at a time.
+async fn folder_entries(folder: &FileSystemDirectoryEntry) -> Result<Vec<FileSystemEntry>, JsValue> {
+    let reader = folder.create_reader();
+    let mut entries = Vec::new();
+
+    loop {
+        let promise = Promise::new(&mut |resolve, reject| {
+            if let Err(err) = reader.read_entries_with_callback_and_callback(&resolve, &reject) {
+                let _ = reject.call1(&JsValue::NULL, &err);
+            }
+        });
+        let batch: Array = JsFuture::from(promise).await?.unchecked_into();
+
+        if batch.length() == 0 {
+            return Ok(entries);
+        }
+        entries.extend(batch.iter().map(JsCast::unchecked_into::<FileSystemEntry>));
+    }
+}
+
+/// The folders and files to create for what was picked, with their paths
+/// relative to the destination, parents first.
+async fn expand(picked: Vec<Picked>) -> Result<(Vec<String>, Vec<(String, File)>), JsValue> {
+    let mut folders = Vec::new();
+    let mut files = Vec::new();
+    let mut pending: Vec<(String, FileSystemEntry)> = Vec::new();
+
+    for picked in picked {
+        match picked {
+            Picked::File(file) => files.push((file.name(), file)),
+            Picked::Entry(entry) => pending.push((entry.name(), entry)),
+        }
+    }
+    pending.reverse();
+
+    while let Some((name, entry)) = pending.pop() {
+        if entry.is_directory() {
+            let children = folder_entries(entry.unchecked_ref()).await?;
+            folders.push(name.clone());
+            pending.extend(children.into_iter().rev().map(|child| (format!("{}/{}", name, child.name()), child)));
+        } else if entry.is_file() {
+            files.push((name, entry_file(entry.unchecked_ref()).await?));
+        }
+    }
+
+    Ok((folders, files))
+}
+
+/// Reads the chunk of a host file from `start`.
+async fn read_slice(file: &File, start: f64) -> Result<Vec<u8>, JsValue> {
+    let end = (start + CHUNK_SIZE).min(file.size());
+    let buffer = JsFuture::from(file.slice_with_f64_and_f64(start, end)?.array_buffer()).await?;
+
+    Ok(Uint8Array::new(&buffer).to_vec())
+}
+
+fn show_read(progress: &mut Progress, name: &str, done: f64, size: f64) {
+    let percent = (done * 100.0 / size) as u64;
+    progress.update(&format!("{}: {}% ({}/{})", name, percent, human_size(done as u64), human_size(size as u64)));
+}
+
+/// Reads a host file a chunk at a time, showing how far it got.
+async fn read_host_file(file: &File, name: &str, progress: &mut Progress) -> Result<Vec<u8>, JsValue> {
+    let size = file.size();
+    let mut data = Vec::with_capacity(size as usize);
+
+    while (data.len() as f64) < size {
+        data.extend(read_slice(file, data.len() as f64).await?);
+        show_read(progress, name, data.len() as f64, size);
+    }
+
+    Ok(data)
+}
+
+/// Copies a host file to `file_path` a chunk at a time, each one being
+/// added to the file as soon as it is read, so that it is never held
+/// whole. The modification time of the host file is kept.
+async fn upload_file(
+    ctx: &CommandContext<'_>,
+    file: &File,
+    name: &str,
+    file_path: &str,
+    progress: &mut Progress,
+) -> Result<u64, String> {
+    let now = chrono::Utc::now().timestamp_millis();
+    let entry = FSEntry {
+        abs_path: file_path.into(),
+        entry: FSEntryKind::File(FSFile::new(path::file_name(name), Vec::new(), now)),
+    };
+    ctx.kernel().await.fs.restore_entry(&entry).await.map_err(|e| e.to_string())?;
+
+    let size = file.size();
+    let mut done = 0.0;
+
+    while done < size {
+        let data = read_slice(file, done).await.map_err(|err| format!("{:?}", err))?;
+        ctx.kernel().await.fs.write_file(file_path, &data, true).await.map_err(|e| e.to_string())?;
+
+        done += data.len() as f64;
+        show_read(progress, name, done, size);
+    }
+
+    let mut kernel = ctx.kernel().await;
+    if let Ok(Some(mut entry)) = kernel.fs.get_entry(file_path).await {
+        entry.entry.metadata_mut().modified_at = file.last_modified() as i64;
+        kernel.fs.restore_entry(&entry).await.map_err(|e| e.to_string())?;
+    }
+
+    Ok(size as u64)
+}
+
+/// Waits for files to be chosen in the picker or dropped on the console.
+/// `None` when cancelled, with Escape or Ctrl-C on the console.
+async fn pick(ctx: &CommandContext<'_>) -> Result<Option<Vec<Picked>>, JsValue> {
+    let document = web_sys::window().and_then(|window| window.document()).ok_or("no document")?;
+    let picked: Rc<RefCell<Option<Vec<Picked>>>> = Rc::new(RefCell::new(None));
+
+    let input: HtmlInputElement = document.create_element("input")?.unchecked_into();
+    input.set_type("file");
+    input.set_multiple(true);
+
+    let on_change = {
+        let (input, picked) = (input.clone(), picked.clone());
+        Closure::<dyn FnMut(Event)>::new(move |_: Event| {
+            let files = input.files().map_or_else(Vec::new, |list| {
+                (0..list.length()).filter_map(|i| list.get(i)).map(Picked::File).collect()
+            });
+            *picked.borrow_mut() = Some(files);
+        })
+    };
+    let on_cancel = {
+        let picked = picked.clone();
+        Closure::<dyn FnMut(Event)>::new(move |_: Event| *picked.borrow_mut() = Some(Vec::new()))
+    };
+    let on_drag_over = Closure::<dyn FnMut(DragEvent)>::new(|event: DragEvent| event.prevent_default());
+    let on_drop = {
+        let picked = picked.clone();
+        Closure::<dyn FnMut(DragEvent)>::new(move |event: DragEvent| {
+            event.prevent_default();
+            let Some(items) = event.data_transfer().map(|transfer| transfer.items()) else {
+                return;
+            };
+
+            // the entries must be taken while the event is dispatched
+            let dropped = (0..items.length()).filter_map(|i| items.get(i)).filter(|item| item.kind() == "file");
+            let dropped = dropped.filter_map(|item| match item.webkit_get_as_entry() {
+                Ok(Some(entry)) => Some(Picked::Entry(entry)),
+                _ => item.get_as_file().ok().flatten().map(Picked::File),
+            });
+            *picked.borrow_mut() = Some(dropped.collect());
+        })
+    };
+
+    input.add_event_listener_with_callback("change", on_change.as_ref().unchecked_ref())?;
+    input.add_event_listener_with_callback("cancel", on_cancel.as_ref().unchecked_ref())?;
+
+    let console = ctx.kernel().await.console.clone();
+    console.add_event_listener_with_callback("dragover", on_drag_over.as_ref().unchecked_ref())?;
+    console.add_event_listener_with_callback("drop", on_drop.as_ref().unchecked_ref())?;
+
+    // keys only cancel when the command owns the console
+    let interactive = matches!(ctx.stdin(), FileDescriptor::Console(_));
+    if interactive {
+        crate::set_raw_mode(true);
+    }
+
+    input.click();
+
+    let result = loop {
+        if let Some(picked) = picked.borrow_mut().take() {
+            break Some(picked);
+        }
+
+        let cancelled = std::iter::from_fn(crate::pop_raw_key)
+            .any(|key| key.key == "Escape" || (key.ctrl && key.key.eq_ignore_ascii_case("c")));
+        if cancelled {
+            break None;
+        }
+
+        sleep(POLL_MS).await;
+    };
+
+    if interactive {
+        crate::set_raw_mode(false);
+    }
+    console.remove_event_listener_with_callback("dragover", on_drag_over.as_ref().unchecked_ref())?;
+    console.remove_event_listener_with_callback("drop", on_drop.as_ref().unchecked_ref())?;
+    // the picker may still be open when cancelled from the console
+    input.remove_event_listener_with_callback("change", on_change.as_ref().unchecked_ref())?;
+    input.remove_event_listener_with_callback("cancel", on_cancel.as_ref().unchecked_ref())?;
+
+    Ok(result)
+}
+
+/// Lets a single file be chosen on the host and reads it, `None` when
+/// cancelled or when only folders were dropped.
+pub async fn pick_file(ctx: &CommandContext<'_>) -> Result<Option<(String, Vec<u8>)>, JsValue> {
+    let Some(picked) = pick(ctx).await? else {
+        return Ok(None);
+    };
+    let Some((name, file)) = expand(picked).await?.1.into_iter().next() else {
+        return Ok(None);
+    };
+
+    let mut progress = Progress::new(ctx);
+    let data = read_host_file(&file, &name, &mut progress).await?;
+    progress.update("");
+
+    Ok(Some((name, data)))
+}
+
+impl Command for UploadCommand {
+    fn name(&self) -> &'static str {
+        "upload"
+    }
+
+    fn usage(&self) -> clap::Command {
+        UploadArgs::command()
+    }
+
+    fn run<'a>(&'a self, ctx: &'a mut CommandContext) -> LocalBoxFuture<'a, i32> {
+        async move {
+            let args = match parse_args::<UploadArgs>(ctx).await {
+                Ok(args) => args,
+                Err(status) => return status,
+            };
+
+            let shown = args.dest.unwrap_or_else(|| ".".to_string());
+            let dest = path::resolve(&ctx.cwd, &shown);
+
+            let found = ctx.kernel().await.fs.get_entry(&dest).await;
+            let is_folder = dest == "/" || matches!(found, Ok(Some(FSEntry { entry: FSEntryKind::Folder(_), .. })));
+            if !is_folder {
+                ctx.eprint(&format!("upload: {}: Not a directory\n", shown)).await;
+                return 1;
+            }
+
+            ctx.print("Choose files to upload, or drop files and folders on the console...\n").await;
+
+            let picked = match pick(ctx).await {
+                Ok(Some(picked)) if !picked.is_empty() => picked,
+                Ok(_) => {
+                    ctx.eprint("upload: cancelled\n").await;
+                    return 1;
+                }
+                Err(err) => {
+                    ctx.eprint(&format!("upload: {:?}\n", err)).await;
+                    return 1;
+                }
+            };
+
+            let (folders, files) = match expand(picked).await {
+                Ok(expanded) => expanded,
+                Err(err) => {
+                    ctx.eprint(&format!("upload: cannot read the dropped files: {:?}\n", err)).await;
+                    return 1;
+                }
+            };
+
+            let mut status = 0;
+
+            for folder in folders {
+                let folder_path = path::join(&dest, &folder);
+                let mut kernel = ctx.kernel().await;
+
+                if !kernel.fs.exists(&folder_path).await.unwrap_or(false)
+                    && let Err(err) = kernel.fs.create_folder_absolute(&folder_path).await
+                {
+                    drop(kernel);
+                    ctx.eprint(&format!("upload: {}: {}\n", folder, err)).await;
+                    status = 1;
+                }
+            }
+
+            let mut progress = Progress::new(ctx);
+            let (mut count, mut total) = (0, 0);
+
+            for (name, file) in files {
+                ctx.shell.checkpoint().await;
+
+                let file_path = path::join(&dest, &name);
+                let size = match upload_file(ctx, &file, &name, &file_path, &mut progress).await {
+                    Ok(size) => size,
+                    Err(err) => {
+                        progress.update("");
+                        ctx.eprint(&format!("upload: {}: {}\n", name, err)).await;
+                        status = 1;
+                        continue;
+                    }
+                };
+
+                progress.finish(ctx, &format!("{} ({})", name, human_size(size))).await;
+                count += 1;
+                total += size;
+            }
+
+            let files = if count == 1 { "file" } else { "files" };
+            ctx.print(&format!("{} {} uploaded, {} in total\n", count, files, human_size(total))).await;
+
+            status
+        }
+        .boxed_local()
+    }
+}
+
+/// Hands `data` to the browser as a download named `name`.
+pub async fn save(name: &str, data: &[u8], mime_type: &str) -> Result<(), JsValue> {
+    let document = web_sys::window().and_then(|window| window.document()).ok_or("no document")?;
+
+    let options = BlobPropertyBag::new();
+    options.set_type(mime_type);
+    let blob = Blob::new_with_u8_array_sequence_and_options(&Array::of1(&Uint8Array::from(data)), &options)?;
+    let url = Url::create_object_url_with_blob(&blob)?;
+
+    let anchor: HtmlAnchorElement = document.create_element("a")?.unchecked_into();
+    anchor.set_href(&url);
+    anchor.set_download(name);
+    anchor.click();
+
+    // the browser reads the blob after the click has been handled
+    sleep(1000).await;
+    Url::revoke_object_url(&url)
+}
+
+impl Command for DownloadCommand {
+    fn name(&self) -> &'static str {
+        "download"
+    }
+
+    fn usage(&self) -> clap::Command {
+        DownloadArgs::command()
+    }
+
+    fn run<'a>(&'a self, ctx: &'a mut CommandContext) -> LocalBoxFuture<'a, i32> {
+        async move {
+            let args = match parse_args::<DownloadArgs>(ctx).await {
+                Ok(args) => args,
+                Err(status) => return status,
+            };
+
+            let target = path::resolve(&ctx.cwd, &args.path);
+            let entry = if target == "/" {
+                None
+            } else {
+                match ctx.kernel().await.fs.get_entry(&target).await {
+                    Ok(Some(entry)) => Some(entry.entry),
+                    Ok(None) => {
+                        ctx.eprint(&format!("download: {}: No such file or directory\n", args.path)).await;
+                        return 1;
+                    }
+                    Err(err) => {
+                        ctx.eprint(&format!("download: {}: {}\n", args.path, err)).await;
+                        return 1;
+                    }
+                }
+            };
+
+            let mut progress = Progress::new(ctx);
+
+            let (name, data, mime_type) = match entry {
+                Some(FSEntryKind::File(_)) => match ctx.kernel().await.fs.read_file(&target).await {
+                    Ok(data) => (path::file_name(&target).to_string(), data, "application/octet-stream"),
+                    Err(err) => {
+                        ctx.eprint(&format!("download: {}: {}\n", args.path, err)).await;
+                        return 1;
+                    }
+                },
+                Some(FSEntryKind::Link(_)) => {
+                    ctx.eprint(&format!("download: {}: Is a link\n", args.path)).await;
+                    return 1;
+                }
+                // folders are zipped, the root under the name of the host
+                _ => {
+                    let (parent, operands) = if target == "/" {
+                        let entries = ctx.kernel().await.fs.read_dir("/").await.unwrap_or_default();
+                        let names = entries.iter().map(|entry| path::file_name(&entry.abs_path).to_string());
+                        ("/".to_string(), names.collect())
+                    } else {
+                        (path::parent(&target), vec![path::file_name(&target).to_string()])
+                    };
+                    let name = if target == "/" { crate::HOSTNAME } else { path::file_name(&target) };
+
+                    progress.update(&format!("{}.zip: zipping...", name));
+                    let (members, _) = collect_members(ctx, "download", &parent, &operands, true).await;
+
+                    match zip::create(&members) {
+                        Ok(data) => (format!("{}.zip", name), data, "application/zip"),
+                        Err(err) => {
+                            progress.update("");
+                            ctx.eprint(&format!("download: {}: {}\n", args.path, err)).await;
+                            return 1;
+                        }
+                    }
+                }
+            };
+
+            progress.update(&format!("{}: saving {}...", name, human_size(data.len() as u64)));
+
+            if let Err(err) = save(&name, &data, mime_type).await {
+                progress.update("");
+                ctx.eprint(&format!("download: {}: {:?}\n", args.path, err)).await;
+                return 1;
+            }
+
+            progress.finish(ctx, &format!("{} ({}) downloaded", name, human_size(data.len() as u64))).await;
+            0
+        }
+        .boxed_local()
+    }
+}
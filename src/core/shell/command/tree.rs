@@ -0,0 +1,189 @@
+use std::collections::BTreeMap;
+
+use clap::{CommandFactory, Parser};
+use futures::{future::LocalBoxFuture, FutureExt};
+
+use crate::{
+    core::shell::command::{context::CommandContext, parse_args, Command},
+    vfs::{
+        entry::{FSEntry, FSEntryKind, FSEntryTrait},
+        path,
+    },
+};
+
+#[derive(Parser, Debug)]
+#[command(name = "tree", about = "rOS command to show folders as a tree", version = "0.1.0")]
+pub struct TreeArgs {
+    /// Folders to show, the current folder by default
+    paths: Vec<String>,
+
+    /// Show hidden entries too
+    #[arg(short, long)]
+    all: bool,
+
+    /// Only show folders
+    #[arg(short = 'd')]
+    folders_only: bool,
+
+    /// Descend at most LEVEL folders deep
+    #[arg(short = 'L', value_name = "LEVEL", value_parser = clap::value_parser!(u64).range(1..))]
+    level: Option<u64>,
+}
+
+pub struct TreeCommand;
+
+register!(TreeCommand);
+
+/// An entry shown in the tree, below its parent.
+struct Node {
+    name: String,
+    path: String,
+    is_folder: bool,
+    target: Option<String>,
+}
+
+#[derive(Default)]
+struct Counts {
+    folders: usize,
+    files: usize,
+}
+
+/// Appends the contents of `folder` to `output`, each line starting with
+/// `prefix` for the levels above.
+fn draw(
+    children: &BTreeMap<String, Vec<Node>>,
+    folder: &str,
+    prefix: &str,
+    depth: u64,
+    level: Option<u64>,
+    output: &mut String,
+    counts: &mut Counts,
+) {
+    let Some(nodes) = children.get(folder) else {
+        return;
+    };
+
+    for (i, node) in nodes.iter().enumerate() {
+        let last = i + 1 == nodes.len();
+        let branch = if last { "└── " } else { "├── " };
+
+        match &node.target {
+            Some(target) => output.push_str(&format!("{}{}{} -> {}\n", prefix, branch, node.name, target)),
+            None => output.push_str(&format!("{}{}{}\n", prefix, branch, node.name)),
+        }
+
+        if !node.is_folder {
+            counts.files += 1;
+            continue;
+        }
+
+        counts.folders += 1;
+        if level.is_none_or(|level| depth < level) {
+            let prefix = format!("{}{}", prefix, if last { "    " } else { "│   " });
+            draw(children, &node.path, &prefix, depth + 1, level, output, counts);
+        }
+    }
+}
+
+fn plural(count: usize, one: &str, many: &str) -> String {
+    format!("{} {}", count, if count == 1 { one } else { many })
+}
+
+impl Command for TreeCommand {
+    fn name(&self) -> &'static str {
+        "tree"
+    }
+
+    fn usage(&self) -> clap::Command {
+        TreeArgs::command()
+    }
+
+    fn run<'a>(&'a self, ctx: &'a mut CommandContext) -> LocalBoxFuture<'a, i32> {
+        async move {
+            let args = match parse_args::<TreeArgs>(ctx).await {
+                Ok(args) => args,
+                Err(status) => return status,
+            };
+
+            let paths = if args.paths.is_empty() { vec![".".to_string()] } else { args.paths };
+            let mut counts = Counts::default();
+            let mut output = String::new();
+            let mut status = 0;
+
+            for shown in paths {
+                let base = path::resolve(&ctx.cwd, &shown);
+                let entries = match ctx.kernel().await.fs.read_tree(&base).await {
+                    Ok(entries) if !entries.is_empty() || base == "/" => entries,
+                    Ok(_) => {
+                        ctx.eprint(&format!("tree: {}: No such file or directory\n", shown)).await;
+                        status = 1;
+                        continue;
+                    }
+                    Err(err) => {
+                        ctx.eprint(&format!("tree: {}: {}\n", shown, err)).await;
+                        status = 1;
+                        continue;
+                    }
+                };
+
+                output.push_str(&format!("{}\n", shown));
+
+                // a file is shown on its own
+                let is_file = |entry: &FSEntry| !matches!(entry.entry, FSEntryKind::Folder(_));
+                if entries.first().is_some_and(|entry| entry.abs_path == base && is_file(entry)) {
+                    counts.files += 1;
+                    continue;
+                }
+
+                let mut children: BTreeMap<String, Vec<Node>> = BTreeMap::new();
+                for entry in entries.into_iter().filter(|entry| entry.abs_path != base) {
+                    let name = entry.entry.name();
+                    let is_folder = matches!(entry.entry, FSEntryKind::Folder(_));
+
+                    // a hidden folder hides what it contains along with itself
+                    if (!args.all && (name.starts_with('.') || entry.entry.is_hidden()))
+                        || (args.folders_only && !is_folder)
+                    {
+                        continue;
+                    }
+
+                    let target = match &entry.entry {
+                        FSEntryKind::Link(link) => Some(link.target.clone()),
+                        _ => None,
+                    };
+
+                    children.entry(path::parent(&entry.abs_path)).or_default().push(Node {
+                        name,
+                        path: entry.abs_path,
+                        is_folder,
+                        target,
+                    });
+                }
+
+                for nodes in children.values_mut() {
+                    nodes.sort_by(|a, b| a.name.cmp(&b.name));
+                }
+
+                draw(&children, &base, "", 1, args.level, &mut output, &mut counts);
+                ctx.shell.checkpoint().await;
+            }
+
+            if args.folders_only {
+                output.push_str(&format!("\n{}\n", plural(counts.folders, "directory", "directories")));
+            } else {
+                output.push_str(&format!(
+                    "\n{}, {}\n",
+                    plural(counts.folders, "directory", "directories"),
+                    plural(counts.files, "file", "files")
+                ));
+            }
+
+            if ctx.write(&output).await.is_err() {
+                return 1;
+            }
+
+            status
+        }
+        .boxed_local()
+    }
+}
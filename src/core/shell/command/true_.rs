@@ -0,0 +1,41 @@
+use clap::{CommandFactory, Parser};
+use futures::{future::LocalBoxFuture, FutureExt};
+
+use crate::core::shell::command::{context::CommandContext, Command};
+
+/// Only describes the usage of `true`, which ignores its arguments.
+#[derive(Parser, Debug)]
+#[command(
+    name = "true",
+    about = "rOS command to do nothing, successfully",
+    version = "0.1.0",
+    disable_help_flag = true,
+    disable_version_flag = true
+)]
+pub struct TrueArgs {
+    /// Ignored
+    #[arg(allow_hyphen_values = true)]
+    args: Vec<String>,
+}
+
+pub struct TrueCommand;
+
+register!(TrueCommand);
+
+impl Command for TrueCommand {
+    fn name(&self) -> &'static str {
+        "true"
+    }
+
+    fn aliases(&self) -> &'static [&'static str] {
+        &[":"]
+    }
+
+    fn usage(&self) -> clap::Command {
+        TrueArgs::command()
+    }
+
+    fn run<'a>(&'a self, _ctx: &'a mut CommandContext) -> LocalBoxFuture<'a, i32> {
+        async { 0 }.boxed_local()
+    }
+}
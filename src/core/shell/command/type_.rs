@@ -0,0 +1,73 @@
+use clap::{CommandFactory, Parser};
+use futures::{future::LocalBoxFuture, FutureExt};
+
+use crate::core::shell::{
+    command::{self, context::CommandContext, parse_args, Command},
+    exec,
+    parser::is_keyword,
+    printer::{function_source, quote},
+};
+
+#[derive(Parser, Debug)]
+#[command(name = "type", about = "rOS command to tell how a name would be interpreted as a command", version = "0.1.0")]
+pub struct TypeArgs {
+    /// Print only `alias`, `keyword`, `function`, `builtin` or `file`
+    #[arg(short = 't', default_value_t = false)]
+    kind: bool,
+
+    #[arg(required = true)]
+    names: Vec<String>,
+}
+
+pub struct TypeCommand;
+
+register!(TypeCommand);
+
+impl Command for TypeCommand {
+    fn name(&self) -> &'static str {
+        "type"
+    }
+
+    fn usage(&self) -> clap::Command {
+        TypeArgs::command()
+    }
+
+    fn run<'a>(&'a self, ctx: &'a mut CommandContext) -> LocalBoxFuture<'a, i32> {
+        async move {
+            let args = match parse_args::<TypeArgs>(ctx).await {
+                Ok(args) => args,
+                Err(status) => return status,
+            };
+
+            let mut status = 0;
+
+            for name in &args.names {
+                let alias = ctx.shell.aliases.lock().await.get(name).cloned();
+                let function = ctx.shell.functions.lock().await.get(name).cloned();
+
+                let (kind, description) = if let Some(value) = alias {
+                    ("alias", format!("{} is aliased to {}", name, quote(&value)))
+                } else if is_keyword(name) {
+                    ("keyword", format!("{} is a shell keyword", name))
+                } else if let Some(body) = function {
+                    ("function", format!("{} is a function\n{}", name, function_source(name, &body).trim_end()))
+                } else if command::find(name).is_some() {
+                    ("builtin", format!("{} is a shell builtin", name))
+                } else if let Some(program) = exec::find_program(ctx.shell.pid, name).await {
+                    ("file", format!("{} is {}", name, program))
+                } else {
+                    if !args.kind {
+                        ctx.eprint(&format!("type: {}: not found\n", name)).await;
+                    }
+                    status = 1;
+                    continue;
+                };
+
+                ctx.print(&format!("{}\n", if args.kind { kind.to_string() } else { description })).await;
+            }
+
+            status
+        }
+        .boxed_local()
+    }
+}
@@ -0,0 +1,76 @@
+use clap::{CommandFactory, Parser};
+use futures::{future::LocalBoxFuture, FutureExt};
+
+use crate::core::shell::{
+    command::{context::CommandContext, parse_args, Command},
+    startup,
+};
+
+#[derive(Parser, Debug)]
+#[command(name = "unalias", about = "rOS command to remove aliases", version = "0.1.0")]
+pub struct UnaliasArgs {
+    /// Remove every alias
+    #[arg(short, default_value_t = false)]
+    all: bool,
+
+    names: Vec<String>,
+}
+
+pub struct UnaliasCommand;
+
+register!(UnaliasCommand);
+
+impl Command for UnaliasCommand {
+    fn name(&self) -> &'static str {
+        "unalias"
+    }
+
+    fn usage(&self) -> clap::Command {
+        UnaliasArgs::command()
+    }
+
+    fn run<'a>(&'a self, ctx: &'a mut CommandContext) -> LocalBoxFuture<'a, i32> {
+        async move {
+            let args = match parse_args::<UnaliasArgs>(ctx).await {
+                Ok(args) => args,
+                Err(status) => return status,
+            };
+
+            if !args.all && args.names.is_empty() {
+                ctx.eprint("unalias: usage: unalias [-a] name [name ...]\n").await;
+                return 2;
+            }
+
+            let mut status = 0;
+            let mut changed = false;
+
+            {
+                let mut aliases = ctx.shell.aliases.lock().await;
+
+                if args.all {
+                    changed = !aliases.is_empty();
+                    aliases.clear();
+                }
+
+                for name in &args.names {
+                    if aliases.remove(name).is_some() {
+                        changed = true;
+                    } else if !args.all {
+                        ctx.eprint(&format!("unalias: {}: not found\n", name)).await;
+                        status = 1;
+                    }
+                }
+            }
+
+            if changed
+                && ctx.shell.at_prompt()
+                && let Err(err) = startup::save_aliases(ctx.shell).await
+            {
+                ctx.eprint(&format!("unalias: cannot save aliases: {}\n", err)).await;
+            }
+
+            status
+        }
+        .boxed_local()
+    }
+}
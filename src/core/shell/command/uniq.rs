@@ -0,0 +1,70 @@
+use clap::{CommandFactory, Parser};
+use futures::{future::LocalBoxFuture, FutureExt};
+
+use crate::core::shell::command::{context::CommandContext, parse_args, Command};
+
+#[derive(Parser, Debug)]
+#[command(name = "uniq", about = "rOS command to report or omit repeated lines", version = "0.1.0")]
+pub struct UniqArgs {
+    /// File to read, `-` or nothing reads stdin
+    input: Option<String>,
+
+    /// Prefix lines by the number of times they occur
+    #[arg(short, long)]
+    count: bool,
+
+    /// Only print the lines that are repeated
+    #[arg(short = 'd', long)]
+    repeated: bool,
+
+    /// Only print the lines that are not repeated
+    #[arg(short, long)]
+    unique: bool,
+}
+
+pub struct UniqCommand;
+
+register!(UniqCommand);
+
+impl Command for UniqCommand {
+    fn name(&self) -> &'static str {
+        "uniq"
+    }
+
+    fn usage(&self) -> clap::Command {
+        UniqArgs::command()
+    }
+
+    fn run<'a>(&'a self, ctx: &'a mut CommandContext) -> LocalBoxFuture<'a, i32> {
+        async move {
+            let args = match parse_args::<UniqArgs>(ctx).await {
+                Ok(args) => args,
+                Err(status) => return status,
+            };
+
+            let (input, status) = ctx.read_inputs("uniq", args.input.as_slice()).await;
+
+            // adjacent equal lines, with how many times they occur
+            let mut groups: Vec<(&str, usize)> = Vec::new();
+            for line in input.lines() {
+                match groups.last_mut() {
+                    Some((last, count)) if *last == line => *count += 1,
+                    _ => groups.push((line, 1)),
+                }
+            }
+
+            let output: String = groups
+                .into_iter()
+                .filter(|(_, count)| (!args.repeated || *count > 1) && (!args.unique || *count == 1))
+                .map(|(line, count)| if args.count { format!("{:>7} {}\n", count, line) } else { format!("{}\n", line) })
+                .collect();
+
+            if ctx.write(&output).await.is_err() {
+                return 1;
+            }
+
+            status
+        }
+        .boxed_local()
+    }
+}
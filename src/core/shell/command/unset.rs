@@ -0,0 +1,82 @@
+use clap::{CommandFactory, Parser};
+use futures::{future::LocalBoxFuture, FutureExt};
+
+use crate::{
+    core::shell::{
+        command::{context::CommandContext, parse_args, Command},
+        startup,
+    },
+    kernel::env::is_valid_name,
+};
+
+#[derive(Parser, Debug)]
+#[command(name = "unset", about = "rOS command to remove shell variables and functions", version = "0.1.1")]
+pub struct UnsetArgs {
+    /// Only remove variables
+    #[arg(short = 'v', default_value_t = false, conflicts_with = "functions")]
+    variables: bool,
+
+    /// Only remove functions
+    #[arg(short = 'f', default_value_t = false)]
+    functions: bool,
+
+    names: Vec<String>,
+}
+
+pub struct UnsetCommand;
+
+register!(UnsetCommand);
+
+impl Command for UnsetCommand {
+    fn name(&self) -> &'static str {
+        "unset"
+    }
+
+    fn usage(&self) -> clap::Command {
+        UnsetArgs::command()
+    }
+
+    fn run<'a>(&'a self, ctx: &'a mut CommandContext) -> LocalBoxFuture<'a, i32> {
+        async move {
+            let args = match parse_args::<UnsetArgs>(ctx).await {
+                Ok(args) => args,
+                Err(status) => return status,
+            };
+
+            let mut status = 0;
+            let mut functions_changed = false;
+
+            for name in &args.names {
+                if !is_valid_name(name) {
+                    ctx.eprint(&format!("unset: '{}': not a valid identifier\n", name)).await;
+                    status = 1;
+                    continue;
+                }
+
+                // without options a name refers to a variable, or to a function
+                // when no such variable exists
+                let mut removed = false;
+                if !args.functions
+                    && let Some(env) = ctx.kernel().await.envs.get_mut(&ctx.shell.pid)
+                {
+                    removed = env.get(name).is_some();
+                    env.unset(name);
+                }
+
+                if !args.variables && !removed && ctx.shell.functions.lock().await.remove(name).is_some() {
+                    functions_changed = true;
+                }
+            }
+
+            if functions_changed
+                && ctx.shell.at_prompt()
+                && let Err(err) = startup::save_functions(ctx.shell).await
+            {
+                ctx.eprint(&format!("unset: cannot save functions: {}\n", err)).await;
+            }
+
+            status
+        }
+        .boxed_local()
+    }
+}
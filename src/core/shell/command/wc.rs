@@ -0,0 +1,59 @@
+use clap::{CommandFactory, Parser};
+use futures::{future::LocalBoxFuture, FutureExt};
+
+use crate::core::shell::command::{context::CommandContext, parse_args, Command};
+
+#[derive(Parser, Debug)]
+#[command(name = "wc", about = "rOS command to count lines, words and bytes of stdin", version = "0.1.0")]
+pub struct WcArgs {
+    #[arg(short, long, default_value_t = false)]
+    lines: bool,
+
+    #[arg(short, long, default_value_t = false)]
+    words: bool,
+
+    #[arg(short = 'c', long, default_value_t = false)]
+    bytes: bool,
+}
+
+pub struct WcCommand;
+
+register!(WcCommand);
+
+impl Command for WcCommand {
+    fn name(&self) -> &'static str {
+        "wc"
+    }
+
+    fn usage(&self) -> clap::Command {
+        WcArgs::command()
+    }
+
+    fn run<'a>(&'a self, ctx: &'a mut CommandContext) -> LocalBoxFuture<'a, i32> {
+        async move {
+            let args = match parse_args::<WcArgs>(ctx).await {
+                Ok(args) => args,
+                Err(status) => return status,
+            };
+
+            let input = ctx.read_to_string().await;
+            let all = !args.lines && !args.words && !args.bytes;
+
+            let mut counts = Vec::new();
+            if all || args.lines {
+                counts.push(input.matches('\n').count());
+            }
+            if all || args.words {
+                counts.push(input.split_whitespace().count());
+            }
+            if all || args.bytes {
+                counts.push(input.len());
+            }
+
+            let output = counts.iter().map(|c| format!("{:>7}", c)).collect::<Vec<String>>().join(" ");
+            ctx.print(&format!("{}\n", output)).await;
+            0
+        }
+        .boxed_local()
+    }
+}
@@ -0,0 +1,397 @@
+use std::io::{Cursor, Read, Write};
+
+use chrono::{Datelike, Timelike};
+use clap::{CommandFactory, Parser};
+use futures::{future::LocalBoxFuture, FutureExt};
+use zip::{write::SimpleFileOptions, CompressionMethod, ZipArchive, ZipWriter};
+
+use crate::{
+    core::shell::command::{
+        context::CommandContext,
+        parse_args,
+        tar::{collect_members, extract_member, write_archive, Member},
+        Command,
+    },
+    vfs::{
+        entry::{FSEntryKind, FSEntryTrait, FSFile, FSFolder, FSLink},
+        path,
+    },
+};
+
+#[derive(Parser, Debug)]
+#[command(
+    name = "zip",
+    about = "rOS command to package files in a zip archive",
+    long_about = "Creates ZIPFILE, with .zip added when it has no extension, holding the files given, and \
+                  with -r everything below the folders given. The archive is made anew, replacing any \
+                  file of that name. Modes and modification times are kept, the latter to two seconds.",
+    version = "0.1.0"
+)]
+pub struct ZipArgs {
+    /// Archive to create, `-` for stdout
+    #[arg(value_name = "ZIPFILE")]
+    archive: String,
+
+    /// Files and folders to add
+    #[arg(required = true)]
+    files: Vec<String>,
+
+    /// Add the contents of folders as well
+    #[arg(short, long)]
+    recurse_paths: bool,
+
+    /// Do not show the files added
+    #[arg(short, long)]
+    quiet: bool,
+}
+
+#[derive(Parser, Debug)]
+#[command(
+    name = "unzip",
+    about = "rOS command to list or extract zip archives",
+    long_about = "Extracts the members of ZIPFILE, or only those named after it, into the working folder or \
+                  the one given with -d, keeping their modes and modification times. Files that already \
+                  exist are skipped unless -o is given. With -l the members are listed instead.",
+    version = "0.1.0"
+)]
+pub struct UnzipArgs {
+    /// Archive to read, `-` for stdin
+    #[arg(value_name = "ZIPFILE")]
+    archive: String,
+
+    /// Members to extract or list, all of them by default
+    files: Vec<String>,
+
+    /// List the members instead of extracting them
+    #[arg(short, long)]
+    list: bool,
+
+    /// Overwrite files without asking
+    #[arg(short, long)]
+    overwrite: bool,
+
+    /// Extract into DIR
+    #[arg(short = 'd', value_name = "DIR")]
+    exdir: Option<String>,
+
+    /// Do not show the members extracted
+    #[arg(short, long)]
+    quiet: bool,
+}
+
+pub struct ZipCommand;
+pub struct UnzipCommand;
+
+register!(ZipCommand, UnzipCommand);
+
+/// `millis` as the local time without zone a zip archive keeps, the
+/// earliest one it can hold standing for anything out of range.
+fn zip_time(millis: i64) -> zip::DateTime {
+    chrono::DateTime::from_timestamp_millis(millis)
+        .and_then(|time| {
+            let year = time.year().clamp(0, u16::MAX as i32) as u16;
+            let (month, day) = (time.month() as u8, time.day() as u8);
+            let (hour, minute, second) = (time.hour() as u8, time.minute() as u8, time.second() as u8);
+            zip::DateTime::from_date_and_time(year, month, day, hour, minute, second).ok()
+        })
+        .unwrap_or_default()
+}
+
+fn from_zip_time(time: zip::DateTime) -> i64 {
+    chrono::NaiveDate::from_ymd_opt(time.year().into(), time.month().into(), time.day().into())
+        .and_then(|date| date.and_hms_opt(time.hour().into(), time.minute().into(), time.second().into()))
+        .map_or(0, |time| time.and_utc().timestamp_millis())
+}
+
+/// Packs `members` in a zip archive.
+pub fn create(members: &[Member]) -> zip::result::ZipResult<Vec<u8>> {
+    let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+
+    for member in members {
+        let options = SimpleFileOptions::default()
+            .unix_permissions(member.entry.mode())
+            .last_modified_time(zip_time(member.entry.modified_at()));
+
+        match &member.entry {
+            FSEntryKind::File(file) => {
+                let data = file.data.as_deref().unwrap_or_default();
+                let method = if data.is_empty() { CompressionMethod::Stored } else { CompressionMethod::Deflated };
+                writer.start_file(&member.name, options.compression_method(method))?;
+                writer.write_all(data)?;
+            }
+            FSEntryKind::Folder(_) => writer.add_directory(&member.name, options)?,
+            FSEntryKind::Link(link) => writer.add_symlink(&member.name, &link.target, options)?,
+        }
+    }
+
+    Ok(writer.finish()?.into_inner())
+}
+
+/// The members of an archive with, for each one, the size it takes in it.
+fn read(data: Vec<u8>) -> zip::result::ZipResult<Vec<(Member, u64)>> {
+    let mut archive = ZipArchive::new(Cursor::new(data))?;
+    let mut members = Vec::new();
+
+    for i in 0..archive.len() {
+        let mut file = archive.by_index(i)?;
+        let name = file.name().to_string();
+        let base_name = path::file_name(&name).to_string();
+        let mode = file.unix_mode();
+        let modified_at = file.last_modified().map_or(0, from_zip_time);
+
+        let kind = if file.is_dir() {
+            FSEntryKind::Folder(FSFolder::new(&base_name, modified_at))
+        } else {
+            let mut data = Vec::new();
+            file.read_to_end(&mut data)?;
+
+            if file.is_symlink() {
+                FSEntryKind::Link(FSLink::new(&base_name, &String::from_utf8_lossy(&data), modified_at))
+            } else {
+                FSEntryKind::File(FSFile::new(&base_name, data, modified_at))
+            }
+        };
+
+        members.push((Member::new(name, kind, mode, modified_at), file.compressed_size()));
+    }
+
+    Ok(members)
+}
+
+/// How much smaller compressing made a file, in percent.
+fn saving(size: u64, compressed: u64) -> u64 {
+    (compressed * 100).checked_div(size).map_or(0, |ratio| 100u64.saturating_sub(ratio))
+}
+
+impl Command for ZipCommand {
+    fn name(&self) -> &'static str {
+        "zip"
+    }
+
+    fn usage(&self) -> clap::Command {
+        ZipArgs::command()
+    }
+
+    fn run<'a>(&'a self, ctx: &'a mut CommandContext) -> LocalBoxFuture<'a, i32> {
+        async move {
+            let args = match parse_args::<ZipArgs>(ctx).await {
+                Ok(args) => args,
+                Err(status) => return status,
+            };
+
+            let archive = if args.archive != "-" && !path::file_name(&args.archive).contains('.') {
+                format!("{}.zip", args.archive)
+            } else {
+                args.archive.clone()
+            };
+
+            let cwd = ctx.cwd.clone();
+            let (members, found) = collect_members(ctx, "zip", &cwd, &args.files, args.recurse_paths).await;
+            if members.is_empty() {
+                ctx.eprint("zip: nothing to do!\n").await;
+                return 12;
+            }
+
+            let data = match create(&members) {
+                Ok(data) => data,
+                Err(err) => {
+                    ctx.eprint(&format!("zip: {}\n", err)).await;
+                    return 2;
+                }
+            };
+
+            if !args.quiet && archive != "-" {
+                // the sizes compressed to are only known from the archive
+                let written = read(data.clone()).unwrap_or_default();
+                let report: String = written
+                    .iter()
+                    .map(|(member, compressed)| {
+                        let (method, size) = match &member.entry {
+                            FSEntryKind::File(file) if file.data.as_ref().is_some_and(|data| !data.is_empty()) => {
+                                ("deflated", member.entry.size())
+                            }
+                            _ => ("stored", 0),
+                        };
+                        format!("  adding: {} ({} {}%)\n", member.name, method, saving(size, *compressed))
+                    })
+                    .collect();
+                ctx.print(&report).await;
+            }
+
+            if let Err(err) = write_archive(ctx, &archive, &data).await {
+                ctx.eprint(&format!("zip: {}: {}\n", archive, err)).await;
+                return 15;
+            }
+
+            if found { 0 } else { 12 }
+        }
+        .boxed_local()
+    }
+}
+
+impl Command for UnzipCommand {
+    fn name(&self) -> &'static str {
+        "unzip"
+    }
+
+    fn usage(&self) -> clap::Command {
+        UnzipArgs::command()
+    }
+
+    fn run<'a>(&'a self, ctx: &'a mut CommandContext) -> LocalBoxFuture<'a, i32> {
+        async move {
+            let args = match parse_args::<UnzipArgs>(ctx).await {
+                Ok(args) => args,
+                Err(status) => return status,
+            };
+
+            let members = match ctx.read_file(&args.archive).await.map(|data| read(data).map_err(|e| e.to_string())) {
+                Ok(Ok(members)) => members,
+                Ok(Err(err)) | Err(err) => {
+                    ctx.eprint(&format!("unzip: {}: {}\n", args.archive, err)).await;
+                    return 9;
+                }
+            };
+
+            let members: Vec<Member> = members
+                .into_iter()
+                .map(|(member, _)| member)
+                .filter(|member| member.is_selected(&args.files))
+                .collect();
+
+            if args.list {
+                let mut listing = format!(
+                    "Archive:  {}\n  Length      Date    Time    Name\n---------  ---------- -----   ----\n",
+                    args.archive
+                );
+                let mut total = 0;
+
+                for member in &members {
+                    let time = chrono::DateTime::from_timestamp_millis(member.entry.modified_at())
+                        .map(|time| time.format("%Y-%m-%d %H:%M").to_string())
+                        .unwrap_or_default();
+                    listing.push_str(&format!("{:>9}  {}   {}\n", member.entry.size(), time, member.name));
+                    total += member.entry.size();
+                }
+
+                let count = if members.len() == 1 { "1 file".to_string() } else { format!("{} files", members.len()) };
+                listing.push_str("---------                     -------\n");
+                listing.push_str(&format!("{:>9}                     {}\n", total, count));
+                ctx.print(&listing).await;
+                return 0;
+            }
+
+            if members.is_empty() && !args.files.is_empty() {
+                ctx.eprint(&format!("unzip: {}: no matching members\n", args.archive)).await;
+                return 11;
+            }
+
+            let dest = path::resolve(&ctx.cwd, args.exdir.as_deref().unwrap_or("."));
+            let mut status = 0;
+
+            if !args.quiet {
+                ctx.print(&format!("Archive:  {}\n", args.archive)).await;
+            }
+
+            for member in members {
+                ctx.shell.checkpoint().await;
+
+                let name = member.name.clone();
+                let target = match member.target(&dest) {
+                    Ok(target) => target,
+                    Err(err) => {
+                        ctx.eprint(&format!("unzip: {}: {}\n", name, err)).await;
+                        status = 2;
+                        continue;
+                    }
+                };
+                let action = match &member.entry {
+                    FSEntryKind::Folder(_) => "   creating",
+                    FSEntryKind::File(_) => "  inflating",
+                    FSEntryKind::Link(_) => "    linking",
+                };
+
+                if !matches!(member.entry, FSEntryKind::Folder(_))
+                    && !args.overwrite
+                    && matches!(ctx.kernel().await.fs.get_entry(&target).await, Ok(Some(_)))
+                {
+                    ctx.eprint(&format!("unzip: {}: already exists, not overwritten without -o\n", name)).await;
+                    status = 1;
+                    continue;
+                }
+
+                let shown = match &member.entry {
+                    FSEntryKind::Link(link) => format!("{} -> {}", name, link.target),
+                    _ => name.clone(),
+                };
+
+                match extract_member(ctx, &dest, member).await {
+                    Ok(_) if !args.quiet => ctx.print(&format!("{}: {}\n", action, shown)).await,
+                    Ok(_) => {}
+                    Err(err) => {
+                        ctx.eprint(&format!("unzip: {}: {}\n", name, err)).await;
+                        status = 2;
+                    }
+                }
+            }
+
+            status
+        }
+        .boxed_local()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn members() -> Vec<Member> {
+        let now = 1_700_000_001_500;
+        let file = FSEntryKind::File(FSFile::new("a.txt", b"hello".to_vec(), now));
+        vec![
+            Member::new("docs/".into(), FSEntryKind::Folder(FSFolder::new("docs", now)), Some(0o700), now),
+            Member::new("docs/a.txt".into(), file, Some(0o640), now),
+            Member::new("latest".into(), FSEntryKind::Link(FSLink::new("latest", "docs/a.txt", now)), None, now),
+        ]
+    }
+
+    #[test]
+    fn members_survive_an_archive_to_two_seconds() {
+        let read = read(create(&members()).unwrap()).unwrap();
+        let summary: Vec<_> = read
+            .iter()
+            .map(|(member, _)| {
+                let contents = match &member.entry {
+                    FSEntryKind::File(file) => String::from_utf8_lossy(file.data.as_deref().unwrap_or_default()).into(),
+                    FSEntryKind::Folder(_) => "folder".to_string(),
+                    FSEntryKind::Link(link) => format!("-> {}", link.target),
+                };
+                (member.name.as_str(), member.entry.mode(), member.entry.modified_at(), contents)
+            })
+            .collect();
+
+        // zip keeps times to two seconds, rounding down
+        assert_eq!(
+            summary,
+            [
+                ("docs/", 0o700, 1_700_000_000_000, "folder".into()),
+                ("docs/a.txt", 0o640, 1_700_000_000_000, "hello".into()),
+                ("latest", 0o777, 1_700_000_000_000, "-> docs/a.txt".into()),
+            ]
+        );
+    }
+
+    #[test]
+    fn times_out_of_range_become_the_earliest_one() {
+        assert_eq!(from_zip_time(zip_time(1_700_000_003_999)), 1_700_000_002_000);
+        assert_eq!(zip_time(0), zip::DateTime::default());
+        assert_eq!(from_zip_time(zip_time(-1)), from_zip_time(zip::DateTime::default()));
+    }
+
+    #[test]
+    fn savings_are_in_percent() {
+        assert_eq!(saving(100, 25), 75);
+        assert_eq!(saving(0, 0), 0);
+        assert_eq!(saving(10, 20), 0);
+    }
+}
@@ -0,0 +1,350 @@
+use crate::{
+    core::shell::{
+        command,
+        parser::{self, is_keyword, Operator, Token},
+        printer::quote,
+    },
+    kernel::env::is_valid_name,
+    vfs::{
+        entry::{FSEntryKind, FSEntryTrait},
+        fs::SimpleFS,
+        path,
+    },
+};
+
+/// Width, in characters, used to lay out ambiguous candidates in columns.
+const COLUMNS_WIDTH: usize = 80;
+
+/// Stands for the cursor at the end of the input, so that the lexer always
+/// ends on the word being completed, even an empty one.
+const CURSOR: char = '\u{1}';
+
+/// Result of a `Tab` press, computed asynchronously and handed back to the
+/// shell through `Message::Completion`.
+#[derive(Debug, Clone)]
+pub struct Completion {
+    /// Input the completion was computed for.
+    pub input: String,
+    /// Input with the word under the cursor completed as far as possible.
+    pub line: String,
+    /// Candidates to list when the word cannot be completed any further.
+    pub candidates: Vec<String>,
+}
+
+struct Candidate {
+    /// Text replacing the word under the cursor.
+    value: String,
+    /// Text shown when listing the candidates.
+    display: String,
+    /// Whether a space should follow the word once it is complete.
+    terminal: bool,
+}
+
+/// The word under the cursor, as the lexer reads it.
+#[derive(Debug)]
+struct Cursor<'a> {
+    /// Input before the word, as typed.
+    head: &'a str,
+    /// The word with its quotes and escapes removed.
+    word: String,
+    /// Words and operators before the word.
+    tokens: Vec<Token>,
+}
+
+/// What the word under the cursor stands for.
+#[derive(Debug, PartialEq, Eq)]
+enum Position {
+    Command,
+    Argument(String),
+    /// The target of a redirection.
+    Redirect,
+}
+
+pub async fn complete(fs: &SimpleFS, cwd: &str, input: &str) -> Completion {
+    let mut completion = Completion {
+        input: input.to_string(),
+        line: input.to_string(),
+        candidates: vec![],
+    };
+
+    let Some(Cursor { head, word, tokens }) = split_input(input) else {
+        return completion;
+    };
+
+    let mut candidates = match position(&tokens) {
+        Position::Command => complete_command(&word),
+        Position::Argument(command) if word.starts_with('-') => complete_flag(&command, &word),
+        Position::Argument(command) => complete_path(fs, cwd, &word, command == "cd").await,
+        Position::Redirect => complete_path(fs, cwd, &word, false).await,
+    };
+
+    candidates.sort_by(|a, b| a.value.cmp(&b.value));
+    candidates.dedup_by(|a, b| a.value == b.value);
+
+    match candidates.as_slice() {
+        [] => {}
+        [single] => {
+            completion.line = format!(
+                "{}{}{}",
+                head,
+                escape(&single.value),
+                if single.terminal { " " } else { "" }
+            );
+        }
+        many => {
+            let prefix = common_prefix(many.iter().map(|c| c.value.as_str()));
+
+            if prefix.len() > word.len() {
+                completion.line = format!("{}{}", head, escape(&prefix));
+            } else {
+                completion.candidates = many.iter().map(|c| c.display.clone()).collect();
+            }
+        }
+    }
+
+    completion
+}
+
+/// Splits `input` into the word under the cursor and what comes before it.
+/// A quote left open is closed for the lexer, the cursor being inside it.
+/// Returns `None` when the cursor is not on a word, e.g. inside a comment.
+fn split_input(input: &str) -> Option<Cursor<'_>> {
+    let (typed, mut tokens) = ["", "'", "\""].iter().find_map(|closing| {
+        let typed = format!("{}{}{}", input, CURSOR, closing);
+        let tokens = parser::tokenize(&typed).ok()?;
+        Some((typed, tokens))
+    })?;
+
+    let Some(Token::Word(current)) = tokens.pop() else {
+        return None;
+    };
+    let word = current.text().strip_suffix(CURSOR)?.to_string();
+
+    // the word starts after a blank or an operator, at the last such place
+    // from which the lexer reads it whole
+    let current = [Token::Word(current)];
+    let start = std::iter::once(0)
+        .chain(
+            input
+                .char_indices()
+                .filter(|(_, c)| c.is_whitespace() || ";&|<>()".contains(*c))
+                .map(|(i, c)| i + c.len_utf8()),
+        )
+        .rev()
+        .find(|start| parser::tokenize(&typed[*start..]).is_ok_and(|tokens| tokens == current))?;
+
+    Some(Cursor { head: &input[..start], word, tokens })
+}
+
+/// Tells from the tokens before the cursor whether it is on a command name,
+/// an argument of a command or the target of a redirection.
+fn position(tokens: &[Token]) -> Position {
+    let mut command = None;
+    let mut redirect = false;
+
+    for token in tokens {
+        match token {
+            Token::Operator(
+                Operator::Less | Operator::Great | Operator::DGreat | Operator::LessAnd | Operator::GreatAnd,
+            ) => redirect = true,
+            Token::Operator(_) => {
+                command = None;
+                redirect = false;
+            }
+            Token::IoNumber(_) => {}
+            Token::HereDoc(_) => redirect = false,
+            Token::Word(_) if redirect => redirect = false,
+            Token::Word(word) if command.is_none() => {
+                let text = word.text();
+                // reserved words and assignments come before the command name
+                let is_assignment = text.split_once('=').is_some_and(|(name, _)| is_valid_name(name));
+
+                if !is_keyword(&text) && !is_assignment {
+                    command = Some(text);
+                }
+            }
+            Token::Word(_) => {}
+        }
+    }
+
+    match command {
+        _ if redirect => Position::Redirect,
+        Some(command) => Position::Argument(command),
+        None => Position::Command,
+    }
+}
+
+/// `text` as typed back into the shell: as is when none of its characters
+/// is special to the lexer or to pathname expansion, quoted otherwise.
+fn escape(text: &str) -> String {
+    let plain = !text.is_empty() && text.chars().all(|c| c.is_alphanumeric() || "-_./+,:@%".contains(c));
+
+    if plain {
+        text.to_string()
+    } else {
+        quote(text)
+    }
+}
+
+fn complete_command(word: &str) -> Vec<Candidate> {
+    let mut names: Vec<&str> = command::names().filter(|name| name.starts_with(word)).collect();
+    names.sort_unstable();
+
+    names
+        .into_iter()
+        .map(|name| Candidate {
+            value: name.to_string(),
+            display: name.to_string(),
+            terminal: true,
+        })
+        .collect()
+}
+
+fn complete_flag(command: &str, word: &str) -> Vec<Candidate> {
+    let Some(mut cmd) = command::find(command).map(|builtin| builtin.usage()) else {
+        return vec![];
+    };
+    cmd.build();
+
+    cmd.get_arguments()
+        .filter(|arg| !arg.is_positional())
+        .flat_map(|arg| {
+            let long = arg.get_long().map(|l| format!("--{}", l));
+            let short = arg.get_short().map(|s| format!("-{}", s));
+            long.into_iter().chain(short)
+        })
+        .filter(|flag| flag.starts_with(word))
+        .map(|flag| Candidate {
+            display: flag.clone(),
+            value: flag,
+            terminal: true,
+        })
+        .collect()
+}
+
+async fn complete_path(fs: &SimpleFS, cwd: &str, word: &str, folders_only: bool) -> Vec<Candidate> {
+    let (dir_part, prefix) = match word.rfind('/') {
+        Some(i) => word.split_at(i + 1),
+        None => ("", word),
+    };
+
+    let folder = if dir_part.is_empty() {
+        path::normalize(cwd)
+    } else {
+        path::resolve(cwd, dir_part)
+    };
+
+    let entries = fs.read_dir(&folder).await.unwrap_or_default();
+
+    entries
+        .iter()
+        .filter(|entry| !entry.entry.is_hidden() || prefix.starts_with('.'))
+        .filter(|entry| !folders_only || matches!(entry.entry, FSEntryKind::Folder(_)))
+        .filter_map(|entry| {
+            let name = entry.entry.name();
+            if !name.starts_with(prefix) {
+                return None;
+            }
+
+            let is_folder = matches!(entry.entry, FSEntryKind::Folder(_));
+            let suffix = if is_folder { "/" } else { "" };
+
+            Some(Candidate {
+                value: format!("{}{}{}", dir_part, name, suffix),
+                display: format!("{}{}", name, suffix),
+                terminal: !is_folder,
+            })
+        })
+        .collect()
+}
+
+fn common_prefix<'a>(mut items: impl Iterator<Item = &'a str>) -> String {
+    let Some(first) = items.next() else {
+        return String::new();
+    };
+
+    let mut len = first.len();
+    for item in items {
+        len = first
+            .char_indices()
+            .zip(item.chars())
+            .take_while(|((i, a), b)| *i < len && a == b)
+            .last()
+            .map_or(0, |((i, a), _)| i + a.len_utf8());
+    }
+
+    first[..len].to_string()
+}
+
+/// Lays `items` out column-major, like `ls` does on a terminal.
+pub fn format_columns(items: &[String]) -> String {
+    let col_width = items.iter().map(|i| i.chars().count()).max().unwrap_or(0) + 2;
+    let cols = (COLUMNS_WIDTH / col_width).max(1);
+    let rows = items.len().div_ceil(cols);
+
+    let mut output = String::new();
+    for row in 0..rows {
+        let line: String = (0..cols)
+            .filter_map(|col| items.get(col * rows + row))
+            .map(|item| format!("{:<width$}", item, width = col_width))
+            .collect();
+        output.push_str(line.trim_end());
+        output.push('\n');
+    }
+
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn split(input: &str) -> (&str, String, Position) {
+        let cursor = split_input(input).unwrap();
+        (cursor.head, cursor.word, position(&cursor.tokens))
+    }
+
+    #[test]
+    fn words_are_split_by_the_lexer() {
+        assert_eq!(split("ec"), ("", "ec".into(), Position::Command));
+        assert_eq!(split("ls -"), ("ls ", "-".into(), Position::Argument("ls".into())));
+        assert_eq!(split("cat a\\ b"), ("cat ", "a b".into(), Position::Argument("cat".into())));
+        assert_eq!(split("cat a "), ("cat a ", String::new(), Position::Argument("cat".into())));
+    }
+
+    #[test]
+    fn quoted_prefixes_are_completed_inside_their_quotes() {
+        assert_eq!(split("cat 'my f"), ("cat ", "my f".into(), Position::Argument("cat".into())));
+        assert_eq!(split("cat \"my f"), ("cat ", "my f".into(), Position::Argument("cat".into())));
+        assert_eq!(split("cat 'a b'/c"), ("cat ", "a b/c".into(), Position::Argument("cat".into())));
+    }
+
+    #[test]
+    fn commands_are_completed_after_operators_and_reserved_words() {
+        assert_eq!(split("ls | gr"), ("ls | ", "gr".into(), Position::Command));
+        assert_eq!(split("cd /;ec"), ("cd /;", "ec".into(), Position::Command));
+        assert_eq!(split("true && "), ("true && ", String::new(), Position::Command));
+        assert_eq!(split("if te"), ("if ", "te".into(), Position::Command));
+        assert_eq!(split("X=1 l"), ("X=1 ", "l".into(), Position::Command));
+        assert_eq!(split("echo a >f"), ("echo a >", "f".into(), Position::Redirect));
+    }
+
+    #[test]
+    fn nothing_is_completed_inside_a_comment() {
+        assert!(split_input("ls # a").is_none());
+    }
+
+    #[test]
+    fn candidates_are_quoted_when_needed() {
+        assert_eq!(escape("notes.txt"), "notes.txt");
+        assert_eq!(escape("my folder/"), "'my folder/'");
+        assert_eq!(escape("it's"), "'it'\\''s'");
+        assert_eq!(escape("a*"), "'a*'");
+    }
+
+    #[test]
+    fn common_prefix_stops_at_the_first_difference() {
+        assert_eq!(common_prefix(["mkdir", "more", "man"].into_iter()), "m");
+        assert_eq!(common_prefix(["cat", "cat"].into_iter()), "cat");
+    }
+}
@@ -0,0 +1,834 @@
+use std::{
+    cell::RefCell,
+    iter,
+    rc::Rc,
+    sync::{
+        atomic::{AtomicI32, Ordering},
+        Arc,
+    },
+};
+
+use async_std::sync::Mutex;
+
+use futures::{
+    future::{join_all, LocalBoxFuture},
+    FutureExt,
+};
+use wasm_bindgen_futures::spawn_local;
+
+use crate::{
+    console_log,
+    core::{
+        shell::{
+            command::{self, context::CommandContext},
+            expand::{expand_pattern, expand_text, expand_to_string, expand_word, Field, Scope},
+            glob,
+            job::Job,
+            parser::{
+                self, AndOr, CaseItem, Command, CompoundCommand, Connector, List, Pipeline, Redirect, RedirectOp,
+                SimpleCommand, Word,
+            },
+            pattern, startup,
+            task::CommandTask,
+            Shell,
+        },
+    },
+    kernel::{
+        env::Environment,
+        fd::{FdTable, FileDescriptor, OpenFile, STDERR, STDIN, STDOUT},
+        pipe::pipe,
+        yield_now, Kernel, Message,
+    },
+    vfs::{
+        entry::{FSEntry, FSEntryKind},
+        path,
+    },
+};
+
+/// Exit status reported when a command name does not resolve to anything.
+pub const STATUS_NOT_FOUND: i32 = 127;
+
+/// Exit status reported when a file exists but cannot be run.
+pub const STATUS_CANNOT_EXECUTE: i32 = 126;
+
+/// A change of control flow requested by `break`, `continue`, `return` or
+/// `exit`, pending until the loop, function or script it targets handles it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Flow {
+    /// Leaves the given number of enclosing loops.
+    Break(usize),
+    /// Leaves `n - 1` enclosing loops and starts the next iteration of the
+    /// outer one.
+    Continue(usize),
+    Return(i32),
+    Exit(i32),
+}
+
+/// Runs `list` on the standard streams of the shell process.
+pub async fn run(shell: &mut Shell, list: &List) -> i32 {
+    let fds = {
+        let kernel = Kernel::clone_rc();
+        let kernel = kernel.lock().await;
+        kernel.fds.get(&shell.pid).cloned().unwrap_or_else(|| FdTable::console(&kernel.console))
+    };
+
+    let status = run_list(shell, list, &fds).await;
+
+    // `exit` and `return` typed at the prompt only stop the current list
+    shell.flow = None;
+    status
+}
+
+/// Runs the and-or lists of `list` one after the other, stopping early when
+/// one of them breaks the control flow.
+pub fn run_list<'a>(shell: &'a mut Shell, list: &'a List, fds: &'a FdTable) -> LocalBoxFuture<'a, i32> {
+    async move {
+        let mut status = 0;
+
+        for item in &list.items {
+            status = if item.background {
+                run_background(shell, item, fds).await
+            } else {
+                run_and_or(shell, item, fds).await
+            };
+
+            if shell.flow.is_some() {
+                break;
+            }
+        }
+
+        status
+    }
+    .boxed_local()
+}
+
+/// Starts `and_or` as a background job and returns at once.
+///
+/// The job runs on a copy of the shell with its own working folder and
+/// `$?`, and reads nothing from the console. An interactive shell adds it to
+/// its job table and prints its job ID and pid.
+async fn run_background(shell: &mut Shell, and_or: &AndOr, fds: &FdTable) -> i32 {
+    let command = and_or.to_string();
+    let k_clone = Kernel::clone_rc();
+
+    let pid = k_clone.lock().await.spawn_child(shell.pid, Box::new(CommandTask::new(&command)));
+    let job = Job::new(Some(pid), &command);
+    let control = job.control.clone();
+
+    if shell.interactive {
+        let id = shell.jobs.lock().await.add(job);
+
+        let stderr = fds.get(STDERR).cloned().unwrap_or(FileDescriptor::Null);
+        let _ = stderr.write(format!("[{}] {}\n", id, pid).as_bytes()).await;
+    }
+
+    let mut background = shell.clone();
+    background.folder = Arc::new(Mutex::new(shell.folder.lock().await.clone()));
+    background.last_status = Arc::new(AtomicI32::new(shell.last_status.load(Ordering::Relaxed)));
+    background.job = Some(control.clone());
+
+    let mut fds = fds.clone();
+    fds.set(STDIN, FileDescriptor::Null);
+
+    let mut and_or = and_or.clone();
+    and_or.background = false;
+
+    let shell_pid = shell.pid;
+
+    spawn_local(async move {
+        let status = run_and_or(&mut background, &and_or, &fds).await;
+        console_log(&format!("[shell] job {} ({}) exited with status {}", pid, command, status));
+
+        drop(fds);
+
+        // a job brought to the foreground with `fg` holds the prompt
+        let foreground = background.jobs.lock().await.finish(&control, status);
+        let mut kernel = k_clone.lock().await;
+        kernel.kill(pid);
+        if foreground {
+            kernel.send(shell_pid, Message::CommandFinished(status));
+        }
+    });
+
+    0
+}
+
+async fn run_and_or(shell: &mut Shell, and_or: &AndOr, fds: &FdTable) -> i32 {
+    shell.checkpoint().await;
+
+    let mut status = run_pipeline(shell, &and_or.first, fds).await;
+    shell.last_status.store(status, Ordering::Relaxed);
+
+    for (connector, pipeline) in &and_or.rest {
+        if shell.flow.is_some() {
+            break;
+        }
+
+        let run = match connector {
+            Connector::And => status == 0,
+            Connector::Or => status != 0,
+        };
+
+        if run {
+            shell.checkpoint().await;
+            status = run_pipeline(shell, pipeline, fds).await;
+            shell.last_status.store(status, Ordering::Relaxed);
+        }
+    }
+
+    status
+}
+
+/// Runs every stage of `pipeline` concurrently, connecting the stdout of
+/// each stage to the stdin of the next one. The status of the pipeline is
+/// the status of its last stage.
+async fn run_pipeline(shell: &mut Shell, pipeline: &Pipeline, fds: &FdTable) -> i32 {
+    let count = pipeline.commands.len();
+
+    let status = if count == 1 {
+        // a lone command runs in the shell itself, so that `break`, `return`
+        // and function definitions reach it
+        run_command(shell, &pipeline.commands[0], fds.clone()).await
+    } else {
+        let mut stdin = fds.get(STDIN).cloned().unwrap_or(FileDescriptor::Null);
+        let mut stages = Vec::with_capacity(count);
+
+        for (i, cmd) in pipeline.commands.iter().enumerate() {
+            let mut fds = fds.clone();
+            fds.set(STDIN, stdin);
+
+            if i + 1 < count {
+                let (reader, writer) = pipe();
+                fds.set(STDOUT, FileDescriptor::PipeWrite(writer));
+                stdin = FileDescriptor::PipeRead(reader);
+            } else {
+                stdin = FileDescriptor::Null;
+            }
+
+            // every stage owns a handle on the shell so builtins such as `cd`
+            // keep affecting the shared state
+            let mut shell = shell.clone();
+            stages.push(async move { run_command(&mut shell, cmd, fds).await });
+        }
+        drop(stdin);
+
+        join_all(stages).await.pop().unwrap_or(0)
+    };
+
+    if pipeline.negated {
+        (status == 0) as i32
+    } else {
+        status
+    }
+}
+
+fn run_command<'a>(shell: &'a mut Shell, cmd: &'a Command, fds: FdTable) -> LocalBoxFuture<'a, i32> {
+    async move {
+        match cmd {
+            Command::Simple(simple) => run_simple(shell, simple, fds).await,
+            Command::Compound(compound, redirects) => run_compound(shell, compound, redirects, fds).await,
+            Command::FunctionDef(def) => {
+                shell.functions.lock().await.insert(def.name.clone(), (*def.body).clone());
+
+                if shell.at_prompt()
+                    && let Err(err) = startup::save_functions(shell).await
+                {
+                    let stderr = fds.get(STDERR).cloned().unwrap_or(FileDescriptor::Null);
+                    let _ = stderr.write(format!("rshell: cannot save functions: {}\n", err).as_bytes()).await;
+                }
+                0
+            }
+        }
+    }
+    .boxed_local()
+}
+
+/// Calls `f` with the variables of `shell`, after bringing `$PWD` in line
+/// with its working folder.
+async fn with_scope<T>(shell: &Shell, f: impl FnOnce(&mut Scope) -> T) -> T {
+    let cwd = shell.folder.lock().await.clone();
+
+    let k_clone = Kernel::clone_rc();
+    let mut kernel = k_clone.lock().await;
+    let env = kernel.envs.entry(shell.pid).or_default();
+    env.set("PWD", &cwd);
+
+    let mut scope = Scope {
+        env,
+        pid: shell.pid,
+        status: shell.last_status.load(Ordering::Relaxed),
+        params: &shell.params,
+    };
+    f(&mut scope)
+}
+
+/// Consumes a `break` or `continue` aimed at the innermost loop. Returns
+/// whether the loop goes on with its next iteration.
+fn loop_control(shell: &mut Shell) -> bool {
+    match shell.flow {
+        None => true,
+        Some(Flow::Break(n)) => {
+            shell.flow = (n > 1).then(|| Flow::Break(n - 1));
+            false
+        }
+        Some(Flow::Continue(n)) if n > 1 => {
+            shell.flow = Some(Flow::Continue(n - 1));
+            false
+        }
+        Some(Flow::Continue(_)) => {
+            shell.flow = None;
+            true
+        }
+        Some(Flow::Return(_) | Flow::Exit(_)) => false,
+    }
+}
+
+async fn run_compound(shell: &mut Shell, compound: &CompoundCommand, redirects: &[Redirect], mut fds: FdTable) -> i32 {
+    let cwd = shell.folder.lock().await.clone();
+    let stderr = fds.get(STDERR).cloned().unwrap_or(FileDescriptor::Null);
+
+    let redirects = match with_scope(shell, |scope| expand_redirects(redirects, scope)).await {
+        Ok(redirects) => redirects,
+        Err(err) => {
+            let _ = stderr.write(format!("rshell: {}\n", err).as_bytes()).await;
+            return 1;
+        }
+    };
+
+    let opened = match apply_redirects(&cwd, &redirects, &mut fds).await {
+        Ok(opened) => opened,
+        Err(err) => {
+            let _ = stderr.write(format!("rshell: {}\n", err).as_bytes()).await;
+            return 1;
+        }
+    };
+
+    let status = match compound {
+        CompoundCommand::Group(body) => run_list(shell, body, &fds).await,
+        CompoundCommand::If { branches, else_branch } => run_if(shell, branches, else_branch.as_ref(), &fds).await,
+        CompoundCommand::While { until, condition, body } => run_while(shell, *until, condition, body, &fds).await,
+        CompoundCommand::For { name, words, body } => run_for(shell, name, words.as_deref(), body, &fds).await,
+        CompoundCommand::Case { word, items } => run_case(shell, word, items, &fds).await,
+    };
+
+    match flush_files(&opened).await {
+        Ok(()) => status,
+        Err(err) => {
+            let _ = stderr.write(format!("rshell: {}\n", err).as_bytes()).await;
+            1
+        }
+    }
+}
+
+async fn run_if(shell: &mut Shell, branches: &[(List, List)], else_branch: Option<&List>, fds: &FdTable) -> i32 {
+    for (condition, body) in branches {
+        let status = run_list(shell, condition, fds).await;
+
+        if shell.flow.is_some() {
+            return status;
+        }
+
+        if status == 0 {
+            return run_list(shell, body, fds).await;
+        }
+    }
+
+    match else_branch {
+        Some(body) => run_list(shell, body, fds).await,
+        None => 0,
+    }
+}
+
+async fn run_while(shell: &mut Shell, until: bool, condition: &List, body: &List, fds: &FdTable) -> i32 {
+    let mut status = 0;
+    shell.loop_depth += 1;
+
+    loop {
+        let condition_status = run_list(shell, condition, fds).await;
+        if !loop_control(shell) || (condition_status == 0) == until {
+            break;
+        }
+
+        status = run_list(shell, body, fds).await;
+        if !loop_control(shell) {
+            break;
+        }
+
+        yield_now().await;
+        shell.checkpoint().await;
+    }
+
+    shell.loop_depth -= 1;
+    status
+}
+
+async fn run_for(shell: &mut Shell, name: &str, words: Option<&[Word]>, body: &List, fds: &FdTable) -> i32 {
+    let fields = with_scope(shell, |scope| match words {
+        Some(words) => words.iter().try_fold(Vec::new(), |mut fields, word| {
+            fields.extend(expand_word(word, scope)?);
+            Ok::<_, String>(fields)
+        }),
+        None => Ok(scope.params.iter().skip(1).map(|param| Field { text: param.clone(), pattern: String::new() }).collect()),
+    })
+    .await;
+
+    let values = match fields {
+        Ok(fields) => expand_globs(shell, fields).await,
+        Err(err) => {
+            let stderr = fds.get(STDERR).cloned().unwrap_or(FileDescriptor::Null);
+            let _ = stderr.write(format!("rshell: {}\n", err).as_bytes()).await;
+            return 1;
+        }
+    };
+
+    let mut status = 0;
+    shell.loop_depth += 1;
+
+    for value in values {
+        if let Some(env) = Kernel::clone_rc().lock().await.envs.get_mut(&shell.pid) {
+            env.set(name, &value);
+        }
+
+        status = run_list(shell, body, fds).await;
+        if !loop_control(shell) {
+            break;
+        }
+
+        yield_now().await;
+        shell.checkpoint().await;
+    }
+
+    shell.loop_depth -= 1;
+    status
+}
+
+async fn run_case(shell: &mut Shell, word: &Word, items: &[CaseItem], fds: &FdTable) -> i32 {
+    let matched = with_scope(shell, |scope| {
+        let subject = expand_to_string(word, scope)?;
+
+        for (i, item) in items.iter().enumerate() {
+            for pattern_word in &item.patterns {
+                if pattern::matches(&expand_pattern(pattern_word, scope)?, &subject) {
+                    return Ok(Some(i));
+                }
+            }
+        }
+
+        Ok::<_, String>(None)
+    })
+    .await;
+
+    match matched {
+        Ok(Some(i)) => run_list(shell, &items[i].body, fds).await,
+        Ok(None) => 0,
+        Err(err) => {
+            let stderr = fds.get(STDERR).cloned().unwrap_or(FileDescriptor::Null);
+            let _ = stderr.write(format!("rshell: {}\n", err).as_bytes()).await;
+            1
+        }
+    }
+}
+
+/// A simple command after parameter expansion.
+struct Expanded {
+    assignments: Vec<(String, String)>,
+    /// Fields of the words, before pathname expansion.
+    args: Vec<Field>,
+    redirects: Vec<Redirect>,
+}
+
+/// Expands the assignments, words and redirections of `cmd`.
+fn expand_command(cmd: &SimpleCommand, scope: &mut Scope) -> Result<Expanded, String> {
+    let mut assignments = Vec::with_capacity(cmd.assignments.len());
+    for (name, value) in &cmd.assignments {
+        assignments.push((name.clone(), expand_to_string(value, scope)?));
+    }
+
+    let mut args = Vec::new();
+    for word in &cmd.words {
+        args.extend(expand_word(word, scope)?);
+    }
+
+    let redirects = expand_redirects(&cmd.redirects, scope)?;
+
+    Ok(Expanded { assignments, args, redirects })
+}
+
+/// Pathname expansion: each field holding unquoted wildcards is replaced by
+/// the sorted paths it matches. One matching nothing is kept as it is, or
+/// removed with the `nullglob` option.
+async fn expand_globs(shell: &Shell, fields: Vec<Field>) -> Vec<String> {
+    if !fields.iter().any(Field::is_glob) {
+        return fields.into_iter().map(|field| field.text).collect();
+    }
+
+    let cwd = shell.folder.lock().await.clone();
+    let (dotglob, nullglob) = (shell.option("dotglob").await, shell.option("nullglob").await);
+
+    let k_clone = Kernel::clone_rc();
+    let kernel = k_clone.lock().await;
+    let mut args = Vec::new();
+
+    for field in fields {
+        if !field.is_glob() {
+            args.push(field.text);
+            continue;
+        }
+
+        let paths = glob::expand(&kernel.fs, &cwd, &field.pattern, dotglob).await;
+        if paths.is_empty() && !nullglob {
+            args.push(field.text);
+        } else {
+            args.extend(paths);
+        }
+    }
+
+    args
+}
+
+/// Replaces redirection targets by quoted words holding their expanded
+/// value, and expands here-doc bodies unless their delimiter was quoted.
+fn expand_redirects(redirects: &[Redirect], scope: &mut Scope) -> Result<Vec<Redirect>, String> {
+    let mut expanded = Vec::with_capacity(redirects.len());
+
+    for redirect in redirects {
+        let op = match &redirect.op {
+            RedirectOp::Input(target) => RedirectOp::Input(Word::quoted(&expand_to_string(target, scope)?)),
+            RedirectOp::Output(target) => RedirectOp::Output(Word::quoted(&expand_to_string(target, scope)?)),
+            RedirectOp::Append(target) => RedirectOp::Append(Word::quoted(&expand_to_string(target, scope)?)),
+            RedirectOp::DupInput(target) => RedirectOp::DupInput(Word::quoted(&expand_to_string(target, scope)?)),
+            RedirectOp::DupOutput(target) => RedirectOp::DupOutput(Word::quoted(&expand_to_string(target, scope)?)),
+            RedirectOp::HereDoc(heredoc) => {
+                let mut heredoc = heredoc.clone();
+                if !heredoc.quoted {
+                    heredoc.body = expand_text(&heredoc.body, scope)?;
+                }
+                RedirectOp::HereDoc(heredoc)
+            }
+        };
+
+        expanded.push(Redirect { fd: redirect.fd, op });
+    }
+
+    Ok(expanded)
+}
+
+async fn run_simple(shell: &mut Shell, cmd: &SimpleCommand, mut fds: FdTable) -> i32 {
+    let cwd = shell.folder.lock().await.clone();
+
+    let stderr = fds.get(STDERR).cloned().unwrap_or(FileDescriptor::Null);
+
+    let Expanded { assignments, args, redirects } = match with_scope(shell, |scope| expand_command(cmd, scope)).await {
+        Ok(expanded) => expanded,
+        Err(err) => {
+            let _ = stderr.write(format!("rshell: {}\n", err).as_bytes()).await;
+            return 1;
+        }
+    };
+
+    let args = expand_globs(shell, args).await;
+
+    let opened = match apply_redirects(&cwd, &redirects, &mut fds).await {
+        Ok(opened) => opened,
+        Err(err) => {
+            let _ = stderr.write(format!("rshell: {}\n", err).as_bytes()).await;
+            return 1;
+        }
+    };
+
+    let status = if args.is_empty() {
+        // only assignments and redirections, e.g. `FOO=bar` or `> file`
+        if let Some(env) = Kernel::clone_rc().lock().await.envs.get_mut(&shell.pid) {
+            for (name, value) in &assignments {
+                env.set(name, value);
+            }
+        }
+        0
+    } else {
+        run_args(shell, &assignments, args, cwd, fds).await
+    };
+
+    match flush_files(&opened).await {
+        Ok(()) => status,
+        Err(err) => {
+            let _ = stderr.write(format!("rshell: {}\n", err).as_bytes()).await;
+            1
+        }
+    }
+}
+
+/// Runs the command `args` names, looking `args[0]` up as a function, a
+/// script path, a builtin and then a program in `$PATH`. Also used by
+/// builtins running other commands, such as `find -exec`.
+pub async fn run_args(
+    shell: &mut Shell,
+    assignments: &[(String, String)],
+    args: Vec<String>,
+    cwd: String,
+    fds: FdTable,
+) -> i32 {
+    let function = shell.functions.lock().await.get(&args[0]).cloned();
+
+    match function {
+        Some(body) => run_function(shell, &body, &args, fds).await,
+        None if args[0].contains('/') => run_script(shell, &cwd, &args, fds).await,
+        None if command::find(&args[0]).is_some() => run_builtin(shell, assignments, args, cwd, fds).await,
+        None => match find_program(shell.pid, &args[0]).await {
+            Some(program) => {
+                let args: Vec<String> = iter::once(program).chain(args[1..].iter().cloned()).collect();
+                run_script(shell, &cwd, &args, fds).await
+            }
+            None => run_builtin(shell, assignments, args, cwd, fds).await,
+        },
+    }
+}
+
+/// Runs the script at `file_path` in `shell` itself, so that the variables
+/// and functions it defines stay around afterwards.
+pub async fn source_file(shell: &mut Shell, file_path: &str, fds: &FdTable) -> Result<i32, String> {
+    let data = Kernel::clone_rc().lock().await.fs.read_file(file_path).await;
+    let data = data.map_err(|e| format!("{}: {}", file_path, e))?;
+
+    let list = parser::parse(&String::from_utf8_lossy(&data)).map_err(|e| format!("{}: {}", file_path, e))?;
+
+    shell.call_depth += 1;
+    let status = run_list(shell, &list, fds).await;
+    shell.call_depth -= 1;
+
+    match shell.flow {
+        Some(Flow::Return(status)) => {
+            shell.flow = None;
+            Ok(status)
+        }
+        _ => Ok(status),
+    }
+}
+
+/// Calls a shell function, `args[1..]` becoming its positional parameters.
+async fn run_function(shell: &mut Shell, body: &Command, args: &[String], fds: FdTable) -> i32 {
+    let params = iter::once(shell.params[0].clone()).chain(args[1..].iter().cloned()).collect();
+    let saved = std::mem::replace(&mut shell.params, params);
+    shell.call_depth += 1;
+
+    let status = run_command(shell, body, fds).await;
+
+    shell.call_depth -= 1;
+    shell.params = saved;
+
+    match shell.flow {
+        Some(Flow::Return(status)) => {
+            shell.flow = None;
+            status
+        }
+        _ => status,
+    }
+}
+
+/// Looks `name` up in the folders listed in `$PATH` of process `pid`,
+/// returning the path of the first file found.
+pub async fn find_program(pid: usize, name: &str) -> Option<String> {
+    let k_clone = Kernel::clone_rc();
+    let kernel = k_clone.lock().await;
+
+    let search_path = kernel.envs.get(&pid).and_then(|env| env.get("PATH")).unwrap_or_default().to_string();
+
+    for folder in search_path.split(':').filter(|folder| !folder.is_empty()) {
+        let candidate = path::join(folder, name);
+
+        if let Ok(Some(FSEntry { entry: FSEntryKind::File(_), .. })) = kernel.fs.get_entry(&candidate).await {
+            return Some(candidate);
+        }
+    }
+
+    None
+}
+
+/// Whether `text` starts with a `#!` line naming rshell as interpreter.
+fn has_shebang(text: &str) -> bool {
+    text.lines()
+        .next()
+        .and_then(|line| line.strip_prefix("#!"))
+        .is_some_and(|line| line.split_whitespace().any(|word| matches!(path::file_name(word), "rsh" | "rshell")))
+}
+
+/// Runs a script file in a new shell process, which inherits the exported
+/// variables and the working folder but nothing else from this one.
+async fn run_script(shell: &Shell, cwd: &str, args: &[String], fds: FdTable) -> i32 {
+    let stderr = fds.get(STDERR).cloned().unwrap_or(FileDescriptor::Null);
+    let script_path = path::resolve(cwd, &args[0]);
+
+    let k_clone = Kernel::clone_rc();
+    let data = k_clone.lock().await.fs.read_file(&script_path).await;
+
+    let text = match data {
+        Ok(data) => String::from_utf8_lossy(&data).into_owned(),
+        Err(err) => {
+            let _ = stderr.write(format!("rshell: {}: {}\n", args[0], err).as_bytes()).await;
+            return STATUS_NOT_FOUND;
+        }
+    };
+
+    if !has_shebang(&text) {
+        let _ = stderr.write(format!("rshell: {}: cannot execute: Exec format error\n", args[0]).as_bytes()).await;
+        return STATUS_CANNOT_EXECUTE;
+    }
+
+    let list = match parser::parse(&text) {
+        Ok(list) => list,
+        Err(err) => {
+            let _ = stderr.write(format!("{}: {}\n", args[0], err).as_bytes()).await;
+            return 2;
+        }
+    };
+
+    let pid = {
+        let mut kernel = k_clone.lock().await;
+        let pid = kernel.spawn_child(shell.pid, Box::new(CommandTask::new(path::file_name(&args[0]))));
+        kernel.fds.insert(pid, fds.clone());
+        pid
+    };
+
+    let mut script = Shell::for_script(pid, cwd, args.to_vec());
+    script.job = shell.job.clone();
+    let status = run_list(&mut script, &list, &fds).await;
+
+    console_log(&format!("[shell] script {} ({}) exited with status {}", pid, args[0], status));
+
+    drop(fds);
+    k_clone.lock().await.kill(pid);
+
+    status
+}
+
+/// Runs a builtin command as a new process.
+async fn run_builtin(
+    shell: &mut Shell,
+    assignments: &[(String, String)],
+    args: Vec<String>,
+    cwd: String,
+    fds: FdTable,
+) -> i32 {
+    let k_clone = Kernel::clone_rc();
+
+    let pid = {
+        let mut kernel = k_clone.lock().await;
+        let pid = kernel.spawn_child(shell.pid, Box::new(CommandTask::new(&args[0])));
+        kernel.fds.insert(pid, fds.clone());
+
+        // assignments in front of a command only apply to that command
+        let env = kernel.envs.entry(pid).or_insert_with(Environment::default);
+        for (name, value) in assignments {
+            env.set_exported(name, value);
+        }
+
+        pid
+    };
+
+    let mut ctx = CommandContext::new(pid, args, cwd, fds, shell, k_clone.clone());
+
+    let status = match command::find(&ctx.args[0]) {
+        Some(builtin) => builtin.run(&mut ctx).await,
+        None => {
+            ctx.eprint(&format!("{}: command not found\n", ctx.args[0])).await;
+            STATUS_NOT_FOUND
+        }
+    };
+
+    console_log(&format!("[shell] process {} ({}) exited with status {}", ctx.pid, ctx.args[0], status));
+
+    // closes the descriptors of the command, signalling end-of-file to the
+    // next stage of the pipeline
+    drop(ctx);
+    k_clone.lock().await.kill(pid);
+
+    status
+}
+
+/// Applies `redirects` on top of `fds`, returning the files opened for
+/// writing so that their contents can be flushed once the command exits.
+async fn apply_redirects(
+    cwd: &str,
+    redirects: &[Redirect],
+    fds: &mut FdTable,
+) -> Result<Vec<Rc<RefCell<OpenFile>>>, String> {
+    let mut opened = Vec::new();
+
+    for redirect in redirects {
+        let desc = match &redirect.op {
+            RedirectOp::Input(target) => {
+                let file_path = path::resolve(cwd, &target.text());
+                let data = Kernel::clone_rc()
+                    .lock()
+                    .await
+                    .fs
+                    .read_file(&file_path)
+                    .await
+                    .map_err(|e| format!("{}: {}", target.text(), e))?;
+
+                FileDescriptor::File(Rc::new(RefCell::new(OpenFile::reader(Some(file_path), data))))
+            }
+            RedirectOp::Output(target) | RedirectOp::Append(target) => {
+                let append = matches!(redirect.op, RedirectOp::Append(_));
+                let file_path = path::resolve(cwd, &target.text());
+
+                // truncates (or creates) the file up front, the output of the
+                // command is appended when flushing
+                Kernel::clone_rc()
+                    .lock()
+                    .await
+                    .fs
+                    .write_file(&file_path, &[], append)
+                    .await
+                    .map_err(|e| format!("{}: {}", target.text(), e))?;
+
+                let file = Rc::new(RefCell::new(OpenFile::writer(file_path)));
+                opened.push(file.clone());
+                FileDescriptor::File(file)
+            }
+            RedirectOp::DupInput(target) | RedirectOp::DupOutput(target) => {
+                let target = target.text();
+
+                if target == "-" {
+                    FileDescriptor::Null
+                } else {
+                    target
+                        .parse::<usize>()
+                        .ok()
+                        .and_then(|fd| fds.get(fd).cloned())
+                        .ok_or_else(|| format!("{}: Bad file descriptor", target))?
+                }
+            }
+            RedirectOp::HereDoc(heredoc) => FileDescriptor::File(Rc::new(RefCell::new(
+                OpenFile::reader(None, heredoc.body.as_bytes().to_vec()),
+            ))),
+        };
+
+        fds.set(redirect.fd, desc);
+    }
+
+    Ok(opened)
+}
+
+/// Writes what a command sent to its redirected descriptors to the VFS.
+async fn flush_files(files: &[Rc<RefCell<OpenFile>>]) -> Result<(), String> {
+    for file in files {
+        let (file_path, data) = {
+            let mut file = file.borrow_mut();
+            (file.path.clone().unwrap_or_default(), file.take_written())
+        };
+
+        if data.is_empty() {
+            continue;
+        }
+
+        Kernel::clone_rc()
+            .lock()
+            .await
+            .fs
+            .write_file(&file_path, &data, true)
+            .await
+            .map_err(|e| format!("{}: {}", file_path, e))?;
+    }
+
+    Ok(())
+}
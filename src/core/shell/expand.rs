@@ -0,0 +1,458 @@
+use crate::{
+    core::shell::{
+        parser::{parse_text, Param, ParamOp, Word, WordPart},
+        pattern,
+    },
+    kernel::env::Environment,
+};
+
+/// Where parameter expansion looks variables up.
+pub trait Variables {
+    fn get(&self, name: &str) -> Option<String>;
+    fn set(&mut self, name: &str, value: &str);
+
+    /// The positional parameters `$1..$n`, expanded one field each by `"$@"`.
+    fn positional(&self) -> Vec<String> {
+        Vec::new()
+    }
+}
+
+/// Variables visible to the shell: its environment plus the special
+/// parameters `$?`, `$$`, `$#`, `$@`, `$*` and `$0..$n`.
+pub struct Scope<'a> {
+    pub env: &'a mut Environment,
+    pub pid: usize,
+    pub status: i32,
+    /// `$0` followed by the positional parameters.
+    pub params: &'a [String],
+}
+
+impl Variables for Scope<'_> {
+    fn get(&self, name: &str) -> Option<String> {
+        match name {
+            "?" => Some(self.status.to_string()),
+            "$" => Some(self.pid.to_string()),
+            "#" => Some(self.params.len().saturating_sub(1).to_string()),
+            "@" | "*" => Some(self.positional().join(" ")),
+            _ if name.chars().all(|c| c.is_ascii_digit()) => {
+                name.parse::<usize>().ok().and_then(|n| self.params.get(n)).cloned()
+            }
+            _ => self.env.get(name).map(str::to_string),
+        }
+    }
+
+    fn positional(&self) -> Vec<String> {
+        self.params.iter().skip(1).cloned().collect()
+    }
+
+    fn set(&mut self, name: &str, value: &str) {
+        self.env.set(name, value);
+    }
+}
+
+/// A field produced by expanding a word, with the pattern it stands for in
+/// pathname expansion: the same text with its quoted characters escaped.
+#[derive(Debug, Clone, Default)]
+pub struct Field {
+    pub text: String,
+    pub pattern: String,
+}
+
+impl Field {
+    fn push_unquoted(&mut self, s: &str) {
+        self.text.push_str(s);
+        self.pattern.push_str(s);
+    }
+
+    fn push_quoted(&mut self, s: &str) {
+        self.text.push_str(s);
+        self.pattern.push_str(&pattern::escape(s));
+    }
+
+    /// Whether the field holds unquoted wildcards to match against files.
+    pub fn is_glob(&self) -> bool {
+        pattern::has_wildcards(&self.pattern)
+    }
+}
+
+/// Expands `word` into the fields it stands for: braces are expanded first,
+/// then unquoted expansions are split on blanks, and a word made only of
+/// empty unquoted expansions disappears altogether.
+pub fn expand_word(word: &Word, vars: &mut dyn Variables) -> Result<Vec<Field>, String> {
+    let mut fields = Vec::new();
+
+    for word in expand_braces(word) {
+        fields.extend(split_fields(&word, vars)?);
+    }
+
+    Ok(fields)
+}
+
+fn split_fields(word: &Word, vars: &mut dyn Variables) -> Result<Vec<Field>, String> {
+    let mut fields = Vec::new();
+    let mut current = Field::default();
+    let mut started = false;
+
+    for part in &word.parts {
+        match part {
+            WordPart::Literal(s) => {
+                current.push_unquoted(s);
+                started = true;
+            }
+            WordPart::Quoted(s) => {
+                current.push_quoted(s);
+                started = true;
+            }
+            WordPart::DoubleQuoted(inner) => {
+                for part in inner {
+                    match part {
+                        // "$@" stands for one field per positional parameter
+                        WordPart::Param(Param { name, op: None }) if name == "@" => {
+                            for (i, param) in vars.positional().into_iter().enumerate() {
+                                if i > 0 {
+                                    fields.push(std::mem::take(&mut current));
+                                }
+                                current.push_quoted(&param);
+                                started = true;
+                            }
+                        }
+                        part => {
+                            current.push_quoted(&expand_parts(std::slice::from_ref(part), vars)?);
+                            started = true;
+                        }
+                    }
+                }
+            }
+            WordPart::Param(param) => {
+                let value = expand_param(param, vars)?;
+
+                if value.starts_with(char::is_whitespace) && started {
+                    fields.push(std::mem::take(&mut current));
+                    started = false;
+                }
+
+                for (i, piece) in value.split_whitespace().enumerate() {
+                    if i > 0 {
+                        fields.push(std::mem::take(&mut current));
+                    }
+                    current.push_unquoted(piece);
+                    started = true;
+                }
+
+                if value.ends_with(char::is_whitespace) && started {
+                    fields.push(std::mem::take(&mut current));
+                    started = false;
+                }
+            }
+        }
+    }
+
+    if started {
+        fields.push(current);
+    }
+
+    Ok(fields)
+}
+
+/// A character of the unquoted text of a word, or one of its other parts
+/// kept whole, as seen by brace expansion.
+#[derive(Clone, Copy)]
+enum Unit<'a> {
+    Char(char),
+    Part(&'a WordPart),
+}
+
+/// Brace expansion: `a{b,c}d` stands for `abd acd`. Only unquoted braces
+/// with a comma between them form a group, and groups may be nested.
+pub fn expand_braces(word: &Word) -> Vec<Word> {
+    let units: Vec<Unit> = word
+        .parts
+        .iter()
+        .flat_map(|part| match part {
+            WordPart::Literal(s) => s.chars().map(Unit::Char).collect::<Vec<_>>(),
+            part => vec![Unit::Part(part)],
+        })
+        .collect();
+
+    expand_units(&units)
+}
+
+fn expand_units(units: &[Unit]) -> Vec<Word> {
+    let Some(bounds) = brace_group(units) else {
+        let mut word = Word::default();
+        for unit in units {
+            match unit {
+                Unit::Char(c) => word.push_literal(*c),
+                Unit::Part(part) => word.parts.push((*part).clone()),
+            }
+        }
+        return vec![word];
+    };
+
+    let (open, close) = (bounds[0], bounds[bounds.len() - 1]);
+
+    bounds
+        .windows(2)
+        .flat_map(|pair| {
+            let alternative: Vec<Unit> =
+                units[..open].iter().chain(&units[pair[0] + 1..pair[1]]).chain(&units[close + 1..]).copied().collect();
+            expand_units(&alternative)
+        })
+        .collect()
+}
+
+/// Finds the first brace group of `units`, returning the positions of its
+/// opening brace, its top-level commas and its closing brace.
+fn brace_group(units: &[Unit]) -> Option<Vec<usize>> {
+    let is = |i: usize, c: char| matches!(units[i], Unit::Char(unit) if unit == c);
+
+    for open in (0..units.len()).filter(|i| is(*i, '{')) {
+        let mut bounds = vec![open];
+        let mut depth = 0;
+
+        for i in open + 1..units.len() {
+            if is(i, '{') {
+                depth += 1;
+            } else if is(i, ',') && depth == 0 {
+                bounds.push(i);
+            } else if is(i, '}') {
+                if depth > 0 {
+                    depth -= 1;
+                    continue;
+                }
+
+                if bounds.len() > 1 {
+                    bounds.push(i);
+                    return Some(bounds);
+                }
+                break;
+            }
+        }
+    }
+
+    None
+}
+
+/// Expands `word` into a single string, without word splitting, as done
+/// for assignments and redirection targets.
+pub fn expand_to_string(word: &Word, vars: &mut dyn Variables) -> Result<String, String> {
+    expand_parts(&word.parts, vars)
+}
+
+/// Expands `word` into a pattern, where only the unquoted parts keep their
+/// special meaning.
+pub fn expand_pattern(word: &Word, vars: &mut dyn Variables) -> Result<String, String> {
+    let mut output = String::new();
+
+    for part in &word.parts {
+        match part {
+            WordPart::Literal(s) => output.push_str(s),
+            WordPart::Quoted(s) => output.push_str(&pattern::escape(s)),
+            WordPart::DoubleQuoted(inner) => output.push_str(&pattern::escape(&expand_parts(inner, vars)?)),
+            WordPart::Param(param) => output.push_str(&expand_param(param, vars)?),
+        }
+    }
+
+    Ok(output)
+}
+
+/// Expands the parameters found in plain text, e.g. a here-doc body.
+pub fn expand_text(text: &str, vars: &mut dyn Variables) -> Result<String, String> {
+    let parts = parse_text(text).map_err(|e| e.to_string())?;
+    expand_parts(&parts, vars)
+}
+
+fn expand_parts(parts: &[WordPart], vars: &mut dyn Variables) -> Result<String, String> {
+    let mut output = String::new();
+
+    for part in parts {
+        match part {
+            WordPart::Literal(s) | WordPart::Quoted(s) => output.push_str(s),
+            WordPart::DoubleQuoted(inner) => output.push_str(&expand_parts(inner, vars)?),
+            WordPart::Param(param) => output.push_str(&expand_param(param, vars)?),
+        }
+    }
+
+    Ok(output)
+}
+
+fn expand_param(param: &Param, vars: &mut dyn Variables) -> Result<String, String> {
+    let value = vars.get(&param.name);
+
+    // whether the operator considers the variable set: with a colon an
+    // empty value counts as unset
+    let is_set = |colon: bool| match &value {
+        Some(v) => !colon || !v.is_empty(),
+        None => false,
+    };
+
+    match &param.op {
+        None => Ok(value.unwrap_or_default()),
+        Some(ParamOp::Length) => Ok(value.unwrap_or_default().chars().count().to_string()),
+        Some(ParamOp::Default { colon, word }) => {
+            if is_set(*colon) {
+                Ok(value.unwrap_or_default())
+            } else {
+                expand_to_string(word, vars)
+            }
+        }
+        Some(ParamOp::Assign { colon, word }) => {
+            if is_set(*colon) {
+                Ok(value.unwrap_or_default())
+            } else {
+                let new_value = expand_to_string(word, vars)?;
+                vars.set(&param.name, &new_value);
+                Ok(new_value)
+            }
+        }
+        Some(ParamOp::Alternative { colon, word }) => {
+            if is_set(*colon) {
+                expand_to_string(word, vars)
+            } else {
+                Ok(String::new())
+            }
+        }
+        Some(ParamOp::Error { colon, word }) => {
+            if is_set(*colon) {
+                Ok(value.unwrap_or_default())
+            } else {
+                let message = expand_to_string(word, vars)?;
+                let message = if message.is_empty() { "parameter null or not set".into() } else { message };
+                Err(format!("{}: {}", param.name, message))
+            }
+        }
+    }
+}
+
+/// Renders a `PS1`-style prompt: backslash escapes first, then parameter
+/// expansion.
+///
+/// Supported escapes are `\u` (user), `\h` (host), `\w` (working folder,
+/// with `$HOME` shown as `~`), `\W` (last component of the working folder),
+/// `\$`, `\n` and `\\`.
+pub fn render_prompt(ps1: &str, cwd: &str, vars: &mut dyn Variables) -> String {
+    let home = vars.get("HOME").unwrap_or_default();
+    let user = vars.get("USER").unwrap_or_default();
+
+    let mut text = String::new();
+    let mut chars = ps1.chars();
+
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            text.push(c);
+            continue;
+        }
+
+        match chars.next() {
+            Some('u') => text.push_str(&user),
+            Some('h') => text.push_str(crate::HOSTNAME),
+            Some('w') => match cwd.strip_prefix(home.as_str()) {
+                Some(rest) if !home.is_empty() && (rest.is_empty() || rest.starts_with('/')) => {
+                    text.push('~');
+                    text.push_str(rest);
+                }
+                _ => text.push_str(cwd),
+            },
+            Some('W') => text.push_str(crate::vfs::path::file_name(cwd)),
+            Some('$') => text.push('$'),
+            Some('n') => text.push('\n'),
+            // left escaped so that expansion keeps it as a backslash
+            Some('\\') => text.push_str("\\\\"),
+            Some(c) => {
+                text.push('\\');
+                text.push(c);
+            }
+            None => text.push('\\'),
+        }
+    }
+
+    expand_text(&text, vars).unwrap_or(text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::shell::parser::{tokenize, Token};
+
+    fn word(input: &str) -> Word {
+        match tokenize(input).unwrap().into_iter().next() {
+            Some(Token::Word(word)) => word,
+            token => panic!("not a word: {:?}", token),
+        }
+    }
+
+    /// Expands `input` with `X` set to `a  b`, `EMPTY` set but empty and the
+    /// positional parameters `1 2`, `3`.
+    fn fields(input: &str) -> Vec<String> {
+        let mut env = Environment::default();
+        env.set("X", "a  b");
+        env.set("EMPTY", "");
+        let params = ["rsh".to_string(), "1 2".into(), "3".into()];
+        let mut scope = Scope { env: &mut env, pid: 7, status: 1, params: &params };
+
+        expand_word(&word(input), &mut scope).unwrap().into_iter().map(|field| field.text).collect()
+    }
+
+    #[test]
+    fn unquoted_expansions_are_split_into_fields() {
+        assert_eq!(fields("$X"), ["a", "b"]);
+        assert_eq!(fields("x$X"), ["xa", "b"]);
+        assert_eq!(fields("\"$X\""), ["a  b"]);
+        assert_eq!(fields("'$X'"), ["$X"]);
+    }
+
+    #[test]
+    fn empty_unquoted_expansions_disappear() {
+        assert!(fields("$EMPTY").is_empty());
+        assert!(fields("$UNSET").is_empty());
+        assert_eq!(fields("\"$EMPTY\""), [""]);
+        assert_eq!(fields("''"), [""]);
+    }
+
+    #[test]
+    fn special_and_positional_parameters() {
+        assert_eq!(fields("$?$$"), ["17"]);
+        assert_eq!(fields("$#"), ["2"]);
+        assert_eq!(fields("\"$@\""), ["1 2", "3"]);
+        assert_eq!(fields("\"$*\""), ["1 2 3"]);
+        assert_eq!(fields("$1"), ["1", "2"]);
+    }
+
+    #[test]
+    fn parameter_operators() {
+        assert_eq!(fields("${#X}"), ["4"]);
+        assert_eq!(fields("${EMPTY:-d}"), ["d"]);
+        assert_eq!(fields("\"${EMPTY-d}\""), [""]);
+        assert_eq!(fields("${X:+set}"), ["set"]);
+        assert_eq!(fields("${UNSET:+set}"), Vec::<String>::new());
+
+        let mut env = Environment::default();
+        let mut scope = Scope { env: &mut env, pid: 1, status: 0, params: &[] };
+        assert_eq!(expand_to_string(&word("${Y:=v}"), &mut scope).unwrap(), "v");
+        assert_eq!(scope.env.get("Y"), Some("v"));
+        assert_eq!(expand_to_string(&word("${Z:?missing}"), &mut scope), Err("Z: missing".into()));
+    }
+
+    #[test]
+    fn braces_expand_before_parameters() {
+        assert_eq!(fields("a{b,c}d"), ["abd", "acd"]);
+        assert_eq!(fields("{a,b{1,2}}"), ["a", "b1", "b2"]);
+        assert_eq!(fields("{a}"), ["{a}"]);
+        assert_eq!(fields("'{a,b}'"), ["{a,b}"]);
+        assert_eq!(fields("{$EMPTY,x}"), ["x"]);
+    }
+
+    #[test]
+    fn only_unquoted_wildcards_make_a_glob() {
+        let glob = |input: &str| {
+            let mut env = Environment::default();
+            let mut scope = Scope { env: &mut env, pid: 1, status: 0, params: &[] };
+            expand_word(&word(input), &mut scope).unwrap().remove(0)
+        };
+
+        assert!(glob("*.txt").is_glob());
+        assert!(!glob("'*.txt'").is_glob());
+        assert!(!glob("\\*").is_glob());
+        assert_eq!(glob("'*'x?").pattern, "\\*x?");
+    }
+}
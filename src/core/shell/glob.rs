@@ -0,0 +1,140 @@
+//! Pathname expansion: `*`, `?` and `[...]` in a path component match the
+//! names of a folder, and a `**` component any number of folders.
+
+use crate::{
+    core::shell::pattern,
+    vfs::{
+        entry::{FSEntryKind, FSEntryTrait},
+        fs::SimpleFS,
+        path,
+    },
+};
+
+/// A path matched so far, as shown (relative when the pattern is) and as
+/// an absolute path.
+#[derive(Debug, Clone)]
+struct Match {
+    shown: String,
+    path: String,
+    /// Whether the path is a folder, `None` when it came from a component
+    /// without wildcards and has not been looked up yet.
+    is_folder: Option<bool>,
+}
+
+impl Match {
+    fn join(&self, name: &str, is_folder: Option<bool>) -> Self {
+        let shown = match self.shown.as_str() {
+            "" => name.to_string(),
+            shown if shown.ends_with('/') => format!("{}{}", shown, name),
+            shown => format!("{}/{}", shown, name),
+        };
+
+        Match { shown, path: path::join(&self.path, name), is_folder }
+    }
+}
+
+/// Expands `pattern` relative to `cwd`, returning the sorted paths it
+/// matches, written relative to `cwd` when the pattern is. Names starting
+/// with a dot are hidden from wildcards unless `dotglob` is set or the
+/// component itself starts with a dot.
+pub async fn expand(fs: &SimpleFS, cwd: &str, pattern: &str, dotglob: bool) -> Vec<String> {
+    let (mut matches, rest) = match pattern.strip_prefix('/') {
+        Some(rest) => (vec![Match { shown: "/".into(), path: "/".into(), is_folder: Some(true) }], rest),
+        None => (vec![Match { shown: String::new(), path: cwd.into(), is_folder: Some(true) }], pattern),
+    };
+
+    // a trailing slash only keeps the folders
+    let folders_only = rest.ends_with('/');
+    let components: Vec<&str> = rest.split('/').filter(|component| !component.is_empty()).collect();
+
+    for (i, component) in components.iter().enumerate() {
+        let last = i + 1 == components.len();
+        let mut next = Vec::new();
+
+        for current in &matches {
+            if current.is_folder == Some(false) {
+                continue;
+            }
+
+            if *component == "**" {
+                if !last {
+                    next.push(current.clone());
+                }
+
+                for (relative, is_folder) in below(fs, &current.path, dotglob).await {
+                    if last || is_folder {
+                        next.push(current.join(&relative, Some(is_folder)));
+                    }
+                }
+            } else if !pattern::has_wildcards(component) {
+                next.push(current.join(&pattern::unescape(component), None));
+            } else {
+                let show_hidden = dotglob || component.starts_with('.') || component.starts_with("\\.");
+
+                for entry in fs.read_dir(&current.path).await.unwrap_or_default() {
+                    let name = entry.entry.name();
+                    let is_folder = matches!(entry.entry, FSEntryKind::Folder(_));
+
+                    if (is_hidden(&name, &entry.entry) && !show_hidden) || !pattern::matches(component, &name) {
+                        continue;
+                    }
+
+                    if last || is_folder {
+                        next.push(current.join(&name, Some(is_folder)));
+                    }
+                }
+            }
+        }
+
+        matches = next;
+    }
+
+    let mut found = Vec::new();
+
+    for current in matches {
+        let is_folder = match current.is_folder {
+            Some(is_folder) => is_folder,
+            None => match fs.get_entry(&current.path).await {
+                Ok(Some(entry)) => matches!(entry.entry, FSEntryKind::Folder(_)),
+                _ => continue,
+            },
+        };
+
+        if folders_only && !is_folder {
+            continue;
+        }
+
+        match current.shown {
+            shown if folders_only && !shown.ends_with('/') => found.push(format!("{}/", shown)),
+            shown => found.push(shown),
+        }
+    }
+
+    found.sort();
+    found.dedup();
+    found
+}
+
+fn is_hidden(name: &str, entry: &FSEntryKind) -> bool {
+    name.starts_with('.') || entry.is_hidden()
+}
+
+/// Everything below `folder`, found with one scan of the directory entries
+/// of each folder, as paths relative to it along with whether they are
+/// folders. Entries inside hidden folders are left out with the folders
+/// themselves.
+async fn below(fs: &SimpleFS, folder: &str, dotglob: bool) -> Vec<(String, bool)> {
+    let prefix = if folder == "/" { "/".to_string() } else { format!("{}/", folder) };
+
+    fs.read_folder(&prefix)
+        .await
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(|entry| {
+            let relative = entry.abs_path.strip_prefix(&prefix)?.to_string();
+            let hidden = relative.split('/').any(|name| name.starts_with('.')) || entry.entry.is_hidden();
+
+            (dotglob || !hidden).then_some((relative, matches!(entry.entry, FSEntryKind::Folder(_))))
+        })
+        .collect()
+}
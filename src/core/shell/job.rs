@@ -0,0 +1,377 @@
+use std::{
+    collections::BTreeMap,
+    fmt,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
+
+use crate::kernel::sleep;
+
+/// How often a stopped job checks whether it has been resumed.
+const RESUME_POLL_MS: i32 = 50;
+
+/// Lets the shell stop and resume a job from outside.
+///
+/// Tasks cannot be preempted, so a job only stops when it reaches one of
+/// its checkpoints: before each pipeline, at each loop iteration and while
+/// a long running builtin such as `sleep` waits.
+#[derive(Debug, Default)]
+pub struct JobControl {
+    stopped: AtomicBool,
+}
+
+impl JobControl {
+    pub fn stop(&self) {
+        self.stopped.store(true, Ordering::Relaxed);
+    }
+
+    pub fn resume(&self) {
+        self.stopped.store(false, Ordering::Relaxed);
+    }
+
+    pub fn is_stopped(&self) -> bool {
+        self.stopped.load(Ordering::Relaxed)
+    }
+
+    /// Parks the caller for as long as the job is stopped.
+    pub async fn checkpoint(&self) {
+        while self.is_stopped() {
+            sleep(RESUME_POLL_MS).await;
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JobState {
+    Running,
+    Stopped,
+    Done(i32),
+}
+
+impl fmt::Display for JobState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            JobState::Running => f.write_str("Running"),
+            JobState::Stopped => f.write_str("Stopped"),
+            JobState::Done(0) => f.write_str("Done"),
+            JobState::Done(status) => write!(f, "Exit {}", status),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Job {
+    /// Process table entry standing for the job, if it has one.
+    pub pid: Option<usize>,
+    /// The command line, as shown by `jobs`.
+    pub command: String,
+    pub state: JobState,
+    pub control: Arc<JobControl>,
+}
+
+impl Job {
+    pub fn new(pid: Option<usize>, command: &str) -> Self {
+        Self { pid, command: command.into(), state: JobState::Running, control: Arc::default() }
+    }
+}
+
+/// The jobs of an interactive shell: the one in the foreground, which owns
+/// the prompt, and the ones stopped or running in the background, by job ID.
+#[derive(Debug, Default)]
+pub struct JobTable {
+    foreground: Option<Job>,
+    jobs: BTreeMap<usize, Job>,
+    /// Background job IDs, least recently started or stopped first.
+    recent: Vec<usize>,
+}
+
+impl JobTable {
+    pub fn set_foreground(&mut self, job: Job) {
+        self.foreground = Some(job);
+    }
+
+    /// Adds a background job, returning its ID.
+    pub fn add(&mut self, job: Job) -> usize {
+        let id = self.jobs.keys().next_back().map_or(1, |id| id + 1);
+
+        self.jobs.insert(id, job);
+        self.touch(id);
+        id
+    }
+
+    pub fn get(&self, id: usize) -> Option<&Job> {
+        self.jobs.get(&id)
+    }
+
+    pub fn remove(&mut self, id: usize) -> Option<Job> {
+        self.recent.retain(|recent| *recent != id);
+        self.jobs.remove(&id)
+    }
+
+    /// Stops the foreground job and moves it to the background, returning
+    /// its new ID.
+    pub fn stop_foreground(&mut self) -> Option<usize> {
+        let mut job = self.foreground.take()?;
+        job.control.stop();
+        job.state = JobState::Stopped;
+
+        Some(self.add(job))
+    }
+
+    /// Resumes job `id` in the background.
+    pub fn resume(&mut self, id: usize) -> Option<&Job> {
+        let job = self.jobs.get_mut(&id)?;
+        job.control.resume();
+        job.state = JobState::Running;
+
+        self.touch(id);
+        self.jobs.get(&id)
+    }
+
+    /// Resumes job `id` and gives it the prompt, in place of the job doing
+    /// the call.
+    pub fn bring_to_foreground(&mut self, id: usize) -> Option<&Job> {
+        let mut job = self.remove(id)?;
+        job.control.resume();
+        job.state = JobState::Running;
+
+        self.foreground = Some(job);
+        self.foreground.as_ref()
+    }
+
+    /// Records that the job controlled by `control` exited. Returns whether
+    /// it was the foreground job, in which case the prompt is due again;
+    /// a background job is kept until its completion has been reported.
+    pub fn finish(&mut self, control: &Arc<JobControl>, status: i32) -> bool {
+        if self.foreground.as_ref().is_some_and(|job| Arc::ptr_eq(&job.control, control)) {
+            self.foreground = None;
+            return true;
+        }
+
+        if let Some(job) = self.jobs.values_mut().find(|job| Arc::ptr_eq(&job.control, control)) {
+            job.state = JobState::Done(status);
+        }
+        false
+    }
+
+    /// Removes the jobs that have exited, returning the lines reporting them.
+    pub fn take_notices(&mut self) -> Vec<String> {
+        let done: Vec<usize> = self
+            .jobs
+            .iter()
+            .filter(|(_, job)| matches!(job.state, JobState::Done(_)))
+            .map(|(id, _)| *id)
+            .collect();
+
+        let notices = done.iter().map(|id| self.describe(*id, false)).collect();
+
+        for id in done {
+            self.remove(id);
+        }
+
+        notices
+    }
+
+    /// The background jobs, by ID.
+    pub fn iter(&self) -> impl Iterator<Item = (usize, &Job)> {
+        self.jobs.iter().map(|(id, job)| (*id, job))
+    }
+
+    /// The line `jobs` prints for job `id`, e.g. `[1]+  Stopped  sleep 10`,
+    /// with the pid of the job after its ID when `show_pid` is set.
+    pub fn describe(&self, id: usize, show_pid: bool) -> String {
+        let Some(job) = self.jobs.get(&id) else {
+            return String::new();
+        };
+
+        let marker = if self.current() == Some(id) {
+            '+'
+        } else if self.previous() == Some(id) {
+            '-'
+        } else {
+            ' '
+        };
+        let pid = match job.pid {
+            Some(pid) if show_pid => format!(" {}", pid),
+            _ => String::new(),
+        };
+        let suffix = if job.state == JobState::Running { " &" } else { "" };
+
+        format!("[{}]{}{}  {:<24}{}{}", id, marker, pid, job.state.to_string(), job.command, suffix)
+    }
+
+    /// The job `%+` refers to: the most recently stopped one, or else the
+    /// most recently started in the background.
+    pub fn current(&self) -> Option<usize> {
+        self.ranked().first().copied()
+    }
+
+    /// The job `%-` refers to, the one that was current before.
+    pub fn previous(&self) -> Option<usize> {
+        self.ranked().get(1).copied()
+    }
+
+    fn ranked(&self) -> Vec<usize> {
+        let is_stopped = |id: &usize| self.jobs.get(id).is_some_and(|job| job.state == JobState::Stopped);
+
+        let stopped = self.recent.iter().rev().filter(|id| is_stopped(id));
+        let others = self.recent.iter().rev().filter(|id| !is_stopped(id));
+
+        stopped.chain(others).copied().collect()
+    }
+
+    fn touch(&mut self, id: usize) {
+        self.recent.retain(|recent| *recent != id);
+        self.recent.push(id);
+    }
+
+    /// Resolves a job spec: `%n`, `%+`/`%%` (current job), `%-` (previous
+    /// job) or `%prefix` (the job whose command starts with `prefix`).
+    /// Without a spec, the current job.
+    pub fn resolve(&self, spec: Option<&str>) -> Result<usize, String> {
+        let Some(spec) = spec else {
+            return self.current().ok_or_else(|| "current: no such job".to_string());
+        };
+
+        let no_such_job = || format!("{}: no such job", spec);
+        let name = spec.strip_prefix('%').unwrap_or(spec);
+
+        match name {
+            "" | "+" | "%" => self.current().ok_or_else(no_such_job),
+            "-" => self.previous().ok_or_else(no_such_job),
+            _ => match name.parse::<usize>() {
+                Ok(id) if self.jobs.contains_key(&id) => Ok(id),
+                Ok(_) => Err(no_such_job()),
+                Err(_) => {
+                    let mut matching = self.jobs.iter().filter(|(_, job)| job.command.starts_with(name));
+
+                    match (matching.next(), matching.next()) {
+                        (Some((id, _)), None) => Ok(*id),
+                        (Some(_), Some(_)) => Err(format!("{}: ambiguous job spec", spec)),
+                        (None, _) => Err(no_such_job()),
+                    }
+                }
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A table with a background job for each of `commands`, IDs from 1.
+    fn table(commands: &[&str]) -> JobTable {
+        let mut jobs = JobTable::default();
+        for command in commands {
+            jobs.add(Job::new(None, command));
+        }
+        jobs
+    }
+
+    #[test]
+    fn ids_follow_the_highest_one_in_use() {
+        let mut jobs = table(&["sleep 1", "sleep 2", "sleep 3"]);
+
+        jobs.remove(3);
+        assert_eq!(jobs.add(Job::new(None, "a")), 3);
+
+        jobs.remove(1);
+        assert_eq!(jobs.add(Job::new(None, "b")), 4);
+        assert_eq!(jobs.iter().map(|(id, _)| id).collect::<Vec<_>>(), [2, 3, 4]);
+    }
+
+    #[test]
+    fn stopped_jobs_are_current_before_running_ones() {
+        let mut jobs = table(&["sleep 1", "sleep 2"]);
+        assert_eq!((jobs.current(), jobs.previous()), (Some(2), Some(1)));
+
+        jobs.set_foreground(Job::new(None, "edit"));
+        assert_eq!(jobs.stop_foreground(), Some(3));
+        jobs.add(Job::new(None, "sleep 4"));
+        assert_eq!((jobs.current(), jobs.previous()), (Some(3), Some(4)));
+
+        jobs.resume(3);
+        jobs.resume(1);
+        assert_eq!((jobs.current(), jobs.previous()), (Some(1), Some(3)));
+    }
+
+    #[test]
+    fn job_specs_resolve_to_ids() {
+        let mut jobs = table(&["sleep 1", "grep x", "sleep 3"]);
+        jobs.remove(3);
+
+        assert_eq!(jobs.resolve(None), Ok(2));
+        assert_eq!(jobs.resolve(Some("%+")), Ok(2));
+        assert_eq!(jobs.resolve(Some("%%")), Ok(2));
+        assert_eq!(jobs.resolve(Some("%-")), Ok(1));
+        assert_eq!(jobs.resolve(Some("%1")), Ok(1));
+        assert_eq!(jobs.resolve(Some("2")), Ok(2));
+        assert_eq!(jobs.resolve(Some("%gr")), Ok(2));
+        assert_eq!(jobs.resolve(Some("%3")), Err("%3: no such job".to_string()));
+        assert_eq!(jobs.resolve(Some("%cat")), Err("%cat: no such job".to_string()));
+
+        jobs.add(Job::new(None, "sleep 4"));
+        assert_eq!(jobs.resolve(Some("%sl")), Err("%sl: ambiguous job spec".to_string()));
+    }
+
+    #[test]
+    fn an_empty_table_has_no_current_job() {
+        let jobs = JobTable::default();
+
+        assert_eq!(jobs.resolve(None), Err("current: no such job".to_string()));
+        assert_eq!(jobs.resolve(Some("%-")), Err("%-: no such job".to_string()));
+    }
+
+    #[test]
+    fn exited_jobs_are_reported_once_in_id_order() {
+        let mut jobs = table(&["sleep 1", "sleep 2", "sleep 3"]);
+        let first = jobs.get(1).unwrap().control.clone();
+        let third = jobs.get(3).unwrap().control.clone();
+
+        assert!(!jobs.finish(&third, 0));
+        assert!(!jobs.finish(&first, 2));
+        assert_eq!(jobs.get(1).unwrap().state, JobState::Done(2));
+
+        assert_eq!(
+            jobs.take_notices(),
+            [
+                format!("[1]   {:<24}sleep 1", "Exit 2"),
+                format!("[3]+  {:<24}sleep 3", "Done"),
+            ]
+        );
+        assert!(jobs.take_notices().is_empty());
+        assert_eq!(jobs.iter().map(|(id, _)| id).collect::<Vec<_>>(), [2]);
+        assert_eq!(jobs.describe(2, false), format!("[2]+  {:<24}sleep 2 &", "Running"));
+    }
+
+    #[test]
+    fn the_foreground_job_finishing_gives_the_prompt_back() {
+        let mut jobs = table(&["sleep 1"]);
+        let control = jobs.get(1).unwrap().control.clone();
+
+        assert_eq!(jobs.bring_to_foreground(1).map(|job| job.state), Some(JobState::Running));
+        assert!(jobs.get(1).is_none());
+
+        assert!(jobs.finish(&control, 0));
+        assert!(jobs.take_notices().is_empty());
+        assert_eq!(jobs.stop_foreground(), None);
+    }
+
+    #[test]
+    fn stopping_the_foreground_job_parks_it_in_the_table() {
+        let mut jobs = JobTable::default();
+        let job = Job::new(Some(1005), "sleep 10");
+        let control = job.control.clone();
+        jobs.set_foreground(job);
+
+        assert_eq!(jobs.stop_foreground(), Some(1));
+        assert!(control.is_stopped());
+        assert_eq!(jobs.describe(1, true), format!("[1]+ 1005  {:<24}sleep 10", "Stopped"));
+
+        jobs.resume(1);
+        assert!(!control.is_stopped());
+    }
+}
@@ -0,0 +1,391 @@
+//! Manual pages, kept in `/usr/share/man/man<section>/<name>.<section>`.
+//!
+//! Pages are written in a small subset of roff, one request per line:
+//!
+//! - `.TH NAME SECTION` starts the page,
+//! - `.SH HEADING` starts a section,
+//! - `.PP` starts a new paragraph,
+//! - `.TP` starts a tagged paragraph, the next line being the tag,
+//! - `.nf` and `.fi` turn line filling off and back on, e.g. for examples,
+//! - `.\"` starts a comment.
+//!
+//! Any other line is text, filled into paragraphs when rendered. Pages of
+//! the builtins are generated from their clap definition at startup; pages
+//! written by hand in the same folders are left alone.
+
+use crate::{
+    console_log,
+    core::shell::command::commands,
+    vfs::{entry::FSEntryKind, fs::SimpleFS, path},
+};
+
+pub const MAN_PATH: &str = "/usr/share/man";
+
+/// First line of the generated pages, which are the only ones rewritten.
+const GENERATED_MARKER: &str = ".\\\" Generated by rshell";
+
+/// Width the pages are laid out to.
+pub const PAGE_WIDTH: usize = 80;
+
+/// Indentation of the text below a section heading.
+const INDENT: usize = 7;
+
+/// Additional indentation of the text of a tagged paragraph.
+const TAG_INDENT: usize = 7;
+
+const RSHELL_PAGE: &str = r#".TH RSHELL 1
+.SH NAME
+rshell \- the command interpreter of R-OS
+.SH SYNOPSIS
+.nf
+rshell
+script [ARGS]...
+.fi
+.SH DESCRIPTION
+rshell reads commands typed at the prompt, or from a script starting with a
+#!/bin/rsh line, and runs them. Builtins are listed by help, and each one
+has its own manual page.
+.SH SYNTAX
+.TP
+cmd1 | cmd2
+Pipeline, the output of cmd1 is the input of cmd2.
+.TP
+cmd1 && cmd2, cmd1 || cmd2
+Runs cmd2 only when cmd1 succeeds, or fails.
+.TP
+cmd &
+Runs cmd as a background job, see jobs, fg and bg. Ctrl-Z stops the
+foreground job.
+.TP
+> file, >> file, < file, 2>&1, <<EOF
+Redirections to and from files of the VFS, and here-documents.
+.TP
+$VAR, ${VAR:-default}, $?, $$, $1, $@
+Parameter expansion.
+.TP
+a{b,c}, *, ?, [abc], **
+Brace expansion, then file names matching the pattern, sorted. ** matches
+any number of folders. Names starting with a dot are only matched with
+shopt -s dotglob.
+.TP
+if, while, until, for, case, name() { ... }
+Control flow and functions.
+.SH FILES
+.TP
+/etc/profile, ~/.rshrc
+Run at startup.
+.TP
+~/.rsh_aliases, ~/.rsh_functions
+Aliases and functions defined at the prompt, restored at startup before
+~/.rshrc, whose definitions take precedence.
+.TP
+/usr/share/man
+Manual pages, read by man and apropos.
+"#;
+
+/// Pages written by hand, installed when missing.
+const PAGES: &[(&str, &str, &str)] = &[("rshell", "1", RSHELL_PAGE)];
+
+/// Path of the page of `name` in `section`.
+pub fn page_path(name: &str, section: &str) -> String {
+    format!("{}/man{}/{}.{}", MAN_PATH, section, name, section)
+}
+
+/// Writes the pages of the builtins and the hand-written pages that are
+/// missing. Generated pages are refreshed when the builtin has changed.
+pub async fn install(fs: &mut SimpleFS) {
+    let section_path = format!("{}/man1", MAN_PATH);
+
+    for folder in ["/usr", "/usr/share", MAN_PATH, section_path.as_str()] {
+        if matches!(fs.get_entry(folder).await, Ok(Some(_))) {
+            continue;
+        }
+
+        if let Err(err) = fs.create_folder(folder).await {
+            console_log(&format!("[man] cannot create '{}': {}", folder, err));
+            return;
+        }
+    }
+
+    for command in commands() {
+        let page_path = page_path(command.name(), "1");
+        let page = generate(&mut command.usage(), command.aliases());
+
+        let current = fs.read_file(&page_path).await.ok();
+        let up_to_date = current.as_deref() == Some(page.as_bytes());
+        let hand_written = current.is_some_and(|data| !data.starts_with(GENERATED_MARKER.as_bytes()));
+
+        if up_to_date || hand_written {
+            continue;
+        }
+
+        if let Err(err) = fs.write_file(&page_path, page.as_bytes(), false).await {
+            console_log(&format!("[man] cannot write '{}': {}", page_path, err));
+        }
+    }
+
+    for (name, section, page) in PAGES {
+        let page_path = page_path(name, section);
+
+        if !matches!(fs.get_entry(&page_path).await, Ok(None)) {
+            continue;
+        }
+
+        if let Err(err) = fs.write_file(&page_path, page.as_bytes(), false).await {
+            console_log(&format!("[man] cannot write '{}': {}", page_path, err));
+        }
+    }
+}
+
+/// Writes the page of a builtin from its clap definition.
+pub fn generate(cmd: &mut clap::Command, aliases: &[&str]) -> String {
+    cmd.build();
+
+    let name = cmd.get_name().to_string();
+    let names: Vec<&str> = std::iter::once(name.as_str()).chain(aliases.iter().copied()).collect();
+    let about = cmd.get_about().map(|about| about.to_string()).unwrap_or_default();
+
+    let mut page = format!("{} from the definition of {}, changes are overwritten.\n", GENERATED_MARKER, name);
+    page.push_str(&format!(".TH {} 1\n", name.to_uppercase()));
+
+    page.push_str(".SH NAME\n");
+    page.push_str(&format!("{} \\- {}\n", names.join(", "), about));
+
+    let usage = cmd.render_usage().to_string();
+    page.push_str(".SH SYNOPSIS\n.nf\n");
+    for line in usage.trim().strip_prefix("Usage:").unwrap_or(&usage).lines() {
+        page.push_str(&format!("{}\n", escape(line.trim())));
+    }
+    page.push_str(".fi\n");
+
+    if let Some(long_about) = cmd.get_long_about() {
+        page.push_str(&format!(".SH DESCRIPTION\n{}\n", escape(&long_about.to_string())));
+    }
+
+    let (positionals, options): (Vec<&clap::Arg>, Vec<&clap::Arg>) =
+        cmd.get_arguments().filter(|arg| !arg.is_hide_set()).partition(|arg| arg.is_positional());
+
+    for (heading, args) in [("ARGUMENTS", positionals), ("OPTIONS", options)] {
+        if args.is_empty() {
+            continue;
+        }
+
+        page.push_str(&format!(".SH {}\n", heading));
+        for arg in args {
+            page.push_str(&format!(".TP\n{}\n", escape(&arg_tag(arg))));
+
+            let help = arg.get_long_help().or(arg.get_help()).map(|help| help.to_string());
+            if let Some(help) = help {
+                page.push_str(&format!("{}\n", escape(&help)));
+            }
+        }
+    }
+
+    if let Some(version) = cmd.get_version() {
+        page.push_str(&format!(".SH VERSION\n{}\n", version));
+    }
+
+    page
+}
+
+/// How an argument is written in a page, e.g. `-n, --lines <N>`.
+fn arg_tag(arg: &clap::Arg) -> String {
+    let value = arg.get_action().takes_values().then(|| {
+        let names = match arg.get_value_names() {
+            Some(names) => names.iter().map(|name| format!("<{}>", name)).collect::<Vec<_>>().join(" "),
+            None => format!("<{}>", arg.get_id().as_str().to_uppercase()),
+        };
+        let repeated = arg.get_num_args().is_some_and(|range| range.max_values() > 1);
+
+        if repeated { format!("{}...", names) } else { names }
+    });
+
+    let mut flags = Vec::new();
+    if let Some(short) = arg.get_short() {
+        flags.push(format!("-{}", short));
+    }
+    if let Some(long) = arg.get_long() {
+        flags.push(format!("--{}", long));
+    }
+
+    match (flags.is_empty(), value) {
+        (true, value) => value.unwrap_or_default(),
+        (false, Some(value)) => format!("{} {}", flags.join(", "), value),
+        (false, None) => flags.join(", "),
+    }
+}
+
+/// Lays `page` out for the console, `width` columns wide.
+pub fn render(page: &str, width: usize) -> String {
+    let mut renderer = Renderer { width, out: String::new(), paragraph: Vec::new(), indent: 0, fill: true };
+    let mut tag_next = false;
+
+    for line in page.lines() {
+        if tag_next {
+            renderer.flush();
+            renderer.push_line(INDENT, &unescape(line));
+            renderer.indent = INDENT + TAG_INDENT;
+            tag_next = false;
+            continue;
+        }
+
+        let (request, rest) = match line.strip_prefix('.') {
+            Some(request) => request.split_once(' ').unwrap_or((request, "")),
+            None => ("", line),
+        };
+
+        match request {
+            "" => {
+                let text = unescape(rest);
+
+                if !renderer.fill {
+                    renderer.push_line(renderer.indent, &text);
+                } else if text.trim().is_empty() {
+                    renderer.flush();
+                    renderer.out.push('\n');
+                } else {
+                    renderer.paragraph.push(text);
+                }
+            }
+            "TH" => {
+                let mut fields = rest.split_whitespace();
+                let title = format!("{}({})", fields.next().unwrap_or_default(), fields.next().unwrap_or("1"));
+                let center = "R-OS Manual";
+
+                let gap = width.saturating_sub(2 * title.len() + center.len()) / 2;
+                renderer.out.push_str(&format!("{}{}{}", title, " ".repeat(gap), center));
+                renderer.out.push_str(&format!("{}{}\n", " ".repeat(gap), title));
+            }
+            "SH" => {
+                renderer.flush();
+                renderer.out.push('\n');
+                renderer.out.push_str(&unescape(rest).to_uppercase());
+                renderer.out.push('\n');
+                renderer.indent = INDENT;
+            }
+            "PP" | "LP" | "P" => {
+                renderer.flush();
+                renderer.out.push('\n');
+                renderer.indent = INDENT;
+            }
+            "TP" => {
+                renderer.flush();
+                tag_next = true;
+            }
+            "nf" => {
+                renderer.flush();
+                renderer.fill = false;
+            }
+            "fi" => renderer.fill = true,
+            // comments and requests we do not know
+            _ => {}
+        }
+    }
+
+    renderer.flush();
+    renderer.out
+}
+
+struct Renderer {
+    width: usize,
+    out: String,
+    /// Lines of the paragraph being filled.
+    paragraph: Vec<String>,
+    indent: usize,
+    fill: bool,
+}
+
+impl Renderer {
+    fn push_line(&mut self, indent: usize, text: &str) {
+        self.out.push_str(&" ".repeat(indent));
+        self.out.push_str(text);
+        self.out.push('\n');
+    }
+
+    /// Writes the current paragraph, wrapping its words at the page width.
+    fn flush(&mut self) {
+        let text = std::mem::take(&mut self.paragraph).join(" ");
+        let available = self.width.saturating_sub(self.indent).max(20);
+        let mut line = String::new();
+
+        for word in text.split_whitespace() {
+            if !line.is_empty() && line.chars().count() + 1 + word.chars().count() > available {
+                self.push_line(self.indent, &line);
+                line.clear();
+            }
+
+            if !line.is_empty() {
+                line.push(' ');
+            }
+            line.push_str(word);
+        }
+
+        if !line.is_empty() {
+            self.push_line(self.indent, &line);
+        }
+    }
+}
+
+/// Makes text from a clap definition safe to put in a page: a line must
+/// not start with a `.`, which would make it a request.
+fn escape(text: &str) -> String {
+    text.lines()
+        .map(|line| if line.starts_with('.') { format!("\\&{}", line) } else { line.to_string() })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Replaces the roff escapes that pages use: `\-`, `\&` (nothing, to
+/// start a line with a `.`) and `\\`.
+fn unescape(text: &str) -> String {
+    text.replace("\\-", "-").replace("\\&", "").replace("\\\\", "\\")
+}
+
+/// The name and the summary of a page, from the first line of its `NAME`
+/// section (`name \- summary`).
+pub fn summary(page: &str) -> Option<(String, String)> {
+    let mut lines = page.lines().skip_while(|line| !line.starts_with(".SH NAME"));
+    lines.next()?;
+
+    let line = lines.find(|line| !line.starts_with('.'))?;
+    let (names, summary) = line.split_once("\\-").or_else(|| line.split_once(" - "))?;
+
+    Some((unescape(names.trim()), unescape(summary.trim())))
+}
+
+/// A page found in the manual.
+pub struct Page {
+    pub name: String,
+    pub section: String,
+    pub path: String,
+}
+
+/// Lists every page, by section then name.
+pub async fn pages(fs: &SimpleFS) -> Vec<Page> {
+    let mut sections: Vec<String> = fs
+        .read_dir(MAN_PATH)
+        .await
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|entry| matches!(entry.entry, FSEntryKind::Folder(_)))
+        .filter_map(|entry| path::file_name(&entry.abs_path).strip_prefix("man").map(str::to_string))
+        .collect();
+    sections.sort();
+
+    let mut pages = Vec::new();
+
+    for section in sections {
+        let folder = format!("{}/man{}", MAN_PATH, section);
+        let mut entries = fs.read_dir(&folder).await.unwrap_or_default();
+        entries.sort_by(|a, b| a.abs_path.cmp(&b.abs_path));
+
+        for entry in entries {
+            let file_name = path::file_name(&entry.abs_path);
+            let name = file_name.strip_suffix(&format!(".{}", section)).unwrap_or(file_name);
+
+            pages.push(Page { name: name.to_string(), section: section.clone(), path: entry.abs_path.clone() });
+        }
+    }
+
+    pages
+}
@@ -1,31 +1,90 @@
-use std::sync::Arc;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    sync::{
+        atomic::{AtomicI32, Ordering},
+        Arc,
+    },
+};
 
 use async_std::sync::Mutex;
-use regex::Regex;
 use wasm_bindgen_futures::spawn_local;
 
 mod command;
+pub mod complete;
+pub mod exec;
+pub mod expand;
+pub mod glob;
+pub mod job;
+pub mod man;
+pub mod parser;
+pub mod pattern;
+pub mod printer;
+pub mod regexp;
+pub mod startup;
+pub mod task;
 
 use crate::{
     console_log,
-    core::{
-        demo::DemoProcess,
-        shell::command::{ShellCommand, ShellCommandWithData, ShellCommandWithShell},
+    core::shell::{
+        complete::{format_columns, Completion},
+        exec::Flow,
+        expand::{render_prompt, Scope},
+        job::{Job, JobControl, JobTable},
+        parser::{Command, ParseError},
     },
     kernel::{Kernel, Message},
     process::{BoxedProcess, Process},
 };
 
-static REG_SHELL: &str = r"(^[\w\d-]+@[\w\d-]+:.+\$\s?)(.+)$";
+/// Prompt printed while a command spans several lines.
+const CONTINUATION_PROMPT: &str = "> ";
+
+/// Exit status of a job stopped with Ctrl-Z, 128 + SIGTSTP as in Unix.
+const STATUS_STOPPED: i32 = 148;
+
+/// Options `shopt` can turn on, all off by default: `dotglob` lets globs
+/// match names starting with a dot, `nullglob` makes a glob matching
+/// nothing expand to nothing rather than to itself.
+pub const OPTIONS: &[&str] = &["dotglob", "nullglob"];
+
+/// Prompt used when `PS1` is unset.
+const DEFAULT_PS1: &str = "\\u@\\h:\\w\\$ ";
 
 #[derive(Debug, Clone)]
 pub struct Shell {
     pid: usize,
     name: String,
+    /// Lines of a command that is still incomplete (open quote, trailing `&&`).
     buffer: String,
     waiting_for_input: bool,
-    regex: Regex,
+    /// Byte offset in the console where the text typed by the user begins.
+    input_start: usize,
     folder: Arc<Mutex<String>>,
+    /// Exit status of the last pipeline, expanded by `$?`.
+    last_status: Arc<AtomicI32>,
+    /// `$0` followed by the positional parameters `$1..$n`.
+    params: Vec<String>,
+    /// Functions defined with `name() { ... }`, by name.
+    functions: Arc<Mutex<BTreeMap<String, Command>>>,
+    /// Replacement text of the aliases, by name.
+    aliases: Arc<Mutex<BTreeMap<String, String>>>,
+    /// Whether commands come from the user rather than a script, in which
+    /// case the aliases and functions they define are saved.
+    interactive: bool,
+    /// Set by `break`, `continue`, `return` and `exit` until the enclosing
+    /// loop, function or script handles it.
+    flow: Option<Flow>,
+    /// How many loops are running, for `break` and `continue`.
+    loop_depth: usize,
+    /// How many functions and sourced scripts are running, for `return`.
+    call_depth: usize,
+    /// Jobs started from the prompt, shared by every copy of the shell.
+    jobs: Arc<Mutex<JobTable>>,
+    /// Control of the job this copy of the shell runs, checked between
+    /// commands so that the job can be stopped.
+    job: Option<Arc<JobControl>>,
+    /// Names of the options turned on with `shopt -s`.
+    options: Arc<Mutex<BTreeSet<&'static str>>>,
 }
 
 impl Shell {
@@ -35,8 +94,33 @@ impl Shell {
             name: "rshell".into(),
             buffer: String::new(),
             waiting_for_input: true,
-            regex: Regex::new(REG_SHELL).unwrap(),
+            input_start: 0,
             folder: Arc::new(Mutex::new("/".to_string())),
+            last_status: Arc::new(AtomicI32::new(0)),
+            params: vec!["rshell".into()],
+            functions: Arc::new(Mutex::new(BTreeMap::new())),
+            aliases: Arc::new(Mutex::new(BTreeMap::new())),
+            interactive: true,
+            flow: None,
+            loop_depth: 0,
+            call_depth: 0,
+            jobs: Arc::new(Mutex::new(JobTable::default())),
+            job: None,
+            options: Arc::new(Mutex::new(BTreeSet::new())),
+        }
+    }
+
+    /// A shell running a script as process `pid`. It starts in `cwd` but
+    /// has its own folder, status, functions and aliases from then on.
+    fn for_script(pid: usize, cwd: &str, params: Vec<String>) -> Self {
+        Shell {
+            pid,
+            name: params[0].clone(),
+            waiting_for_input: false,
+            interactive: false,
+            folder: Arc::new(Mutex::new(cwd.into())),
+            params,
+            ..Shell::new()
         }
     }
 }
@@ -51,55 +135,74 @@ impl Process for Shell {
     }
 
     fn tick(&mut self, k: &mut Kernel) {
-        let shell_prompt = self.shell_prompt();
-
         if k.tick_count == 1 && self.buffer.is_empty() {
             console_log("[shell] Shell process started");
-            self.print_welcome(k);
-            k.print(&shell_prompt);
+            self.start(k);
         }
 
         if self.waiting_for_input {
             let text = k.console.value();
 
-            let last_line = text.lines().last().unwrap_or("").to_string();
-
-            if (last_line.is_empty() || self.regex.is_match(&last_line)) && text.ends_with("\n") {
-                if last_line.is_empty() {
-                    k.print(&shell_prompt);
-                    return;
-                }
+            let Some(line) = text.get(self.input_start..) else {
+                // the prompt was erased, start over from the end of the console
+                self.input_start = text.len();
+                return;
+            };
 
-                let split = self
-                    .regex
-                    .captures(&last_line)
-                    .expect("regex matched but no captures");
+            if !line.ends_with('\n') {
+                return;
+            }
 
-                let command = split.get(2).map_or("", |m| m.as_str());
+            let line = line.to_string();
+            self.buffer.push_str(&line);
 
-                console_log(&format!("[shell] detected command: '{}'", command.trim()));
+            match self.parse(&self.buffer) {
+                Ok(list) if list.items.is_empty() => {
+                    self.buffer.clear();
+                    self.print_prompt(k);
+                }
+                Ok(_) => {
+                    let command = std::mem::take(&mut self.buffer);
+                    console_log(&format!("[shell] detected command: '{}'", command.trim()));
 
-                if !command.is_empty() {
                     self.execute_command(&command, k);
                 }
-
-                k.print("\n");
-                k.print(&shell_prompt);
+                Err(ParseError::Incomplete) => {
+                    k.print(CONTINUATION_PROMPT);
+                    self.input_start = k.console.value().len();
+                }
+                Err(err) => {
+                    self.buffer.clear();
+                    k.print(&format!("rshell: {}\n", err));
+                    self.print_prompt(k);
+                }
             }
         }
     }
 
     fn on_message(&mut self, _k: &mut Kernel, msg: Message) {
         match msg {
-            Message::SetWaitingForInput(wait) => {
-                self.waiting_for_input = wait;
-            }
             Message::Print(s) => {
                 _k.print(&s);
             }
             Message::Kill => {
                 // opzionale: gestione di terminazione
             }
+            Message::Key(key) => {
+                if key.key == "Tab" && !key.ctrl {
+                    self.request_completion(_k);
+                } else if key.ctrl && key.key.eq_ignore_ascii_case("z") {
+                    self.stop_foreground(_k);
+                }
+            }
+            Message::Completion(completion) => {
+                self.apply_completion(_k, completion);
+            }
+            Message::CommandFinished(status) => {
+                console_log(&format!("[shell] command exited with status {}", status));
+                self.print_prompt(_k);
+                self.waiting_for_input = true;
+            }
         }
     }
     
@@ -109,136 +212,188 @@ impl Process for Shell {
 }
 
 impl Shell {
-    pub fn shell_prompt(&self) -> String {
+    /// Sets the variables every login shell starts with.
+    fn init_env(&self, k: &mut Kernel) {
+        let env = k.envs.entry(self.pid).or_default();
+
+        env.set_exported("HOME", "/home/user");
+        env.set_exported("USER", "user");
+        env.set_exported("PATH", "/bin:/usr/bin");
+        env.set_exported("PWD", "/");
+    }
+
+    /// Prints the message of the day and runs the startup files, then
+    /// waits for input.
+    fn start(&mut self, k: &mut Kernel) {
+        self.init_env(k);
+        self.waiting_for_input = false;
+
+        let k_clone = Kernel::clone_rc();
+        let mut shell = self.clone();
+        let shell_pid = self.pid;
+
+        spawn_local(async move {
+            let status = startup::login(&mut shell).await;
+
+            let mut kernel = k_clone.lock().await;
+            kernel.send(shell_pid, Message::CommandFinished(status));
+        });
+    }
+
+    /// Renders `PS1` for the current folder.
+    pub fn shell_prompt(&self, k: &Kernel) -> String {
         let folder = futures::executor::block_on(async { self.folder.lock().await.clone() });
-        format!("user@r-os:{}$ ", folder)
+
+        // rendered against a copy, so that `${VAR:=...}` in the prompt does
+        // not leak into the shell
+        let mut env = k.envs.get(&self.pid).cloned().unwrap_or_default();
+        let ps1 = env.get("PS1").unwrap_or(DEFAULT_PS1).to_string();
+
+        let mut scope = Scope {
+            env: &mut env,
+            pid: self.pid,
+            status: self.last_status.load(Ordering::Relaxed),
+            params: &self.params,
+        };
+        render_prompt(&ps1, &folder, &mut scope)
     }
 
-    pub fn print_welcome(&mut self, kernel: &mut Kernel) {
-        let welcome = r#"
-==================================================
-                Welcome to R-OS
-==================================================
+    /// Reports the background jobs that have exited, then prints the prompt
+    /// and marks the end of the console as the start of the next command.
+    fn print_prompt(&mut self, k: &mut Kernel) {
+        let notices = futures::executor::block_on(async { self.jobs.lock().await.take_notices() });
+        for notice in notices {
+            k.print(&format!("{}\n", notice));
+        }
 
-Version: 0.1.0
-Author : Antonio Ricciardi
-Kernel : Custom Rust/WebAssembly Kernel
-Type   : Experimental Browser OS
+        let prompt = self.shell_prompt(k);
+        k.print(&prompt);
+        self.input_start = k.console.value().len();
+    }
 
-Type 'help' to see available commands.
-Enjoy your stay!
+    /// Whether the command being run was typed at the prompt, rather than
+    /// coming from a script or a function.
+    fn at_prompt(&self) -> bool {
+        self.interactive && self.call_depth == 0
+    }
 
-"#;
+    /// Waits while the job run by this shell is stopped.
+    async fn checkpoint(&self) {
+        if let Some(job) = &self.job {
+            job.checkpoint().await;
+        }
+    }
 
-        kernel.print(welcome);
+    /// Whether the `shopt` option `name` is on.
+    async fn option(&self, name: &str) -> bool {
+        self.options.lock().await.contains(name)
     }
 
-    fn execute_command<'a>(&mut self, cmd: &'a str, k: &mut Kernel) {
-        match cmd {
-            "help" => {
-                k.print("\nCommands: help, clear, ls, echo <text>, spawn_demo\n");
-            }
-            "clear" => {
-                k.clear();
-            }
-            "time" => {
-                let k_clone = Kernel::clone_rc();
-                let cmd = cmd.to_string();
-
-                spawn_local(async move {
-                    let mut kernel = k_clone.lock().await;
-                    command::time::TimeCommand::execute(&mut kernel, &cmd).await;
-                });
-            }
-            "top" => {
-                let k_clone = Kernel::clone_rc();
-                let cmd = cmd.to_string();
-
-                spawn_local(async move {
-                    let mut kernel = k_clone.lock().await;
-                    command::top::TopCommand::execute(&mut kernel, &cmd).await;
-                });
-            }
-            c if c.starts_with("ls") => {
-                let k_clone = Kernel::clone_rc();
-                let c_owned = c.to_string();
-
-                spawn_local(async move {
-                    let result = {
-                        let mut kernel = k_clone.lock().await;
-                        command::ls::LsCommand::execute(&mut kernel, &c_owned).await
-                    };
-
-                    {
-                        let kernel = k_clone.lock().await;
-                        kernel.print(&format!("\n{}\n", result));
-                    }
-                });
-            }
-            c if c.starts_with("exists") => {
-                let k_clone = Kernel::clone_rc();
-                let c_owned = c.to_string();
-
-                spawn_local(async move {
-                    let result = {
-                        let mut kernel = k_clone.lock().await;
-                        command::exists::ExistsCommand::execute(&mut kernel, &c_owned).await
-                    };
-
-                    {
-                        let kernel = k_clone.lock().await;
-                        kernel.print(&format!("\n{}\n", result));
-                    }
-                });
-            }
-            c if c.starts_with("echo ") => {
-                let rest = c.trim_start_matches("echo ").trim();
-                k.print(&format!("\n{}\n", rest));
-            }
-            c if c.starts_with("cd") => {
-                let k_clone = Kernel::clone_rc();
-                let c_owned = c.to_string();
+    /// Stops the foreground job on Ctrl-Z and gives the prompt back.
+    fn stop_foreground(&mut self, k: &mut Kernel) {
+        if self.waiting_for_input {
+            return;
+        }
 
-                let folder_clone = self.folder.clone();
+        let stopped = futures::executor::block_on(async {
+            let mut jobs = self.jobs.lock().await;
+            jobs.stop_foreground().map(|id| jobs.describe(id, false))
+        });
 
-                spawn_local(async move {
-                    let shell_prompt = format!("\nuser@r-os:{}$ ", folder_clone.lock().await);
+        let Some(stopped) = stopped else {
+            return;
+        };
 
-                    let result = {
-                        let mut kernel = k_clone.lock().await;
-                        command::cd::CdCommand::execute_data(&mut kernel, &c_owned, folder_clone)
-                            .await
-                    };
-                });
-            }
-            "demo" => {
-                k.print("\nSpawning demo process...\n");
-                k.spawn(Box::new(DemoProcess::new(self)));
-            }
-            c if c.starts_with("mkdir") => {
-                let k_clone = Kernel::clone_rc();
-                let c_owned = c.to_string();
-                let mut shell = self.clone();
-
-                console_log(&format!("[shell] executing command: '{}'", c_owned));
-
-                spawn_local(async move {
-                    let result = {
-                        let mut kernel = k_clone.lock().await;
-                        command::mkdir::MkDirCommand::execute(&mut kernel, &mut shell, &c_owned)
-                            .await
-                    };
-
-                    {
-                        let kernel = k_clone.lock().await;
-                        kernel.print(&format!("\n{}\n", result));
-                    }
-                });
+        k.print(&format!("^Z\n{}\n", stopped));
+        self.last_status.store(STATUS_STOPPED, Ordering::Relaxed);
+        self.print_prompt(k);
+        self.waiting_for_input = true;
+    }
+
+    /// Parses `input`, expanding the aliases of the shell.
+    fn parse(&self, input: &str) -> Result<parser::List, ParseError> {
+        let aliases = futures::executor::block_on(async { self.aliases.lock().await.clone() });
+        parser::parse_with_aliases(input, &aliases)
+    }
+
+    /// Returns what the user typed after the prompt.
+    fn current_input(&self, k: &Kernel) -> Option<String> {
+        k.console.value().get(self.input_start..).map(|input| input.to_string())
+    }
+
+    fn request_completion(&self, _k: &mut Kernel) {
+        if !self.waiting_for_input || !self.buffer.is_empty() {
+            return;
+        }
+
+        let Some(input) = self.current_input(_k) else {
+            return;
+        };
+
+        let k_clone = Kernel::clone_rc();
+        let folder_clone = self.folder.clone();
+        let shell_pid = self.pid;
+
+        spawn_local(async move {
+            let cwd = folder_clone.lock().await.clone();
+            let mut kernel = k_clone.lock().await;
+
+            let completion = complete::complete(&kernel.fs, &cwd, &input).await;
+            kernel.send(shell_pid, Message::Completion(completion));
+        });
+    }
+
+    fn apply_completion(&mut self, k: &mut Kernel, completion: Completion) {
+        // The user kept typing while the candidates were being computed.
+        if self.current_input(k).as_deref() != Some(completion.input.as_str()) {
+            return;
+        }
+
+        if !completion.candidates.is_empty() {
+            k.print(&format!("\n{}", format_columns(&completion.candidates)));
+            self.print_prompt(k);
+            k.print(&completion.line);
+            return;
+        }
+
+        let text = k.console.value();
+
+        k.console.set_value(&format!("{}{}", &text[..self.input_start], completion.line));
+        k.console.set_scroll_top(k.console.scroll_height());
+    }
+
+    /// Parses `cmd` and runs it as the foreground job, printing the prompt
+    /// again once it has finished.
+    fn execute_command(&mut self, cmd: &str, _k: &mut Kernel) {
+        let list = match self.parse(cmd) {
+            Ok(list) => list,
+            Err(err) => {
+                _k.print(&format!("rshell: {}\n", err));
+                self.print_prompt(_k);
+                return;
             }
-            _ => {
-                k.print(&format!("\nUnknown: {}\n", cmd));
+        };
+
+        self.waiting_for_input = false;
+
+        let job = Job::new(None, cmd.trim());
+        let control = job.control.clone();
+        futures::executor::block_on(async { self.jobs.lock().await.set_foreground(job) });
+
+        let k_clone = Kernel::clone_rc();
+        let mut shell = self.clone();
+        shell.job = Some(control.clone());
+        let shell_pid = self.pid;
+
+        spawn_local(async move {
+            let status = exec::run(&mut shell, &list).await;
+
+            // a job stopped with Ctrl-Z already gave the prompt back
+            if shell.jobs.lock().await.finish(&control, status) {
+                let mut kernel = k_clone.lock().await;
+                kernel.send(shell_pid, Message::CommandFinished(status));
             }
-        }
+        });
     }
 }
 
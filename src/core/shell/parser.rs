@@ -0,0 +1,1329 @@
+use std::{collections::BTreeMap, fmt, iter::Peekable, str::Chars};
+
+use crate::kernel::env::is_valid_name;
+
+/// A piece of a word, keeping track of how it was quoted so that later
+/// stages know which parts are subject to expansion and word splitting.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WordPart {
+    /// Unquoted text.
+    Literal(String),
+    /// Text coming from single quotes or a backslash escape.
+    Quoted(String),
+    /// Contents of double quotes: quoted text and parameter expansions.
+    DoubleQuoted(Vec<WordPart>),
+    /// `$NAME` or `${NAME...}`.
+    Param(Param),
+}
+
+/// A parameter expansion such as `$HOME` or `${NAME:-default}`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Param {
+    pub name: String,
+    pub op: Option<ParamOp>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParamOp {
+    /// `${#NAME}`
+    Length,
+    /// `${NAME:-word}`, or `${NAME-word}` when `colon` is false
+    Default { colon: bool, word: Word },
+    /// `${NAME:=word}`
+    Assign { colon: bool, word: Word },
+    /// `${NAME:+word}`
+    Alternative { colon: bool, word: Word },
+    /// `${NAME:?word}`
+    Error { colon: bool, word: Word },
+}
+
+impl fmt::Display for Param {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (colon, op, word) = match &self.op {
+            None => return write!(f, "${}", self.name),
+            Some(ParamOp::Length) => return write!(f, "${{#{}}}", self.name),
+            Some(ParamOp::Default { colon, word }) => (colon, '-', word),
+            Some(ParamOp::Assign { colon, word }) => (colon, '=', word),
+            Some(ParamOp::Alternative { colon, word }) => (colon, '+', word),
+            Some(ParamOp::Error { colon, word }) => (colon, '?', word),
+        };
+
+        write!(f, "${{{}{}{}{}}}", self.name, if *colon { ":" } else { "" }, op, word)
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Word {
+    pub parts: Vec<WordPart>,
+}
+
+impl Word {
+    /// A word standing for exactly `text`, with no further expansion.
+    pub fn quoted(text: &str) -> Self {
+        Word { parts: vec![WordPart::Quoted(text.into())] }
+    }
+
+    /// The word with quotes removed and expansions left as written.
+    pub fn text(&self) -> String {
+        parts_text(&self.parts)
+    }
+
+    pub fn push_literal(&mut self, c: char) {
+        match self.parts.last_mut() {
+            Some(WordPart::Literal(s)) => s.push(c),
+            _ => self.parts.push(WordPart::Literal(c.to_string())),
+        }
+    }
+
+    fn push_quoted(&mut self, c: char) {
+        match self.parts.last_mut() {
+            Some(WordPart::Quoted(s)) => s.push(c),
+            _ => self.parts.push(WordPart::Quoted(c.to_string())),
+        }
+    }
+
+    /// The reserved word this word stands for, if it is one. Quoted words
+    /// are never reserved, e.g. `"if"` is an ordinary word.
+    fn as_keyword(&self) -> Option<&'static str> {
+        match self.parts.as_slice() {
+            [WordPart::Literal(s)] => KEYWORDS.iter().find(|k| **k == s).copied(),
+            _ => None,
+        }
+    }
+
+    /// The word as a plain name, e.g. the name of a function definition or
+    /// the variable of a `for` loop.
+    fn as_name(&self) -> Option<&str> {
+        match self.parts.as_slice() {
+            [WordPart::Literal(s)] if is_valid_name(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    fn is_quoted(&self) -> bool {
+        self.parts
+            .iter()
+            .any(|part| matches!(part, WordPart::Quoted(_) | WordPart::DoubleQuoted(_)))
+    }
+
+    /// An unquoted word made only of digits, e.g. the `2` in `2>`.
+    fn as_io_number(&self) -> Option<usize> {
+        match self.parts.as_slice() {
+            [WordPart::Literal(s)] if s.chars().all(|c| c.is_ascii_digit()) => s.parse().ok(),
+            _ => None,
+        }
+    }
+
+    /// Splits `NAME=value` into its name and value when the word is a
+    /// variable assignment.
+    fn as_assignment(&self) -> Option<(String, Word)> {
+        let Some(WordPart::Literal(first)) = self.parts.first() else {
+            return None;
+        };
+        let (name, rest) = first.split_once('=')?;
+
+        if !is_valid_name(name) {
+            return None;
+        }
+
+        let mut value = Word::default();
+        if !rest.is_empty() {
+            value.parts.push(WordPart::Literal(rest.into()));
+        }
+        value.parts.extend(self.parts[1..].iter().cloned());
+
+        Some((name.into(), value))
+    }
+}
+
+fn parts_text(parts: &[WordPart]) -> String {
+    parts
+        .iter()
+        .map(|part| match part {
+            WordPart::Literal(s) | WordPart::Quoted(s) => s.clone(),
+            WordPart::DoubleQuoted(inner) => parts_text(inner),
+            WordPart::Param(param) => param.to_string(),
+        })
+        .collect()
+}
+
+/// Words with a special meaning when they start a command.
+const KEYWORDS: &[&str] = &[
+    "!", "{", "}", "case", "do", "done", "elif", "else", "esac", "fi", "for", "if", "in", "then", "until",
+    "while",
+];
+
+/// Whether `name` is a reserved word such as `if` or `done`.
+pub fn is_keyword(name: &str) -> bool {
+    KEYWORDS.contains(&name)
+}
+
+/// Reserved words closing a list, e.g. the `fi` of an `if`.
+const TERMINATORS: &[&str] = &["}", "do", "done", "elif", "else", "esac", "fi", "then"];
+
+/// The body of a `<<DELIMITER` redirection.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HereDoc {
+    pub delimiter: String,
+    /// Whether the delimiter was quoted, which disables expansion of the body.
+    pub quoted: bool,
+    pub body: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operator {
+    Semi,
+    /// `;;`, ending an item of a `case`
+    DSemi,
+    Newline,
+    AndIf,
+    OrIf,
+    Pipe,
+    Amp,
+    Less,
+    Great,
+    DGreat,
+    DLess,
+    LessAnd,
+    GreatAnd,
+    LParen,
+    RParen,
+}
+
+impl fmt::Display for Operator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            Operator::Semi => ";",
+            Operator::DSemi => ";;",
+            Operator::Newline => "newline",
+            Operator::AndIf => "&&",
+            Operator::OrIf => "||",
+            Operator::Pipe => "|",
+            Operator::Amp => "&",
+            Operator::Less => "<",
+            Operator::Great => ">",
+            Operator::DGreat => ">>",
+            Operator::DLess => "<<",
+            Operator::LessAnd => "<&",
+            Operator::GreatAnd => ">&",
+            Operator::LParen => "(",
+            Operator::RParen => ")",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Token {
+    Word(Word),
+    Operator(Operator),
+    /// File descriptor number written right before a redirection operator.
+    IoNumber(usize),
+    /// Delimiter of a here-doc, together with the lines that followed it.
+    HereDoc(HereDoc),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseError {
+    /// The input stops in the middle of a construct (open quote, trailing
+    /// `&&`, ...) and continues on the next line.
+    Incomplete,
+    UnexpectedToken(String),
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::Incomplete => write!(f, "syntax error: unexpected end of file"),
+            ParseError::UnexpectedToken(t) => write!(f, "syntax error near unexpected token `{}'", t),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RedirectOp {
+    /// `< file`
+    Input(Word),
+    /// `> file`
+    Output(Word),
+    /// `>> file`
+    Append(Word),
+    /// `<& fd`
+    DupInput(Word),
+    /// `>& fd`
+    DupOutput(Word),
+    /// `<< DELIMITER`
+    HereDoc(HereDoc),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Redirect {
+    pub fd: usize,
+    pub op: RedirectOp,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SimpleCommand {
+    /// `NAME=value` words preceding the command name.
+    pub assignments: Vec<(String, Word)>,
+    pub words: Vec<Word>,
+    /// Redirections, applied left to right.
+    pub redirects: Vec<Redirect>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Connector {
+    And,
+    Or,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+    Simple(SimpleCommand),
+    /// A compound command and the redirections applied to all of it.
+    Compound(CompoundCommand, Vec<Redirect>),
+    FunctionDef(FunctionDef),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CompoundCommand {
+    /// `{ list; }`
+    Group(List),
+    /// `if list; then list; [elif list; then list;]... [else list;] fi`
+    If { branches: Vec<(List, List)>, else_branch: Option<List> },
+    /// `while list; do list; done`, or `until list; do list; done`
+    While { until: bool, condition: List, body: List },
+    /// `for name [in word...]; do list; done`, iterating over the positional
+    /// parameters when `words` is `None`.
+    For { name: String, words: Option<Vec<Word>>, body: List },
+    /// `case word in pattern[|pattern]...) list;; ... esac`
+    Case { word: Word, items: Vec<CaseItem> },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CaseItem {
+    pub patterns: Vec<Word>,
+    pub body: List,
+}
+
+/// `name() compound-command`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FunctionDef {
+    pub name: String,
+    pub body: Box<Command>,
+}
+
+/// Commands joined by `|`, each one reading the output of the previous.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pipeline {
+    /// `! pipeline`, inverting the exit status.
+    pub negated: bool,
+    pub commands: Vec<Command>,
+}
+
+/// Pipelines joined by `&&` and `||`, evaluated left to right.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AndOr {
+    pub first: Pipeline,
+    pub rest: Vec<(Connector, Pipeline)>,
+    /// Terminated by `&`, run as a background job.
+    pub background: bool,
+}
+
+/// A sequence of `AndOr` separated by `;`, `&` or newlines.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct List {
+    pub items: Vec<AndOr>,
+}
+
+pub fn tokenize(input: &str) -> Result<Vec<Token>, ParseError> {
+    let mut tokens = Vec::new();
+    let mut lexer = Lexer { chars: input.chars().peekable() };
+    let mut word: Option<Word> = None;
+    // indexes of the here-doc delimiters whose body starts on the next line
+    let mut heredocs: Vec<usize> = Vec::new();
+
+    let push_word = |tokens: &mut Vec<Token>, heredocs: &mut Vec<usize>, word: Option<Word>| {
+        if let Some(w) = word {
+            if tokens.last() == Some(&Token::Operator(Operator::DLess)) {
+                heredocs.push(tokens.len());
+            }
+            tokens.push(Token::Word(w));
+        }
+    };
+
+    while let Some(c) = lexer.chars.next() {
+        match c {
+            '\'' => {
+                let text = lexer.single_quoted()?;
+                word.get_or_insert_with(Word::default).parts.push(WordPart::Quoted(text));
+            }
+            '"' => {
+                let parts = lexer.double_quoted()?;
+                word.get_or_insert_with(Word::default).parts.push(WordPart::DoubleQuoted(parts));
+            }
+            '\\' => match lexer.chars.next() {
+                // line continuation
+                Some('\n') => {}
+                Some(c) => word.get_or_insert_with(Word::default).push_quoted(c),
+                None => return Err(ParseError::Incomplete),
+            },
+            '$' => {
+                let w = word.get_or_insert_with(Word::default);
+                match lexer.dollar()? {
+                    Some(param) => w.parts.push(WordPart::Param(param)),
+                    None => w.push_literal('$'),
+                }
+            }
+            '#' if word.is_none() => {
+                while lexer.chars.next_if(|c| *c != '\n').is_some() {}
+            }
+            ' ' | '\t' | '\r' => {
+                push_word(&mut tokens, &mut heredocs, word.take());
+            }
+            '<' | '>' => {
+                if let Some(w) = word.take() {
+                    match w.as_io_number() {
+                        Some(n) => tokens.push(Token::IoNumber(n)),
+                        None => push_word(&mut tokens, &mut heredocs, Some(w)),
+                    }
+                }
+
+                let op = match (c, lexer.chars.peek()) {
+                    ('<', Some('<')) => Operator::DLess,
+                    ('<', Some('&')) => Operator::LessAnd,
+                    ('>', Some('>')) => Operator::DGreat,
+                    ('>', Some('&')) => Operator::GreatAnd,
+                    ('<', _) => Operator::Less,
+                    _ => Operator::Great,
+                };
+                if !matches!(op, Operator::Less | Operator::Great) {
+                    lexer.chars.next();
+                }
+                tokens.push(Token::Operator(op));
+            }
+            '(' | ')' => {
+                push_word(&mut tokens, &mut heredocs, word.take());
+                tokens.push(Token::Operator(if c == '(' { Operator::LParen } else { Operator::RParen }));
+            }
+            '\n' | ';' | '&' | '|' => {
+                push_word(&mut tokens, &mut heredocs, word.take());
+
+                let op = match (c, lexer.chars.peek()) {
+                    ('&', Some('&')) => {
+                        lexer.chars.next();
+                        Operator::AndIf
+                    }
+                    ('|', Some('|')) => {
+                        lexer.chars.next();
+                        Operator::OrIf
+                    }
+                    (';', Some(';')) => {
+                        lexer.chars.next();
+                        Operator::DSemi
+                    }
+                    ('&', _) => Operator::Amp,
+                    ('|', _) => Operator::Pipe,
+                    (';', _) => Operator::Semi,
+                    _ => Operator::Newline,
+                };
+                tokens.push(Token::Operator(op));
+
+                if op == Operator::Newline {
+                    for index in heredocs.drain(..) {
+                        let Token::Word(delimiter) = &tokens[index] else {
+                            unreachable!("here-doc delimiters are words");
+                        };
+                        let heredoc = read_heredoc(&mut lexer.chars, delimiter)?;
+                        tokens[index] = Token::HereDoc(heredoc);
+                    }
+                }
+            }
+            c => word.get_or_insert_with(Word::default).push_literal(c),
+        }
+    }
+
+    push_word(&mut tokens, &mut heredocs, word.take());
+
+    if !heredocs.is_empty() {
+        return Err(ParseError::Incomplete);
+    }
+
+    Ok(tokens)
+}
+
+/// Splits plain text, such as a here-doc body or a prompt, into quoted
+/// text and parameter expansions. Only `$` and backslash are special.
+pub fn parse_text(input: &str) -> Result<Vec<WordPart>, ParseError> {
+    let mut lexer = Lexer { chars: input.chars().peekable() };
+    let mut parts = Vec::new();
+    let mut text = String::new();
+
+    while let Some(c) = lexer.chars.next() {
+        match c {
+            '\\' => match lexer.chars.next_if(|c| matches!(c, '$' | '`' | '\\')) {
+                Some(c) => text.push(c),
+                None => text.push('\\'),
+            },
+            '$' => match lexer.dollar()? {
+                Some(param) => {
+                    if !text.is_empty() {
+                        parts.push(WordPart::Quoted(std::mem::take(&mut text)));
+                    }
+                    parts.push(WordPart::Param(param));
+                }
+                None => text.push('$'),
+            },
+            c => text.push(c),
+        }
+    }
+
+    if !text.is_empty() {
+        parts.push(WordPart::Quoted(text));
+    }
+
+    Ok(parts)
+}
+
+struct Lexer<'a> {
+    chars: Peekable<Chars<'a>>,
+}
+
+impl Lexer<'_> {
+    /// Reads up to the closing `'`, the opening one being already consumed.
+    fn single_quoted(&mut self) -> Result<String, ParseError> {
+        let mut text = String::new();
+
+        loop {
+            match self.chars.next() {
+                Some('\'') => return Ok(text),
+                Some(c) => text.push(c),
+                None => return Err(ParseError::Incomplete),
+            }
+        }
+    }
+
+    /// Reads up to the closing `"`, the opening one being already consumed.
+    fn double_quoted(&mut self) -> Result<Vec<WordPart>, ParseError> {
+        let mut parts = Vec::new();
+        let mut text = String::new();
+
+        loop {
+            match self.chars.next() {
+                Some('"') => break,
+                Some('\\') => match self.chars.next() {
+                    Some(c @ ('$' | '`' | '"' | '\\')) => text.push(c),
+                    Some('\n') => {}
+                    Some(c) => {
+                        text.push('\\');
+                        text.push(c);
+                    }
+                    None => return Err(ParseError::Incomplete),
+                },
+                Some('$') => match self.dollar()? {
+                    Some(param) => {
+                        if !text.is_empty() {
+                            parts.push(WordPart::Quoted(std::mem::take(&mut text)));
+                        }
+                        parts.push(WordPart::Param(param));
+                    }
+                    None => text.push('$'),
+                },
+                Some(c) => text.push(c),
+                None => return Err(ParseError::Incomplete),
+            }
+        }
+
+        if !text.is_empty() || parts.is_empty() {
+            parts.push(WordPart::Quoted(text));
+        }
+
+        Ok(parts)
+    }
+
+    /// Reads a parameter expansion after a `$`. Returns `None` when the `$`
+    /// is not followed by a name and stands for itself.
+    fn dollar(&mut self) -> Result<Option<Param>, ParseError> {
+        match self.chars.peek() {
+            Some('{') => {
+                self.chars.next();
+                self.braced_param().map(Some)
+            }
+            Some(c) if c.is_ascii_alphabetic() || *c == '_' => {
+                let mut name = String::new();
+                while let Some(c) = self.chars.next_if(|c| c.is_ascii_alphanumeric() || *c == '_') {
+                    name.push(c);
+                }
+                Ok(Some(Param { name, op: None }))
+            }
+            Some(c) if c.is_ascii_digit() || matches!(c, '?' | '$' | '#' | '@' | '*') => {
+                let name = self.chars.next().unwrap().to_string();
+                Ok(Some(Param { name, op: None }))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// Reads `${...}`, the `${` being already consumed.
+    fn braced_param(&mut self) -> Result<Param, ParseError> {
+        let mut body = String::new();
+        let mut depth = 0;
+
+        loop {
+            match self.chars.next() {
+                Some('}') if depth == 0 => break,
+                Some('}') => {
+                    depth -= 1;
+                    body.push('}');
+                }
+                Some('{') => {
+                    depth += 1;
+                    body.push('{');
+                }
+                Some(c) => body.push(c),
+                None => return Err(ParseError::Incomplete),
+            }
+        }
+
+        let bad_substitution = || ParseError::UnexpectedToken(format!("${{{}}}", body));
+
+        if let Some(name) = body.strip_prefix('#').filter(|name| !name.is_empty()) {
+            return Ok(Param { name: name.into(), op: Some(ParamOp::Length) });
+        }
+
+        let name_len = match body.chars().next() {
+            Some(c) if c.is_ascii_digit() || matches!(c, '?' | '$' | '#' | '@' | '*') => 1,
+            _ => body
+                .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                .unwrap_or(body.len()),
+        };
+
+        let (name, rest) = body.split_at(name_len);
+        if name.is_empty() {
+            return Err(bad_substitution());
+        }
+
+        if rest.is_empty() {
+            return Ok(Param { name: name.into(), op: None });
+        }
+
+        let (colon, rest) = match rest.strip_prefix(':') {
+            Some(rest) => (true, rest),
+            None => (false, rest),
+        };
+
+        let mut rest_chars = rest.chars();
+        let op_char = rest_chars.next().ok_or_else(bad_substitution)?;
+        let word = operand_word(rest_chars.as_str())?;
+
+        let op = match op_char {
+            '-' => ParamOp::Default { colon, word },
+            '=' => ParamOp::Assign { colon, word },
+            '+' => ParamOp::Alternative { colon, word },
+            '?' => ParamOp::Error { colon, word },
+            _ => return Err(bad_substitution()),
+        };
+
+        Ok(Param { name: name.into(), op: Some(op) })
+    }
+}
+
+/// Parses the word after the operator of `${NAME:-word}`, where blanks do
+/// not separate words.
+fn operand_word(input: &str) -> Result<Word, ParseError> {
+    let mut lexer = Lexer { chars: input.chars().peekable() };
+    let mut word = Word::default();
+
+    while let Some(c) = lexer.chars.next() {
+        match c {
+            '\'' => {
+                let text = lexer.single_quoted()?;
+                word.parts.push(WordPart::Quoted(text));
+            }
+            '"' => {
+                let parts = lexer.double_quoted()?;
+                word.parts.push(WordPart::DoubleQuoted(parts));
+            }
+            '\\' => match lexer.chars.next() {
+                Some(c) => word.push_quoted(c),
+                None => word.push_literal('\\'),
+            },
+            '$' => match lexer.dollar()? {
+                Some(param) => word.parts.push(WordPart::Param(param)),
+                None => word.push_literal('$'),
+            },
+            c => word.push_literal(c),
+        }
+    }
+
+    Ok(word)
+}
+
+/// Consumes the lines following a here-doc operator up to the delimiter.
+fn read_heredoc(chars: &mut Peekable<Chars>, delimiter: &Word) -> Result<HereDoc, ParseError> {
+    let delimiter_text = delimiter.text();
+    let mut body = String::new();
+
+    loop {
+        if chars.peek().is_none() {
+            return Err(ParseError::Incomplete);
+        }
+
+        let mut line = String::new();
+        let mut terminated = false;
+        for c in chars.by_ref() {
+            if c == '\n' {
+                terminated = true;
+                break;
+            }
+            line.push(c);
+        }
+
+        if line == delimiter_text {
+            return Ok(HereDoc {
+                delimiter: delimiter_text,
+                quoted: delimiter.is_quoted(),
+                body,
+            });
+        }
+
+        if !terminated {
+            return Err(ParseError::Incomplete);
+        }
+
+        body.push_str(&line);
+        body.push('\n');
+    }
+}
+
+pub fn parse(input: &str) -> Result<List, ParseError> {
+    parse_with_aliases(input, &BTreeMap::new())
+}
+
+/// Parses `input`, replacing the first word of each simple command with its
+/// alias from `aliases`.
+pub fn parse_with_aliases(input: &str, aliases: &BTreeMap<String, String>) -> Result<List, ParseError> {
+    let tokens = tokenize(input)?;
+    let mut parser = Parser { tokens, pos: 0, aliases, expanding: Vec::new(), alias_blank_end: None };
+
+    parser.program()
+}
+
+struct Parser<'a> {
+    tokens: Vec<Token>,
+    pos: usize,
+    aliases: &'a BTreeMap<String, String>,
+    /// Aliases being expanded, with the position where their expansion ends,
+    /// so that an alias is never expanded within itself.
+    expanding: Vec<(String, usize)>,
+    /// Position of the word following an alias whose value ends with a
+    /// blank, which is checked for aliases as well.
+    alias_blank_end: Option<usize>,
+}
+
+impl Parser<'_> {
+    /// Replaces the word at the current position with the tokens of its
+    /// alias, returning whether it did.
+    fn expand_alias(&mut self) -> Result<bool, ParseError> {
+        let pos = self.pos;
+        self.expanding.retain(|(_, end)| *end > pos);
+
+        let name = match self.peek() {
+            Some(Token::Word(Word { parts })) => match parts.as_slice() {
+                [WordPart::Literal(name)] => name,
+                _ => return Ok(false),
+            },
+            _ => return Ok(false),
+        };
+
+        if self.expanding.iter().any(|(expanding, _)| expanding == name) {
+            return Ok(false);
+        }
+
+        let Some(value) = self.aliases.get(name) else {
+            return Ok(false);
+        };
+
+        let name = name.clone();
+        let tokens = tokenize(value)?;
+        let len = tokens.len();
+        self.tokens.splice(pos..pos + 1, tokens);
+
+        for (_, end) in &mut self.expanding {
+            *end = *end + len - 1;
+        }
+        self.expanding.push((name, pos + len));
+        self.alias_blank_end = value.ends_with([' ', '\t']).then_some(pos + len);
+
+        Ok(true)
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn peek_operator(&self) -> Option<Operator> {
+        match self.peek() {
+            Some(Token::Operator(op)) => Some(*op),
+            _ => None,
+        }
+    }
+
+    fn skip_newlines(&mut self) {
+        while self.peek_operator() == Some(Operator::Newline) {
+            self.pos += 1;
+        }
+    }
+
+    fn unexpected(&self) -> ParseError {
+        match self.peek() {
+            None => ParseError::Incomplete,
+            Some(Token::Operator(op)) => ParseError::UnexpectedToken(op.to_string()),
+            Some(Token::Word(w)) => ParseError::UnexpectedToken(w.text()),
+            Some(Token::IoNumber(n)) => ParseError::UnexpectedToken(n.to_string()),
+            Some(Token::HereDoc(h)) => ParseError::UnexpectedToken(h.delimiter.clone()),
+        }
+    }
+
+    fn peek_keyword(&self) -> Option<&'static str> {
+        match self.peek() {
+            Some(Token::Word(w)) => w.as_keyword(),
+            _ => None,
+        }
+    }
+
+    fn expect_keyword(&mut self, keyword: &str) -> Result<(), ParseError> {
+        if self.peek_keyword() != Some(keyword) {
+            return Err(self.unexpected());
+        }
+
+        self.pos += 1;
+        Ok(())
+    }
+
+    fn expect_operator(&mut self, op: Operator) -> Result<(), ParseError> {
+        if self.peek_operator() != Some(op) {
+            return Err(self.unexpected());
+        }
+
+        self.pos += 1;
+        Ok(())
+    }
+
+    /// Parses the whole input, which must not stop at a reserved word such
+    /// as a stray `fi`.
+    fn program(&mut self) -> Result<List, ParseError> {
+        let list = self.list()?;
+
+        if self.peek().is_some() {
+            return Err(self.unexpected());
+        }
+
+        Ok(list)
+    }
+
+    /// Parses and-or lists up to the end of the input, a reserved word
+    /// closing the enclosing compound command, or a `)`/`;;` of a `case`.
+    fn list(&mut self) -> Result<List, ParseError> {
+        let mut list = List::default();
+
+        loop {
+            self.skip_newlines();
+
+            let at_end = self.peek().is_none()
+                || matches!(self.peek_operator(), Some(Operator::RParen | Operator::DSemi))
+                || self.peek_keyword().is_some_and(|k| TERMINATORS.contains(&k));
+
+            if at_end {
+                return Ok(list);
+            }
+
+            let mut item = self.and_or()?;
+
+            match self.peek_operator() {
+                Some(Operator::Semi) | Some(Operator::Newline) => self.pos += 1,
+                Some(Operator::Amp) => {
+                    item.background = true;
+                    self.pos += 1;
+                }
+                _ => {
+                    list.items.push(item);
+                    return Ok(list);
+                }
+            }
+
+            list.items.push(item);
+        }
+    }
+
+    /// The body of a compound command, which cannot be empty.
+    fn compound_list(&mut self) -> Result<List, ParseError> {
+        let list = self.list()?;
+
+        if list.items.is_empty() {
+            return Err(self.unexpected());
+        }
+
+        Ok(list)
+    }
+
+    fn and_or(&mut self) -> Result<AndOr, ParseError> {
+        let first = self.pipeline()?;
+        let mut rest = Vec::new();
+
+        loop {
+            let connector = match self.peek_operator() {
+                Some(Operator::AndIf) => Connector::And,
+                Some(Operator::OrIf) => Connector::Or,
+                _ => break,
+            };
+            self.pos += 1;
+            self.skip_newlines();
+
+            rest.push((connector, self.pipeline()?));
+        }
+
+        Ok(AndOr { first, rest, background: false })
+    }
+
+    fn pipeline(&mut self) -> Result<Pipeline, ParseError> {
+        let negated = self.peek_keyword() == Some("!");
+        if negated {
+            self.pos += 1;
+        }
+
+        let mut commands = vec![self.command()?];
+
+        while self.peek_operator() == Some(Operator::Pipe) {
+            self.pos += 1;
+            self.skip_newlines();
+
+            commands.push(self.command()?);
+        }
+
+        Ok(Pipeline { negated, commands })
+    }
+
+    fn command(&mut self) -> Result<Command, ParseError> {
+        if self.peek_keyword().is_none() {
+            while self.expand_alias()? {}
+        }
+
+        if let Some(keyword) = self.peek_keyword() {
+            let compound = self.compound_command(keyword)?;
+            let redirects = self.redirects()?;
+
+            return Ok(Command::Compound(compound, redirects));
+        }
+
+        let is_function = matches!(
+            self.tokens.get(self.pos..self.pos + 3),
+            Some([Token::Word(w), Token::Operator(Operator::LParen), Token::Operator(Operator::RParen)])
+                if w.as_name().is_some()
+        );
+
+        if is_function {
+            return self.function_def().map(Command::FunctionDef);
+        }
+
+        self.simple_command().map(Command::Simple)
+    }
+
+    fn compound_command(&mut self, keyword: &str) -> Result<CompoundCommand, ParseError> {
+        match keyword {
+            "{" => {
+                self.pos += 1;
+                let body = self.compound_list()?;
+                self.expect_keyword("}")?;
+
+                Ok(CompoundCommand::Group(body))
+            }
+            "if" => self.if_clause(),
+            "while" | "until" => {
+                self.pos += 1;
+                let condition = self.compound_list()?;
+                self.expect_keyword("do")?;
+                let body = self.compound_list()?;
+                self.expect_keyword("done")?;
+
+                Ok(CompoundCommand::While { until: keyword == "until", condition, body })
+            }
+            "for" => self.for_clause(),
+            "case" => self.case_clause(),
+            _ => Err(self.unexpected()),
+        }
+    }
+
+    fn if_clause(&mut self) -> Result<CompoundCommand, ParseError> {
+        self.pos += 1;
+        let mut branches = Vec::new();
+
+        loop {
+            let condition = self.compound_list()?;
+            self.expect_keyword("then")?;
+            let body = self.compound_list()?;
+            branches.push((condition, body));
+
+            match self.peek_keyword() {
+                Some("elif") => self.pos += 1,
+                Some("else") => {
+                    self.pos += 1;
+                    let else_branch = self.compound_list()?;
+                    self.expect_keyword("fi")?;
+
+                    return Ok(CompoundCommand::If { branches, else_branch: Some(else_branch) });
+                }
+                Some("fi") => {
+                    self.pos += 1;
+
+                    return Ok(CompoundCommand::If { branches, else_branch: None });
+                }
+                _ => return Err(self.unexpected()),
+            }
+        }
+    }
+
+    fn for_clause(&mut self) -> Result<CompoundCommand, ParseError> {
+        self.pos += 1;
+
+        let name = match self.peek() {
+            Some(Token::Word(w)) if w.as_name().is_some() => w.as_name().unwrap_or_default().to_string(),
+            _ => return Err(self.unexpected()),
+        };
+        self.pos += 1;
+        self.skip_newlines();
+
+        let words = if self.peek_keyword() == Some("in") {
+            self.pos += 1;
+
+            let mut words = Vec::new();
+            while let Some(Token::Word(w)) = self.peek() {
+                words.push(w.clone());
+                self.pos += 1;
+            }
+
+            match self.peek_operator() {
+                Some(Operator::Semi) | Some(Operator::Newline) => self.pos += 1,
+                _ => return Err(self.unexpected()),
+            }
+            Some(words)
+        } else {
+            if self.peek_operator() == Some(Operator::Semi) {
+                self.pos += 1;
+            }
+            None
+        };
+
+        self.skip_newlines();
+        self.expect_keyword("do")?;
+        let body = self.compound_list()?;
+        self.expect_keyword("done")?;
+
+        Ok(CompoundCommand::For { name, words, body })
+    }
+
+    fn case_clause(&mut self) -> Result<CompoundCommand, ParseError> {
+        self.pos += 1;
+
+        let Some(Token::Word(word)) = self.peek() else {
+            return Err(self.unexpected());
+        };
+        let word = word.clone();
+        self.pos += 1;
+
+        self.skip_newlines();
+        self.expect_keyword("in")?;
+
+        let mut items = Vec::new();
+
+        loop {
+            self.skip_newlines();
+
+            if self.peek_keyword() == Some("esac") {
+                self.pos += 1;
+                return Ok(CompoundCommand::Case { word, items });
+            }
+
+            if self.peek_operator() == Some(Operator::LParen) {
+                self.pos += 1;
+            }
+
+            let mut patterns = Vec::new();
+            loop {
+                let Some(Token::Word(pattern)) = self.peek() else {
+                    return Err(self.unexpected());
+                };
+                patterns.push(pattern.clone());
+                self.pos += 1;
+
+                if self.peek_operator() != Some(Operator::Pipe) {
+                    break;
+                }
+                self.pos += 1;
+            }
+            self.expect_operator(Operator::RParen)?;
+
+            let body = self.list()?;
+            items.push(CaseItem { patterns, body });
+
+            match self.peek_operator() {
+                Some(Operator::DSemi) => self.pos += 1,
+                _ if self.peek_keyword() == Some("esac") => {}
+                _ => return Err(self.unexpected()),
+            }
+        }
+    }
+
+    fn function_def(&mut self) -> Result<FunctionDef, ParseError> {
+        let Some(Token::Word(name)) = self.peek() else {
+            return Err(self.unexpected());
+        };
+        let name = name.text();
+        self.pos += 3;
+        self.skip_newlines();
+
+        // the body of a function must be a compound command
+        let Some(keyword) = self.peek_keyword().filter(|k| matches!(*k, "{" | "if" | "while" | "until" | "for" | "case"))
+        else {
+            return Err(self.unexpected());
+        };
+
+        let compound = self.compound_command(keyword)?;
+        let redirects = self.redirects()?;
+
+        Ok(FunctionDef { name, body: Box::new(Command::Compound(compound, redirects)) })
+    }
+
+    fn redirects(&mut self) -> Result<Vec<Redirect>, ParseError> {
+        let mut redirects = Vec::new();
+
+        loop {
+            match self.peek() {
+                Some(Token::IoNumber(fd)) => {
+                    let fd = *fd;
+                    self.pos += 1;
+                    redirects.push(self.redirect(Some(fd))?);
+                }
+                Some(Token::Operator(
+                    Operator::Less
+                    | Operator::Great
+                    | Operator::DGreat
+                    | Operator::DLess
+                    | Operator::LessAnd
+                    | Operator::GreatAnd,
+                )) => redirects.push(self.redirect(None)?),
+                _ => return Ok(redirects),
+            }
+        }
+    }
+
+    fn simple_command(&mut self) -> Result<SimpleCommand, ParseError> {
+        let mut assignments = Vec::new();
+        let mut words = Vec::new();
+        let mut redirects = Vec::new();
+
+        loop {
+            match self.peek() {
+                Some(Token::Word(_)) if self.alias_blank_end == Some(self.pos) => {
+                    self.alias_blank_end = None;
+                    while self.expand_alias()? {}
+                }
+                Some(Token::Word(w)) => {
+                    match w.as_assignment() {
+                        Some(assignment) if words.is_empty() => assignments.push(assignment),
+                        _ => words.push(w.clone()),
+                    }
+                    self.pos += 1;
+                }
+                Some(Token::IoNumber(fd)) => {
+                    let fd = *fd;
+                    self.pos += 1;
+                    redirects.push(self.redirect(Some(fd))?);
+                }
+                Some(Token::Operator(
+                    Operator::Less
+                    | Operator::Great
+                    | Operator::DGreat
+                    | Operator::DLess
+                    | Operator::LessAnd
+                    | Operator::GreatAnd,
+                )) => redirects.push(self.redirect(None)?),
+                _ => break,
+            }
+        }
+
+        if assignments.is_empty() && words.is_empty() && redirects.is_empty() {
+            return Err(self.unexpected());
+        }
+
+        Ok(SimpleCommand { assignments, words, redirects })
+    }
+
+    fn redirect(&mut self, fd: Option<usize>) -> Result<Redirect, ParseError> {
+        let Some(op) = self.peek_operator() else {
+            return Err(self.unexpected());
+        };
+        self.pos += 1;
+
+        if op == Operator::DLess {
+            let Some(Token::HereDoc(heredoc)) = self.peek() else {
+                return Err(self.unexpected());
+            };
+            let heredoc = heredoc.clone();
+            self.pos += 1;
+
+            return Ok(Redirect { fd: fd.unwrap_or(0), op: RedirectOp::HereDoc(heredoc) });
+        }
+
+        let Some(Token::Word(target)) = self.peek() else {
+            return Err(self.unexpected());
+        };
+        let target = target.clone();
+        self.pos += 1;
+
+        let (default_fd, op) = match op {
+            Operator::Less => (0, RedirectOp::Input(target)),
+            Operator::Great => (1, RedirectOp::Output(target)),
+            Operator::DGreat => (1, RedirectOp::Append(target)),
+            Operator::LessAnd => (0, RedirectOp::DupInput(target)),
+            Operator::GreatAnd => (1, RedirectOp::DupOutput(target)),
+            _ => return Err(ParseError::UnexpectedToken(op.to_string())),
+        };
+
+        Ok(Redirect { fd: fd.unwrap_or(default_fd), op })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The words of `input` with their quotes removed.
+    fn words(input: &str) -> Vec<String> {
+        tokenize(input)
+            .unwrap()
+            .into_iter()
+            .filter_map(|token| match token {
+                Token::Word(word) => Some(word.text()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// The names of the simple commands of `pipeline`.
+    fn commands(pipeline: &Pipeline) -> Vec<String> {
+        pipeline
+            .commands
+            .iter()
+            .map(|command| match command {
+                Command::Simple(simple) => simple.words[0].text(),
+                _ => panic!("not a simple command"),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn quotes_keep_blanks_and_operators() {
+        assert_eq!(words("echo 'a  b' \"c ; d\""), ["echo", "a  b", "c ; d"]);
+        assert_eq!(words("mkdir my' 'folder"), ["mkdir", "my folder"]);
+        assert_eq!(words("echo '' \"\""), ["echo", "", ""]);
+    }
+
+    #[test]
+    fn single_quotes_are_literal_and_double_quotes_expand() {
+        let tokens = tokenize("'$HOME' \"$HOME/x\"").unwrap();
+        let home = Param { name: "HOME".into(), op: None };
+
+        assert_eq!(tokens[0], Token::Word(Word::quoted("$HOME")));
+        assert_eq!(
+            tokens[1],
+            Token::Word(Word {
+                parts: vec![WordPart::DoubleQuoted(vec![WordPart::Param(home), WordPart::Quoted("/x".into())])]
+            })
+        );
+    }
+
+    #[test]
+    fn backslash_escapes_the_next_character() {
+        assert_eq!(words("cat a\\ b \\'c\\' \\$d"), ["cat", "a b", "'c'", "$d"]);
+        assert_eq!(words("echo \"a \\\"b\\\" \\c\""), ["echo", "a \"b\" \\c"]);
+        assert_eq!(words("echo a\\\nb"), ["echo", "ab"]);
+    }
+
+    #[test]
+    fn comments_run_to_the_end_of_the_line() {
+        assert_eq!(words("echo a # b c"), ["echo", "a"]);
+        assert_eq!(words("echo a#b '#c'"), ["echo", "a#b", "#c"]);
+    }
+
+    #[test]
+    fn unterminated_input_is_incomplete() {
+        assert_eq!(tokenize("echo 'a"), Err(ParseError::Incomplete));
+        assert_eq!(tokenize("echo \"a"), Err(ParseError::Incomplete));
+        assert_eq!(tokenize("echo a\\"), Err(ParseError::Incomplete));
+        assert_eq!(parse("true &&"), Err(ParseError::Incomplete));
+    }
+
+    #[test]
+    fn lists_split_on_separators_and_connectors() {
+        let list = parse("a; b && c || d & e | f").unwrap();
+
+        assert_eq!(list.items.len(), 3);
+        assert_eq!(commands(&list.items[0].first), ["a"]);
+
+        let and_or = &list.items[1];
+        assert!(and_or.background);
+        assert_eq!(commands(&and_or.first), ["b"]);
+        let connectors: Vec<Connector> = and_or.rest.iter().map(|(connector, _)| *connector).collect();
+        assert_eq!(connectors, [Connector::And, Connector::Or]);
+
+        assert_eq!(commands(&list.items[2].first), ["e", "f"]);
+    }
+
+    #[test]
+    fn operators_end_words_without_blanks() {
+        assert_eq!(words("a;b&&c|d"), ["a", "b", "c", "d"]);
+
+        let tokens = tokenize("ls 2>err").unwrap();
+        assert_eq!(tokens[1], Token::IoNumber(2));
+        assert_eq!(tokens[2], Token::Operator(Operator::Great));
+    }
+
+    #[test]
+    fn quoted_reserved_words_are_ordinary_words() {
+        let list = parse("\"if\" a").unwrap();
+        assert_eq!(commands(&list.items[0].first), ["if"]);
+
+        assert!(matches!(
+            &list.items[0].first.commands[0],
+            Command::Simple(simple) if simple.words.len() == 2
+        ));
+        assert!(parse("if a").is_err());
+    }
+
+    #[test]
+    fn assignments_precede_the_command_name() {
+        let list = parse("A=1 B='x y' env").unwrap();
+        let Command::Simple(simple) = &list.items[0].first.commands[0] else {
+            panic!("not a simple command");
+        };
+
+        assert_eq!(simple.assignments.iter().map(|(name, _)| name.as_str()).collect::<Vec<_>>(), ["A", "B"]);
+        assert_eq!(simple.assignments[1].1.text(), "x y");
+        assert_eq!(simple.words[0].text(), "env");
+    }
+
+    #[test]
+    fn parameter_operators_are_parsed() {
+        let Token::Word(word) = &tokenize("${X:-a b}").unwrap()[0] else {
+            panic!("not a word");
+        };
+        let [WordPart::Param(Param { name, op: Some(ParamOp::Default { colon: true, word }) })] = &word.parts[..] else {
+            panic!("not a default value: {:?}", word);
+        };
+
+        assert_eq!(name, "X");
+        assert_eq!(word.text(), "a b");
+        assert!(tokenize("${X").is_err());
+    }
+}
@@ -0,0 +1,181 @@
+/// Matches `text` against a shell pattern, as used by `case`.
+///
+/// `*` matches any string, `?` any character and `[...]` a set of
+/// characters (`[!...]` or `[^...]` for its complement, with `a-z` ranges).
+/// A backslash makes the next character match literally.
+pub fn matches(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+
+    matches_from(&pattern, &text)
+}
+
+fn matches_from(pattern: &[char], text: &[char]) -> bool {
+    let (mut p, mut t) = (0, 0);
+    // where to resume after the last `*`: its position in the pattern and
+    // the position in the text it is currently matching up to
+    let mut backtrack: Option<(usize, usize)> = None;
+
+    while t < text.len() {
+        let step = match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p, t));
+                p += 1;
+                continue;
+            }
+            Some('?') => Some(1),
+            Some('[') => match_class(&pattern[p..], text[t]),
+            Some('\\') if p + 1 < pattern.len() => (pattern[p + 1] == text[t]).then_some(2),
+            Some(c) => (*c == text[t]).then_some(1),
+            None => None,
+        };
+
+        match step {
+            Some(len) => {
+                p += len;
+                t += 1;
+            }
+            None => match backtrack {
+                Some((star, matched)) => {
+                    p = star + 1;
+                    t = matched + 1;
+                    backtrack = Some((star, matched + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+/// Matches `c` against the bracket expression at the start of `pattern`,
+/// returning the length of the expression when it matches. An unterminated
+/// `[` stands for itself.
+fn match_class(pattern: &[char], c: char) -> Option<usize> {
+    let mut i = 1;
+    let negated = matches!(pattern.get(i), Some('!' | '^'));
+    if negated {
+        i += 1;
+    }
+
+    let mut found = false;
+    let mut first = true;
+
+    loop {
+        let Some(&current) = pattern.get(i) else {
+            return (c == '[').then_some(1);
+        };
+
+        if current == ']' && !first {
+            break;
+        }
+        first = false;
+
+        if pattern.get(i + 1) == Some(&'-') && pattern.get(i + 2).is_some_and(|end| *end != ']') {
+            if (current..=pattern[i + 2]).contains(&c) {
+                found = true;
+            }
+            i += 3;
+        } else {
+            if current == c {
+                found = true;
+            }
+            i += 1;
+        }
+    }
+
+    (found != negated).then_some(i + 1)
+}
+
+/// Escapes the characters that are special in a pattern, so that `text`
+/// only matches itself.
+pub fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+
+    for c in text.chars() {
+        if matches!(c, '*' | '?' | '[' | ']' | '\\') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+
+    escaped
+}
+
+/// Whether `pattern` holds an unescaped `*`, `?` or bracket expression,
+/// i.e. can match more than its own text. A `[` without a closing `]`, as
+/// in `[ -f file ]`, stands for itself.
+pub fn has_wildcards(pattern: &str) -> bool {
+    let mut chars = pattern.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => {
+                chars.next();
+            }
+            '*' | '?' => return true,
+            '[' if chars.clone().skip(1).any(|c| c == ']') => return true,
+            _ => {}
+        }
+    }
+
+    false
+}
+
+/// The text a pattern without wildcards matches, its escapes removed.
+pub fn unescape(pattern: &str) -> String {
+    let mut text = String::with_capacity(pattern.len());
+    let mut chars = pattern.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => text.extend(chars.next()),
+            c => text.push(c),
+        }
+    }
+
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wildcards_match_any_string_and_any_character() {
+        assert!(matches("*.rs", "main.rs"));
+        assert!(matches("*.rs", ".rs"));
+        assert!(!matches("*.rs", "main.rsx"));
+        assert!(matches("a?c", "abc"));
+        assert!(!matches("a?c", "ac"));
+        assert!(matches("*a*b*", "xxaxxbxx"));
+    }
+
+    #[test]
+    fn bracket_expressions_match_sets_and_ranges() {
+        assert!(matches("[abc]", "b"));
+        assert!(matches("[a-c]x", "cx"));
+        assert!(!matches("[!a-c]", "b"));
+        assert!(matches("[^a-c]", "d"));
+        assert!(matches("[]]", "]"));
+    }
+
+    #[test]
+    fn escaped_characters_match_literally() {
+        assert!(matches("\\*", "*"));
+        assert!(!matches("\\*", "a"));
+        assert!(matches(&escape("a[1]*"), "a[1]*"));
+        assert!(!matches(&escape("a[1]*"), "a1"));
+        assert_eq!(unescape(&escape("a[1]*?")), "a[1]*?");
+    }
+
+    #[test]
+    fn only_unescaped_wildcards_count() {
+        assert!(has_wildcards("*.txt"));
+        assert!(has_wildcards("file[12]"));
+        assert!(!has_wildcards("\\*.txt"));
+        assert!(!has_wildcards("[ -f file"));
+        assert!(!has_wildcards("plain"));
+    }
+}
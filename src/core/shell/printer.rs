@@ -0,0 +1,281 @@
+use std::fmt::{self, Write};
+
+use crate::core::shell::parser::{
+    AndOr, CompoundCommand, Connector, HereDoc, List, Pipeline, Redirect, RedirectOp, Command, Word, WordPart,
+};
+
+/// Quotes `text` with single quotes, so that the shell reads it back as is.
+pub fn quote(text: &str) -> String {
+    format!("'{}'", text.replace('\'', "'\\''"))
+}
+
+impl fmt::Display for Word {
+    /// Writes the word back as shell source, quotes included.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.parts.iter().try_for_each(|part| write_part(f, part, false))
+    }
+}
+
+fn write_part(f: &mut fmt::Formatter<'_>, part: &WordPart, in_double_quotes: bool) -> fmt::Result {
+    match part {
+        WordPart::Literal(s) => f.write_str(s),
+        WordPart::Quoted(s) if in_double_quotes => s.chars().try_for_each(|c| {
+            if matches!(c, '$' | '`' | '"' | '\\') {
+                f.write_char('\\')?;
+            }
+            f.write_char(c)
+        }),
+        WordPart::Quoted(s) => f.write_str(&quote(s)),
+        WordPart::DoubleQuoted(inner) => {
+            f.write_char('"')?;
+            inner.iter().try_for_each(|part| write_part(f, part, true))?;
+            f.write_char('"')
+        }
+        WordPart::Param(param) => write!(f, "{}", param),
+    }
+}
+
+impl fmt::Display for AndOr {
+    /// Writes the and-or list back on one line, as shown by `jobs`. The
+    /// trailing `&` of a background job is left out.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut printer = Printer::default();
+        printer.and_or(self);
+
+        f.write_str(&printer.out)
+    }
+}
+
+/// Writes the definition of a function back as shell source, as shown by
+/// `type` and saved for the next session.
+pub fn function_source(name: &str, body: &Command) -> String {
+    let mut printer = Printer::default();
+
+    printer.out.push_str(name);
+    printer.out.push_str("() ");
+    printer.command(body);
+    printer.newline();
+
+    printer.out
+}
+
+#[derive(Default)]
+struct Printer {
+    out: String,
+    indent: usize,
+    /// Here-docs whose body goes after the current line.
+    heredocs: Vec<HereDoc>,
+}
+
+impl Printer {
+    fn line_start(&mut self) {
+        self.out.push_str(&"    ".repeat(self.indent));
+    }
+
+    fn newline(&mut self) {
+        self.out.push('\n');
+
+        for heredoc in self.heredocs.drain(..) {
+            self.out.push_str(&heredoc.body);
+            self.out.push_str(&heredoc.delimiter);
+            self.out.push('\n');
+        }
+    }
+
+    /// Writes each item of `list` on its own line, one level deeper.
+    fn block(&mut self, list: &List) {
+        self.indent += 1;
+
+        for item in &list.items {
+            self.line_start();
+            self.and_or(item);
+            if item.background {
+                self.out.push_str(" &");
+            }
+            self.newline();
+        }
+
+        self.indent -= 1;
+    }
+
+    fn inline(&mut self, list: &List) {
+        for (i, item) in list.items.iter().enumerate() {
+            if i > 0 {
+                self.out.push_str(if list.items[i - 1].background { " " } else { "; " });
+            }
+            self.and_or(item);
+            if item.background {
+                self.out.push_str(" &");
+            }
+        }
+    }
+
+    /// Writes `keyword` after a list written inline, which needs a `;`
+    /// unless its last item already ends with `&`.
+    fn keyword_after(&mut self, list: &List, keyword: &str) {
+        let background = list.items.last().is_some_and(|item| item.background);
+
+        self.out.push_str(if background { " " } else { "; " });
+        self.out.push_str(keyword);
+    }
+
+    fn and_or(&mut self, and_or: &AndOr) {
+        self.pipeline(&and_or.first);
+
+        for (connector, pipeline) in &and_or.rest {
+            self.out.push_str(match connector {
+                Connector::And => " && ",
+                Connector::Or => " || ",
+            });
+            self.pipeline(pipeline);
+        }
+    }
+
+    fn pipeline(&mut self, pipeline: &Pipeline) {
+        if pipeline.negated {
+            self.out.push_str("! ");
+        }
+
+        for (i, cmd) in pipeline.commands.iter().enumerate() {
+            if i > 0 {
+                self.out.push_str(" | ");
+            }
+            self.command(cmd);
+        }
+    }
+
+    fn command(&mut self, cmd: &Command) {
+        match cmd {
+            Command::Simple(simple) => {
+                let mut words: Vec<String> = simple
+                    .assignments
+                    .iter()
+                    .map(|(name, value)| format!("{}={}", name, value))
+                    .collect();
+                words.extend(simple.words.iter().map(Word::to_string));
+                self.out.push_str(&words.join(" "));
+
+                self.redirects(&simple.redirects);
+            }
+            Command::Compound(compound, redirects) => {
+                self.compound(compound);
+                self.redirects(redirects);
+            }
+            Command::FunctionDef(def) => {
+                self.out.push_str(&def.name);
+                self.out.push_str("() ");
+                self.command(&def.body);
+            }
+        }
+    }
+
+    fn redirects(&mut self, redirects: &[Redirect]) {
+        for redirect in redirects {
+            let (default_fd, op, target) = match &redirect.op {
+                RedirectOp::Input(target) => (0, "<", target.to_string()),
+                RedirectOp::Output(target) => (1, ">", target.to_string()),
+                RedirectOp::Append(target) => (1, ">>", target.to_string()),
+                RedirectOp::DupInput(target) => (0, "<&", target.to_string()),
+                RedirectOp::DupOutput(target) => (1, ">&", target.to_string()),
+                RedirectOp::HereDoc(heredoc) => {
+                    self.heredocs.push(heredoc.clone());
+
+                    let delimiter = if heredoc.quoted { quote(&heredoc.delimiter) } else { heredoc.delimiter.clone() };
+                    (0, "<<", delimiter)
+                }
+            };
+
+            self.out.push(' ');
+            if redirect.fd != default_fd {
+                self.out.push_str(&redirect.fd.to_string());
+            }
+            self.out.push_str(op);
+            self.out.push_str(&target);
+        }
+    }
+
+    fn compound(&mut self, compound: &CompoundCommand) {
+        match compound {
+            CompoundCommand::Group(body) => {
+                self.out.push('{');
+                self.newline();
+                self.block(body);
+                self.line_start();
+                self.out.push('}');
+            }
+            CompoundCommand::If { branches, else_branch } => {
+                for (i, (condition, body)) in branches.iter().enumerate() {
+                    if i > 0 {
+                        self.line_start();
+                    }
+                    self.out.push_str(if i == 0 { "if " } else { "elif " });
+                    self.inline(condition);
+                    self.keyword_after(condition, "then");
+                    self.newline();
+                    self.block(body);
+                }
+
+                if let Some(body) = else_branch {
+                    self.line_start();
+                    self.out.push_str("else");
+                    self.newline();
+                    self.block(body);
+                }
+
+                self.line_start();
+                self.out.push_str("fi");
+            }
+            CompoundCommand::While { until, condition, body } => {
+                self.out.push_str(if *until { "until " } else { "while " });
+                self.inline(condition);
+                self.keyword_after(condition, "do");
+                self.newline();
+                self.block(body);
+                self.line_start();
+                self.out.push_str("done");
+            }
+            CompoundCommand::For { name, words, body } => {
+                self.out.push_str("for ");
+                self.out.push_str(name);
+
+                if let Some(words) = words {
+                    self.out.push_str(" in");
+                    for word in words {
+                        self.out.push(' ');
+                        self.out.push_str(&word.to_string());
+                    }
+                }
+
+                self.out.push_str("; do");
+                self.newline();
+                self.block(body);
+                self.line_start();
+                self.out.push_str("done");
+            }
+            CompoundCommand::Case { word, items } => {
+                self.out.push_str(&format!("case {} in", word));
+                self.newline();
+                self.indent += 1;
+
+                for item in items {
+                    self.line_start();
+                    let patterns: Vec<String> = item.patterns.iter().map(Word::to_string).collect();
+                    self.out.push_str(&patterns.join(" | "));
+                    self.out.push(')');
+                    self.newline();
+
+                    self.block(&item.body);
+                    self.indent += 1;
+                    self.line_start();
+                    self.out.push_str(";;");
+                    self.newline();
+                    self.indent -= 1;
+                }
+
+                self.indent -= 1;
+                self.line_start();
+                self.out.push_str("esac");
+            }
+        }
+    }
+}
@@ -0,0 +1,110 @@
+//! POSIX regular expressions, as taken by `grep` and the like, translated
+//! to the syntax of the `regex` crate.
+//!
+//! Basic expressions (BRE) only treat `*`, `.`, `[...]`, `^` and `$` as
+//! special: `\(`, `\)`, `\{`, `\}`, `\|`, `\+` and `\?` are the operators
+//! while the bare characters match themselves. Extended expressions (ERE)
+//! are the other way around, which is also what the crate expects.
+
+use regex::{Regex, RegexBuilder};
+
+/// Compiles `pattern`, a basic expression unless `extended` is set.
+pub fn compile(pattern: &str, extended: bool, ignore_case: bool) -> Result<Regex, regex::Error> {
+    RegexBuilder::new(&translate(pattern, extended)).case_insensitive(ignore_case).build()
+}
+
+/// Rewrites `pattern` in the syntax of the `regex` crate.
+pub fn translate(pattern: &str, extended: bool) -> String {
+    let chars: Vec<char> = pattern.chars().collect();
+    let mut out = String::new();
+    // a `*` with nothing to repeat matches itself
+    let mut at_start = true;
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+
+        match c {
+            '[' => {
+                i = bracket(&chars, i, &mut out);
+                at_start = false;
+                continue;
+            }
+            '\\' if i + 1 < chars.len() => {
+                let next = chars[i + 1];
+
+                if !extended && "(){}|+?".contains(next) {
+                    out.push(next);
+                } else {
+                    out.push('\\');
+                    out.push(next);
+                }
+
+                at_start = !extended && matches!(next, '(' | '|');
+                i += 2;
+                continue;
+            }
+            '*' if at_start => out.push_str("\\*"),
+            '(' | ')' | '{' | '}' | '|' | '+' | '?' if !extended => {
+                out.push('\\');
+                out.push(c);
+            }
+            _ => out.push(c),
+        }
+
+        at_start = c == '^' || (extended && matches!(c, '(' | '|'));
+        i += 1;
+    }
+
+    out
+}
+
+/// Copies the bracket expression starting at `chars[start]`, returning the
+/// index following it. Backslashes and nested brackets are literal in POSIX
+/// but not for the crate, so they are escaped; character classes such as
+/// `[:digit:]` are understood by both.
+fn bracket(chars: &[char], start: usize, out: &mut String) -> usize {
+    let mut i = start + 1;
+    out.push('[');
+
+    if chars.get(i) == Some(&'^') {
+        out.push('^');
+        i += 1;
+    }
+
+    // a `]` right after the opening bracket is part of the set
+    if chars.get(i) == Some(&']') {
+        out.push_str("\\]");
+        i += 1;
+    }
+
+    while i < chars.len() {
+        match chars[i] {
+            ']' => {
+                out.push(']');
+                return i + 1;
+            }
+            '[' if chars.get(i + 1) == Some(&':') => {
+                let end = (i + 2..chars.len().saturating_sub(1)).find(|j| chars[*j] == ':' && chars[j + 1] == ']');
+
+                match end {
+                    Some(end) => {
+                        out.extend(&chars[i..end + 2]);
+                        i = end + 2;
+                        continue;
+                    }
+                    None => out.push_str("\\["),
+                }
+            }
+            c @ ('[' | '\\' | '&' | '~') => {
+                out.push('\\');
+                out.push(c);
+            }
+            c => out.push(c),
+        }
+        i += 1;
+    }
+
+    // left unclosed for the crate to report
+    i
+}
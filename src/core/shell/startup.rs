@@ -0,0 +1,180 @@
+use crate::{
+    console_log,
+    core::shell::{
+        exec, man,
+        printer::{function_source, quote},
+        Shell,
+    },
+    kernel::{
+        fd::{FdTable, FileDescriptor, STDERR, STDOUT},
+        Kernel,
+    },
+    vfs::{fs::SimpleFS, path},
+};
+
+/// Printed before anything else when the shell starts.
+pub const MOTD_PATH: &str = "/etc/motd";
+
+/// System-wide startup script, run before the one of the user.
+pub const PROFILE_PATH: &str = "/etc/profile";
+
+/// Startup script of the user, relative to `$HOME`.
+pub const RC_FILE: &str = ".rshrc";
+
+/// Aliases defined at the prompt, relative to `$HOME`.
+pub const ALIASES_FILE: &str = ".rsh_aliases";
+
+/// Functions defined at the prompt, relative to `$HOME`.
+pub const FUNCTIONS_FILE: &str = ".rsh_functions";
+
+const SAVED_HEADER: &str = "# Saved by rshell, changes made here are overwritten.\n";
+
+const DEFAULT_MOTD: &str = r#"
+==================================================
+                Welcome to R-OS
+==================================================
+
+Version: 0.1.0
+Author : Antonio Ricciardi
+Kernel : Custom Rust/WebAssembly Kernel
+Type   : Experimental Browser OS
+
+Type 'help' to see available commands.
+Enjoy your stay!
+
+"#;
+
+const DEFAULT_PROFILE: &str = r#"# /etc/profile: run by rshell at startup, before ~/.rshrc
+
+export PATH=/bin:/usr/bin
+PS1='\u@\h:\w\$ '
+"#;
+
+const DEFAULT_RC: &str = r#"# ~/.rshrc: run by rshell at startup, after /etc/profile.
+# Customize your variables, functions and prompt here, e.g.
+#
+#   export EDITOR=edit
+#   PS1='[\W]\$ '
+"#;
+
+/// Creates the startup files and the folders holding them when they are
+/// missing. Existing files are left alone, they belong to the user.
+async fn seed(fs: &mut SimpleFS, home: &str) {
+    for folder in ["/etc", path::parent(home).as_str(), home] {
+        if folder == "/" || matches!(fs.get_entry(folder).await, Ok(Some(_))) {
+            continue;
+        }
+
+        if let Err(err) = fs.create_folder(folder).await {
+            console_log(&format!("[shell] cannot create '{}': {}", folder, err));
+        }
+    }
+
+    let rc_path = path::join(home, RC_FILE);
+    let files = [(MOTD_PATH, DEFAULT_MOTD), (PROFILE_PATH, DEFAULT_PROFILE), (rc_path.as_str(), DEFAULT_RC)];
+
+    for (file_path, contents) in files {
+        if !matches!(fs.get_entry(file_path).await, Ok(None)) {
+            continue;
+        }
+
+        if let Err(err) = fs.write_file(file_path, contents.as_bytes(), false).await {
+            console_log(&format!("[shell] cannot create '{}': {}", file_path, err));
+        }
+    }
+}
+
+/// Prints `/etc/motd`, then runs `/etc/profile`, the saved aliases and
+/// functions and `~/.rshrc` in `shell`. The saved definitions are snapshots
+/// of everything defined at the prompt, so `~/.rshrc` comes last for its
+/// own definitions to win. Missing files are skipped, errors are reported
+/// on stderr.
+pub async fn login(shell: &mut Shell) -> i32 {
+    let k_clone = Kernel::clone_rc();
+
+    let (fds, home) = {
+        let mut kernel = k_clone.lock().await;
+        let home = kernel.envs.get(&shell.pid).and_then(|env| env.get("HOME")).unwrap_or("/").to_string();
+
+        seed(&mut kernel.fs, &home).await;
+        man::install(&mut kernel.fs).await;
+
+        let fds = kernel.fds.get(&shell.pid).cloned().unwrap_or_else(|| FdTable::console(&kernel.console));
+        (fds, home)
+    };
+
+    let stdout = fds.get(STDOUT).cloned().unwrap_or(FileDescriptor::Null);
+    let stderr = fds.get(STDERR).cloned().unwrap_or(FileDescriptor::Null);
+
+    let motd = k_clone.lock().await.fs.read_file(MOTD_PATH).await;
+    if let Ok(motd) = motd {
+        let _ = stdout.write(&motd).await;
+    }
+
+    let mut status = 0;
+
+    let files = [
+        PROFILE_PATH.to_string(),
+        path::join(&home, ALIASES_FILE),
+        path::join(&home, FUNCTIONS_FILE),
+        path::join(&home, RC_FILE),
+    ];
+
+    for file_path in files {
+        if !matches!(k_clone.lock().await.fs.get_entry(&file_path).await, Ok(Some(_))) {
+            continue;
+        }
+
+        status = match exec::source_file(shell, &file_path, &fds).await {
+            Ok(status) => status,
+            Err(err) => {
+                let _ = stderr.write(format!("rshell: {}\n", err).as_bytes()).await;
+                1
+            }
+        };
+
+        // `exit` in a startup file only ends that file
+        shell.flow = None;
+    }
+
+    status
+}
+
+/// Writes `contents` to a file in the home folder of `shell`.
+async fn save_to_home(shell: &Shell, file_name: &str, contents: &str) -> Result<(), String> {
+    let k_clone = Kernel::clone_rc();
+    let mut kernel = k_clone.lock().await;
+
+    let home = kernel.envs.get(&shell.pid).and_then(|env| env.get("HOME")).ok_or("HOME not set")?;
+    let file_path = path::join(home, file_name);
+
+    kernel
+        .fs
+        .write_file(&file_path, contents.as_bytes(), false)
+        .await
+        .map(|_| ())
+        .map_err(|e| format!("{}: {}", file_path, e))
+}
+
+/// Saves the aliases of `shell` to `~/.rsh_aliases`, restored at login.
+pub async fn save_aliases(shell: &Shell) -> Result<(), String> {
+    let mut contents = String::from(SAVED_HEADER);
+
+    for (name, value) in shell.aliases.lock().await.iter() {
+        contents.push_str(&format!("alias {}={}\n", name, quote(value)));
+    }
+
+    save_to_home(shell, ALIASES_FILE, &contents).await
+}
+
+/// Saves the functions of `shell` to `~/.rsh_functions`, restored at login.
+pub async fn save_functions(shell: &Shell) -> Result<(), String> {
+    let mut contents = String::from(SAVED_HEADER);
+
+    for (name, body) in shell.functions.lock().await.iter() {
+        contents.push('\n');
+        contents.push_str(&function_source(name, body));
+    }
+
+    save_to_home(shell, FUNCTIONS_FILE, &contents).await
+}
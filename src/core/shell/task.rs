@@ -0,0 +1,32 @@
+use crate::{kernel::Kernel, process::Process};
+
+/// Process table entry for a command started by the shell.
+///
+/// The command itself runs as an async task; this entry gives it a pid,
+/// a slot for its file descriptors and makes it visible to `top`.
+pub struct CommandTask {
+    pid: usize,
+    name: String,
+}
+
+impl CommandTask {
+    pub fn new(name: &str) -> Self {
+        Self { pid: 0, name: name.into() }
+    }
+}
+
+impl Process for CommandTask {
+    fn pid(&self) -> usize {
+        self.pid
+    }
+
+    fn set_pid(&mut self, pid: usize) {
+        self.pid = pid;
+    }
+
+    fn tick(&mut self, _k: &mut Kernel) {}
+
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+}
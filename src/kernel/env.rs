@@ -0,0 +1,74 @@
+use std::collections::BTreeMap;
+
+#[derive(Debug, Clone)]
+struct Variable {
+    value: String,
+    exported: bool,
+}
+
+/// Variables of a process. Only exported variables are passed on to the
+/// children it spawns.
+#[derive(Debug, Clone, Default)]
+pub struct Environment {
+    vars: BTreeMap<String, Variable>,
+}
+
+impl Environment {
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.vars.get(name).map(|v| v.value.as_str())
+    }
+
+    /// Sets a variable, keeping its exported flag if it already exists.
+    pub fn set(&mut self, name: &str, value: &str) {
+        match self.vars.get_mut(name) {
+            Some(var) => var.value = value.into(),
+            None => {
+                self.vars.insert(name.into(), Variable { value: value.into(), exported: false });
+            }
+        }
+    }
+
+    pub fn set_exported(&mut self, name: &str, value: &str) {
+        self.vars.insert(name.into(), Variable { value: value.into(), exported: true });
+    }
+
+    /// Marks an existing variable as exported, returns `false` if it is unset.
+    pub fn export(&mut self, name: &str) -> bool {
+        match self.vars.get_mut(name) {
+            Some(var) => {
+                var.exported = true;
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn unset(&mut self, name: &str) {
+        self.vars.remove(name);
+    }
+
+    /// Iterates over `(name, value, exported)` sorted by name.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str, bool)> {
+        self.vars.iter().map(|(name, var)| (name.as_str(), var.value.as_str(), var.exported))
+    }
+
+    /// The environment a child process inherits.
+    pub fn exported(&self) -> Environment {
+        Environment {
+            vars: self
+                .vars
+                .iter()
+                .filter(|(_, var)| var.exported)
+                .map(|(name, var)| (name.clone(), var.clone()))
+                .collect(),
+        }
+    }
+}
+
+/// Whether `name` can be used as a variable name.
+pub fn is_valid_name(name: &str) -> bool {
+    let mut chars = name.chars();
+
+    matches!(chars.next(), Some(c) if c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
@@ -1,4 +1,20 @@
+#[allow(dead_code)]
 #[derive(Debug)]
 pub enum KernelError {
     SystemClockProcessNotFound
-}
\ No newline at end of file
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IoError {
+    BrokenPipe,
+    BadDescriptor,
+}
+
+impl std::fmt::Display for IoError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            IoError::BrokenPipe => write!(f, "Broken pipe"),
+            IoError::BadDescriptor => write!(f, "Bad file descriptor"),
+        }
+    }
+}
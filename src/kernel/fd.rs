@@ -0,0 +1,132 @@
+use std::{cell::RefCell, rc::Rc};
+
+use web_sys::HtmlTextAreaElement;
+
+use crate::kernel::{
+    errors::IoError,
+    pipe::{PipeReader, PipeWriter},
+    write_console,
+};
+
+pub const STDIN: usize = 0;
+pub const STDOUT: usize = 1;
+pub const STDERR: usize = 2;
+
+/// Bytes returned by a single `read` at most.
+const READ_CHUNK: usize = 1024;
+
+/// A file opened on behalf of a process.
+///
+/// Reads are served from the contents loaded when the file was opened and
+/// writes are kept in memory: whoever opened the file is responsible for
+/// flushing `take_written` back to the file system once the process exits.
+pub struct OpenFile {
+    pub path: Option<String>,
+    contents: Vec<u8>,
+    offset: usize,
+    written: Vec<u8>,
+}
+
+impl OpenFile {
+    /// A file opened for reading, `path` is `None` for in-memory documents
+    /// such as here-docs.
+    pub fn reader(path: Option<String>, contents: Vec<u8>) -> Self {
+        Self { path, contents, offset: 0, written: Vec::new() }
+    }
+
+    pub fn writer(path: String) -> Self {
+        Self { path: Some(path), contents: Vec::new(), offset: 0, written: Vec::new() }
+    }
+
+    fn read(&mut self, max: usize) -> Vec<u8> {
+        let end = (self.offset + max).min(self.contents.len());
+        let chunk = self.contents[self.offset..end].to_vec();
+        self.offset = end;
+        chunk
+    }
+
+    pub fn take_written(&mut self) -> Vec<u8> {
+        std::mem::take(&mut self.written)
+    }
+}
+
+/// An open stream a process can read from or write to.
+#[derive(Clone)]
+pub enum FileDescriptor {
+    /// The browser console. Writes are appended to it, reads hit
+    /// end-of-file straight away since typed lines belong to the shell.
+    Console(HtmlTextAreaElement),
+    PipeRead(PipeReader),
+    PipeWrite(PipeWriter),
+    File(Rc<RefCell<OpenFile>>),
+    /// Discards writes and is always at end-of-file.
+    Null,
+}
+
+impl FileDescriptor {
+    pub async fn write(&self, data: &[u8]) -> Result<(), IoError> {
+        match self {
+            FileDescriptor::Console(console) => {
+                write_console(console, &String::from_utf8_lossy(data));
+                Ok(())
+            }
+            FileDescriptor::PipeWrite(writer) => writer.write(data).await,
+            FileDescriptor::File(file) => {
+                file.borrow_mut().written.extend_from_slice(data);
+                Ok(())
+            }
+            FileDescriptor::Null => Ok(()),
+            FileDescriptor::PipeRead(_) => Err(IoError::BadDescriptor),
+        }
+    }
+
+    /// Reads the next chunk of bytes, an empty chunk means end-of-file.
+    pub async fn read(&self) -> Result<Vec<u8>, IoError> {
+        match self {
+            FileDescriptor::PipeRead(reader) => Ok(reader.read(READ_CHUNK).await),
+            FileDescriptor::File(file) => Ok(file.borrow_mut().read(READ_CHUNK)),
+            FileDescriptor::Console(_) | FileDescriptor::Null => Ok(Vec::new()),
+            FileDescriptor::PipeWrite(_) => Err(IoError::BadDescriptor),
+        }
+    }
+
+    pub async fn read_to_end(&self) -> Result<Vec<u8>, IoError> {
+        let mut data = Vec::new();
+
+        loop {
+            let chunk = self.read().await?;
+            if chunk.is_empty() {
+                return Ok(data);
+            }
+            data.extend(chunk);
+        }
+    }
+}
+
+/// Descriptors owned by a process, indexed by file descriptor number.
+#[derive(Clone, Default)]
+pub struct FdTable {
+    fds: Vec<Option<FileDescriptor>>,
+}
+
+impl FdTable {
+    /// stdin, stdout and stderr all attached to the console.
+    pub fn console(console: &HtmlTextAreaElement) -> Self {
+        let mut table = Self::default();
+        table.set(STDIN, FileDescriptor::Console(console.clone()));
+        table.set(STDOUT, FileDescriptor::Console(console.clone()));
+        table.set(STDERR, FileDescriptor::Console(console.clone()));
+        table
+    }
+
+    pub fn get(&self, fd: usize) -> Option<&FileDescriptor> {
+        self.fds.get(fd).and_then(Option::as_ref)
+    }
+
+    pub fn set(&mut self, fd: usize, desc: FileDescriptor) {
+        if self.fds.len() <= fd {
+            self.fds.resize(fd + 1, None);
+        }
+        self.fds[fd] = Some(desc);
+    }
+}
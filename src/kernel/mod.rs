@@ -1,29 +1,42 @@
 use wasm_bindgen::prelude::*;
 use web_sys::{window, HtmlTextAreaElement};
-use crate::console_log;
+use crate::{console_log, pop_key, KeyEvent};
+use crate::core::shell::complete::Completion;
 use crate::kernel::defaults::{PID_DEFAULT_SYSTEM_CLOCK, PID_DEFAULT_SYSTEM_SHELL};
-use crate::process::{Process, BoxedProcess};
+use crate::kernel::env::Environment;
+use crate::kernel::fd::FdTable;
+use crate::process::BoxedProcess;
 use crate::vfs::fs::SimpleFS;
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
 use std::collections::{BTreeMap, VecDeque};
 use std::rc::Rc;
 
 pub mod defaults;
+pub mod env;
 pub mod errors;
+pub mod fd;
+pub mod pipe;
 
 thread_local! {
-    static KERNEL: RefCell<Option<Rc<Mutex<Kernel>>>> = RefCell::new(None);
+    static KERNEL: RefCell<Option<Rc<Mutex<Kernel>>>> = const { RefCell::new(None) };
 }
 
+#[allow(dead_code)]
 pub enum Message {
-    SetWaitingForInput(bool),
     Print(String),
     Kill,
+    Key(KeyEvent),
+    Completion(Completion),
+    CommandFinished(i32),
 }
 pub struct Kernel {
     pub console: HtmlTextAreaElement,
     pub last_pid: usize,
     pub processes: BTreeMap<usize, BoxedProcess>,
+    /// stdin/stdout/stderr and other open descriptors of each process.
+    pub fds: BTreeMap<usize, FdTable>,
+    /// Environment variables of each process.
+    pub envs: BTreeMap<usize, Environment>,
     pub fs: SimpleFS,
     pub tick_count: u64,
     pub messages: VecDeque<(usize, Message)>,
@@ -37,6 +50,8 @@ impl Kernel {
             console,
             last_pid: 1000,
             processes: BTreeMap::new(),
+            fds: BTreeMap::new(),
+            envs: BTreeMap::new(),
             fs: SimpleFS::new(),
             tick_count: 0,
             messages: VecDeque::new(),
@@ -66,10 +81,7 @@ impl Kernel {
     }
 
     pub fn print(&self, s: &str) {
-        let mut val = self.console.value();
-        val.push_str(s);
-        self.console.set_value(&val);
-        self.console.set_scroll_top(self.console.scroll_height());
+        write_console(&self.console, s);
     }
 
     pub fn clear(&self) {
@@ -81,10 +93,22 @@ impl Kernel {
         self.last_pid
     }
 
-    pub fn spawn(&mut self, p: BoxedProcess) {
+    pub fn spawn(&mut self, p: BoxedProcess) -> usize {
         let pid = self.get_next_pid();
 
         self.spawn_with_pid(p, pid);
+        pid
+    }
+
+    /// Spawns `p` as a child of `parent`, inheriting its exported variables.
+    pub fn spawn_child(&mut self, parent: usize, p: BoxedProcess) -> usize {
+        let pid = self.get_next_pid();
+
+        let env = self.envs.get(&parent).map(Environment::exported).unwrap_or_default();
+        self.envs.insert(pid, env);
+
+        self.spawn_with_pid(p, pid);
+        pid
     }
 
     pub fn set_time(&mut self, time: i64) {
@@ -109,10 +133,16 @@ impl Kernel {
         let pname = p.name();
 
         self.processes.insert(p.pid(), p);
+        self.fds.entry(pid).or_insert_with(|| FdTable::console(&self.console));
+        self.envs.entry(pid).or_default();
         console_log(&format!("Spawning process with pid {} ({})\nProcesses: {:?}", pid, pname, self.processes.keys()));
     }
 
     pub fn kill(&mut self, pid: usize) {
+        // dropping the descriptors closes the pipe ends held by the process
+        self.fds.remove(&pid);
+        self.envs.remove(&pid);
+
         if self.processes.remove(&pid).is_some() {
             console_log(&format!("Killed process with pid {}", pid));
         } else {
@@ -125,7 +155,11 @@ impl Kernel {
 
         let kernel_ptr: *mut Kernel = self;
 
-        for (pid, proc) in self.processes.iter_mut() {
+        while let Some(key) = pop_key() {
+            self.send(PID_DEFAULT_SYSTEM_SHELL, Message::Key(key));
+        }
+
+        for proc in self.processes.values_mut() {
             unsafe {
                 proc.tick(&mut *kernel_ptr);
             }
@@ -140,6 +174,50 @@ impl Kernel {
 }
 use async_std::sync::Mutex;
 
+thread_local! {
+    static LAST_YIELD: Cell<f64> = const { Cell::new(0.0) };
+}
+
+/// Milliseconds a task may run before `yield_now` hands control back to
+/// the browser.
+const YIELD_INTERVAL_MS: f64 = 16.0;
+
+/// Resolves after `ms` milliseconds, letting the browser run in between.
+pub async fn sleep(ms: i32) {
+    let promise = js_sys::Promise::new(&mut |resolve, _reject| {
+        let _ = window()
+            .expect("should have a window")
+            .set_timeout_with_callback_and_timeout_and_arguments_0(&resolve, ms);
+    });
+
+    let _ = wasm_bindgen_futures::JsFuture::from(promise).await;
+}
+
+/// Resolves once process `pid` is gone from the process table.
+pub async fn wait_for_exit(pid: usize) {
+    while Kernel::clone_rc().lock().await.processes.contains_key(&pid) {
+        sleep(50).await;
+    }
+}
+
+/// Lets the browser render and deliver input when the caller has been
+/// running for longer than a frame, e.g. a busy shell loop.
+pub async fn yield_now() {
+    if js_sys::Date::now() - LAST_YIELD.get() < YIELD_INTERVAL_MS {
+        return;
+    }
+
+    sleep(0).await;
+    LAST_YIELD.set(js_sys::Date::now());
+}
+
+pub fn write_console(console: &HtmlTextAreaElement, s: &str) {
+    let mut val = console.value();
+    val.push_str(s);
+    console.set_value(&val);
+    console.set_scroll_top(console.scroll_height());
+}
+
 pub async fn start_kernel() -> Result<(), JsValue> {
     let window = window().ok_or("no window")?;
     let doc = window.document().ok_or("no document")?;
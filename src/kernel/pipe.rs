@@ -0,0 +1,261 @@
+use std::{
+    cell::RefCell,
+    collections::VecDeque,
+    future::poll_fn,
+    rc::Rc,
+    task::{Poll, Waker},
+};
+
+use crate::kernel::errors::IoError;
+
+/// Bytes a pipe buffers before writers have to wait for the reader.
+pub const PIPE_CAPACITY: usize = 4096;
+
+struct PipeState {
+    buffer: VecDeque<u8>,
+    capacity: usize,
+    readers: usize,
+    writers: usize,
+    read_wakers: Vec<Waker>,
+    write_wakers: Vec<Waker>,
+}
+
+impl PipeState {
+    fn wake_readers(&mut self) {
+        self.read_wakers.drain(..).for_each(Waker::wake);
+    }
+
+    fn wake_writers(&mut self) {
+        self.write_wakers.drain(..).for_each(Waker::wake);
+    }
+}
+
+/// Creates a unidirectional byte stream with a bounded buffer.
+///
+/// Writers wait while the buffer is full, readers wait while it is empty.
+/// Once every writer is dropped readers get end-of-file, once every reader
+/// is dropped writes fail with `IoError::BrokenPipe`.
+pub fn pipe() -> (PipeReader, PipeWriter) {
+    let state = Rc::new(RefCell::new(PipeState {
+        buffer: VecDeque::new(),
+        capacity: PIPE_CAPACITY,
+        readers: 1,
+        writers: 1,
+        read_wakers: Vec::new(),
+        write_wakers: Vec::new(),
+    }));
+
+    (
+        PipeReader { state: state.clone() },
+        PipeWriter { state },
+    )
+}
+
+pub struct PipeReader {
+    state: Rc<RefCell<PipeState>>,
+}
+
+impl PipeReader {
+    /// Reads up to `max` bytes, waiting for data. An empty result means
+    /// end-of-file.
+    pub async fn read(&self, max: usize) -> Vec<u8> {
+        poll_fn(|cx| {
+            let mut state = self.state.borrow_mut();
+
+            if !state.buffer.is_empty() {
+                let n = max.min(state.buffer.len());
+                let data: Vec<u8> = state.buffer.drain(..n).collect();
+                state.wake_writers();
+                return Poll::Ready(data);
+            }
+
+            if state.writers == 0 {
+                return Poll::Ready(Vec::new());
+            }
+
+            state.read_wakers.push(cx.waker().clone());
+            Poll::Pending
+        })
+        .await
+    }
+}
+
+impl Clone for PipeReader {
+    fn clone(&self) -> Self {
+        self.state.borrow_mut().readers += 1;
+        Self { state: self.state.clone() }
+    }
+}
+
+impl Drop for PipeReader {
+    fn drop(&mut self) {
+        let mut state = self.state.borrow_mut();
+        state.readers -= 1;
+
+        if state.readers == 0 {
+            state.wake_writers();
+        }
+    }
+}
+
+pub struct PipeWriter {
+    state: Rc<RefCell<PipeState>>,
+}
+
+impl PipeWriter {
+    /// Writes all of `data`, waiting for the reader whenever the buffer is
+    /// full.
+    pub async fn write(&self, data: &[u8]) -> Result<(), IoError> {
+        let mut written = 0;
+
+        poll_fn(|cx| {
+            let mut state = self.state.borrow_mut();
+
+            loop {
+                if state.readers == 0 {
+                    return Poll::Ready(Err(IoError::BrokenPipe));
+                }
+
+                if written == data.len() {
+                    return Poll::Ready(Ok(()));
+                }
+
+                let space = state.capacity.saturating_sub(state.buffer.len());
+
+                if space == 0 {
+                    state.write_wakers.push(cx.waker().clone());
+                    return Poll::Pending;
+                }
+
+                let n = space.min(data.len() - written);
+                state.buffer.extend(&data[written..written + n]);
+                written += n;
+                state.wake_readers();
+            }
+        })
+        .await
+    }
+}
+
+impl Clone for PipeWriter {
+    fn clone(&self) -> Self {
+        self.state.borrow_mut().writers += 1;
+        Self { state: self.state.clone() }
+    }
+}
+
+impl Drop for PipeWriter {
+    fn drop(&mut self) {
+        let mut state = self.state.borrow_mut();
+        state.writers -= 1;
+
+        if state.writers == 0 {
+            state.wake_readers();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        future::Future,
+        pin::pin,
+        sync::{
+            atomic::{AtomicBool, Ordering},
+            Arc,
+        },
+        task::{Context, Wake},
+    };
+
+    use futures::FutureExt;
+
+    use super::*;
+
+    /// Records whether it has been woken.
+    #[derive(Default)]
+    struct Flag(AtomicBool);
+
+    impl Wake for Flag {
+        fn wake(self: Arc<Self>) {
+            self.0.store(true, Ordering::SeqCst);
+        }
+    }
+
+    impl Flag {
+        fn woken(&self) -> bool {
+            self.0.swap(false, Ordering::SeqCst)
+        }
+    }
+
+    #[test]
+    fn writes_wait_once_the_pipe_is_full() {
+        let (reader, writer) = pipe();
+        let data: Vec<u8> = (0..PIPE_CAPACITY + 10).map(|i| i as u8).collect();
+
+        let flag = Arc::new(Flag::default());
+        let waker = Waker::from(flag.clone());
+        let mut cx = Context::from_waker(&waker);
+        let mut write = pin!(writer.write(&data));
+
+        assert!(write.as_mut().poll(&mut cx).is_pending());
+        assert!(!flag.woken());
+
+        let read = reader.read(usize::MAX).now_or_never().unwrap();
+        assert_eq!(read, data[..PIPE_CAPACITY]);
+        assert!(flag.woken());
+
+        assert_eq!(write.as_mut().poll(&mut cx), Poll::Ready(Ok(())));
+        assert_eq!(reader.read(usize::MAX).now_or_never().unwrap(), data[PIPE_CAPACITY..]);
+    }
+
+    #[test]
+    fn reads_stop_at_max_and_wait_while_empty() {
+        let (reader, writer) = pipe();
+
+        writer.write(b"hello").now_or_never().unwrap().unwrap();
+
+        assert_eq!(reader.read(2).now_or_never().unwrap(), b"he");
+        assert_eq!(reader.read(8).now_or_never().unwrap(), b"llo");
+        assert_eq!(reader.read(8).now_or_never(), None);
+    }
+
+    #[test]
+    fn readers_get_end_of_file_after_the_last_writer_drops() {
+        let (reader, writer) = pipe();
+        let other = writer.clone();
+
+        writer.write(b"ab").now_or_never().unwrap().unwrap();
+        drop(writer);
+
+        assert_eq!(reader.read(8).now_or_never().unwrap(), b"ab");
+        assert_eq!(reader.read(8).now_or_never(), None);
+
+        let flag = Arc::new(Flag::default());
+        let waker = Waker::from(flag.clone());
+        let mut read = pin!(reader.read(8));
+        assert!(read.as_mut().poll(&mut Context::from_waker(&waker)).is_pending());
+
+        drop(other);
+
+        assert!(flag.woken());
+        assert_eq!(read.as_mut().poll(&mut Context::from_waker(&waker)), Poll::Ready(Vec::new()));
+    }
+
+    #[test]
+    fn writes_fail_after_the_reader_drops() {
+        let (reader, writer) = pipe();
+        let data = vec![0; PIPE_CAPACITY + 1];
+
+        let flag = Arc::new(Flag::default());
+        let waker = Waker::from(flag.clone());
+        let mut write = pin!(writer.write(&data));
+        assert!(write.as_mut().poll(&mut Context::from_waker(&waker)).is_pending());
+
+        drop(reader);
+
+        assert!(flag.woken());
+        let result = write.as_mut().poll(&mut Context::from_waker(&waker));
+        assert_eq!(result, Poll::Ready(Err(IoError::BrokenPipe)));
+        assert_eq!(writer.write(b"x").now_or_never(), Some(Err(IoError::BrokenPipe)));
+    }
+}
@@ -1,4 +1,5 @@
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
+use std::collections::VecDeque;
 use wasm_bindgen::prelude::*;
 
 mod vfs;
@@ -9,7 +10,19 @@ mod process;
 pub static HOSTNAME: &str = "r-os";
 
 thread_local! {
-    static INPUT_QUEUE: RefCell<Vec<String>> = RefCell::new(Vec::new());
+    static INPUT_QUEUE: RefCell<Vec<String>> = const { RefCell::new(Vec::new()) };
+    static KEY_QUEUE: RefCell<VecDeque<KeyEvent>> = const { RefCell::new(VecDeque::new()) };
+    static RAW_MODE: Cell<bool> = const { Cell::new(false) };
+    static RAW_KEY_QUEUE: RefCell<VecDeque<KeyEvent>> = const { RefCell::new(VecDeque::new()) };
+}
+
+/// A key press forwarded from the browser that the textarea would otherwise
+/// handle on its own (e.g. `Tab` moving the focus away).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyEvent {
+    pub key: String,
+    pub ctrl: bool,
+    pub alt: bool,
 }
 
 #[wasm_bindgen]
@@ -17,11 +30,56 @@ pub fn receive_line(line: String) {
     INPUT_QUEUE.with(|q| q.borrow_mut().push(line));
 }
 
+/// Queues a key press for the kernel. Returns `true` when the key has been
+/// taken over by R-OS and the browser default action must be prevented.
+///
+/// Handled keys are `Tab` (completion) and `Ctrl-Z` (suspend the foreground
+/// job). In raw mode every key is handled, and queued for the program that
+/// owns the console instead.
+#[wasm_bindgen]
+pub fn receive_key(key: String, ctrl: bool, alt: bool) -> bool {
+    let event = KeyEvent { key, ctrl, alt };
+
+    if RAW_MODE.get() {
+        RAW_KEY_QUEUE.with(|q| q.borrow_mut().push_back(event));
+        return true;
+    }
+
+    let handled = event.key == "Tab" || (event.ctrl && event.key.eq_ignore_ascii_case("z"));
+
+    if handled {
+        push_key(event);
+    }
+
+    handled
+}
+
+/// Sends every key press to `pop_raw_key` while `raw` is set, for
+/// full-screen programs. Keys left from an earlier raw session are dropped.
+pub fn set_raw_mode(raw: bool) {
+    RAW_MODE.set(raw);
+    RAW_KEY_QUEUE.with(|q| q.borrow_mut().clear());
+}
+
+pub fn pop_raw_key() -> Option<KeyEvent> {
+    RAW_KEY_QUEUE.with(|q| q.borrow_mut().pop_front())
+}
+
+/// Queues a key press for the kernel, e.g. a `Ctrl-Z` a raw-mode program
+/// hands back to the shell.
+pub fn push_key(event: KeyEvent) {
+    KEY_QUEUE.with(|q| q.borrow_mut().push_back(event));
+}
+
 // funzione di utilità per la shell
 pub fn pop_line() -> Option<String> {
     INPUT_QUEUE.with(|q| q.borrow_mut().pop())
 }
 
+pub fn pop_key() -> Option<KeyEvent> {
+    KEY_QUEUE.with(|q| q.borrow_mut().pop_front())
+}
+
 #[cfg(feature = "wee_alloc")]
 #[global_allocator]
 static ALLOC: wee_alloc::WeeAlloc = wee_alloc::WeeAlloc::INIT;
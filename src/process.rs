@@ -2,6 +2,7 @@ use crate::{kernel::{Kernel, Message}};
 use std::{any::Any, boxed::Box};
 
 pub trait Process: Any {
+    #[allow(dead_code)]
     fn as_any(&self) -> &dyn Any
     where
         Self: Sized,
@@ -12,7 +13,7 @@ pub trait Process: Any {
     fn name(&self) -> String;
     fn set_pid(&mut self, pid: usize);
     fn tick(&mut self, k: &mut Kernel);
-    fn on_message(&mut self, k: &mut Kernel, msg: Message) {
+    fn on_message(&mut self, _k: &mut Kernel, _msg: Message) {
         // default: ignora
     }
 }
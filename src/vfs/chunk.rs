@@ -0,0 +1,80 @@
+//! Contents of files, kept apart from their entries in fixed-size chunks,
+//! so that scanning entries never reads them and a file is only ever
+//! loaded a piece at a time.
+
+use serde::{Deserialize, Serialize};
+
+/// Bytes in a chunk, all but the last one of a file being full.
+pub const CHUNK_SIZE: u64 = 64 * 1024;
+
+/// A piece of the contents of the file stored under `inode`.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Chunk {
+    pub key: String,
+    pub inode: u64,
+    pub index: u32,
+    #[serde(with = "bytes")]
+    pub data: Vec<u8>,
+}
+
+impl Chunk {
+    pub fn new(inode: u64, index: u32, data: Vec<u8>) -> Self {
+        Self { key: key(inode, index), inode, index, data }
+    }
+}
+
+/// Key of a chunk, in fixed-width hexadecimal so that the chunks of a file
+/// are next to each other and in order.
+pub fn key(inode: u64, index: u32) -> String {
+    format!("{:016x}:{:08x}", inode, index)
+}
+
+/// Index of the chunk holding the byte at `offset`.
+pub fn index(offset: u64) -> u32 {
+    (offset / CHUNK_SIZE) as u32
+}
+
+/// Chunk data as bytes rather than as a list of numbers, read back from
+/// either.
+mod bytes {
+    use std::fmt;
+
+    use serde::{
+        de::{SeqAccess, Visitor},
+        Deserializer, Serializer,
+    };
+
+    pub fn serialize<S: Serializer>(data: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_bytes(data)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+        deserializer.deserialize_byte_buf(BytesVisitor)
+    }
+
+    struct BytesVisitor;
+
+    impl<'de> Visitor<'de> for BytesVisitor {
+        type Value = Vec<u8>;
+
+        fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            f.write_str("bytes")
+        }
+
+        fn visit_bytes<E>(self, data: &[u8]) -> Result<Vec<u8>, E> {
+            Ok(data.to_vec())
+        }
+
+        fn visit_byte_buf<E>(self, data: Vec<u8>) -> Result<Vec<u8>, E> {
+            Ok(data)
+        }
+
+        fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Vec<u8>, A::Error> {
+            let mut data = Vec::with_capacity(seq.size_hint().unwrap_or_default());
+            while let Some(byte) = seq.next_element()? {
+                data.push(byte);
+            }
+            Ok(data)
+        }
+    }
+}
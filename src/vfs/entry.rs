@@ -1,10 +1,21 @@
 use serde::{Deserialize, Serialize};
 
+/// Permission bits of new entries, as with a `022` umask.
+pub const DEFAULT_FILE_MODE: u32 = 0o644;
+pub const DEFAULT_FOLDER_MODE: u32 = 0o755;
+pub const DEFAULT_LINK_MODE: u32 = 0o777;
+
+/// Owner of new entries, R-OS having a single user.
+pub const DEFAULT_OWNER: &str = "user";
+
 pub trait FSEntryTrait {
     fn is_hidden(&self) -> bool;
     fn name(&self) -> String;
     fn created_at(&self) -> i64;
     fn modified_at(&self) -> i64;
+    fn mode(&self) -> u32;
+    fn owner(&self) -> String;
+    fn links(&self) -> u32;
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -47,6 +58,48 @@ impl FSEntryTrait for FSEntryKind {
             FSEntryKind::Link(f) => f.modified_at(),
         }
     }
+
+    fn mode(&self) -> u32 {
+        match self {
+            FSEntryKind::File(f) => f.mode(),
+            FSEntryKind::Folder(f) => f.mode(),
+            FSEntryKind::Link(f) => f.mode(),
+        }
+    }
+
+    fn owner(&self) -> String {
+        match self {
+            FSEntryKind::File(f) => f.owner(),
+            FSEntryKind::Folder(f) => f.owner(),
+            FSEntryKind::Link(f) => f.owner(),
+        }
+    }
+
+    fn links(&self) -> u32 {
+        match self {
+            FSEntryKind::File(f) => f.links(),
+            FSEntryKind::Folder(f) => f.links(),
+            FSEntryKind::Link(f) => f.links(),
+        }
+    }
+}
+
+impl FSEntryKind {
+    pub fn metadata_mut(&mut self) -> &mut FSEntryMetadata {
+        match self {
+            FSEntryKind::File(f) => &mut f.metadata,
+            FSEntryKind::Folder(f) => &mut f.metadata,
+            FSEntryKind::Link(f) => &mut f.metadata,
+        }
+    }
+
+    /// Length in bytes of the contents of a file, zero for anything else.
+    pub fn size(&self) -> u64 {
+        match self {
+            FSEntryKind::File(f) => f.size,
+            _ => 0,
+        }
+    }
 }
 
 
@@ -68,6 +121,38 @@ pub struct FSEntryMetadata {
     pub name: String,
     pub created_at: i64,
     pub modified_at: i64,
+    /// Permission bits, missing from entries stored before they existed,
+    /// which then get the default of their kind.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mode: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub owner: Option<String>,
+    /// Names the entry has in the volume, missing from entries stored
+    /// before hard links existed, which all had a single one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub links: Option<u32>,
+}
+
+impl FSEntryMetadata {
+    pub fn new(name: &str, now: i64, mode: u32) -> Self {
+        Self {
+            is_hidden: name.starts_with('.'),
+            name: name.into(),
+            created_at: now,
+            modified_at: now,
+            mode: Some(mode),
+            owner: Some(DEFAULT_OWNER.into()),
+            links: Some(1),
+        }
+    }
+
+    fn owner(&self) -> String {
+        self.owner.clone().unwrap_or_else(|| DEFAULT_OWNER.into())
+    }
+
+    fn links(&self) -> u32 {
+        self.links.unwrap_or(1)
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -75,6 +160,12 @@ pub struct FSFolder {
     pub metadata: FSEntryMetadata,
 }
 
+impl FSFolder {
+    pub fn new(name: &str, now: i64) -> Self {
+        Self { metadata: FSEntryMetadata::new(name, now, DEFAULT_FOLDER_MODE) }
+    }
+}
+
 impl FSEntryTrait for FSFolder {
     fn is_hidden(&self) -> bool {
         self.metadata.is_hidden
@@ -91,15 +182,68 @@ impl FSEntryTrait for FSFolder {
     fn modified_at(&self) -> i64 {
         self.metadata.modified_at
     }
+
+    fn mode(&self) -> u32 {
+        self.metadata.mode.unwrap_or(DEFAULT_FOLDER_MODE)
+    }
+
+    fn owner(&self) -> String {
+        self.metadata.owner()
+    }
+
+    fn links(&self) -> u32 {
+        self.metadata.links()
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FSFile {
     metadata: FSEntryMetadata,
 
+    /// Inode of the file, which its contents are stored under in the chunk
+    /// store, zero until the file has been stored.
+    #[serde(default)]
+    pub inode: u64,
+
+    /// Length of the contents in bytes.
+    #[serde(default)]
+    pub size: u64,
+
+    /// Contents of the file, only there once loaded or until stored. They
+    /// are never stored with the entry itself.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub data: Option<Vec<u8>>,
 }
 
+impl FSFile {
+    pub fn new(name: &str, data: Vec<u8>, now: i64) -> Self {
+        Self {
+            metadata: FSEntryMetadata::new(name, now, DEFAULT_FILE_MODE),
+            inode: 0,
+            size: data.len() as u64,
+            data: Some(data),
+        }
+    }
+
+    /// Replaces the contents of the file, to be stored on the next write.
+    pub fn write(&mut self, data: Vec<u8>, now: i64) {
+        self.size = data.len() as u64;
+        self.data = Some(data);
+        self.metadata.modified_at = now;
+    }
+
+    /// Accounts for `len` bytes added at the end of the stored contents.
+    pub fn grow(&mut self, len: u64, now: i64) {
+        self.size += len;
+        self.metadata.modified_at = now;
+    }
+
+    /// The file as its entry is stored, without its contents.
+    pub fn without_data(&self) -> Self {
+        Self { metadata: self.metadata.clone(), inode: self.inode, size: self.size, data: None }
+    }
+}
+
 impl FSEntryTrait for FSFile {
     fn is_hidden(&self) -> bool {
         self.metadata.is_hidden
@@ -116,11 +260,36 @@ impl FSEntryTrait for FSFile {
     fn modified_at(&self) -> i64 {
         self.metadata.modified_at
     }
+
+    fn mode(&self) -> u32 {
+        self.metadata.mode.unwrap_or(DEFAULT_FILE_MODE)
+    }
+
+    fn owner(&self) -> String {
+        self.metadata.owner()
+    }
+
+    fn links(&self) -> u32 {
+        self.metadata.links()
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FSLink {
     metadata: FSEntryMetadata,
+
+    /// Path the link points to.
+    #[serde(default)]
+    pub target: String,
+}
+
+impl FSLink {
+    pub fn new(name: &str, target: &str, now: i64) -> Self {
+        Self {
+            metadata: FSEntryMetadata::new(name, now, DEFAULT_LINK_MODE),
+            target: target.into(),
+        }
+    }
 }
 
 impl FSEntryTrait for FSLink {
@@ -139,4 +308,16 @@ impl FSEntryTrait for FSLink {
     fn modified_at(&self) -> i64 {
         self.metadata.modified_at
     }
-}
\ No newline at end of file
+
+    fn mode(&self) -> u32 {
+        self.metadata.mode.unwrap_or(DEFAULT_LINK_MODE)
+    }
+
+    fn owner(&self) -> String {
+        self.metadata.owner()
+    }
+
+    fn links(&self) -> u32 {
+        self.metadata.links()
+    }
+}
@@ -4,7 +4,12 @@ pub enum SimpleFSError {
     NotFound,
     ParentNotFound,
     AlreadyExists,
+    NotAFile,
+    NotAFolder,
+    FolderNotEmpty,
     IOError,
+    /// A stored entry that cannot be read, by its path.
+    CorruptedEntry(String),
     IndexedDBError(idb::Error),
 }
 impl std::fmt::Display for SimpleFSError {
@@ -14,8 +19,44 @@ impl std::fmt::Display for SimpleFSError {
             SimpleFSError::NotFound => write!(f, "Not found"),
             SimpleFSError::ParentNotFound => write!(f, "Parent folder not found"),
             SimpleFSError::AlreadyExists => write!(f, "Already exists"),
+            SimpleFSError::NotAFile => write!(f, "Is a folder"),
+            SimpleFSError::NotAFolder => write!(f, "Not a folder"),
+            SimpleFSError::FolderNotEmpty => write!(f, "Folder not empty"),
             SimpleFSError::IOError => write!(f, "IO Error"),
+            SimpleFSError::CorruptedEntry(path) => write!(f, "{}: Corrupted entry", path),
             SimpleFSError::IndexedDBError(e) => write!(f, "IndexedDB Error: {}", e),
         }
     }
-}
\ No newline at end of file
+}
+
+impl From<idb::Error> for SimpleFSError {
+    fn from(e: idb::Error) -> Self {
+        SimpleFSError::IndexedDBError(e)
+    }
+}
+
+/// Why a volume image could not be read.
+#[derive(Debug)]
+pub enum ImageError {
+    NotAnImage,
+    UnsupportedVersion(u32),
+    Truncated,
+    ChecksumMismatch,
+    InvalidTable(String),
+    BlobChecksumMismatch(String),
+    InvalidEntry(String),
+}
+
+impl std::fmt::Display for ImageError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ImageError::NotAnImage => write!(f, "Not a volume image"),
+            ImageError::UnsupportedVersion(version) => write!(f, "Unsupported image version {}", version),
+            ImageError::Truncated => write!(f, "Image is truncated"),
+            ImageError::ChecksumMismatch => write!(f, "Image checksum mismatch"),
+            ImageError::InvalidTable(e) => write!(f, "Invalid entry table: {}", e),
+            ImageError::BlobChecksumMismatch(path) => write!(f, "{}: Checksum mismatch", path),
+            ImageError::InvalidEntry(path) => write!(f, "{}: Invalid entry", path),
+        }
+    }
+}
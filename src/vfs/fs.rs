@@ -1,5 +1,6 @@
-use idb::{Query, TransactionMode};
-use serde::Serialize;
+use futures::{future::LocalBoxFuture, FutureExt};
+use idb::{KeyRange, ObjectStore, Query, Transaction, TransactionMode};
+use serde::{de::DeserializeOwned, Serialize};
 use serde_wasm_bindgen::Serializer;
 use std::{collections::HashMap};
 use wasm_bindgen::JsValue;
@@ -10,38 +11,371 @@ const REG_FOLDER: &str = r"(/?([^/\\0]+/)*[^/\\0]*)";
 use crate::{
     console_log,
     vfs::{
-        entry::{FSEntry, FSFolder},
-        storage::init_storage,
+        chunk::{self, Chunk, CHUNK_SIZE},
+        entry::{FSEntry, FSEntryKind, FSEntryTrait, FSFile, FSFolder},
+        inode::{self, DirEntry, Inode, ROOT},
+        path,
+        storage::{init_storage, CHUNKS, DIRENTS, INODES},
     },
 };
 
+/// Every object store of the volume, which transactions are over.
+const STORES: &[&str] = &[INODES, DIRENTS, CHUNKS];
+
 pub struct SimpleFS {
     files: HashMap<String, FSEntry>,
     database: Option<idb::Database>,
+    /// Inode the next entry stored gets.
+    next_inode: u64,
+}
+
+fn decode<T: DeserializeOwned>(value: JsValue) -> Result<T, SimpleFSError> {
+    serde_wasm_bindgen::from_value(value).map_err(|_| SimpleFSError::IOError)
+}
+
+fn encode(value: &impl Serialize) -> Result<JsValue, SimpleFSError> {
+    value.serialize(&Serializer::json_compatible()).map_err(|_| SimpleFSError::IOError)
+}
+
+/// Chunks `first` to `last` of the file stored under `inode`.
+fn chunk_range(inode: u64, first: u32, last: u32) -> Result<Query, SimpleFSError> {
+    let lower = JsValue::from_str(&chunk::key(inode, first));
+    let upper = JsValue::from_str(&chunk::key(inode, last));
+
+    Ok(Query::KeyRange(KeyRange::bound(&lower, &upper, Some(false), Some(false))?))
+}
+
+async fn read_chunks(chunks: &ObjectStore, inode: u64, first: u32, last: u32) -> Result<Vec<Chunk>, SimpleFSError> {
+    let values = chunks.get_all(Some(chunk_range(inode, first, last)?), None)?.await?;
+
+    values.into_iter().map(decode).collect()
+}
+
+/// Stores `data` as the chunks of `inode` from `first` on.
+async fn write_chunks(chunks: &ObjectStore, inode: u64, first: u32, data: &[u8]) -> Result<(), SimpleFSError> {
+    let serializer = Serializer::json_compatible().serialize_bytes_as_arrays(false);
+
+    for (index, data) in (first..).zip(data.chunks(CHUNK_SIZE as usize)) {
+        let chunk = Chunk::new(inode, index, data.to_vec());
+        let value = chunk.serialize(&serializer).map_err(|_| SimpleFSError::IOError)?;
+        chunks.put(&value, None)?.await?;
+    }
+
+    Ok(())
+}
+
+pub(crate) async fn delete_chunks(chunks: &ObjectStore, inode: u64) -> Result<(), SimpleFSError> {
+    chunks.delete(chunk_range(inode, 0, u32::MAX)?)?.await?;
+    Ok(())
+}
+
+/// The entry of `inode` as reached at `path`, named after it whatever
+/// name it was created with.
+fn named(path: String, inode: Inode) -> FSEntry {
+    let mut entry = inode.entry;
+    entry.metadata_mut().name = path::file_name(&path).into();
+
+    FSEntry { abs_path: path, entry }
+}
+
+/// The object stores of the volume, within a transaction over them.
+struct Stores {
+    inodes: ObjectStore,
+    dirents: ObjectStore,
+    chunks: ObjectStore,
+}
+
+impl Stores {
+    fn of(transaction: &Transaction) -> Result<Self, SimpleFSError> {
+        Ok(Self {
+            inodes: transaction.object_store(INODES)?,
+            dirents: transaction.object_store(DIRENTS)?,
+            chunks: transaction.object_store(CHUNKS)?,
+        })
+    }
+
+    async fn inode(&self, inode: u64) -> Result<Inode, SimpleFSError> {
+        match self.inodes.get(JsValue::from(inode as f64))?.await? {
+            Some(value) => decode(value),
+            // a directory entry pointing nowhere
+            None => Err(SimpleFSError::IOError),
+        }
+    }
+
+    async fn put_inode(&self, inode: &Inode) -> Result<(), SimpleFSError> {
+        self.inodes.put(&encode(inode)?, None)?.await?;
+        Ok(())
+    }
+
+    async fn dirent(&self, parent: u64, name: &str) -> Result<Option<DirEntry>, SimpleFSError> {
+        self.dirents.get(JsValue::from_str(&inode::key(parent, name)))?.await?.map(decode).transpose()
+    }
+
+    /// The directory entries of the folder `parent`, by name, in a single
+    /// scan of its key range.
+    async fn children(&self, parent: u64) -> Result<Vec<DirEntry>, SimpleFSError> {
+        let lower = JsValue::from_str(&inode::key(parent, ""));
+        let upper = JsValue::from_str(&inode::key(parent, "\u{FFFF}"));
+        let query = Query::KeyRange(KeyRange::bound(&lower, &upper, Some(false), Some(false))?);
+
+        self.dirents.get_all(Some(query), None)?.await?.into_iter().map(decode).collect()
+    }
+
+    /// The directory entry and inode at the absolute `path`, going down
+    /// its folders from the root, which has neither.
+    async fn lookup(&self, path: &str) -> Result<Option<(DirEntry, Inode)>, SimpleFSError> {
+        if !SimpleFS::is_absolute_path(path) {
+            return Ok(None);
+        }
+
+        let mut found: Option<(DirEntry, Inode)> = None;
+
+        for name in path::normalize(path).split('/').filter(|name| !name.is_empty()) {
+            let parent = match &found {
+                None => ROOT,
+                Some((_, Inode { inode, entry: FSEntryKind::Folder(_) })) => *inode,
+                Some(_) => return Ok(None),
+            };
+
+            let Some(dirent) = self.dirent(parent, name).await? else {
+                return Ok(None);
+            };
+            let inode = self.inode(dirent.inode).await?;
+            found = Some((dirent, inode));
+        }
+
+        Ok(found)
+    }
+
+    /// Inode of the folder at `path`, if there is one.
+    async fn folder(&self, path: &str) -> Result<Option<u64>, SimpleFSError> {
+        if path::normalize(path) == "/" {
+            return Ok(Some(ROOT));
+        }
+
+        match self.lookup(path).await? {
+            Some((_, Inode { inode, entry: FSEntryKind::Folder(_) })) => Ok(Some(inode)),
+            _ => Ok(None),
+        }
+    }
+
+    /// Every entry below the folder at `path`, whose inode is `folder`,
+    /// sorted by path.
+    async fn below(&self, path: &str, folder: u64) -> Result<Vec<FSEntry>, SimpleFSError> {
+        let mut entries = Vec::new();
+        let mut folders = vec![(path.to_string(), folder)];
+
+        while let Some((path, folder)) = folders.pop() {
+            for dirent in self.children(folder).await? {
+                let entry = named(path::join(&path, &dirent.name), self.inode(dirent.inode).await?);
+                if let FSEntryKind::Folder(_) = entry.entry {
+                    folders.push((entry.abs_path.clone(), dirent.inode));
+                }
+                entries.push(entry);
+            }
+        }
+
+        entries.sort_by(|a, b| a.abs_path.cmp(&b.abs_path));
+        Ok(entries)
+    }
+
+    /// Removes the directory entry `dirent`, along with its inode and the
+    /// contents of a file when it was the last name of it. A folder goes
+    /// with everything below it.
+    fn unlink<'a>(&'a self, dirent: &'a DirEntry) -> LocalBoxFuture<'a, Result<(), SimpleFSError>> {
+        async move {
+            self.dirents.delete(JsValue::from_str(&dirent.key))?.await?;
+
+            let mut inode = self.inode(dirent.inode).await?;
+            let links = inode.entry.links();
+            if links > 1 {
+                inode.entry.metadata_mut().links = Some(links - 1);
+                return self.put_inode(&inode).await;
+            }
+
+            match inode.entry {
+                FSEntryKind::File(_) => delete_chunks(&self.chunks, dirent.inode).await?,
+                FSEntryKind::Folder(_) => {
+                    for child in self.children(dirent.inode).await? {
+                        self.unlink(&child).await?;
+                    }
+                }
+                FSEntryKind::Link(_) => {}
+            }
+            self.inodes.delete(JsValue::from(dirent.inode as f64))?.await?;
+
+            Ok(())
+        }
+        .boxed_local()
+    }
+
+    /// Names the entry of `inode` `path`, replacing what had that name.
+    /// Returns the entry as reached at `path`.
+    async fn link(&self, path: &str, inode: u64) -> Result<FSEntry, SimpleFSError> {
+        let path = path::normalize(path);
+        let name = path::file_name(&path);
+        let parent = self.folder(&path::parent(&path)).await?.ok_or(SimpleFSError::ParentNotFound)?;
+
+        match self.dirent(parent, name).await? {
+            Some(dirent) if dirent.inode == inode => {
+                let inode = self.inode(inode).await?;
+                return Ok(named(path, inode));
+            }
+            Some(dirent) => self.unlink(&dirent).await?,
+            None => {}
+        }
+
+        let mut linked = self.inode(inode).await?;
+        let links = linked.entry.links();
+        linked.entry.metadata_mut().links = Some(links + 1);
+        self.put_inode(&linked).await?;
+        self.dirents.put(&encode(&DirEntry::new(parent, name, inode))?, None)?.await?;
+
+        Ok(named(path, linked))
+    }
+}
+
+/// Stores `entry` at its path, within a transaction over the volume. A
+/// folder already there takes the metadata of a folder, and a file those
+/// of a file, along with the contents it carries, which every name of the
+/// file then shows. With `replace` a file given contents gets an inode of
+/// its own instead. Anything else there is unlinked. The contents become
+/// chunks of the inode and are left out of the entry, which is returned
+/// as stored.
+async fn store_entry(
+    stores: &Stores,
+    next_inode: &mut u64,
+    entry: &FSEntry,
+    replace: bool,
+) -> Result<FSEntry, SimpleFSError> {
+    let path = path::normalize(&entry.abs_path);
+    let name = path::file_name(&path);
+    let parent = stores.folder(&path::parent(&path)).await?.ok_or(SimpleFSError::ParentNotFound)?;
+    let data = match &entry.entry {
+        FSEntryKind::File(file) => file.data.as_deref(),
+        _ => None,
+    };
+
+    let keeps = |current: &FSEntryKind| match (current, &entry.entry) {
+        (FSEntryKind::Folder(_), FSEntryKind::Folder(_)) => true,
+        (FSEntryKind::File(_), FSEntryKind::File(_)) => !replace || data.is_none(),
+        _ => false,
+    };
+    let current = match stores.dirent(parent, name).await? {
+        Some(dirent) => Some((stores.inode(dirent.inode).await?, dirent)),
+        None => None,
+    };
+    let kept = match current {
+        Some((inode, _)) if keeps(&inode.entry) => Some(inode),
+        Some((_, dirent)) => {
+            stores.unlink(&dirent).await?;
+            None
+        }
+        None => None,
+    };
+
+    let number = match &kept {
+        Some(inode) => inode.inode,
+        None => {
+            *next_inode += 1;
+            *next_inode - 1
+        }
+    };
+
+    let mut kind = match &entry.entry {
+        FSEntryKind::File(file) => FSEntryKind::File(file.without_data()),
+        kind => kind.clone(),
+    };
+    kind.metadata_mut().links = Some(kept.as_ref().map_or(1, |inode| inode.entry.links()));
+
+    if let FSEntryKind::File(file) = &mut kind {
+        file.inode = number;
+
+        match data {
+            Some(data) => {
+                file.size = data.len() as u64;
+                delete_chunks(&stores.chunks, number).await?;
+                write_chunks(&stores.chunks, number, 0, data).await?;
+            }
+            // a file stored without its contents keeps those it had
+            None => file.size = kept.as_ref().map_or(0, |inode| inode.entry.size()),
+        }
+    }
+
+    stores.put_inode(&Inode { inode: number, entry: kind.clone() }).await?;
+    if kept.is_none() {
+        stores.dirents.put(&encode(&DirEntry::new(parent, name, number))?, None)?.await?;
+    }
+
+    Ok(FSEntry { abs_path: path, entry: kind })
+}
+
+/// Commits `transaction` when `result` is a success, and aborts it
+/// otherwise, so that nothing done before the error is kept.
+async fn commit_or_abort<T>(
+    transaction: Transaction,
+    result: Result<T, SimpleFSError>,
+) -> Result<T, SimpleFSError> {
+    match result {
+        Ok(value) => {
+            transaction.commit()?.await?;
+            Ok(value)
+        }
+        Err(err) => {
+            console_log(&format!("[vfs] rolling back: {}\n", err));
+            let _ = transaction.abort();
+            Err(err)
+        }
+    }
+}
+
+/// Removes the name `path`, along with the entry when it was its last
+/// one, within a transaction over the volume.
+async fn delete_entry(stores: &Stores, path: &str) -> Result<(), SimpleFSError> {
+    if let Some((dirent, _)) = stores.lookup(path).await? {
+        stores.unlink(&dirent).await?;
+    }
+
+    Ok(())
 }
 
 impl SimpleFS {
     pub fn new() -> Self {
-        return Self {
+        Self {
             files: HashMap::new(),
             database: None,
-        };
+            next_inode: 1,
+        }
     }
 
     pub async fn init(&mut self) {
-        let database = init_storage().await.unwrap();
+        let database = match init_storage().await {
+            Ok(database) => database,
+            Err(err) => {
+                console_log(&format!("[vfs] cannot open storage, running without a volume: {}\n", err));
+                return;
+            }
+        };
         self.database = Some(database);
+
+        // inodes go on from the highest one stored, the keys of the inodes
+        // being all there is to read
+        self.next_inode = self.highest_inode().await.unwrap_or_default() + 1;
+
         console_log("[vfs] storage initialized\n");
     }
 
+    #[allow(dead_code)]
     pub fn write(&mut self, name: &str, contents: FSEntry) {
         self.files.insert(name.into(), contents);
     }
 
+    #[allow(dead_code)]
     pub fn read(&self, name: &str) -> Option<&FSEntry> {
         self.files.get(name)
     }
 
+    #[allow(dead_code)]
     pub fn list(&self) -> Vec<String> {
         let mut v: Vec<_> = self.files.keys().cloned().collect();
         v.sort();
@@ -57,84 +391,438 @@ impl SimpleFS {
         path.starts_with('/')
     }
 
+    /// A transaction over every store of the volume.
+    fn transaction(&self, mode: TransactionMode) -> Result<Transaction, SimpleFSError> {
+        let Some(db) = &self.database else {
+            console_log("[vfs] database not initialized\n");
+            return Err(SimpleFSError::IOError);
+        };
+
+        Ok(db.transaction(STORES, mode)?)
+    }
+
+    async fn highest_inode(&self) -> Result<u64, SimpleFSError> {
+        let transaction = self.transaction(TransactionMode::ReadOnly)?;
+        let keys = transaction.object_store(INODES)?.get_all_keys(None, None)?.await?;
+        transaction.await?;
+
+        Ok(keys.last().and_then(JsValue::as_f64).unwrap_or_default() as u64)
+    }
+
     pub async fn exists(&self, path: &str) -> Result<bool, SimpleFSError> {
         if !SimpleFS::is_folder_path(path) {
             console_log(&format!("[vfs] invalid path '{}'\n", path));
             return Err(SimpleFSError::InvalidPath);
         }
 
-        if let Some(db) = &self.database {
-            console_log(&format!("[vfs] checking if path '{}' exists\n", path));
-            let transaction = db
-                .transaction(&["vol_0"], TransactionMode::ReadOnly)
-                .unwrap();
+        console_log(&format!("[vfs] checking if path '{}' exists\n", path));
+        let transaction = self.transaction(TransactionMode::ReadOnly)?;
+        let found = Stores::of(&transaction)?.lookup(path).await?;
+        transaction.await?;
+
+        if found.is_none() {
+            console_log(&format!("[vfs] path '{}' does not exist\n", path));
+            return Ok(false);
+        }
+
+        console_log(&format!("[vfs] path '{}' exists\n", path));
+        Ok(true)
+    }
+
+    /// Returns every entry whose path starts with `path`, e.g. those below
+    /// a folder with `/home/`, the whole volume with `/`.
+    pub async fn read_folder(&self, path: &str) -> Result<Vec<FSEntry>, SimpleFSError> {
+        if !SimpleFS::is_folder_path(path) {
+            console_log(&format!("[vfs] invalid path '{}'\n", path));
+            return Ok(vec![]);
+        }
+
+        console_log(&format!("[vfs] reading folder '{}'\n", path));
+
+        // what starts with the path is below the folder it ends in
+        let folder = if path.ends_with('/') { path::normalize(path) } else { path::parent(path) };
+
+        let transaction = self.transaction(TransactionMode::ReadOnly)?;
+        let stores = Stores::of(&transaction)?;
+        let entries = match stores.folder(&folder).await? {
+            Some(inode) => stores.below(&folder, inode).await?,
+            None => Vec::new(),
+        };
+        transaction.await?;
+
+        let entries: Vec<FSEntry> = entries.into_iter().filter(|entry| entry.abs_path.starts_with(path)).collect();
+
+        console_log(&format!("[vfs] found {} entries\n", entries.len()));
+        Ok(entries)
+    }
+
+    /// Lists the direct children of `path`, reading only the directory
+    /// entries of the folder.
+    pub async fn read_dir(&self, path: &str) -> Result<Vec<FSEntry>, SimpleFSError> {
+        if !SimpleFS::is_folder_path(path) || !SimpleFS::is_absolute_path(path) {
+            console_log(&format!("[vfs] invalid path '{}'\n", path));
+            return Err(SimpleFSError::InvalidPath);
+        }
+
+        let path = path::normalize(path);
+
+        let transaction = self.transaction(TransactionMode::ReadOnly)?;
+        let stores = Stores::of(&transaction)?;
+        let mut entries = Vec::new();
+
+        if let Some(folder) = stores.folder(&path).await? {
+            for dirent in stores.children(folder).await? {
+                entries.push(named(path::join(&path, &dirent.name), stores.inode(dirent.inode).await?));
+            }
+        }
+        transaction.await?;
+
+        Ok(entries)
+    }
 
-            let store = transaction.object_store("vol_0").unwrap();
+    /// Returns the entry at `path` followed by everything below it, sorted
+    /// by path. The root folder has no entry of its own, only its contents
+    /// are returned for it.
+    pub async fn read_tree(&self, path: &str) -> Result<Vec<FSEntry>, SimpleFSError> {
+        let path = path::normalize(path);
+
+        let transaction = self.transaction(TransactionMode::ReadOnly)?;
+        let stores = Stores::of(&transaction)?;
+        let mut entries = Vec::new();
+
+        let folder = match stores.lookup(&path).await? {
+            _ if path == "/" => Some(ROOT),
+            Some((_, inode)) => {
+                let folder = matches!(inode.entry, FSEntryKind::Folder(_)).then_some(inode.inode);
+                entries.push(named(path.clone(), inode));
+                folder
+            }
+            None => None,
+        };
 
-            let key = JsValue::from_str(path);
-            let req = store.get(key).unwrap();
+        if let Some(folder) = folder {
+            entries.extend(stores.below(&path, folder).await?);
+        }
+        transaction.await?;
 
-            let result = req.await.unwrap();
+        Ok(entries)
+    }
 
-            transaction.await.unwrap();
+    /// Returns the entry stored at `path`, if any.
+    pub async fn get_entry(&self, path: &str) -> Result<Option<FSEntry>, SimpleFSError> {
+        let transaction = self.transaction(TransactionMode::ReadOnly)?;
+        let found = Stores::of(&transaction)?.lookup(path).await?;
+        transaction.await?;
 
-            if result.is_none() {
-                console_log(&format!("[vfs] path '{}' does not exist\n", path));
-                return Ok(false);
+        Ok(found.map(|(_, inode)| named(path::normalize(path), inode)))
+    }
+
+    async fn put_entry(&mut self, entry: &FSEntry, replace: bool) -> Result<(), SimpleFSError> {
+        let transaction = self.transaction(TransactionMode::ReadWrite)?;
+
+        let result = store_entry(&Stores::of(&transaction)?, &mut self.next_inode, entry, replace).await;
+        let stored = commit_or_abort(transaction, result).await?;
+
+        self.files.insert(stored.abs_path.clone(), stored);
+
+        Ok(())
+    }
+
+    /// Removes the file, link or empty folder at `path`. A file with other
+    /// names only loses this one.
+    pub async fn remove_entry(&mut self, path: &str) -> Result<(), SimpleFSError> {
+        if !SimpleFS::is_absolute_path(path) || path::normalize(path) == "/" {
+            console_log(&format!("[vfs] invalid path '{}'\n", path));
+            return Err(SimpleFSError::InvalidPath);
+        }
+
+        match self.get_entry(path).await? {
+            None => return Err(SimpleFSError::NotFound),
+            Some(FSEntry { entry: FSEntryKind::Folder(_), .. }) if !self.read_dir(path).await?.is_empty() => {
+                return Err(SimpleFSError::FolderNotEmpty);
             }
+            Some(_) => {}
+        }
 
-            console_log(&format!("[vfs] path '{}' exists\n", path));
-            return Ok(true);
-        } else {
-            console_log("[vfs] database not initialized\n");
-            return Err(SimpleFSError::IOError);
+        console_log(&format!("[vfs] removing '{}'\n", path));
+
+        let transaction = self.transaction(TransactionMode::ReadWrite)?;
+        let result = delete_entry(&Stores::of(&transaction)?, path).await;
+        commit_or_abort(transaction, result).await?;
+
+        let below = format!("{}/", path.trim_end_matches('/'));
+        self.files.retain(|cached, _| cached != path && !cached.starts_with(&below));
+
+        Ok(())
+    }
+
+    /// Gives the file or link at `target` another name, `path`: both are
+    /// then the same entry, which stays until the last of its names is
+    /// removed. With `replace` a file or link at `path` is unlinked in the
+    /// same transaction, a folder there never being.
+    pub async fn link(&mut self, target: &str, path: &str, replace: bool) -> Result<FSEntry, SimpleFSError> {
+        if !SimpleFS::is_absolute_path(path) || path::normalize(path) == "/" {
+            console_log(&format!("[vfs] invalid path '{}'\n", path));
+            return Err(SimpleFSError::InvalidPath);
+        }
+
+        self.check_parent(path).await?;
+
+        console_log(&format!("[vfs] linking '{}' to '{}'\n", path, target));
+
+        let transaction = self.transaction(TransactionMode::ReadWrite)?;
+        let stores = Stores::of(&transaction)?;
+
+        let result = async {
+            let inode = match stores.lookup(target).await? {
+                _ if path::normalize(target) == "/" => return Err(SimpleFSError::NotAFile),
+                Some((_, Inode { entry: FSEntryKind::Folder(_), .. })) => return Err(SimpleFSError::NotAFile),
+                Some((_, inode)) => inode.inode,
+                None => return Err(SimpleFSError::NotFound),
+            };
+            match stores.lookup(path).await? {
+                Some((_, Inode { entry: FSEntryKind::Folder(_), .. })) => return Err(SimpleFSError::AlreadyExists),
+                Some(_) if !replace => return Err(SimpleFSError::AlreadyExists),
+                _ => {}
+            }
+
+            stores.link(path, inode).await
         }
+        .await;
+
+        let entry = commit_or_abort(transaction, result).await?;
+
+        self.files.insert(entry.abs_path.clone(), entry.clone());
+
+        Ok(entry)
     }
 
-    pub async fn read_folder(&self, path: &str) -> Result<Vec<FSEntry>, SimpleFSError> {
-        if SimpleFS::is_folder_path(path) == false {
+    /// Stores `entry` as it is, metadata included, e.g. when extracting it
+    /// from an archive. A file or link at its path is replaced, its other
+    /// names keeping what it was, while a folder there only takes the
+    /// metadata of a folder. A file without contents only updates the
+    /// metadata of the file there.
+    pub async fn restore_entry(&mut self, entry: &FSEntry) -> Result<(), SimpleFSError> {
+        let path = &entry.abs_path;
+
+        if !SimpleFS::is_absolute_path(path) || path::normalize(path) == "/" {
             console_log(&format!("[vfs] invalid path '{}'\n", path));
-            return Ok(vec![]);
+            return Err(SimpleFSError::InvalidPath);
         }
-        
-        if let Some(db) = &self.database {
-            console_log(&format!("[vfs] reading folder '{}'\n", path));
-            let transaction = db
-                .transaction(&["vol_0"], TransactionMode::ReadOnly)
-                .unwrap();
 
-            let store = transaction.object_store("vol_0").unwrap();
+        self.check_parent(path).await?;
 
-            let key = JsValue::from_str(path);
-       
-            let query = Query::KeyRange(idb::KeyRange::bound(
-                &JsValue::from_str(&format!("{}", path)),
-                &JsValue::from_str(&format!("{}\u{FFFF}", path)),
-                Some(false),
-                Some(false),
-            ).unwrap());
+        let is_folder = matches!(entry.entry, FSEntryKind::Folder(_));
+        match self.get_entry(path).await? {
+            Some(FSEntry { entry: FSEntryKind::Folder(_), .. }) if !is_folder => return Err(SimpleFSError::NotAFile),
+            Some(FSEntry { entry: FSEntryKind::File(_) | FSEntryKind::Link(_), .. }) if is_folder => {
+                return Err(SimpleFSError::NotAFolder);
+            }
+            _ => {}
+        }
 
-            let req = store.get_all(None, None).unwrap();
-            let result = req.await.unwrap();
+        console_log(&format!("[vfs] restoring '{}'\n", path));
+        self.put_entry(entry, true).await
+    }
 
-            console_log(&format!("[vfs] found {} entries\n", result.len()));
+    /// Removes the entries at `removed` then stores `written` as they are,
+    /// in a single transaction: either every change is made or none is.
+    /// Files of `written` with the same inode, which then only tells them
+    /// apart, become names of a single file. Nothing is checked, the caller
+    /// making sure the volume stays whole.
+    pub async fn apply_entries(&mut self, removed: &[String], written: &[FSEntry]) -> Result<(), SimpleFSError> {
+        console_log(&format!("[vfs] removing {} and writing {} entries\n", removed.len(), written.len()));
+
+        let transaction = self.transaction(TransactionMode::ReadWrite)?;
+        let stores = Stores::of(&transaction)?;
+        let next_inode = &mut self.next_inode;
+
+        let result: Result<Vec<FSEntry>, SimpleFSError> = async {
+            for path in removed {
+                delete_entry(&stores, path).await?;
+            }
 
-            if result.is_empty() {
-                console_log(&format!("[vfs] folder '{}' is empty\n", path));
-                return Ok(vec![]);
+            // inodes of `written` by those they are stored under
+            let mut linked = HashMap::new();
+            let mut stored = Vec::with_capacity(written.len());
+
+            for entry in written {
+                let group = match &entry.entry {
+                    FSEntryKind::File(file) if file.inode != 0 => Some(file.inode),
+                    _ => None,
+                };
+
+                let entry = match group.and_then(|group| linked.get(&group)) {
+                    Some(&inode) => stores.link(&entry.abs_path, inode).await?,
+                    None => store_entry(&stores, next_inode, entry, true).await?,
+                };
+                if let (Some(group), FSEntryKind::File(file)) = (group, &entry.entry) {
+                    linked.insert(group, file.inode);
+                }
+                stored.push(entry);
             }
+            Ok(stored)
+        }
+        .await;
 
-            let entries = result
-                .into_iter().map(|entry| serde_wasm_bindgen::from_value(entry).unwrap()).collect();
+        let stored = commit_or_abort(transaction, result).await?;
 
-            transaction.await.unwrap();
+        for path in removed {
+            self.files.remove(path);
+        }
+        for entry in stored {
+            self.files.insert(entry.abs_path.clone(), entry);
+        }
 
-            console_log(&format!("[vfs] folder '{}' read\n", path));
-            Ok(entries)
-        } else {
+        Ok(())
+    }
+
+    /// Checks that the parent of `path` exists and is a folder.
+    async fn check_parent(&self, path: &str) -> Result<(), SimpleFSError> {
+        let parent = path::parent(path);
+
+        if parent == "/" {
+            return Ok(());
+        }
+
+        match self.get_entry(&parent).await? {
+            Some(FSEntry { entry: FSEntryKind::Folder(_), .. }) => Ok(()),
+            Some(_) => Err(SimpleFSError::NotAFolder),
+            None => Err(SimpleFSError::ParentNotFound),
+        }
+    }
+
+    pub async fn read_file(&self, path: &str) -> Result<Vec<u8>, SimpleFSError> {
+        self.read_range(path, 0, u64::MAX).await
+    }
+
+    /// Reads at most `len` bytes of the file at `path` from `offset`, only
+    /// loading the chunks holding them, to go through a file a piece at a
+    /// time.
+    pub async fn read_range(&self, path: &str, offset: u64, len: u64) -> Result<Vec<u8>, SimpleFSError> {
+        console_log(&format!("[vfs] reading file '{}'\n", path));
+
+        match self.get_entry(path).await? {
+            Some(FSEntry { entry: FSEntryKind::File(file), .. }) => self.read_contents(&file, offset, len).await,
+            Some(_) => Err(SimpleFSError::NotAFile),
+            None => Err(SimpleFSError::NotFound),
+        }
+    }
+
+    /// Loads the contents of `entry` when it is a file, entries being read
+    /// without them.
+    pub async fn load_contents(&self, entry: &mut FSEntry) -> Result<(), SimpleFSError> {
+        if let FSEntryKind::File(file) = &entry.entry
+            && file.data.is_none()
+        {
+            let data = self.read_contents(file, 0, u64::MAX).await?;
+            if let FSEntryKind::File(file) = &mut entry.entry {
+                file.data = Some(data);
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn read_contents(&self, file: &FSFile, offset: u64, len: u64) -> Result<Vec<u8>, SimpleFSError> {
+        let end = offset.saturating_add(len).min(file.size);
+        if offset >= end {
+            return Ok(Vec::new());
+        }
+
+        if let Some(data) = &file.data {
+            return Ok(data.get(offset as usize..end as usize).unwrap_or_default().to_vec());
+        }
+
+        let Some(db) = &self.database else {
             console_log("[vfs] database not initialized\n");
-            Err(SimpleFSError::IOError)
+            return Err(SimpleFSError::IOError);
+        };
+
+        let transaction = db.transaction(&[CHUNKS], TransactionMode::ReadOnly)?;
+        let chunks = transaction.object_store(CHUNKS)?;
+
+        let (first, last) = (chunk::index(offset), chunk::index(end - 1));
+        let mut data = Vec::with_capacity((end - offset) as usize);
+        for chunk in read_chunks(&chunks, file.inode, first, last).await? {
+            data.extend(chunk.data);
         }
+        transaction.await?;
+
+        data.drain(..((offset - first as u64 * CHUNK_SIZE) as usize).min(data.len()));
+        data.truncate((end - offset) as usize);
+
+        Ok(data)
+    }
+
+    /// Writes `data` to the file at `path`, creating it when missing. Every
+    /// name of the file shows the new contents. With `append` the data is
+    /// added at the end of the current contents, only their last chunk
+    /// being rewritten, to write a file a piece at a time.
+    pub async fn write_file(&mut self, path: &str, data: &[u8], append: bool) -> Result<FSEntry, SimpleFSError> {
+        if !SimpleFS::is_absolute_path(path) || path::normalize(path) == "/" {
+            console_log(&format!("[vfs] invalid file path '{}'\n", path));
+            return Err(SimpleFSError::InvalidPath);
+        }
+
+        self.check_parent(path).await?;
+
+        let now = chrono::Utc::now().timestamp_millis();
+
+        console_log(&format!("[vfs] writing {} bytes to '{}'\n", data.len(), path));
+
+        let mut file = match self.get_entry(path).await? {
+            Some(FSEntry { entry: FSEntryKind::File(file), .. }) if append && file.inode != 0 => {
+                return self.append_chunks(path, file, data, now).await;
+            }
+            Some(FSEntry { entry: FSEntryKind::File(file), .. }) => file,
+            Some(_) => return Err(SimpleFSError::NotAFile),
+            None => FSFile::new(path::file_name(path), Vec::new(), now),
+        };
+        file.write(data.to_vec(), now);
+
+        let entry = FSEntry {
+            abs_path: path.into(),
+            entry: FSEntryKind::File(file),
+        };
+        self.put_entry(&entry, false).await?;
+
+        Ok(entry)
+    }
+
+    /// Adds `data` at the end of the stored contents of `file`.
+    async fn append_chunks(
+        &mut self,
+        path: &str,
+        mut file: FSFile,
+        data: &[u8],
+        now: i64,
+    ) -> Result<FSEntry, SimpleFSError> {
+        let transaction = self.transaction(TransactionMode::ReadWrite)?;
+        let stores = Stores::of(&transaction)?;
+
+        let result = async {
+            // the last chunk may not be full, the data goes on from its end
+            let last = chunk::index(file.size);
+            let tail = read_chunks(&stores.chunks, file.inode, last, last).await?.pop();
+            let mut tail = tail.map(|chunk| chunk.data).unwrap_or_default();
+            tail.truncate((file.size - last as u64 * CHUNK_SIZE) as usize);
+            tail.extend_from_slice(data);
+
+            write_chunks(&stores.chunks, file.inode, last, &tail).await?;
+            file.grow(data.len() as u64, now);
+
+            let inode = Inode { inode: file.inode, entry: FSEntryKind::File(file) };
+            stores.put_inode(&inode).await?;
+            Ok(inode)
+        }
+        .await;
+        let inode = commit_or_abort(transaction, result).await?;
+
+        let entry = FSEntry { abs_path: path.into(), entry: inode.entry };
+        self.files.insert(entry.abs_path.clone(), entry.clone());
+
+        Ok(entry)
     }
 
     pub async fn create_folder(&mut self, path: &str) -> Result<FSEntry, SimpleFSError>{
@@ -145,11 +833,7 @@ impl SimpleFS {
         let full_path = if path.starts_with('/') {
             path.to_string()
         } else {
-            if current_folder == "/" {
-                format!("/{}", path)
-            } else {
-                format!("{}/{}", current_folder.trim_end_matches('/'), path)
-            }
+            path::join(current_folder, path)
         };
 
         return self.create_folder_absolute(&full_path).await;
@@ -172,33 +856,12 @@ impl SimpleFS {
             return Err(SimpleFSError::AlreadyExists);
         }
 
-        let path_parts: Vec<&str> = path.split('/').filter(|p| !p.is_empty()).collect();
-
-        if path_parts.len() < 1 {
+        if path::normalize(path) == "/" {
             console_log(&format!("[vfs] cannot create root folder '{}'\n", path));
             return Err(SimpleFSError::InvalidPath);
         }
 
-        let parent_path = if path == "/" {
-            "/".to_string()
-        } else {
-            let mut p_path = "".to_string();
-
-            let last = path_parts.len() - 1;
-            for (i, p) in path_parts.iter().enumerate() {
-                if i == last {
-                    continue;
-                }
-                
-                p_path.push_str(format!("/{}", p).as_str());
-            }
-
-            if p_path.is_empty() {
-                "/".to_string()
-            } else {
-                p_path
-            }
-        };
+        let parent_path = path::parent(path);
 
         console_log(&format!("[vfs] parent folder of '{}' is '{}'\n", path, parent_path));
 
@@ -207,49 +870,20 @@ impl SimpleFS {
             return Err(SimpleFSError::ParentNotFound);
         }
 
-        if let Some(db) = &self.database {
-            console_log(&format!("[vfs] creating folder '{}'\n", path));
-            
-            let transaction = db
-                .transaction(&["vol_0"], TransactionMode::ReadWrite)
-                .unwrap();
-
-            let name = path.rsplit('/').next().unwrap_or("");
-            let store = transaction.object_store("vol_0").unwrap();
-
-            let now = chrono::Utc::now().timestamp_millis();
-            let folder = FSFolder {
-                metadata: crate::vfs::entry::FSEntryMetadata {
-                    name: name.into(),
-                    created_at: now,
-                    modified_at: now,
-                    is_hidden: false,
-                },
-            };
+        console_log(&format!("[vfs] creating folder '{}'\n", path));
 
-            let serializer = Serializer::json_compatible();
-            let entry = FSEntry {
-                abs_path: path.into(),
-                entry: crate::vfs::entry::FSEntryKind::Folder(folder.clone()),
-            };
-            self.files.insert(entry.abs_path.clone(), entry.clone());
+        let now = chrono::Utc::now().timestamp_millis();
+        let folder = FSFolder::new(path::file_name(path), now);
+        let entry = FSEntry { abs_path: path.into(), entry: FSEntryKind::Folder(folder) };
 
-            let id = store
-                .add(&entry.serialize(&serializer).unwrap(), None)
-                .unwrap()
-                .await
-                .unwrap();
+        let transaction = self.transaction(TransactionMode::ReadWrite)?;
+        let result = store_entry(&Stores::of(&transaction)?, &mut self.next_inode, &entry, false).await;
+        let entry = commit_or_abort(transaction, result).await?;
 
-            console_log(&format!("[vfs] folder id: {:?}\n", id));
-            transaction.commit().unwrap().await.unwrap();
+        self.files.insert(entry.abs_path.clone(), entry.clone());
 
-            console_log(&format!("[vfs] folder '{}' created\n", entry.abs_path));
+        console_log(&format!("[vfs] folder '{}' created\n", entry.abs_path));
 
-            Ok(entry)
-        } else {
-            console_log("[vfs] database not initialized\n");
-
-            Err(SimpleFSError::IOError)
-        }
+        Ok(entry)
     }
 }
@@ -0,0 +1,339 @@
+//! Images of a whole volume, to move it to another browser. An image is
+//! laid out as follows, integers being little-endian:
+//!
+//! | size | contents                                   |
+//! |------|--------------------------------------------|
+//! | 8    | `ROSIMAGE`                                 |
+//! | 4    | version of the format                      |
+//! | 8    | length of the entry table                  |
+//! | 32   | SHA-256 of the entry table                 |
+//! |      | entry table, in JSON                       |
+//! |      | contents of the files, one after the other |
+//! | 32   | SHA-256 of everything before it            |
+//!
+//! The entry table names the volume, tells when the image was made and
+//! holds every entry as it is stored, the contents of files aside, which
+//! are given by their place after the table and their own SHA-256. Files
+//! with the same inode are names of a single file, whose contents are
+//! there once.
+
+use std::collections::{HashMap, HashSet};
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::vfs::{
+    entry::{FSEntry, FSEntryKind},
+    errors::ImageError,
+    path,
+};
+
+pub const MAGIC: &[u8; 8] = b"ROSIMAGE";
+
+/// Version of the format written, the only one read so far.
+pub const VERSION: u32 = 1;
+
+const HEADER_LEN: usize = 8 + 4 + 8 + 32;
+const CHECKSUM_LEN: usize = 32;
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Table {
+    volume: String,
+    created_at: i64,
+    entries: Vec<TableEntry>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct TableEntry {
+    abs_path: String,
+    entry: FSEntryKind,
+    /// Where the contents of a file are, for files only.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    blob: Option<Blob>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Blob {
+    /// Offset from the end of the entry table.
+    offset: u64,
+    length: u64,
+    sha256: String,
+}
+
+/// Every entry of a volume, as kept in an image.
+#[derive(Debug)]
+pub struct Image {
+    pub volume: String,
+    pub created_at: i64,
+    pub entries: Vec<FSEntry>,
+}
+
+fn sha256(data: &[u8]) -> [u8; 32] {
+    Sha256::digest(data).into()
+}
+
+fn hex(digest: &[u8]) -> String {
+    digest.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// Takes `len` bytes of `data` from `offset`, which must all be there.
+fn slice(data: &[u8], offset: usize, len: usize) -> Result<&[u8], ImageError> {
+    offset
+        .checked_add(len)
+        .and_then(|end| data.get(offset..end))
+        .ok_or(ImageError::Truncated)
+}
+
+impl Image {
+    pub fn new(volume: &str, created_at: i64, entries: Vec<FSEntry>) -> Self {
+        Self { volume: volume.into(), created_at, entries }
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let mut blobs = Vec::new();
+        // blobs of the inodes already in the image
+        let mut linked: HashMap<u64, Blob> = HashMap::new();
+        let entries = self
+            .entries
+            .iter()
+            .map(|entry| {
+                let mut kind = entry.entry.clone();
+                let blob = match &mut kind {
+                    FSEntryKind::File(file) => {
+                        let data = file.data.take().unwrap_or_default();
+                        let blob = linked.get(&file.inode).cloned().unwrap_or_else(|| {
+                            let (offset, length) = (blobs.len() as u64, data.len() as u64);
+                            let blob = Blob { offset, length, sha256: hex(&sha256(&data)) };
+                            blobs.extend(data);
+                            blob
+                        });
+                        if file.inode != 0 {
+                            linked.insert(file.inode, blob.clone());
+                        }
+                        Some(blob)
+                    }
+                    _ => None,
+                };
+                TableEntry { abs_path: entry.abs_path.clone(), entry: kind, blob }
+            })
+            .collect();
+
+        let table = Table { volume: self.volume.clone(), created_at: self.created_at, entries };
+        let table = serde_json::to_vec(&table).unwrap_or_default();
+
+        let mut image = Vec::with_capacity(HEADER_LEN + table.len() + blobs.len() + CHECKSUM_LEN);
+        image.extend(MAGIC);
+        image.extend(VERSION.to_le_bytes());
+        image.extend((table.len() as u64).to_le_bytes());
+        image.extend(sha256(&table));
+        image.extend(table);
+        image.extend(blobs);
+        image.extend(sha256(&image));
+
+        image
+    }
+
+    /// Reads an image, checking every checksum and that its entries make
+    /// up a volume: absolute paths, each one once, in folders of the image.
+    pub fn decode(data: &[u8]) -> Result<Self, ImageError> {
+        if !data.starts_with(MAGIC) {
+            return Err(ImageError::NotAnImage);
+        }
+
+        let version = u32::from_le_bytes(slice(data, 8, 4)?.try_into().unwrap_or_default());
+        if version != VERSION {
+            return Err(ImageError::UnsupportedVersion(version));
+        }
+
+        let Some(body_len) = data.len().checked_sub(CHECKSUM_LEN).filter(|len| *len >= HEADER_LEN) else {
+            return Err(ImageError::Truncated);
+        };
+        let (body, checksum) = data.split_at(body_len);
+        if sha256(body) != checksum {
+            return Err(ImageError::ChecksumMismatch);
+        }
+
+        let table_len = u64::from_le_bytes(slice(body, 12, 8)?.try_into().unwrap_or_default());
+        let table_len = usize::try_from(table_len).map_err(|_| ImageError::Truncated)?;
+        let table = slice(body, HEADER_LEN, table_len)?;
+        if sha256(table) != slice(body, 20, 32)? {
+            return Err(ImageError::ChecksumMismatch);
+        }
+
+        let table: Table = serde_json::from_slice(table).map_err(|e| ImageError::InvalidTable(e.to_string()))?;
+        let blobs = &body[HEADER_LEN + table_len..];
+
+        let folders: HashSet<&str> = table
+            .entries
+            .iter()
+            .filter(|entry| matches!(entry.entry, FSEntryKind::Folder(_)))
+            .map(|entry| entry.abs_path.as_str())
+            .collect();
+        let mut seen = HashSet::new();
+
+        for entry in &table.entries {
+            let path = entry.abs_path.as_str();
+            let parent = path::parent(path);
+
+            let valid = path.starts_with('/')
+                && path != "/"
+                && path::normalize(path) == path
+                && (parent == "/" || folders.contains(parent.as_str()))
+                && matches!(entry.entry, FSEntryKind::File(_)) == entry.blob.is_some()
+                && seen.insert(path);
+            if !valid {
+                return Err(ImageError::InvalidEntry(path.to_string()));
+            }
+        }
+
+        let mut entries = Vec::with_capacity(table.entries.len());
+
+        for TableEntry { abs_path, mut entry, blob } in table.entries {
+            if let (FSEntryKind::File(file), Some(blob)) = (&mut entry, blob) {
+                let offset = usize::try_from(blob.offset).map_err(|_| ImageError::Truncated)?;
+                let length = usize::try_from(blob.length).map_err(|_| ImageError::Truncated)?;
+                let data = slice(blobs, offset, length)?;
+
+                if hex(&sha256(data)) != blob.sha256 {
+                    return Err(ImageError::BlobChecksumMismatch(abs_path));
+                }
+                file.size = data.len() as u64;
+                file.data = Some(data.to_vec());
+            }
+
+            entries.push(FSEntry { abs_path, entry });
+        }
+
+        Ok(Self { volume: table.volume, created_at: table.created_at, entries })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vfs::entry::{FSFile, FSFolder, FSLink};
+
+    fn image() -> Image {
+        let file = |name: &str, data: &[u8], inode| {
+            let mut file = FSFile::new(name, data.to_vec(), 1);
+            file.inode = inode;
+            FSEntryKind::File(file)
+        };
+        let entries = vec![
+            FSEntry { abs_path: "/docs".into(), entry: FSEntryKind::Folder(FSFolder::new("docs", 1)) },
+            FSEntry { abs_path: "/docs/a.txt".into(), entry: file("a.txt", b"hello", 7) },
+            FSEntry { abs_path: "/docs/b.txt".into(), entry: file("b.txt", b"hello", 7) },
+            FSEntry { abs_path: "/empty".into(), entry: file("empty", b"", 0) },
+            FSEntry { abs_path: "/latest".into(), entry: FSEntryKind::Link(FSLink::new("latest", "docs/a.txt", 1)) },
+        ];
+
+        Image::new("home", 42, entries)
+    }
+
+    /// `image` with its last checksum made right again, so that the
+    /// changes made to it are only caught by the other ones.
+    fn resealed(mut image: Vec<u8>) -> Vec<u8> {
+        let body_len = image.len() - CHECKSUM_LEN;
+        let checksum = sha256(&image[..body_len]);
+        image[body_len..].copy_from_slice(&checksum);
+        image
+    }
+
+    #[test]
+    fn images_decode_to_what_was_encoded() {
+        let encoded = image().encode();
+        // the contents of hard links are only there once
+        assert_eq!(encoded.windows(5).filter(|window| window == b"hello").count(), 1);
+
+        let decoded = Image::decode(&encoded).unwrap();
+        assert_eq!((decoded.volume.as_str(), decoded.created_at), ("home", 42));
+
+        let summary: Vec<_> = decoded
+            .entries
+            .iter()
+            .map(|entry| {
+                let contents = match &entry.entry {
+                    FSEntryKind::File(file) => {
+                        assert_eq!(file.size, file.data.as_ref().unwrap().len() as u64);
+                        String::from_utf8_lossy(file.data.as_deref().unwrap()).into_owned()
+                    }
+                    FSEntryKind::Folder(_) => "folder".into(),
+                    FSEntryKind::Link(link) => format!("-> {}", link.target),
+                };
+                (entry.abs_path.as_str(), contents)
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                ("/docs", "folder".to_string()),
+                ("/docs/a.txt", "hello".to_string()),
+                ("/docs/b.txt", "hello".to_string()),
+                ("/empty", String::new()),
+                ("/latest", "-> docs/a.txt".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn bad_checksums_are_rejected() {
+        let encoded = image().encode();
+
+        let mut flipped = encoded.clone();
+        let last = flipped.len() - 1;
+        flipped[last] ^= 1;
+        assert!(matches!(Image::decode(&flipped), Err(ImageError::ChecksumMismatch)));
+
+        // the checksum of the entry table
+        let mut flipped = encoded.clone();
+        flipped[20] ^= 1;
+        assert!(matches!(Image::decode(&resealed(flipped)), Err(ImageError::ChecksumMismatch)));
+
+        // the contents of a file, the last byte before the checksum
+        let mut flipped = encoded.clone();
+        let blob = flipped.len() - CHECKSUM_LEN - 1;
+        flipped[blob] ^= 1;
+        assert!(matches!(
+            Image::decode(&resealed(flipped)),
+            Err(ImageError::BlobChecksumMismatch(path)) if path == "/docs/a.txt"
+        ));
+    }
+
+    #[test]
+    fn truncated_images_are_rejected() {
+        let encoded = image().encode();
+
+        for len in MAGIC.len()..encoded.len() {
+            assert!(Image::decode(&encoded[..len]).is_err(), "decoded {} bytes", len);
+        }
+        // checksums made right do not hide a table longer than the image
+        let mut long_table = encoded.clone();
+        long_table[12..20].copy_from_slice(&u64::MAX.to_le_bytes());
+        assert!(matches!(Image::decode(&resealed(long_table)), Err(ImageError::Truncated)));
+
+        assert!(matches!(Image::decode(b"ROS"), Err(ImageError::NotAnImage)));
+        assert!(matches!(Image::decode(MAGIC), Err(ImageError::Truncated)));
+    }
+
+    #[test]
+    fn unknown_versions_are_rejected() {
+        let mut encoded = image().encode();
+        encoded[8..12].copy_from_slice(&(VERSION + 1).to_le_bytes());
+
+        assert!(matches!(
+            Image::decode(&resealed(encoded)),
+            Err(ImageError::UnsupportedVersion(version)) if version == VERSION + 1
+        ));
+    }
+
+    #[test]
+    fn entries_outside_a_volume_are_rejected() {
+        let mut image = image();
+        image.entries.remove(0);
+
+        assert!(matches!(
+            Image::decode(&image.encode()),
+            Err(ImageError::InvalidEntry(path)) if path == "/docs/a.txt"
+        ));
+    }
+}
@@ -0,0 +1,41 @@
+//! Entries of the volume as inodes, holding what an entry is, named in
+//! their folder by directory entries. A file with several names is a
+//! single inode that each of its directory entries points to.
+
+use serde::{Deserialize, Serialize};
+
+use crate::vfs::entry::FSEntryKind;
+
+/// Inode of the root folder, which has no inode of its own, only
+/// directory entries in it.
+pub const ROOT: u64 = 0;
+
+/// An entry of the volume, whatever it is named.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Inode {
+    pub inode: u64,
+    /// The entry under the name it was created with, its metadata counting
+    /// the directory entries pointing to it.
+    pub entry: FSEntryKind,
+}
+
+/// The name of an inode in the folder `parent`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DirEntry {
+    pub key: String,
+    pub parent: u64,
+    pub name: String,
+    pub inode: u64,
+}
+
+impl DirEntry {
+    pub fn new(parent: u64, name: &str, inode: u64) -> Self {
+        Self { key: key(parent, name), parent, name: name.into(), inode }
+    }
+}
+
+/// Key of a directory entry, the entries of a folder being next to each
+/// other, in the order of their names.
+pub fn key(parent: u64, name: &str) -> String {
+    format!("{:016x}/{}", parent, name)
+}
@@ -1,4 +1,8 @@
 pub mod fs;
 pub mod entry;
 pub mod storage;
-pub mod errors;
\ No newline at end of file
+pub mod errors;
+pub mod path;
+pub mod image;
+pub mod chunk;
+pub mod inode;
@@ -0,0 +1,49 @@
+/// Resolves `path` against `cwd`, collapsing `.` and `..` components.
+///
+/// The result is always absolute and never ends with a trailing slash
+/// (except for the root folder itself).
+pub fn resolve(cwd: &str, path: &str) -> String {
+    let joined = if path.starts_with('/') {
+        path.to_string()
+    } else {
+        format!("{}/{}", cwd.trim_end_matches('/'), path)
+    };
+
+    normalize(&joined)
+}
+
+pub fn normalize(path: &str) -> String {
+    let mut parts: Vec<&str> = Vec::new();
+
+    for part in path.split('/') {
+        match part {
+            "" | "." => {}
+            ".." => {
+                parts.pop();
+            }
+            p => parts.push(p),
+        }
+    }
+
+    format!("/{}", parts.join("/"))
+}
+
+/// Returns the parent folder of an absolute path, `/` for top-level entries.
+pub fn parent(path: &str) -> String {
+    match path.trim_end_matches('/').rsplit_once('/') {
+        Some(("", _)) | None => "/".to_string(),
+        Some((parent, _)) => parent.to_string(),
+    }
+}
+
+pub fn file_name(path: &str) -> &str {
+    path.trim_end_matches('/').rsplit('/').next().unwrap_or("")
+}
+
+pub fn join(folder: &str, name: &str) -> String {
+    if folder == "/" {
+        format!("/{}", name)
+    } else {
+        format!("{}/{}", folder.trim_end_matches('/'), name)
+    }
+}
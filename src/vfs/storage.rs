@@ -1,31 +1,686 @@
-use idb::{Database, DatabaseEvent, Error, Factory, IndexParams, KeyPath, ObjectStoreParams};
+use std::collections::{HashMap, HashSet};
 
-pub async fn init_storage() -> Result<Database, Error> {
-    let factory = Factory::new()?;
+use futures::{future::LocalBoxFuture, FutureExt};
+use idb::{
+    Database, DatabaseEvent, Error, Event, Factory, IndexParams, KeyPath, ObjectStoreParams, Request, Transaction,
+};
+use serde::Serialize;
+use serde_json::{json, Value};
+use serde_wasm_bindgen::Serializer;
 
- let mut open_request = factory.open("vfs", Some(1)).unwrap();
+use crate::{
+    console_log,
+    vfs::{
+        chunk::{self, CHUNK_SIZE},
+        entry::{FSEntryKind, FSFolder, DEFAULT_FILE_MODE, DEFAULT_FOLDER_MODE, DEFAULT_LINK_MODE, DEFAULT_OWNER},
+        errors::SimpleFSError,
+        fs::delete_chunks,
+        inode::{self, ROOT},
+        path,
+    },
+};
 
-    // Add an upgrade handler for database
-    open_request.on_upgrade_needed(|event| {
-        // Get database instance from event
-        let database = event.database().unwrap();
+/// Name of the volume, that of the object store its entries were kept in,
+/// keyed by absolute path, up to version 4.
+pub const VOLUME: &str = "vol_0";
+
+/// Object store holding the entries of the volume, keyed by inode.
+pub const INODES: &str = "inodes";
+
+/// Object store holding the names of the entries, keyed by the inode of
+/// their folder and name.
+pub const DIRENTS: &str = "dirents";
+
+/// Object store holding the contents of files, keyed by inode and index.
+pub const CHUNKS: &str = "chunks";
+
+/// The object stores of the database, as a migration sees them: stored
+/// values are handed out as JSON, whatever they are kept as.
+pub trait Backend {
+    /// Creates a store keyed by the field `key` of its values.
+    fn create_store(&mut self, name: &str, key: &str) -> Result<(), SimpleFSError>;
+
+    fn delete_store(&mut self, name: &str) -> Result<(), SimpleFSError>;
+
+    fn get_all<'a>(&'a self, store: &'a str) -> LocalBoxFuture<'a, Result<Vec<Value>, SimpleFSError>>;
+
+    /// Stores `value`, replacing the one with the same key.
+    fn put<'a>(&'a mut self, store: &'a str, value: Value) -> LocalBoxFuture<'a, Result<(), SimpleFSError>>;
+
+    /// Deletes every chunk of the file stored under `inode`.
+    fn delete_chunks(&mut self, inode: u64) -> LocalBoxFuture<'_, Result<(), SimpleFSError>>;
+
+    /// Reports what a migration did to the data, e.g. what it left out.
+    fn log(&mut self, message: &str);
+}
+
+/// A step of the layout of the database, from the version before it.
+pub struct Migration {
+    /// Database version the stores are at once it has run.
+    pub version: u32,
+    pub description: &'static str,
+    pub run: fn(&mut dyn Backend) -> LocalBoxFuture<'_, Result<(), SimpleFSError>>,
+}
+
+/// Every migration, by increasing version. Stored data is never changed in
+/// place by a new release: a migration is added here instead, and is run
+/// on the databases opened at an earlier version.
+pub const MIGRATIONS: &[Migration] = &[
+    Migration { version: 1, description: "create the volume store", run: create_volume },
+    Migration { version: 2, description: "store the mode and owner of every entry", run: add_permissions },
+    Migration { version: 3, description: "move the contents of files to chunks", run: split_file_data },
+    Migration { version: 4, description: "key entries by inode, named by directory entries", run: split_paths },
+];
+
+/// Version the database is opened at, that of the last migration.
+pub const DB_VERSION: u32 = MIGRATIONS[MIGRATIONS.len() - 1].version;
+
+fn create_volume(backend: &mut dyn Backend) -> LocalBoxFuture<'_, Result<(), SimpleFSError>> {
+    async move { backend.create_store(VOLUME, "abs_path") }.boxed_local()
+}
+
+/// Entries stored before modes and owners existed get the defaults they
+/// were shown with.
+fn add_permissions(backend: &mut dyn Backend) -> LocalBoxFuture<'_, Result<(), SimpleFSError>> {
+    async move {
+        map_values(backend, VOLUME, |entry| {
+            let mode = match entry.pointer("/entry/type").and_then(Value::as_str) {
+                Some("File") => DEFAULT_FILE_MODE,
+                Some("Folder") => DEFAULT_FOLDER_MODE,
+                Some("Link") => DEFAULT_LINK_MODE,
+                _ => return Err(corrupted(entry)),
+            };
+            let Some(metadata) = entry.pointer_mut("/entry/metadata").and_then(Value::as_object_mut) else {
+                return Err(corrupted(entry));
+            };
+
+            if metadata.get("mode").is_none_or(Value::is_null) {
+                metadata.insert("mode".into(), mode.into());
+            }
+            if metadata.get("owner").is_none_or(Value::is_null) {
+                metadata.insert("owner".into(), DEFAULT_OWNER.into());
+            }
+            Ok(())
+        })
+        .await
+    }
+    .boxed_local()
+}
+
+/// Contents stored inline with the entries of files move to the chunk
+/// store, each file getting an inode for them.
+fn split_file_data(backend: &mut dyn Backend) -> LocalBoxFuture<'_, Result<(), SimpleFSError>> {
+    async move {
+        backend.create_store(CHUNKS, "key")?;
+
+        let mut inode = 0;
+
+        for mut entry in backend.get_all(VOLUME).await? {
+            if entry.pointer("/entry/type").and_then(Value::as_str) != Some("File") {
+                continue;
+            }
+            let err = corrupted(&entry);
+            let Some(file) = entry.get_mut("entry").and_then(Value::as_object_mut) else {
+                return Err(err);
+            };
+
+            let data = match file.remove("data") {
+                Some(Value::Array(data)) => data,
+                Some(Value::Null) | None => Vec::new(),
+                Some(_) => return Err(err),
+            };
+            inode += 1;
+            file.insert("inode".into(), inode.into());
+            file.insert("size".into(), data.len().into());
+
+            for (index, data) in data.chunks(CHUNK_SIZE as usize).enumerate() {
+                let key = chunk::key(inode, index as u32);
+                let chunk = json!({ "key": key, "inode": inode, "index": index, "data": data });
+                backend.put(CHUNKS, chunk).await?;
+            }
+            backend.put(VOLUME, entry).await?;
+        }
+
+        Ok(())
+    }
+    .boxed_local()
+}
+
+/// Entries keyed by path become inodes, named in their folder by
+/// directory entries. Files keep the inodes their chunks are stored
+/// under, folders and links get the next ones. Folders missing above an
+/// entry are made again, and an entry below a file is left out with its
+/// chunks and logged, so that the upgrade never fails over a stray entry.
+fn split_paths(backend: &mut dyn Backend) -> LocalBoxFuture<'_, Result<(), SimpleFSError>> {
+    async move {
+        backend.create_store(INODES, "inode")?;
+        backend.create_store(DIRENTS, "key")?;
+
+        let mut entries = Vec::new();
+        for entry in backend.get_all(VOLUME).await? {
+            let Some(path) = entry.get("abs_path").and_then(Value::as_str) else {
+                return Err(corrupted(&entry));
+            };
+            entries.push((path::normalize(path), entry));
+        }
+        // folders come before what they hold
+        entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+        let files = entries.iter().filter_map(|(_, entry)| entry.pointer("/entry/inode").and_then(Value::as_u64));
+        let mut next_inode = files.max().unwrap_or_default() + 1;
+        let mut folders = HashMap::from([("/".to_string(), ROOT)]);
+        // paths of the entries that are not folders
+        let mut others = HashSet::new();
+
+        for (path, mut entry) in entries {
+            let kind = entry.pointer("/entry/type").and_then(Value::as_str).map(str::to_string);
+            let file_inode = entry.pointer("/entry/inode").and_then(Value::as_u64).filter(|inode| *inode != 0);
+            match (kind.as_deref(), file_inode) {
+                (Some("File"), Some(_)) | (Some("Folder" | "Link"), _) => {}
+                _ => return Err(corrupted(&entry)),
+            }
+
+            // the folders missing above the entry, up to the nearest one found
+            let mut missing = Vec::new();
+            let mut folder = path::parent(&path);
+            while !folders.contains_key(&folder) && !others.contains(&folder) {
+                missing.push(folder.clone());
+                folder = path::parent(&folder);
+            }
+            // nothing can be below a file, nor twice at the same path
+            let left_out = if others.contains(&folder) {
+                Some("it is below a file")
+            } else if folders.contains_key(&path) || others.contains(&path) {
+                Some("its path is taken")
+            } else {
+                None
+            };
+            if let Some(reason) = left_out {
+                backend.log(&format!("[vfs] leaving out {}, as {}\n", path, reason));
+                if let Some(inode) = file_inode {
+                    backend.delete_chunks(inode).await?;
+                }
+                continue;
+            }
 
-        // Prepare object store params
+            let now = chrono::Utc::now().timestamp_millis();
+            for folder in missing.into_iter().rev() {
+                let name = path::file_name(&folder);
+                let kind = serde_json::to_value(FSEntryKind::Folder(FSFolder::new(name, now)))
+                    .map_err(|_| SimpleFSError::IOError)?;
+
+                put_named(backend, folders[&path::parent(&folder)], name, next_inode, kind).await?;
+                folders.insert(folder, next_inode);
+                next_inode += 1;
+            }
+
+            let inode = match (kind.as_deref(), file_inode) {
+                (Some("File"), Some(inode)) => inode,
+                _ => {
+                    next_inode += 1;
+                    next_inode - 1
+                }
+            };
+            if kind.as_deref() == Some("Folder") {
+                folders.insert(path.clone(), inode);
+            } else {
+                others.insert(path.clone());
+            }
+
+            let parent = folders[&path::parent(&path)];
+            put_named(backend, parent, path::file_name(&path), inode, entry["entry"].take()).await?;
+        }
+
+        backend.delete_store(VOLUME)
+    }
+    .boxed_local()
+}
+
+/// Stores the inode `inode` holding `kind`, named `name` in `parent`.
+async fn put_named(
+    backend: &mut dyn Backend,
+    parent: u64,
+    name: &str,
+    inode: u64,
+    kind: Value,
+) -> Result<(), SimpleFSError> {
+    let dirent = json!({ "key": inode::key(parent, name), "parent": parent, "name": name, "inode": inode });
+    backend.put(INODES, json!({ "inode": inode, "entry": kind })).await?;
+    backend.put(DIRENTS, dirent).await
+}
+
+fn corrupted(entry: &Value) -> SimpleFSError {
+    let path = entry.get("abs_path").and_then(Value::as_str).unwrap_or_default();
+    SimpleFSError::CorruptedEntry(path.to_string())
+}
+
+/// Rewrites every value of `store` with `update`.
+pub async fn map_values(
+    backend: &mut dyn Backend,
+    store: &str,
+    update: impl Fn(&mut Value) -> Result<(), SimpleFSError>,
+) -> Result<(), SimpleFSError> {
+    for mut value in backend.get_all(store).await? {
+        update(&mut value)?;
+        backend.put(store, value).await?;
+    }
+
+    Ok(())
+}
+
+/// Runs the migrations after `old_version`, the one the database was at,
+/// zero when it did not exist.
+pub async fn migrate(backend: &mut dyn Backend, old_version: u32) -> Result<(), SimpleFSError> {
+    for migration in MIGRATIONS.iter().filter(|migration| migration.version > old_version) {
+        (migration.run)(backend).await?;
+    }
+
+    Ok(())
+}
+
+/// The database within the transaction upgrading it.
+struct IdbBackend {
+    database: Database,
+    transaction: Transaction,
+}
+
+impl Backend for IdbBackend {
+    fn create_store(&mut self, name: &str, key: &str) -> Result<(), SimpleFSError> {
         let mut store_params = ObjectStoreParams::new();
         store_params.auto_increment(false);
-        store_params.key_path(Some(KeyPath::new_single("abs_path")));
+        store_params.key_path(Some(KeyPath::new_single(key)));
 
-        let store = database
-            .create_object_store("vol_0", store_params)
-            .unwrap();
+        let store = self.database.create_object_store(name, store_params)?;
 
         let mut index_params = IndexParams::new();
         index_params.unique(true);
 
-        store
-            .create_index("abs_path", KeyPath::new_single("abs_path"), Some(index_params))
-            .unwrap();
+        store.create_index(key, KeyPath::new_single(key), Some(index_params))?;
+        Ok(())
+    }
+
+    fn delete_store(&mut self, name: &str) -> Result<(), SimpleFSError> {
+        self.database.delete_object_store(name)?;
+        Ok(())
+    }
+
+    fn get_all<'a>(&'a self, store: &'a str) -> LocalBoxFuture<'a, Result<Vec<Value>, SimpleFSError>> {
+        async move {
+            let values = self.transaction.object_store(store)?.get_all(None, None)?.await?;
+
+            values
+                .into_iter()
+                .map(|value| serde_wasm_bindgen::from_value(value).map_err(|_| SimpleFSError::IOError))
+                .collect()
+        }
+        .boxed_local()
+    }
+
+    fn put<'a>(&'a mut self, store: &'a str, value: Value) -> LocalBoxFuture<'a, Result<(), SimpleFSError>> {
+        async move {
+            let value = value.serialize(&Serializer::json_compatible()).map_err(|_| SimpleFSError::IOError)?;
+            self.transaction.object_store(store)?.put(&value, None)?.await?;
+            Ok(())
+        }
+        .boxed_local()
+    }
+
+    fn delete_chunks(&mut self, inode: u64) -> LocalBoxFuture<'_, Result<(), SimpleFSError>> {
+        async move { delete_chunks(&self.transaction.object_store(CHUNKS)?, inode).await }.boxed_local()
+    }
+
+    fn log(&mut self, message: &str) {
+        console_log(message);
+    }
+}
+
+/// Opens the database, migrating it first when it is at an earlier
+/// version. A failed migration aborts the upgrade, the database then
+/// staying as it was, and so does the opening.
+pub async fn init_storage() -> Result<Database, Error> {
+    let factory = Factory::new()?;
+
+    let mut open_request = factory.open("vfs", Some(DB_VERSION))?;
+
+    open_request.on_upgrade_needed(|event| {
+        let old_version = event.old_version().unwrap_or(0);
+        console_log(&format!("[vfs] upgrading storage from version {} to {}\n", old_version, DB_VERSION));
+        for migration in MIGRATIONS.iter().filter(|migration| migration.version > old_version) {
+            console_log(&format!("[vfs] version {}: {}\n", migration.version, migration.description));
+        }
+
+        let transaction = event.target().ok().and_then(|request| request.transaction());
+        let (Ok(database), Some(transaction)) = (event.database(), transaction) else {
+            console_log("[vfs] no upgrade transaction\n");
+            return;
+        };
+
+        // the transaction stays active while its requests are awaited
+        wasm_bindgen_futures::spawn_local(async move {
+            let mut backend = IdbBackend { database, transaction };
+
+            if let Err(err) = migrate(&mut backend, old_version).await {
+                console_log(&format!("[vfs] migration failed, staying at version {}: {}\n", old_version, err));
+                let _ = backend.transaction.abort();
+            }
+        });
     });
 
     open_request.await
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use futures::executor::block_on;
+
+    use super::*;
+    use crate::vfs::{
+        chunk::Chunk,
+        entry::{FSEntry, FSEntryKind, FSEntryTrait},
+        inode::{DirEntry, Inode},
+    };
+
+    /// Object stores kept in memory, keyed as IndexedDB would.
+    #[derive(Default)]
+    struct MemoryBackend {
+        stores: BTreeMap<String, (String, BTreeMap<String, Value>)>,
+        logged: Vec<String>,
+    }
+
+    impl MemoryBackend {
+        /// A database at `version`, below 4, its volume store holding `values`.
+        fn at_version(version: u32, values: &[Value]) -> Self {
+            let mut backend = Self::default();
+            block_on(migrate_to(&mut backend, 0, version)).unwrap();
+
+            for value in values {
+                block_on(backend.put(VOLUME, value.clone())).unwrap();
+            }
+
+            backend
+        }
+
+        fn get(&self, store: &str, key: &str) -> Option<&Value> {
+            self.stores.get(store).and_then(|(_, values)| values.get(key))
+        }
+    }
+
+    impl Backend for MemoryBackend {
+        fn create_store(&mut self, name: &str, key: &str) -> Result<(), SimpleFSError> {
+            if self.stores.contains_key(name) {
+                return Err(SimpleFSError::AlreadyExists);
+            }
+
+            self.stores.insert(name.into(), (key.into(), BTreeMap::new()));
+            Ok(())
+        }
+
+        fn delete_store(&mut self, name: &str) -> Result<(), SimpleFSError> {
+            self.stores.remove(name).map(|_| ()).ok_or(SimpleFSError::NotFound)
+        }
+
+        fn get_all<'a>(&'a self, store: &'a str) -> LocalBoxFuture<'a, Result<Vec<Value>, SimpleFSError>> {
+            let values = self.stores.get(store).map(|(_, values)| values.values().cloned().collect());
+            async move { values.ok_or(SimpleFSError::NotFound) }.boxed_local()
+        }
+
+        fn put<'a>(&'a mut self, store: &'a str, value: Value) -> LocalBoxFuture<'a, Result<(), SimpleFSError>> {
+            async move {
+                let (key, values) = self.stores.get_mut(store).ok_or(SimpleFSError::NotFound)?;
+                let key = match value.get(key.as_str()) {
+                    Some(Value::String(key)) => key.clone(),
+                    Some(Value::Number(key)) => key.to_string(),
+                    _ => return Err(SimpleFSError::InvalidPath),
+                };
+
+                values.insert(key, value);
+                Ok(())
+            }
+            .boxed_local()
+        }
+
+        fn delete_chunks(&mut self, inode: u64) -> LocalBoxFuture<'_, Result<(), SimpleFSError>> {
+            async move {
+                let (_, chunks) = self.stores.get_mut(CHUNKS).ok_or(SimpleFSError::NotFound)?;
+                chunks.retain(|_, chunk| chunk["inode"] != json!(inode));
+                Ok(())
+            }
+            .boxed_local()
+        }
+
+        fn log(&mut self, message: &str) {
+            self.logged.push(message.into());
+        }
+    }
+
+    /// Runs the migrations from `old_version` up to `version` only.
+    async fn migrate_to(backend: &mut dyn Backend, old_version: u32, version: u32) -> Result<(), SimpleFSError> {
+        for migration in MIGRATIONS.iter().filter(|m| m.version > old_version && m.version <= version) {
+            (migration.run)(backend).await?;
+        }
+
+        Ok(())
+    }
+
+    /// An entry as stored at version 1, without mode nor owner.
+    fn v1_entry(path: &str, kind: &str) -> Value {
+        let name = path.rsplit('/').next().unwrap();
+        let mut entry = json!({
+            "abs_path": path,
+            "entry": {
+                "type": kind,
+                "metadata": { "is_hidden": false, "name": name, "created_at": 1, "modified_at": 2 },
+            },
+        });
+
+        match kind {
+            "File" => entry["entry"]["data"] = json!([104, 105]),
+            "Link" => entry["entry"]["target"] = json!("/home"),
+            _ => {}
+        }
+
+        entry
+    }
+
+    #[test]
+    fn versions_increase_from_one() {
+        assert!(MIGRATIONS.iter().enumerate().all(|(i, migration)| migration.version == i as u32 + 1));
+        assert_eq!(DB_VERSION, MIGRATIONS.len() as u32);
+    }
+
+    #[test]
+    fn new_database_gets_every_store() {
+        let mut backend = MemoryBackend::default();
+        block_on(migrate(&mut backend, 0)).unwrap();
+
+        let stores: Vec<_> = backend.stores.iter().map(|(name, (key, _))| (name.as_str(), key.as_str())).collect();
+        assert_eq!(stores, [(CHUNKS, "key"), (DIRENTS, "key"), (INODES, "inode")]);
+        assert!(block_on(backend.get_all(INODES)).unwrap().is_empty());
+    }
+
+    #[test]
+    fn entries_get_default_permissions() {
+        let entries = [v1_entry("/home", "Folder"), v1_entry("/home/a.txt", "File"), v1_entry("/ln", "Link")];
+        let mut backend = MemoryBackend::at_version(1, &entries);
+
+        block_on(migrate_to(&mut backend, 1, 2)).unwrap();
+
+        for (path, mode) in [("/home", 0o755), ("/home/a.txt", 0o644), ("/ln", 0o777)] {
+            let value = backend.get(VOLUME, path).unwrap();
+            assert_eq!(value["entry"]["metadata"]["mode"], json!(mode));
+            assert_eq!(value["entry"]["metadata"]["owner"], json!("user"));
+
+            let entry: FSEntry = serde_json::from_value(value.clone()).unwrap();
+            assert_eq!(entry.entry.mode(), mode);
+            assert_eq!(entry.entry.modified_at(), 2);
+        }
+
+    }
+
+    #[test]
+    fn file_data_moves_to_chunks() {
+        let mut big = v1_entry("/big", "File");
+        big["entry"]["data"] = json!(vec![7; CHUNK_SIZE as usize + 3]);
+        let mut empty = v1_entry("/empty", "File");
+        empty["entry"]["data"] = json!(null);
+        let mut backend = MemoryBackend::at_version(2, &[big, empty, v1_entry("/small", "File")]);
+
+        block_on(migrate_to(&mut backend, 2, 3)).unwrap();
+
+        let file = |path| match serde_json::from_value(backend.get(VOLUME, path).unwrap().clone()).unwrap() {
+            FSEntry { entry: FSEntryKind::File(file), .. } => file,
+            entry => panic!("not a file: {:?}", entry),
+        };
+        let (big, empty, small) = (file("/big"), file("/empty"), file("/small"));
+        assert!(big.data.is_none() && empty.data.is_none() && small.data.is_none());
+        assert_eq!((big.inode, big.size), (1, CHUNK_SIZE + 3));
+        assert_eq!((empty.inode, empty.size), (2, 0));
+        assert_eq!((small.inode, small.size), (3, 2));
+
+        let chunks: Vec<Chunk> = block_on(backend.get_all(CHUNKS))
+            .unwrap()
+            .into_iter()
+            .map(|value| serde_json::from_value(value).unwrap())
+            .collect();
+        let lengths: Vec<_> = chunks.iter().map(|chunk| (chunk.inode, chunk.index, chunk.data.len())).collect();
+        assert_eq!(lengths, [(1, 0, CHUNK_SIZE as usize), (1, 1, 3), (3, 0, 2)]);
+        assert_eq!(chunks[2].data, b"hi");
+        assert!(chunks.iter().all(|chunk| chunk.key == chunk::key(chunk.inode, chunk.index)));
+    }
+
+    #[test]
+    fn permissions_already_set_are_kept() {
+        let mut entry = v1_entry("/run.sh", "File");
+        entry["entry"]["metadata"]["mode"] = json!(0o755);
+        entry["entry"]["metadata"]["owner"] = json!("root");
+        let mut backend = MemoryBackend::at_version(1, &[entry]);
+
+        block_on(migrate_to(&mut backend, 1, 2)).unwrap();
+
+        let metadata = &backend.get(VOLUME, "/run.sh").unwrap()["entry"]["metadata"];
+        assert_eq!(metadata["mode"], json!(0o755));
+        assert_eq!(metadata["owner"], json!("root"));
+    }
+
+    #[test]
+    fn paths_become_inodes_and_directory_entries() {
+        let entries = [
+            v1_entry("/home", "Folder"),
+            v1_entry("/home/a.txt", "File"),
+            v1_entry("/home/sub", "Folder"),
+            v1_entry("/home/sub/b", "File"),
+            v1_entry("/ln", "Link"),
+        ];
+        let mut backend = MemoryBackend::at_version(1, &entries);
+
+        block_on(migrate(&mut backend, 1)).unwrap();
+
+        assert!(!backend.stores.contains_key(VOLUME));
+
+        let dirent = |parent, name| -> DirEntry {
+            serde_json::from_value(backend.get(DIRENTS, &inode::key(parent, name)).unwrap().clone()).unwrap()
+        };
+        let inode = |inode: u64| -> Inode {
+            serde_json::from_value(backend.get(INODES, &inode.to_string()).unwrap().clone()).unwrap()
+        };
+
+        // files keep the inodes of their chunks, the others follow them by path
+        let names = [(ROOT, "home", 3), (3, "a.txt", 1), (3, "sub", 4), (4, "b", 2), (ROOT, "ln", 5)];
+        for (parent, name, number) in names {
+            let dirent = dirent(parent, name);
+            assert_eq!((dirent.parent, dirent.name.as_str(), dirent.inode), (parent, name, number));
+
+            let stored = inode(number);
+            assert_eq!(stored.entry.name(), name);
+            assert_eq!(stored.entry.links(), 1);
+        }
+
+        let stored = inode(1);
+        assert!(matches!(stored.entry, FSEntryKind::File(file) if file.inode == 1 && file.size == 2));
+    }
+
+    #[test]
+    fn orphans_get_their_folders_back_or_are_left_out() {
+        let entries = [
+            v1_entry("/home", "Folder"),
+            v1_entry("/home/a.txt", "File"),
+            v1_entry("/home/a.txt/b", "File"),
+            v1_entry("/tmp/x/y", "Link"),
+        ];
+        let mut backend = MemoryBackend::at_version(1, &entries);
+
+        block_on(migrate(&mut backend, 1)).unwrap();
+
+        let dirent = |parent, name| -> Option<DirEntry> {
+            let value = backend.get(DIRENTS, &inode::key(parent, name))?;
+            Some(serde_json::from_value(value.clone()).unwrap())
+        };
+        let inode = |inode: u64| -> Option<Inode> {
+            let value = backend.get(INODES, &inode.to_string())?;
+            Some(serde_json::from_value(value.clone()).unwrap())
+        };
+
+        // the folders above the link are made again, in order
+        let names = [(ROOT, "home", 3), (3, "a.txt", 1), (ROOT, "tmp", 4), (4, "x", 5), (5, "y", 6)];
+        for (parent, name, number) in names {
+            assert_eq!(dirent(parent, name).map(|dirent| dirent.inode), Some(number));
+            assert_eq!(inode(number).unwrap().entry.name(), name);
+        }
+        assert!(matches!(inode(4).unwrap().entry, FSEntryKind::Folder(_)));
+        assert!(matches!(inode(6).unwrap().entry, FSEntryKind::Link(_)));
+
+        // nothing can be below a file
+        assert!(dirent(1, "b").is_none());
+        assert!(inode(2).is_none());
+    }
+
+    #[test]
+    fn entries_left_out_lose_their_chunks_and_are_logged() {
+        let entries = [
+            v1_entry("/a.txt", "File"),
+            v1_entry("/a.txt/b.txt", "File"),
+            v1_entry("/a.txt/c", "Folder"),
+            v1_entry("/a.txt/c/d.txt", "File"),
+            v1_entry("/e.txt", "File"),
+        ];
+        let mut backend = MemoryBackend::at_version(1, &entries);
+
+        block_on(migrate(&mut backend, 1)).unwrap();
+
+        // by path, the files are inodes 1, 2, 3 and 4
+        let chunks = block_on(backend.get_all(CHUNKS)).unwrap();
+        let inodes: Vec<_> = chunks.iter().map(|chunk| &chunk["inode"]).collect();
+        assert_eq!(inodes, [&json!(1), &json!(4)]);
+        assert_eq!(
+            backend.logged,
+            [
+                "[vfs] leaving out /a.txt/b.txt, as it is below a file\n",
+                "[vfs] leaving out /a.txt/c, as it is below a file\n",
+                "[vfs] leaving out /a.txt/c/d.txt, as it is below a file\n",
+            ]
+        );
+        assert!(backend.get(DIRENTS, &inode::key(ROOT, "e.txt")).is_some());
+    }
+
+    #[test]
+    fn current_database_is_left_alone() {
+        let mut backend = MemoryBackend::at_version(DB_VERSION, &[]);
+        let dirent = json!({ "key": inode::key(ROOT, "a"), "parent": ROOT, "name": "a", "inode": 1 });
+        block_on(backend.put(DIRENTS, dirent.clone())).unwrap();
+
+        block_on(migrate(&mut backend, DB_VERSION)).unwrap();
+
+        assert_eq!(backend.get(DIRENTS, &inode::key(ROOT, "a")), Some(&dirent));
+    }
+
+    #[test]
+    fn corrupted_entry_fails_the_migration() {
+        let mut backend = MemoryBackend::at_version(1, &[json!({ "abs_path": "/bad", "entry": { "type": "Pipe" } })]);
+
+        let err = block_on(migrate(&mut backend, 1)).unwrap_err();
+
+        assert!(matches!(err, SimpleFSError::CorruptedEntry(path) if path == "/bad"));
+    }
+}
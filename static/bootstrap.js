@@ -1,4 +1,4 @@
-import init from "./pkg/r_os.js";
+import init, { receive_key } from "./pkg/r_os.js";
 
 async function run() {
   await init();
@@ -11,6 +11,13 @@ async function run() {
       // nothing special: kernel inspects textarea content to detect newline
     }
 
+    // keys like Tab are handled by the kernel instead of the browser; the
+    // caret is left alone for them, full-screen programs placing it
+    if (receive_key(e.key, e.ctrlKey, e.altKey)) {
+      e.preventDefault();
+      return;
+    }
+
     ta.selectionStart = ta.selectionEnd = ta.value.length;
     //ta.scrollTop = ta.scrollHeight;
   });